@@ -0,0 +1,330 @@
+//! Staged file edits for the manifest-changing commands.
+//!
+//! `add`, `sync` and `rm` read and write `Cargo.toml`, the workspace
+//! manifest, `battery-pack.toml` and `build.rs` through a [`Changeset`],
+//! which keeps every edit in memory until it is committed. Committing
+//! replaces every file at once, or none of them, so an error partway
+//! through a command never leaves the manifests out of step with each
+//! other. With `--dry-run` the changeset is printed as a diff (or a
+//! [`cargo_bp_script::PlanReport`]) instead.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::merge::unified_diff;
+
+/// A set of pending file edits.
+#[derive(Debug, Default)]
+pub(crate) struct Changeset {
+    files: BTreeMap<PathBuf, StagedFile>,
+}
+
+/// One file's content on disk when first staged, and its staged content.
+/// `None` means the file doesn't exist (or is to be deleted).
+#[derive(Debug)]
+struct StagedFile {
+    original: Option<String>,
+    content: Option<String>,
+}
+
+impl Changeset {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current content of `path`, including staged edits. `None` if the
+    /// file doesn't exist or is staged for deletion.
+    pub(crate) fn read(&self, path: &Path) -> Result<Option<String>> {
+        match self.files.get(path) {
+            Some(staged) => Ok(staged.content.clone()),
+            None => read_optional(path),
+        }
+    }
+
+    /// Like [`read`](Self::read), but a missing file is an error.
+    pub(crate) fn read_to_string(&self, path: &Path) -> Result<String> {
+        self.read(path)?
+            .with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    /// Stage `content` as the new content of `path`.
+    pub(crate) fn write(&mut self, path: &Path, content: impl Into<String>) -> Result<()> {
+        self.stage(path, Some(content.into()))
+    }
+
+    /// Stage the deletion of `path`.
+    pub(crate) fn remove(&mut self, path: &Path) -> Result<()> {
+        self.stage(path, None)
+    }
+
+    fn stage(&mut self, path: &Path, content: Option<String>) -> Result<()> {
+        if !self.files.contains_key(path) {
+            let original = read_optional(path)?;
+            self.files.insert(
+                path.to_path_buf(),
+                StagedFile {
+                    original,
+                    content: None,
+                },
+            );
+        }
+        self.files.get_mut(path).expect("just inserted").content = content;
+        Ok(())
+    }
+
+    /// Staged files whose content differs from disk, in path order.
+    fn changed(&self) -> impl Iterator<Item = (&Path, &StagedFile)> {
+        self.files
+            .iter()
+            .filter(|(_, staged)| staged.original != staged.content)
+            .map(|(path, staged)| (path.as_path(), staged))
+    }
+
+    /// Whether committing would change nothing.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.changed().next().is_none()
+    }
+
+    /// Write every staged edit to disk as one transaction.
+    ///
+    /// New content is first written to a temporary file next to each
+    /// target; only once every one of those writes has succeeded are they
+    /// renamed into place (and deletions made). If a rename or deletion
+    /// fails, the files already replaced are restored to their original
+    /// content, so the project is never left half-updated. Each file is
+    /// recorded for `cargo bp undo` before anything is written.
+    // [impl manifest.write.atomic]
+    // [impl manifest.write.rollback]
+    pub(crate) fn commit(self) -> Result<()> {
+        for (path, _) in self.changed() {
+            crate::undo::record(path)?;
+        }
+
+        let mut prepared: Vec<(&Path, &StagedFile, Option<PathBuf>)> = Vec::new();
+        for (path, staged) in self.changed() {
+            let temp = match &staged.content {
+                Some(content) => match write_temp_file(path, content) {
+                    Ok(temp) => Some(temp),
+                    Err(e) => {
+                        remove_temp_files(prepared.iter().filter_map(|(_, _, t)| t.as_deref()));
+                        return Err(e);
+                    }
+                },
+                None => None,
+            };
+            prepared.push((path, staged, temp));
+        }
+
+        for i in 0..prepared.len() {
+            let (path, _, temp) = &prepared[i];
+            let result = match temp {
+                Some(temp) => std::fs::rename(temp, path)
+                    .with_context(|| format!("Failed to write {}", path.display())),
+                None => std::fs::remove_file(path)
+                    .with_context(|| format!("Failed to delete {}", path.display())),
+            };
+            if let Err(e) = result {
+                remove_temp_files(prepared[i..].iter().filter_map(|(_, _, t)| t.as_deref()));
+                restore(
+                    prepared[..i]
+                        .iter()
+                        .map(|(path, staged, _)| (*path, *staged)),
+                );
+                return Err(
+                    e.context("Failed to apply changes; files already written were restored")
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// A unified diff of every staged edit, with paths shown relative to
+    /// `root`.
+    pub(crate) fn diff(&self, root: &Path) -> String {
+        let mut output = String::new();
+        for (path, staged) in self.changed() {
+            let display = display_path(root, path);
+            let old_name = match staged.original {
+                Some(_) => format!("a/{display}"),
+                None => "/dev/null".to_string(),
+            };
+            let new_name = match staged.content {
+                Some(_) => format!("b/{display}"),
+                None => "/dev/null".to_string(),
+            };
+            output.push_str(&format!("--- {old_name}\n+++ {new_name}\n"));
+            output.push_str(&unified_diff(
+                staged.original.as_deref().unwrap_or(""),
+                staged.content.as_deref().unwrap_or(""),
+                &display,
+            ));
+        }
+        output
+    }
+
+    /// Describe the staged edits as a [`cargo_bp_script::PlanReport`] for
+    /// `command`, with paths relative to `root`.
+    pub(crate) fn to_report(&self, command: &str, root: &Path) -> cargo_bp_script::PlanReport {
+        use cargo_bp_script::{FileAction, PlannedChange};
+
+        let changes = self.changed().map(|(path, staged)| {
+            let action = match (&staged.original, &staged.content) {
+                (None, _) => FileAction::Create,
+                (_, None) => FileAction::Delete,
+                _ => FileAction::Modify,
+            };
+            let display = display_path(root, path);
+            let diff = unified_diff(
+                staged.original.as_deref().unwrap_or(""),
+                staged.content.as_deref().unwrap_or(""),
+                &display,
+            );
+            PlannedChange::new(display, action).with_diff(console::strip_ansi_codes(&diff))
+        });
+        cargo_bp_script::PlanReport::new(command).with_changes(changes)
+    }
+}
+
+/// Write `content` to a new temporary file in the same directory as
+/// `path`, carrying over `path`'s permissions if it exists.
+fn write_temp_file(path: &Path, content: &str) -> Result<PathBuf> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{file_name}.bp-{}.tmp", std::process::id()));
+    let result = (|| {
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)?;
+        file.write_all(content.as_bytes())?;
+        if let Ok(metadata) = std::fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.sync_all()
+    })();
+    match result {
+        Ok(()) => Ok(temp),
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp);
+            Err(e).with_context(|| format!("Failed to write {}", path.display()))
+        }
+    }
+}
+
+fn remove_temp_files<'a>(temps: impl Iterator<Item = &'a Path>) {
+    for temp in temps {
+        let _ = std::fs::remove_file(temp);
+    }
+}
+
+/// Put files already committed back the way they were. Best effort: the
+/// commit has already failed, so errors here are not reported.
+fn restore<'a>(committed: impl DoubleEndedIterator<Item = (&'a Path, &'a StagedFile)>) {
+    for (path, staged) in committed.rev() {
+        let _ = match &staged.original {
+            Some(original) => std::fs::write(path, original),
+            None => std::fs::remove_file(path),
+        };
+    }
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// `path` relative to `root` with `/` separators, or in full if it lies
+/// outside `root`.
+fn display_path(root: &Path, path: &Path) -> String {
+    let canonical = |p: &Path| {
+        let p = if p.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            p
+        };
+        p.canonicalize().unwrap_or_else(|_| p.to_path_buf())
+    };
+    let root = canonical(root);
+    let path = match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => canonical(dir).join(name),
+        _ => path.to_path_buf(),
+    };
+    path.strip_prefix(&root)
+        .unwrap_or(&path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Whether a manifest-changing command writes its changes or only shows
+/// them.
+// [impl cli.dry-run.flag]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum DryRun {
+    /// Write the changes.
+    #[default]
+    Off,
+    /// Print the changes as a unified diff.
+    Diff,
+    /// Print the changes as a [`cargo_bp_script::PlanReport`].
+    Json,
+}
+
+impl DryRun {
+    pub(crate) fn from_flags(dry_run: bool, json: bool) -> Self {
+        match (dry_run, json) {
+            (false, _) => DryRun::Off,
+            (true, false) => DryRun::Diff,
+            (true, true) => DryRun::Json,
+        }
+    }
+
+    pub(crate) fn is_on(self) -> bool {
+        self != DryRun::Off
+    }
+
+    /// Where progress messages go: stdout, except when stdout carries JSON.
+    pub(crate) fn progress(self) -> Box<dyn Write> {
+        match self {
+            DryRun::Json => Box::new(std::io::sink()),
+            DryRun::Off | DryRun::Diff => Box::new(std::io::stdout()),
+        }
+    }
+
+    /// Commit `changes`, or print them for `command` with paths relative to
+    /// `root`.
+    // [impl cli.dry-run.no-write]
+    // [impl cli.dry-run.diff]
+    // [impl cli.dry-run.json]
+    pub(crate) fn finish(self, changes: Changeset, command: &str, root: &Path) -> Result<()> {
+        match self {
+            DryRun::Off => changes.commit(),
+            DryRun::Diff => {
+                let mut out = std::io::stdout().lock();
+                if changes.is_empty() {
+                    writeln!(out, "Dry run: no files would change.")?;
+                } else {
+                    let count = changes.changed().count();
+                    write!(out, "{}", changes.diff(root))?;
+                    writeln!(
+                        out,
+                        "Dry run: {count} file(s) would change; nothing was written."
+                    )?;
+                }
+                Ok(())
+            }
+            DryRun::Json => {
+                let mut out = std::io::stdout().lock();
+                serde_json::to_writer(&mut out, &changes.to_report(command, root))?;
+                writeln!(out)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
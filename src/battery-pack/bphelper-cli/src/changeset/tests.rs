@@ -0,0 +1,171 @@
+use super::*;
+use cargo_bp_script::FileAction;
+
+#[test]
+fn staged_writes_are_visible_but_not_on_disk() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("Cargo.toml");
+    std::fs::write(&path, "old\n").unwrap();
+
+    let mut changes = Changeset::new();
+    changes.write(&path, "new\n").unwrap();
+
+    assert_eq!(changes.read(&path).unwrap().as_deref(), Some("new\n"));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "old\n");
+    assert!(!changes.is_empty());
+}
+
+#[test]
+fn commit_writes_and_deletes() {
+    let tmp = tempfile::tempdir().unwrap();
+    let kept = tmp.path().join("Cargo.toml");
+    let created = tmp.path().join("battery-pack.toml");
+    let deleted = tmp.path().join("build.rs");
+    std::fs::write(&kept, "old\n").unwrap();
+    std::fs::write(&deleted, "fn main() {}\n").unwrap();
+
+    let mut changes = Changeset::new();
+    changes.write(&kept, "new\n").unwrap();
+    changes.write(&created, "version = 1\n").unwrap();
+    changes.remove(&deleted).unwrap();
+    changes.commit().unwrap();
+
+    assert_eq!(std::fs::read_to_string(&kept).unwrap(), "new\n");
+    assert_eq!(std::fs::read_to_string(&created).unwrap(), "version = 1\n");
+    assert!(!deleted.exists());
+}
+
+#[test]
+fn unchanged_content_is_not_a_change() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("Cargo.toml");
+    std::fs::write(&path, "same\n").unwrap();
+
+    let mut changes = Changeset::new();
+    changes.write(&path, "edited\n").unwrap();
+    changes.write(&path, "same\n").unwrap();
+
+    assert!(changes.is_empty());
+    assert_eq!(changes.diff(tmp.path()), "");
+}
+
+// [verify cli.dry-run.diff]
+#[test]
+fn diff_shows_paths_relative_to_root() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::create_dir(tmp.path().join("api")).unwrap();
+    let path = tmp.path().join("api/Cargo.toml");
+    std::fs::write(&path, "[dependencies]\nclap = \"4.4\"\n").unwrap();
+
+    let mut changes = Changeset::new();
+    changes
+        .write(&path, "[dependencies]\nclap = \"4.5\"\n")
+        .unwrap();
+    let diff = console::strip_ansi_codes(&changes.diff(tmp.path())).into_owned();
+
+    assert!(diff.starts_with("--- a/api/Cargo.toml\n+++ b/api/Cargo.toml\n"));
+    assert!(diff.contains("-clap = \"4.4\"\n"));
+    assert!(diff.contains("+clap = \"4.5\"\n"));
+}
+
+// [verify cli.dry-run.json]
+#[test]
+fn report_classifies_each_file() {
+    let tmp = tempfile::tempdir().unwrap();
+    let modified = tmp.path().join("Cargo.toml");
+    let deleted = tmp.path().join("build.rs");
+    std::fs::write(&modified, "a\n").unwrap();
+    std::fs::write(&deleted, "fn main() {}\n").unwrap();
+
+    let mut changes = Changeset::new();
+    changes.write(&modified, "b\n").unwrap();
+    changes
+        .write(&tmp.path().join("battery-pack.toml"), "version = 1\n")
+        .unwrap();
+    changes.remove(&deleted).unwrap();
+
+    let report = changes.to_report("rm", tmp.path());
+    assert_eq!(report.command, "rm");
+    let actions: Vec<_> = report
+        .changes
+        .iter()
+        .map(|c| (c.path.as_str(), c.action))
+        .collect();
+    assert_eq!(
+        actions,
+        [
+            ("Cargo.toml", FileAction::Modify),
+            ("battery-pack.toml", FileAction::Create),
+            ("build.rs", FileAction::Delete),
+        ]
+    );
+    assert!(report.changes[0].diff.contains("-a\n+b\n"));
+}
+
+// [verify manifest.write.atomic]
+#[test]
+fn commit_keeps_permissions_and_leaves_no_temp_files() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("Cargo.toml");
+    std::fs::write(&path, "old\n").unwrap();
+    let permissions = std::fs::metadata(&path).unwrap().permissions();
+
+    let mut changes = Changeset::new();
+    changes.write(&path, "new\n").unwrap();
+    changes.commit().unwrap();
+
+    assert_eq!(std::fs::metadata(&path).unwrap().permissions(), permissions);
+    let names: Vec<_> = std::fs::read_dir(tmp.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(names, ["Cargo.toml"]);
+}
+
+// [verify manifest.write.atomic]
+#[test]
+fn failed_temp_write_changes_nothing() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manifest = tmp.path().join("Cargo.toml");
+    std::fs::write(&manifest, "old\n").unwrap();
+    std::fs::create_dir(tmp.path().join("sub")).unwrap();
+
+    let mut changes = Changeset::new();
+    changes.write(&manifest, "new\n").unwrap();
+    changes
+        .write(&tmp.path().join("sub/battery-pack.toml"), "version = 1\n")
+        .unwrap();
+    // The second file's directory vanishes before the commit.
+    std::fs::remove_dir(tmp.path().join("sub")).unwrap();
+
+    assert!(changes.commit().is_err());
+    assert_eq!(std::fs::read_to_string(&manifest).unwrap(), "old\n");
+    let names: Vec<_> = std::fs::read_dir(tmp.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(names, ["Cargo.toml"]);
+}
+
+// [verify manifest.write.rollback]
+#[test]
+fn failed_commit_restores_files_already_replaced() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manifest = tmp.path().join("Cargo.toml");
+    let created = tmp.path().join("battery-pack.toml");
+    let build_rs = tmp.path().join("build.rs");
+    std::fs::write(&manifest, "old\n").unwrap();
+    std::fs::write(&build_rs, "fn main() {}\n").unwrap();
+
+    let mut changes = Changeset::new();
+    changes.write(&manifest, "new\n").unwrap();
+    changes.write(&created, "version = 1\n").unwrap();
+    changes.remove(&build_rs).unwrap();
+    // Deleting build.rs, the last step, fails because it's already gone.
+    std::fs::remove_file(&build_rs).unwrap();
+
+    let err = changes.commit().unwrap_err();
+    assert!(format!("{err:#}").contains("restored"), "{err:#}");
+    assert_eq!(std::fs::read_to_string(&manifest).unwrap(), "old\n");
+    assert!(!created.exists());
+}
@@ -7,21 +7,31 @@ use anyhow::{Context, Result, bail};
 use bphelper_manifest::{ActiveFeatures, parse_battery_pack_from_path};
 use clap::{CommandFactory, Parser, Subcommand};
 use std::collections::{BTreeMap, BTreeSet};
-use std::io::IsTerminal;
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
 
+use crate::changeset::{Changeset, DryRun};
+use crate::lockfile::{
+    LockMode, Lockfile, PackResolver, lock_file_path, read_lockfile, remove_locked_pack,
+    write_lockfile,
+};
 use crate::manifest::{
-    add_dep_to_table, dep_kind_section, find_installed_bp_names, find_user_manifest,
-    find_workspace_manifest, read_active_features_for_project, read_active_features_from_state,
-    read_applied_templates_from_state, read_managed_deps_for_project, record_applied_template,
-    remove_battery_pack_state_entry, remove_deps_by_kind, should_upgrade_version,
-    sync_dep_in_table, write_battery_pack_state, write_deps_by_kind, write_workspace_refs_by_kind,
+    AppliedTemplate, add_dep_to_table, apply_dep_kind_overrides, dep_kind_section, dep_table_mut,
+    dep_tables, find_installed_bp_names, find_user_manifest, find_workspace_manifest,
+    move_deps_to_kind, read_active_features_for_project, read_active_features_from_state,
+    read_applied_template_records, read_applied_templates_from_state, read_dep_kind_overrides,
+    read_managed_deps_for_project, read_managed_deps_from_state, read_pinned_packs_from_state,
+    read_staged_version_req, read_version_req_from_state, record_applied_template,
+    remove_battery_pack_state_entry, remove_dep_from_any_table, remove_deps_by_kind,
+    set_dep_version, should_upgrade_version, specs_by_kind, sync_dep_in_table,
+    write_battery_pack_state, write_dep_kind_overrides, write_deps_by_kind,
+    write_version_req_to_state, write_workspace_refs_by_kind,
 };
 use crate::registry::{
     CrateSource, InstalledPack, TemplateConfig, fetch_battery_pack_detail,
-    fetch_battery_pack_detail_from_source, fetch_battery_pack_list, fetch_bp_spec,
-    load_installed_bp_spec, resolve_crate_name, short_name,
+    fetch_battery_pack_detail_from_source, fetch_battery_pack_list, resolve_crate_name, short_name,
 };
+use crate::template_engine::HookPolicy;
 
 // [impl cli.bare.help]
 #[derive(Parser)]
@@ -42,10 +52,25 @@ pub(crate) enum Commands {
         #[arg(long)]
         crate_source: Option<PathBuf>,
 
+        // [impl cli.registry.flag]
+        /// Use a registry from cargo's config (`[registries.<NAME>]`) instead of crates.io
+        #[arg(
+            long,
+            value_name = "NAME",
+            env = "CARGO_BP_REGISTRY",
+            conflicts_with = "crate_source"
+        )]
+        registry: Option<String>,
+
         /// Disable interactive prompts and TUI mode
         #[arg(long, short = 'N', global = true, env = "CARGO_BP_NON_INTERACTIVE")]
         non_interactive: bool,
 
+        // [impl cli.offline.flag]
+        /// Resolve battery packs only from the local cache, without network access
+        #[arg(long, global = true, env = "CARGO_NET_OFFLINE")]
+        offline: bool,
+
         #[command(subcommand)]
         command: BpCommands,
     },
@@ -75,6 +100,16 @@ pub(crate) enum BpCommands {
         /// Set a template placeholder value (e.g., -d description="My project")
         #[arg(long = "define", short = 'd', value_parser = parse_define)]
         define: Vec<(String, String)>,
+
+        // [impl cli.new.hooks]
+        /// Run the template's post-generate steps without asking
+        #[arg(long)]
+        allow_hooks: bool,
+
+        // [impl cli.new.locked]
+        /// Fail unless the battery pack is locked in ./battery-pack.lock
+        #[arg(long, conflicts_with = "path")]
+        locked: bool,
     },
 
     /// Add a battery pack and sync its dependencies.
@@ -110,6 +145,34 @@ pub(crate) enum BpCommands {
         #[arg(long)]
         all_features: bool,
 
+        // [impl cli.add.dep-kind-override]
+        /// Add these crates as dev-dependencies, whichever section the pack lists them in
+        #[arg(
+            long,
+            value_name = "CRATE",
+            value_delimiter = ',',
+            conflicts_with = "template"
+        )]
+        as_dev: Vec<String>,
+
+        /// Add these crates as build-dependencies, whichever section the pack lists them in
+        #[arg(
+            long,
+            value_name = "CRATE",
+            value_delimiter = ',',
+            conflicts_with = "template"
+        )]
+        as_build: Vec<String>,
+
+        /// Add these crates as regular dependencies, whichever section the pack lists them in
+        #[arg(
+            long,
+            value_name = "CRATE",
+            value_delimiter = ',',
+            conflicts_with = "template"
+        )]
+        as_normal: Vec<String>,
+
         /// Use a local path instead of downloading from crates.io
         #[arg(long)]
         path: Option<String>,
@@ -122,7 +185,49 @@ pub(crate) enum BpCommands {
         #[arg(long = "define", short = 'd', value_parser = parse_define)]
         define: Vec<(String, String)>,
 
-        /// Overwrite existing files without prompting (TOML and YAML files are always merged, never overwritten)
+        /// Overwrite existing files without prompting (TOML, YAML, JSON, Markdown and ignore files are always merged, never overwritten)
+        #[arg(long)]
+        overwrite: bool,
+
+        // [impl cli.new.hooks]
+        /// With --template, run the template's post-generate steps without asking
+        #[arg(long, requires = "template")]
+        allow_hooks: bool,
+
+        // [impl cli.dry-run.flag]
+        /// Show the changes as a diff without writing any files
+        #[arg(long, conflicts_with = "template")]
+        dry_run: bool,
+
+        // [impl cli.dry-run.json]
+        /// With --dry-run, print the changes as JSON
+        #[arg(long, requires = "dry_run")]
+        json: bool,
+    },
+
+    /// Upgrade applied templates to the latest battery pack version.
+    ///
+    /// Re-renders each template at the version it was applied from and at the
+    /// new version, then three-way merges the changes into the project so
+    /// local edits are kept.
+    UpgradeTemplate {
+        /// Name of the battery pack whose templates to upgrade
+        #[arg(add = clap_complete::ArgValueCompleter::new(crate::completions::installed_packs))]
+        battery_pack: String,
+
+        /// Upgrade only this template (defaults to every applied template of the pack)
+        #[arg(long, short = 't')]
+        template: Option<String>,
+
+        /// Use a local path for the new version instead of downloading from crates.io
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Override a recorded template variable (e.g., -d ci_platform=github)
+        #[arg(long = "define", short = 'd', value_parser = parse_define)]
+        define: Vec<(String, String)>,
+
+        /// Overwrite existing files without prompting and proceed with a dirty working tree
         #[arg(long)]
         overwrite: bool,
     },
@@ -133,6 +238,35 @@ pub(crate) enum BpCommands {
         /// Use a local path instead of downloading from crates.io
         #[arg(long)]
         path: Option<String>,
+
+        #[command(flatten)]
+        members: MemberSelection,
+
+        // [impl cli.sync.locked]
+        /// Fail instead of updating battery-pack.lock if resolution would change it
+        #[arg(long)]
+        locked: bool,
+
+        // [impl cli.dry-run.flag]
+        /// Show the changes as a diff without writing any files
+        #[arg(long)]
+        dry_run: bool,
+
+        // [impl cli.dry-run.json]
+        /// With --dry-run, print the changes as JSON
+        #[arg(long, requires = "dry_run")]
+        json: bool,
+    },
+
+    /// Move pinned battery packs forward to their newest version, then sync.
+    ///
+    /// Each pin keeps its precision: `0.4` becomes `0.6`. Pass
+    /// `<pack>@<requirement>` to set a pin explicitly.
+    Update {
+        /// Battery pack to update, optionally as `<pack>@<requirement>`
+        /// (defaults to every pinned pack)
+        #[arg(add = clap_complete::ArgValueCompleter::new(crate::completions::installed_packs))]
+        battery_pack: Option<String>,
     },
 
     /// Remove a battery pack from the current project
@@ -149,8 +283,29 @@ pub(crate) enum BpCommands {
         /// Keep all dependencies (don't prompt)
         #[arg(long)]
         keep_deps: bool,
+
+        // [impl cli.dry-run.flag]
+        /// Show the changes as a diff without writing any files
+        #[arg(long)]
+        dry_run: bool,
+
+        // [impl cli.dry-run.json]
+        /// With --dry-run, print the changes as JSON
+        #[arg(long, requires = "dry_run")]
+        json: bool,
     },
 
+    /// Revert the files changed by the last `add`, `sync`, `rm`, `update` or
+    /// `upgrade-template`.
+    ///
+    /// Each of those commands saves the files it touches under
+    /// `target/bp-undo/`. Running `undo` again steps further back.
+    Undo,
+
+    /// Open the interactive TUI: manage installed packs, browse, or start a project
+    // [impl cli.tui.menu]
+    Tui,
+
     /// List available battery packs on crates.io
     #[command(visible_alias = "ls")]
     List {
@@ -199,13 +354,26 @@ pub(crate) enum BpCommands {
         // [impl cli.status.json]
         #[arg(long)]
         json: bool,
+
+        #[command(flatten)]
+        members: MemberSelection,
     },
 
-    /// Check that installed battery packs match project dependencies
+    /// Check that installed battery packs match project dependencies.
+    ///
+    /// Exits with a non-zero status if any dependency is out of sync, so it
+    /// can gate CI.
     Check {
         /// Use a local path instead of downloading from crates.io
         #[arg(long)]
         path: Option<String>,
+
+        /// Emit machine-readable JSON instead of the default text output
+        #[arg(long)]
+        json: bool,
+
+        #[command(flatten)]
+        members: MemberSelection,
     },
 
     /// Validate that the current battery pack is well-formed
@@ -225,6 +393,46 @@ pub(crate) enum BpCommands {
     UpdateCache,
 }
 
+/// Workspace member selection for `sync`, `status` and `check`.
+// [impl cli.workspace.flag]
+// [impl cli.workspace.package]
+#[derive(clap::Args, Debug, Default)]
+pub(crate) struct MemberSelection {
+    /// Run for every workspace member that has battery packs installed
+    #[arg(long)]
+    workspace: bool,
+
+    /// Run only for the named workspace member (may be repeated)
+    #[arg(long = "package", short = 'p', value_name = "SPEC")]
+    package: Vec<String>,
+}
+
+impl BpCommands {
+    /// Whether this is an `add`, `sync` or `rm` that must not write files.
+    fn is_dry_run(&self) -> bool {
+        matches!(
+            self,
+            BpCommands::Add { dry_run: true, .. }
+                | BpCommands::Sync { dry_run: true, .. }
+                | BpCommands::Rm { dry_run: true, .. }
+        )
+    }
+
+    /// Whether this command changes project files, and so should save them
+    /// for `cargo bp undo`.
+    // [impl cli.undo.snapshot]
+    fn records_undo(&self) -> bool {
+        match self {
+            BpCommands::Add { battery_pack, .. } => battery_pack.is_some() && !self.is_dry_run(),
+            BpCommands::Sync { .. } | BpCommands::Rm { .. } => !self.is_dry_run(),
+            BpCommands::Update { .. } | BpCommands::UpgradeTemplate { .. } | BpCommands::Tui => {
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
 pub fn main() -> Result<()> {
     clap_complete::env::CompleteEnv::with_factory(Cli::command).complete();
     let cli = Cli::parse();
@@ -234,16 +442,33 @@ pub fn main() -> Result<()> {
     match cli.command {
         Commands::Bp {
             crate_source,
+            registry,
             non_interactive,
+            offline,
             command,
         } => {
-            if let Err(err) = sync_state_with_current_manifest(&project_dir) {
+            // [impl cli.dry-run.no-write]
+            if !command.is_dry_run()
+                && !matches!(command, BpCommands::Undo)
+                && let Err(err) = sync_state_with_current_manifest(&project_dir)
+            {
                 eprintln!("warning: failed to prune battery-pack state: {err}");
             }
-            let source = match crate_source {
-                Some(path) => CrateSource::Local(path),
-                None => CrateSource::Registry,
-            };
+            let _recording = command.records_undo().then(|| {
+                let args: Vec<String> = std::env::args().skip(1).collect();
+                crate::undo::begin(
+                    &undo_root(&project_dir),
+                    &format!("cargo {}", args.join(" ")),
+                )
+            });
+            let source = match (crate_source, registry) {
+                (Some(path), _) => CrateSource::Local(path),
+                (None, Some(name)) => CrateSource::Registry(
+                    crate::registry::Registry::from_cargo_config(&name, &project_dir)?,
+                ),
+                (None, None) => CrateSource::crates_io(),
+            }
+            .with_offline(offline);
             let interactive = interactive && !non_interactive;
             match command {
                 BpCommands::New {
@@ -252,14 +477,19 @@ pub fn main() -> Result<()> {
                     template,
                     path,
                     define,
+                    allow_hooks,
+                    locked,
                 } => new_from_battery_pack(NewFromBpOpts {
                     battery_pack: &battery_pack,
                     name,
                     template,
                     path_override: path,
                     source: &source,
+                    project_dir: &project_dir,
                     define: &define,
                     interactive,
+                    hooks: HookPolicy::from_flags(allow_hooks, interactive),
+                    lock_mode: LockMode::from_flags(locked),
                 }),
                 BpCommands::Add {
                     battery_pack,
@@ -267,11 +497,21 @@ pub fn main() -> Result<()> {
                     features,
                     no_default_features,
                     all_features,
+                    as_dev,
+                    as_build,
+                    as_normal,
                     path,
                     template,
                     define,
                     overwrite,
+                    allow_hooks,
+                    dry_run,
+                    json,
                 } => match (battery_pack, template) {
+                    (Some(name), Some(_)) if name.contains('@') => bail!(
+                        "a version requirement can't be combined with --template; \
+                         pin the pack with `cargo bp add {name}` first"
+                    ),
                     // Template merge: cargo bp add <pack> -t <template>
                     (Some(name), Some(tmpl)) => add_template(AddTemplateOpts {
                         battery_pack: &name,
@@ -283,6 +523,7 @@ pub fn main() -> Result<()> {
                         active_features: BTreeSet::new(),
                         overwrite,
                         interactive,
+                        hooks: HookPolicy::from_flags(allow_hooks, interactive),
                     }),
                     // Normal add: cargo bp add <pack>
                     (Some(name), None) => add_battery_pack(
@@ -291,26 +532,70 @@ pub fn main() -> Result<()> {
                         no_default_features,
                         all_features,
                         &crates,
+                        &dep_kind_choices(&as_normal, &as_dev, &as_build)?,
                         path.as_deref(),
                         &source,
                         &project_dir,
+                        DryRun::from_flags(dry_run, json),
                     ),
                     (None, _) => show_add_help(&project_dir),
                 },
-                BpCommands::Sync { path } => {
-                    sync_battery_packs(&project_dir, path.as_deref(), &source)
+                BpCommands::UpgradeTemplate {
+                    battery_pack,
+                    template,
+                    path,
+                    define,
+                    overwrite,
+                } => upgrade_template(UpgradeTemplateOpts {
+                    battery_pack: &battery_pack,
+                    template: template.as_deref(),
+                    path_override: path.as_deref(),
+                    source: &source,
+                    project_dir: &project_dir,
+                    defines: define.into_iter().collect(),
+                    overwrite,
+                    interactive,
+                }),
+                BpCommands::Sync {
+                    path,
+                    members,
+                    locked,
+                    dry_run,
+                    json,
+                } => sync_battery_packs(
+                    &project_dir,
+                    &members,
+                    path.as_deref(),
+                    &source,
+                    LockMode::from_flags(locked),
+                    DryRun::from_flags(dry_run, json),
+                    Changeset::new(),
+                ),
+                BpCommands::Update { battery_pack } => {
+                    update_battery_packs(battery_pack.as_deref(), &source, &project_dir)
                 }
                 BpCommands::Rm {
                     battery_pack,
                     remove_deps,
                     keep_deps,
+                    dry_run,
+                    json,
                 } => remove_battery_pack(
                     &battery_pack,
                     remove_deps,
                     keep_deps,
                     interactive,
                     &project_dir,
+                    DryRun::from_flags(dry_run, json),
                 ),
+                BpCommands::Undo => undo_last_command(&project_dir),
+                BpCommands::Tui => {
+                    // [impl cli.tui.non-interactive]
+                    if !interactive {
+                        bail!("`cargo bp tui` needs an interactive terminal");
+                    }
+                    crate::tui::run_menu(source)
+                }
                 BpCommands::List { filter, json } => {
                     // [impl cli.list.interactive]
                     // [impl cli.list.non-interactive]
@@ -362,12 +647,16 @@ pub fn main() -> Result<()> {
                         }
                     }
                 }
-                BpCommands::Status { path, json } => {
-                    status_battery_packs(&project_dir, path.as_deref(), &source, json)
-                }
-                BpCommands::Check { path } => {
-                    check_battery_packs(&project_dir, path.as_deref(), &source)
-                }
+                BpCommands::Status {
+                    path,
+                    json,
+                    members,
+                } => status_battery_packs(&project_dir, &members, path.as_deref(), &source, json),
+                BpCommands::Check {
+                    path,
+                    json,
+                    members,
+                } => check_battery_packs(&project_dir, &members, path.as_deref(), &source, json),
                 BpCommands::Validate { path } => {
                     crate::validate::validate_battery_pack_cmd(path.as_deref())
                 }
@@ -440,8 +729,12 @@ struct NewFromBpOpts<'a> {
     template: Option<String>,
     path_override: Option<String>,
     source: &'a CrateSource,
+    /// Where `cargo bp new` runs; its `battery-pack.lock`, if any, is reused.
+    project_dir: &'a Path,
     define: &'a [(String, String)],
     interactive: bool,
+    hooks: HookPolicy,
+    lock_mode: LockMode,
 }
 
 // [impl cli.new.template]
@@ -454,11 +747,14 @@ fn new_from_battery_pack(opts: NewFromBpOpts<'_>) -> Result<()> {
         bail!("--name is required in non-interactive mode");
     }
 
-    let new_opts = NewOpts {
+    let mut new_opts = NewOpts {
         battery_pack: opts.battery_pack.to_string(),
         name: opts.name,
         defines: opts.define.iter().cloned().collect(),
         interactive: opts.interactive,
+        hooks: opts.hooks,
+        lock: None,
+        source: opts.source.clone(),
     };
 
     // --path takes precedence over --crate-source
@@ -466,11 +762,18 @@ fn new_from_battery_pack(opts: NewFromBpOpts<'_>) -> Result<()> {
         return generate_from_local(new_opts, &path, opts.template);
     }
 
+    // [impl cli.new.locked]
+    // [impl manifest.lock.reuse]
     let crate_name = resolve_crate_name(opts.battery_pack);
-    let resolved = crate::registry::resolve_crate_dir(opts.battery_pack, None, opts.source)?;
+    let lock_path = lock_file_path(&opts.project_dir.join("Cargo.toml"));
+    let lock = read_lockfile(&Changeset::new(), &lock_path)?;
+    let mut resolver = PackResolver::new(opts.source, lock, opts.lock_mode);
+    let (_version, crate_dir) = resolver.fetch_dir(opts.battery_pack, None)?;
+    resolver.ensure_unchanged(&lock_path)?;
+    new_opts.lock = resolver.resolved_lock();
 
     // Read template metadata from the Cargo.toml
-    let manifest_path = resolved.dir.join("Cargo.toml");
+    let manifest_path = crate_dir.join("Cargo.toml");
     let templates = parse_template_metadata(&manifest_path, &crate_name)?;
 
     // Resolve which template to use
@@ -479,7 +782,7 @@ fn new_from_battery_pack(opts: NewFromBpOpts<'_>) -> Result<()> {
     // Generate the project from the crate directory
     generate_from_path(
         new_opts,
-        &resolved.dir,
+        &crate_dir,
         &resolved_tmpl.name,
         &resolved_tmpl.path,
     )
@@ -487,7 +790,11 @@ fn new_from_battery_pack(opts: NewFromBpOpts<'_>) -> Result<()> {
 
 /// True if `a` and `b` are two distinct items sharing an `at-most-one`
 /// category — i.e. picking one should replace the other.
-fn exclusive_siblings(spec: &bphelper_manifest::BatteryPackSpec, a: &str, b: &str) -> bool {
+pub(crate) fn exclusive_siblings(
+    spec: &bphelper_manifest::BatteryPackSpec,
+    a: &str,
+    b: &str,
+) -> bool {
     if a == b {
         return false;
     }
@@ -573,6 +880,25 @@ pub(crate) fn validate_exclusive_constraints(
     Ok(())
 }
 
+/// Reject a `--as-<kind>` for a crate this `add` doesn't install, rather
+/// than storing a kind that only takes effect when the crate is added later.
+// [impl cli.add.dep-kind-override]
+fn check_dep_kinds_selected(
+    dep_kinds: &BTreeMap<String, bphelper_manifest::DepKind>,
+    crates: &BTreeMap<String, bphelper_manifest::CrateSpec>,
+) -> Result<()> {
+    if let Some((name, kind)) = dep_kinds
+        .iter()
+        .find(|(name, _)| !crates.contains_key(*name))
+    {
+        bail!(
+            "'{name}' can't be added as {kind}: it isn't among the crates being added \
+             (select it with -F or by name)"
+        );
+    }
+    Ok(())
+}
+
 /// Result of resolving which crates to add from a battery pack.
 pub(crate) enum ResolvedAdd {
     /// Resolved to a concrete set of crates (no interactive picker needed).
@@ -686,6 +1012,7 @@ struct AddTemplateOpts<'a> {
     active_features: BTreeSet<String>,
     overwrite: bool,
     interactive: bool,
+    hooks: HookPolicy,
 }
 
 /// Warn if the git working tree has uncommitted changes.
@@ -766,6 +1093,7 @@ fn add_template(opts: AddTemplateOpts<'_>) -> Result<()> {
     let manifest_path = crate_dir.join("Cargo.toml");
     let templates = parse_template_metadata(&manifest_path, &crate_name)?;
     let resolved_tmpl = resolve_template(&templates, Some(opts.template), opts.interactive)?;
+    let bp_version = read_pack_version(&manifest_path);
 
     // Load post-merge hints before moving template_path.
     let hints = crate::template_engine::load_template_hints(&crate_dir, &resolved_tmpl.path);
@@ -782,21 +1110,36 @@ fn add_template(opts: AddTemplateOpts<'_>) -> Result<()> {
         defines: opts.defines,
         active_features: opts.active_features,
         interactive_override,
+        source: opts.source.clone(),
     };
-    let files = crate::template_engine::preview(render_opts)?;
+    let (files, placeholders) = crate::template_engine::preview_with_values(&render_opts)?;
+    let hooks = crate::template_engine::template_hooks(&render_opts, &placeholders)?;
 
     // Apply rendered files with format-aware merging.
     let apply_opts = crate::merge::ApplyOpts {
         project_dir: opts.project_dir.to_path_buf(),
         overwrite: opts.overwrite,
         interactive: opts.interactive,
+        merge_overrides: crate::template_engine::merge_overrides(&render_opts)?,
     };
     let results = crate::merge::apply_rendered_files(&files, &apply_opts)?;
     crate::merge::print_summary(&results);
 
-    // Record the applied template in battery-pack.toml.
+    // [impl cli.add.template-record]
+    // Record the applied template in battery-pack.toml, along with what's
+    // needed to re-render it for `cargo bp upgrade-template`.
     let user_manifest_path = find_user_manifest(opts.project_dir)?;
-    record_applied_template(&user_manifest_path, &crate_name, &resolved_tmpl.name)?;
+    record_applied_template(
+        &user_manifest_path,
+        &crate_name,
+        AppliedTemplate {
+            name: resolved_tmpl.name,
+            version: bp_version,
+            placeholders,
+        },
+    )?;
+
+    crate::template_engine::run_hooks(&hooks, opts.project_dir, opts.hooks)?;
 
     // Print post-merge hints if the template defines any.
     if !hints.is_empty() {
@@ -831,10 +1174,409 @@ fn infer_project_name(project_dir: &Path) -> Result<String> {
         .to_string())
 }
 
+/// Read a battery pack's version from its `Cargo.toml`, if it parses.
+fn read_pack_version(manifest_path: &Path) -> Option<String> {
+    parse_battery_pack_from_path(manifest_path)
+        .ok()
+        .map(|spec| spec.version)
+}
+
+// ============================================================================
+// Template upgrade: cargo bp upgrade-template <pack>
+// ============================================================================
+
+/// Options for `cargo bp upgrade-template <pack>`.
+struct UpgradeTemplateOpts<'a> {
+    battery_pack: &'a str,
+    template: Option<&'a str>,
+    path_override: Option<&'a str>,
+    source: &'a CrateSource,
+    project_dir: &'a Path,
+    defines: BTreeMap<String, String>,
+    overwrite: bool,
+    interactive: bool,
+}
+
+/// Upgrade templates previously applied with `cargo bp add -t` or `cargo bp new`.
+///
+/// The template is rendered twice: at the pack version recorded in
+/// `battery-pack.toml` (the merge base) and at the new version, both with the
+/// recorded placeholder values. The difference between the two is then
+/// three-way merged into the project. When the base can't be reproduced
+/// (nothing recorded, or that version is unavailable) this falls back to the
+/// additive merge used by `cargo bp add -t`.
+// [impl cli.upgrade-template.merge]
+// [impl cli.upgrade-template.select]
+// [impl cli.upgrade-template.fallback]
+// [impl cli.upgrade-template.record]
+fn upgrade_template(opts: UpgradeTemplateOpts<'_>) -> Result<()> {
+    let crate_name = resolve_crate_name(opts.battery_pack);
+    let user_manifest_path = find_user_manifest(opts.project_dir)?;
+
+    let applied = read_applied_template_records(&user_manifest_path, &crate_name);
+    if applied.is_empty() {
+        bail!("No templates from '{crate_name}' have been applied to this project");
+    }
+    let selected: Vec<AppliedTemplate> = match opts.template {
+        Some(name) => {
+            let Some(found) = applied.iter().find(|a| a.name == name) else {
+                let names: Vec<_> = applied.iter().map(|a| a.name.as_str()).collect();
+                bail!(
+                    "Template '{name}' from '{crate_name}' has not been applied. Applied templates: {}",
+                    names.join(", ")
+                );
+            };
+            vec![found.clone()]
+        }
+        None => applied,
+    };
+
+    check_git_clean(opts.project_dir, opts.interactive, opts.overwrite)?;
+
+    let resolved =
+        crate::registry::resolve_crate_dir(opts.battery_pack, opts.path_override, opts.source)?;
+    let new_manifest = resolved.dir.join("Cargo.toml");
+    let templates = parse_template_metadata(&new_manifest, &crate_name)?;
+    let new_version = read_pack_version(&new_manifest);
+    let project_name = infer_project_name(opts.project_dir)?;
+    let interactive_override = if opts.interactive { None } else { Some(false) };
+    let mut apply_opts = crate::merge::ApplyOpts {
+        project_dir: opts.project_dir.to_path_buf(),
+        overwrite: opts.overwrite,
+        interactive: opts.interactive,
+        merge_overrides: Vec::new(),
+    };
+    let label = match &new_version {
+        Some(v) => format!("{crate_name} {v}"),
+        None => crate_name.clone(),
+    };
+
+    let selected_items = installed_pack_items(&user_manifest_path, &crate_name);
+    let mut any_conflicts = false;
+    for record in selected {
+        let Some(config) = templates.get(&record.name) else {
+            eprintln!(
+                "warning: {label} no longer ships template '{}'; skipping",
+                record.name
+            );
+            continue;
+        };
+        if record.version.is_some() && record.version == new_version {
+            println!("Template '{}' is already at {label}.", record.name);
+            continue;
+        }
+
+        eprintln!("Upgrading template '{}' to {label}...", record.name);
+
+        // Recorded values, with any -d overrides on top.
+        let mut defines = record.placeholders.clone();
+        defines.extend(opts.defines.clone());
+        let render_opts = crate::template_engine::RenderOpts {
+            crate_root: resolved.dir.clone(),
+            template_path: config.path.clone(),
+            project_name: project_name.clone(),
+            defines,
+            active_features: selected_items.clone(),
+            interactive_override,
+            source: opts.source.clone(),
+        };
+        let (files, placeholders) = crate::template_engine::preview_with_values(&render_opts)?;
+        apply_opts.merge_overrides = crate::template_engine::merge_overrides(&render_opts)?;
+
+        let base = match render_applied_base(
+            &record,
+            &crate_name,
+            &project_name,
+            &selected_items,
+            opts.source,
+        ) {
+            Ok(base) => Some(base),
+            Err(e) => {
+                eprintln!(
+                    "warning: can't reproduce the originally applied '{}' ({e:#}); \
+                     falling back to an additive merge",
+                    record.name
+                );
+                None
+            }
+        };
+        let results = match &base {
+            Some(base) => crate::merge::apply_template_upgrade(base, &files, &apply_opts, &label)?,
+            None => crate::merge::apply_rendered_files(&files, &apply_opts)?,
+        };
+        crate::merge::print_summary(&results);
+        any_conflicts |= results
+            .iter()
+            .any(|r| matches!(r, crate::merge::FileResult::Conflicted(_)));
+
+        // A file that didn't take the new version still has the old one as
+        // its merge base, so keep the old record until every file has.
+        // Files the user deleted are skipped on purpose and don't count.
+        if results.iter().any(|r| match r {
+            crate::merge::FileResult::Conflicted(_) => true,
+            crate::merge::FileResult::Skipped(path) => opts.project_dir.join(path).exists(),
+            _ => false,
+        }) {
+            eprintln!(
+                "note: '{}' is still recorded at its previous version because some files \
+                 were not upgraded; run `cargo bp upgrade-template` again once they are resolved",
+                record.name
+            );
+            continue;
+        }
+
+        record_applied_template(
+            &user_manifest_path,
+            &crate_name,
+            AppliedTemplate {
+                name: record.name,
+                version: new_version.clone(),
+                placeholders,
+            },
+        )?;
+    }
+
+    if any_conflicts {
+        eprintln!();
+        eprintln!(
+            "Some files have conflicting changes. Resolve the <<<<<<< / >>>>>>> markers before committing."
+        );
+    }
+
+    Ok(())
+}
+
+/// The features and crates selected from an installed pack, as `cargo bp
+/// add` passes them when it applies a template.
+fn installed_pack_items(user_manifest_path: &Path, crate_name: &str) -> BTreeSet<String> {
+    let mut items = match read_active_features_from_state(user_manifest_path, crate_name) {
+        Some(ActiveFeatures::All) => BTreeSet::from(["all".to_string()]),
+        Some(ActiveFeatures::Subset(features)) => features,
+        None => BTreeSet::new(),
+    };
+    items.extend(read_managed_deps_from_state(user_manifest_path, crate_name).unwrap_or_default());
+    items
+}
+
+/// Re-render a template exactly as it was when it was applied: same pack
+/// version, same placeholder values, same selected features.
+fn render_applied_base(
+    record: &AppliedTemplate,
+    crate_name: &str,
+    project_name: &str,
+    selected_items: &BTreeSet<String>,
+    source: &CrateSource,
+) -> Result<Vec<crate::template_engine::RenderedFile>> {
+    let Some(version) = &record.version else {
+        bail!("no version was recorded when it was applied");
+    };
+    let resolved = crate::registry::resolve_crate_dir_at_version(crate_name, version, source)?;
+    let templates = parse_template_metadata(&resolved.dir.join("Cargo.toml"), crate_name)?;
+    let config = templates.get(&record.name).ok_or_else(|| {
+        anyhow::anyhow!("{crate_name} {version} has no template '{}'", record.name)
+    })?;
+    crate::template_engine::preview(crate::template_engine::RenderOpts {
+        crate_root: resolved.dir.clone(),
+        template_path: config.path.clone(),
+        project_name: project_name.to_string(),
+        defines: record.placeholders.clone(),
+        active_features: selected_items.clone(),
+        interactive_override: Some(false),
+        source: source.clone(),
+    })
+}
+
 // ============================================================================
 // Dependency add: cargo bp add <pack>
 // ============================================================================
 
+/// Stage the crates picked from a pack: the pack's build-dependency, the
+/// crates in the workspace and crate manifests, and its `battery-pack.toml`
+/// entry. Crates the pack previously managed that are no longer picked are
+/// removed.
+///
+/// `dep_kinds` are the dependency kinds chosen for crates in this run; they
+/// are merged into the pack's stored overrides, and crates whose kind
+/// changed are moved to their new section.
+#[allow(clippy::too_many_arguments)]
+fn stage_pack_crates(
+    changes: &mut Changeset,
+    out: &mut impl Write,
+    user_manifest_path: &Path,
+    bp_spec: &bphelper_manifest::BatteryPackSpec,
+    bp_version: Option<&str>,
+    path: Option<&str>,
+    source: &CrateSource,
+    active_features: &BTreeSet<String>,
+    crates_to_sync: &BTreeMap<String, bphelper_manifest::CrateSpec>,
+    dep_kinds: &BTreeMap<String, bphelper_manifest::DepKind>,
+) -> Result<()> {
+    let crate_name = bp_spec.name.as_str();
+    let user_manifest_content = changes
+        .read_to_string(user_manifest_path)
+        .context("Failed to read Cargo.toml")?;
+    // [impl manifest.toml.preserve]
+    let mut user_doc: toml_edit::DocumentMut = user_manifest_content
+        .parse()
+        .context("Failed to parse Cargo.toml")?;
+
+    // [impl cli.add.dep-kind-override]
+    // A kind matching the pack's own drops the override.
+    let previous_overrides = read_dep_kind_overrides(changes, user_manifest_path, crate_name)?;
+    let mut overrides = previous_overrides.clone();
+    for (dep_name, kind) in dep_kinds {
+        match bp_spec.crates.get(dep_name) {
+            Some(spec) if spec.dep_kind == *kind => overrides.remove(dep_name),
+            _ => overrides.insert(dep_name.clone(), *kind),
+        };
+    }
+    let previous_kinds: BTreeMap<String, bphelper_manifest::DepKind> = crates_to_sync
+        .iter()
+        .map(|(name, spec)| {
+            let kind = previous_overrides.get(name).unwrap_or(&spec.dep_kind);
+            (name.clone(), *kind)
+        })
+        .collect();
+    let mut crates_to_sync = crates_to_sync.clone();
+    apply_dep_kind_overrides(&mut crates_to_sync, &overrides);
+    let crates_to_sync = &crates_to_sync;
+    move_deps_to_kind(&mut user_doc, crates_to_sync, &previous_kinds);
+
+    // [impl manifest.register.workspace-default]
+    let workspace_manifest = find_workspace_manifest(user_manifest_path)?;
+
+    // [impl manifest.deps.workspace]
+    // Add crate dependencies + workspace deps (including the battery pack itself).
+    // Load workspace doc once; both deps and metadata are written to it before a
+    // single flush at the end (avoids a double read-modify-write).
+    let mut ws_doc: Option<toml_edit::DocumentMut> = if let Some(ref ws_path) = workspace_manifest {
+        let ws_content = changes
+            .read_to_string(ws_path)
+            .context("Failed to read workspace Cargo.toml")?;
+        Some(
+            ws_content
+                .parse()
+                .context("Failed to parse workspace Cargo.toml")?,
+        )
+    } else {
+        None
+    };
+
+    if let Some(ref mut doc) = ws_doc {
+        let ws_deps = doc["workspace"]["dependencies"]
+            .or_insert(toml_edit::Item::Table(toml_edit::Table::new()));
+        if let Some(ws_table) = ws_deps.as_table_mut() {
+            // Add the battery pack itself to workspace deps
+            if let Some(local_path) = path {
+                let mut dep = toml_edit::InlineTable::new();
+                dep.insert("path", toml_edit::Value::from(local_path));
+                ws_table.insert(
+                    crate_name,
+                    toml_edit::Item::Value(toml_edit::Value::InlineTable(dep)),
+                );
+            } else {
+                let version = bp_version
+                    .as_ref()
+                    .context("battery pack version not available (--path without workspace)")?;
+                ws_table.insert(crate_name, battery_pack_dep_item(version, source));
+            }
+            // Add the resolved crate dependencies
+            for (dep_name, dep_spec) in crates_to_sync {
+                add_dep_to_table(ws_table, dep_name, dep_spec);
+            }
+        }
+
+        // [impl cli.add.dep-kind]
+        write_workspace_refs_by_kind(&mut user_doc, crates_to_sync, false);
+    } else {
+        // [impl manifest.deps.no-workspace]
+        // [impl cli.add.dep-kind]
+        write_deps_by_kind(&mut user_doc, crates_to_sync, false);
+    }
+
+    // Edit semantics: remove deselected crates from previous installation
+    let prev_managed =
+        read_managed_deps_for_project(user_manifest_path, &user_manifest_content, crate_name);
+    let new_crate_names: BTreeSet<String> = crates_to_sync.keys().cloned().collect();
+    let mut removed_count = 0;
+
+    if let Some(prev) = &prev_managed {
+        // Find crates that were previously managed but are no longer selected.
+        // Crates from an extended pack aren't in this pack's spec, so they
+        // are removed from whichever section they are in.
+        let deselected: Vec<&String> = prev
+            .iter()
+            .filter(|name| !new_crate_names.contains(name.as_str()))
+            .collect();
+        let to_remove: BTreeMap<String, bphelper_manifest::CrateSpec> = deselected
+            .iter()
+            .filter_map(|name| {
+                let mut spec = bp_spec.crates.get(*name)?.clone();
+                if let Some(kind) = previous_overrides.get(*name) {
+                    spec.dep_kind = *kind;
+                }
+                Some(((*name).clone(), spec))
+            })
+            .collect();
+
+        if !deselected.is_empty() {
+            if let Some(ref mut doc) = ws_doc {
+                // Remove from workspace deps
+                let ws_deps = doc["workspace"]["dependencies"].as_table_mut();
+                if let Some(ws_table) = ws_deps {
+                    for name in &deselected {
+                        ws_table.remove(name);
+                    }
+                }
+            }
+            removed_count = remove_deps_by_kind(&mut user_doc, &to_remove);
+            for name in deselected {
+                if !to_remove.contains_key(name) && remove_dep_from_any_table(&mut user_doc, name) {
+                    removed_count += 1;
+                }
+            }
+        }
+    }
+
+    // Stage workspace Cargo.toml once (deps combined)
+    if let (Some(ws_path), Some(doc)) = (&workspace_manifest, &ws_doc) {
+        // [impl manifest.toml.preserve]
+        changes.write(ws_path, doc.to_string())?;
+    }
+
+    // Stage the final Cargo.toml
+    // [impl manifest.toml.preserve]
+    changes.write(user_manifest_path, user_doc.to_string())?;
+
+    write_battery_pack_state(
+        changes,
+        user_manifest_path,
+        crate_name,
+        &active_features.into(),
+        crates_to_sync,
+    )?;
+    write_dep_kind_overrides(changes, user_manifest_path, crate_name, &overrides)?;
+
+    writeln!(
+        out,
+        "Added {} with {} crate(s)",
+        crate_name,
+        crates_to_sync.len()
+    )?;
+    for (dep_name, dep_spec) in crates_to_sync {
+        match bp_spec.crates.get(dep_name) {
+            Some(spec) if spec.dep_kind != dep_spec.dep_kind => {
+                writeln!(out, "  + {} (as {})", dep_name, dep_spec.dep_kind)?
+            }
+            _ => writeln!(out, "  + {}", dep_name)?,
+        }
+    }
+    if removed_count > 0 {
+        writeln!(out, "Removed {} deselected crate(s)", removed_count)?;
+    }
+    Ok(())
+}
+
 // [impl cli.add.specific-crates]
 // [impl cli.add.unknown-crate]
 // [impl manifest.register.location]
@@ -849,17 +1591,35 @@ pub(crate) fn add_battery_pack(
     no_default_features: bool,
     all_features: bool,
     specific_crates: &[String],
+    dep_kinds: &BTreeMap<String, bphelper_manifest::DepKind>,
     path: Option<&str>,
     source: &CrateSource,
     project_dir: &Path,
+    dry_run: DryRun,
 ) -> Result<()> {
+    let (name, pin) = parse_pack_arg(name)?;
     let crate_name = resolve_crate_name(name);
+    let user_manifest_path = find_user_manifest(project_dir)?;
+
+    // A requirement on the command line replaces any existing pin; otherwise
+    // re-adding a pinned pack stays within its pin.
+    // [impl cli.add.pin]
+    let version_req = match pin {
+        Some(req) => Some(semver::VersionReq::parse(req)?),
+        None => read_version_req_from_state(&user_manifest_path, &crate_name)?,
+    };
 
     // Step 1: Read the battery pack spec WITHOUT modifying any manifests.
     // --path takes precedence over --crate-source.
     // [impl cli.path.flag]
     // [impl cli.path.no-resolve]
     // [impl cli.source.replace]
+    let mut resolver = PackResolver::for_manifest(
+        source,
+        &Changeset::new(),
+        &user_manifest_path,
+        LockMode::Reuse,
+    )?;
     let (bp_version, bp_spec) = if let Some(local_path) = path {
         let manifest_path = Path::new(local_path).join("Cargo.toml");
         let spec = parse_battery_pack_from_path(&manifest_path)
@@ -867,7 +1627,8 @@ pub(crate) fn add_battery_pack(
 
         (None, spec)
     } else {
-        fetch_bp_spec(source, name)?
+        // [impl manifest.lock.reuse]
+        resolver.fetch_spec(name, version_req.as_ref())?
     };
 
     // Reject conflicting exclusive picks on the command line before any work.
@@ -883,6 +1644,11 @@ pub(crate) fn add_battery_pack(
         validate_exclusive_constraints(&bp_spec, &requested, &[])?;
     }
 
+    // [impl cli.add.dep-kind-override]
+    if let Some(unknown) = dep_kinds.keys().find(|c| !bp_spec.crates.contains_key(*c)) {
+        bail!("'{unknown}' is not a crate in {crate_name}");
+    }
+
     // Step 2: Determine which crates to install — interactive picker, explicit flags, or defaults.
     // No manifest changes have been made yet, so cancellation is free.
     //
@@ -890,7 +1656,6 @@ pub(crate) fn add_battery_pack(
     // additive rather than replacing the existing feature set.
     // Skip merging when the user explicitly narrows (--no-default-features,
     // --all-features, or specific crates) since those signal a fresh selection.
-    let user_manifest_path = find_user_manifest(project_dir)?;
     // If the user isn't resetting with --no-default-features or --all-features or specific
     // crates, merge their -F flags with the previously stored feature set.
     let (merged_features, all_features) = if !no_default_features
@@ -936,24 +1701,32 @@ pub(crate) fn add_battery_pack(
         all_features,
         specific_crates,
     );
-    let (active_features, crates_to_sync, selected_templates) = match resolved {
+    let (active_features, crates_to_sync, selected_templates, dep_kinds) = match resolved {
         ResolvedAdd::Crates {
             active_features,
             crates,
-        } => (active_features, crates, Vec::new()),
+        } => {
+            check_dep_kinds_selected(dep_kinds, &crates)?;
+            (active_features, crates, Vec::new(), dep_kinds.clone())
+        }
         ResolvedAdd::Interactive if std::io::stdout().is_terminal() => {
-            // Pre-select crates already in the project (edit mode)
+            // Pre-select crates already in the project (edit mode), showing
+            // the kinds chosen before and on the command line.
             let pre_selected = compute_pre_selection(&bp_spec, project_dir);
+            let mut kinds =
+                read_dep_kind_overrides(&Changeset::new(), &user_manifest_path, &crate_name)?;
+            kinds.extend(dep_kinds.clone());
             let preview_ctx = Some(PickerPreviewContext {
                 battery_pack: crate_name.clone(),
                 path: path.map(|s| s.to_string()),
                 source: source.clone(),
             });
-            match pick_crates_interactive(&bp_spec, &pre_selected, preview_ctx)? {
+            match pick_crates_interactive(&bp_spec, &pre_selected, &kinds, preview_ctx)? {
                 Some(result) => (
                     result.active_features,
                     result.crates,
                     result.selected_templates,
+                    result.dep_kinds,
                 ),
                 None => {
                     return Ok(());
@@ -963,139 +1736,63 @@ pub(crate) fn add_battery_pack(
         ResolvedAdd::Interactive => {
             // Non-interactive fallback: use defaults
             let crates = bp_spec.resolve_crates(&["default"]);
-            (BTreeSet::from(["default".to_string()]), crates, Vec::new())
+            check_dep_kinds_selected(dep_kinds, &crates)?;
+            (
+                BTreeSet::from(["default".to_string()]),
+                crates,
+                Vec::new(),
+                dep_kinds.clone(),
+            )
         }
     };
 
+    // [impl cli.add.extends]
+    let crates_to_sync = resolver.expand_extended_packs(&bp_spec.name, crates_to_sync)?;
+
+    let mut out = dry_run.progress();
+    let mut changes = Changeset::new();
+    let plan_root = plan_root(&user_manifest_path)?;
     if crates_to_sync.is_empty() && selected_templates.is_empty() {
-        println!("No crates or templates selected.");
-        return Ok(());
+        writeln!(out, "No crates or templates selected.")?;
+        return dry_run.finish(changes, "add", &plan_root);
     }
 
-    // Step 3: Now write everything — build-dep, workspace deps, crate deps, metadata.
+    // Step 3: Now stage everything — build-dep, workspace deps, crate deps, metadata.
     if !crates_to_sync.is_empty() {
-        let user_manifest_content =
-            std::fs::read_to_string(&user_manifest_path).context("Failed to read Cargo.toml")?;
-        // [impl manifest.toml.preserve]
-        let mut user_doc: toml_edit::DocumentMut = user_manifest_content
-            .parse()
-            .context("Failed to parse Cargo.toml")?;
-
-        // [impl manifest.register.workspace-default]
-        let workspace_manifest = find_workspace_manifest(&user_manifest_path)?;
-
-        // [impl manifest.deps.workspace]
-        // Add crate dependencies + workspace deps (including the battery pack itself).
-        // Load workspace doc once; both deps and metadata are written to it before a
-        // single flush at the end (avoids a double read-modify-write).
-        let mut ws_doc: Option<toml_edit::DocumentMut> =
-            if let Some(ref ws_path) = workspace_manifest {
-                let ws_content = std::fs::read_to_string(ws_path)
-                    .context("Failed to read workspace Cargo.toml")?;
-                Some(
-                    ws_content
-                        .parse()
-                        .context("Failed to parse workspace Cargo.toml")?,
-                )
-            } else {
-                None
-            };
-
-        if let Some(ref mut doc) = ws_doc {
-            let ws_deps = doc["workspace"]["dependencies"]
-                .or_insert(toml_edit::Item::Table(toml_edit::Table::new()));
-            if let Some(ws_table) = ws_deps.as_table_mut() {
-                // Add the battery pack itself to workspace deps
-                if let Some(local_path) = path {
-                    let mut dep = toml_edit::InlineTable::new();
-                    dep.insert("path", toml_edit::Value::from(local_path));
-                    ws_table.insert(
-                        &crate_name,
-                        toml_edit::Item::Value(toml_edit::Value::InlineTable(dep)),
-                    );
-                } else {
-                    let version = bp_version
-                        .as_ref()
-                        .context("battery pack version not available (--path without workspace)")?;
-                    ws_table.insert(&crate_name, toml_edit::value(version));
-                }
-                // Add the resolved crate dependencies
-                for (dep_name, dep_spec) in &crates_to_sync {
-                    add_dep_to_table(ws_table, dep_name, dep_spec);
-                }
-            }
-
-            // [impl cli.add.dep-kind]
-            write_workspace_refs_by_kind(&mut user_doc, &crates_to_sync, false);
-        } else {
-            // [impl manifest.deps.no-workspace]
-            // [impl cli.add.dep-kind]
-            write_deps_by_kind(&mut user_doc, &crates_to_sync, false);
-        }
-
-        // Edit semantics: remove deselected crates from previous installation
-        let prev_managed =
-            read_managed_deps_for_project(&user_manifest_path, &user_manifest_content, &crate_name);
-        let new_crate_names: BTreeSet<String> = crates_to_sync.keys().cloned().collect();
-        let mut removed_count = 0;
-
-        if let Some(prev) = &prev_managed {
-            // Find crates that were previously managed but are no longer selected
-            let to_remove: BTreeMap<String, bphelper_manifest::CrateSpec> = prev
-                .iter()
-                .filter(|name| !new_crate_names.contains(name.as_str()))
-                .filter_map(|name| {
-                    bp_spec
-                        .crates
-                        .get(name)
-                        .map(|spec| (name.clone(), spec.clone()))
-                })
-                .collect();
-
-            if !to_remove.is_empty() {
-                if let Some(ref mut doc) = ws_doc {
-                    // Remove from workspace deps
-                    let ws_deps = doc["workspace"]["dependencies"].as_table_mut();
-                    if let Some(ws_table) = ws_deps {
-                        for name in to_remove.keys() {
-                            ws_table.remove(name);
-                        }
-                    }
-                }
-                removed_count = remove_deps_by_kind(&mut user_doc, &to_remove);
-            }
-        }
-
-        // Write workspace Cargo.toml once (deps combined)
-        if let (Some(ws_path), Some(doc)) = (&workspace_manifest, &ws_doc) {
-            // [impl manifest.toml.preserve]
-            std::fs::write(ws_path, doc.to_string())
-                .context("Failed to write workspace Cargo.toml")?;
-        }
-
-        // Write the final Cargo.toml
-        // [impl manifest.toml.preserve]
-        std::fs::write(&user_manifest_path, user_doc.to_string())
-            .context("Failed to write Cargo.toml")?;
-
-        write_battery_pack_state(
+        stage_pack_crates(
+            &mut changes,
+            &mut out,
             &user_manifest_path,
-            &crate_name,
-            &(&active_features).into(),
+            &bp_spec,
+            bp_version.as_deref(),
+            path,
+            source,
+            &active_features,
             &crates_to_sync,
+            &dep_kinds,
         )?;
+    }
 
-        println!(
-            "Added {} with {} crate(s)",
-            crate_name,
-            crates_to_sync.len()
-        );
-        for dep_name in crates_to_sync.keys() {
-            println!("  + {}", dep_name);
+    // [impl manifest.lock.record]
+    resolver.finish(&mut changes, &user_manifest_path)?;
+
+    // [impl cli.add.pin]
+    if let Some(req) = pin {
+        write_version_req_to_state(&mut changes, &user_manifest_path, &crate_name, req)?;
+        match &bp_version {
+            Some(version) => writeln!(out, "Pinned {crate_name} to {req} (resolved {version})")?,
+            None => writeln!(out, "Pinned {crate_name} to {req}")?,
         }
-        if removed_count > 0 {
-            println!("Removed {} deselected crate(s)", removed_count);
+    }
+
+    dry_run.finish(changes, "add", &plan_root)?;
+    if dry_run.is_on() {
+        // Templates render and merge files interactively; they're previewed
+        // with `cargo bp show -t` rather than planned here.
+        for tmpl_name in &selected_templates {
+            eprintln!("note: --dry-run skipped applying template '{tmpl_name}'");
         }
+        return Ok(());
     }
 
     // Step 4: Apply any selected templates, pre-filling category-linked
@@ -1115,12 +1812,42 @@ pub(crate) fn add_battery_pack(
             active_features: selected_items.clone(),
             overwrite: false,
             interactive: std::io::stdout().is_terminal(),
+            hooks: HookPolicy::from_flags(false, std::io::stdout().is_terminal()),
         })?;
     }
 
     Ok(())
 }
 
+/// Split a `name@requirement` argument into the pack name and its semver
+/// requirement, checking that the requirement parses.
+// [impl cli.add.pin]
+fn parse_pack_arg(arg: &str) -> Result<(&str, Option<&str>)> {
+    let Some((name, req)) = arg.split_once('@') else {
+        return Ok((arg, None));
+    };
+    let req = req.trim();
+    semver::VersionReq::parse(req)
+        .with_context(|| format!("invalid version requirement '{req}' for '{name}'"))?;
+    Ok((name, Some(req)))
+}
+
+/// Dependency entry for a battery pack fetched from `source`: a bare version
+/// for crates.io, or `{ version, registry }` for an alternative registry.
+// [impl cli.registry.dependency]
+fn battery_pack_dep_item(version: &str, source: &CrateSource) -> toml_edit::Item {
+    if let CrateSource::Registry(registry) = source
+        && let Some(name) = registry.name()
+    {
+        let mut dep = toml_edit::InlineTable::new();
+        dep.insert("version", toml_edit::Value::from(version));
+        dep.insert("registry", toml_edit::Value::from(name));
+        toml_edit::Item::Value(toml_edit::Value::InlineTable(dep))
+    } else {
+        toml_edit::value(version)
+    }
+}
+
 /// Show a helpful message when `cargo bp add` is run without arguments.
 /// Determine which managed deps are safe to remove (not shared with other packs).
 pub(crate) fn deps_safe_to_remove(
@@ -1150,6 +1877,7 @@ fn remove_battery_pack(
     keep_deps: bool,
     interactive: bool,
     project_dir: &Path,
+    dry_run: DryRun,
 ) -> Result<()> {
     let crate_name = resolve_crate_name(name);
     let user_manifest_path = find_user_manifest(project_dir)?;
@@ -1205,6 +1933,8 @@ fn remove_battery_pack(
         .context("Failed to parse Cargo.toml")?;
 
     let workspace_manifest = find_workspace_manifest(&user_manifest_path)?;
+    let mut out = dry_run.progress();
+    let mut changes = Changeset::new();
 
     // Remove battery pack from [build-dependencies]
     if let Some(table) = user_doc
@@ -1224,19 +1954,37 @@ fn remove_battery_pack(
             &user_manifest_content,
         );
 
-        // Remove from user doc (all dep sections)
-        for section in ["dependencies", "dev-dependencies"] {
-            if let Some(table) = user_doc.get_mut(section).and_then(|t| t.as_table_mut()) {
-                for dep in &safe {
-                    table.remove(dep.as_str());
+        // Remove from user doc (all dep sections, including platform-gated ones)
+        let mut tables: Vec<&mut toml_edit::Table> = Vec::new();
+        let root = user_doc.as_table_mut();
+        for (key, item) in root.iter_mut() {
+            match key.get() {
+                "dependencies" | "dev-dependencies" => tables.extend(item.as_table_mut()),
+                "target" => {
+                    for (_, cfg) in item.as_table_mut().into_iter().flat_map(|t| t.iter_mut()) {
+                        for (section, deps) in
+                            cfg.as_table_mut().into_iter().flat_map(|t| t.iter_mut())
+                        {
+                            if matches!(section.get(), "dependencies" | "dev-dependencies") {
+                                tables.extend(deps.as_table_mut());
+                            }
+                        }
+                    }
                 }
+                _ => {}
+            }
+        }
+        for table in tables {
+            for dep in &safe {
+                table.remove(dep.as_str());
             }
         }
 
         // Remove from workspace deps
         if let Some(ref ws_path) = workspace_manifest {
-            let ws_content =
-                std::fs::read_to_string(ws_path).context("Failed to read workspace Cargo.toml")?;
+            let ws_content = changes
+                .read_to_string(ws_path)
+                .context("Failed to read workspace Cargo.toml")?;
             let mut ws_doc: toml_edit::DocumentMut = ws_content
                 .parse()
                 .context("Failed to parse workspace Cargo.toml")?;
@@ -1253,41 +2001,45 @@ fn remove_battery_pack(
                 ws_table.remove(&crate_name);
             }
 
-            std::fs::write(ws_path, ws_doc.to_string())
-                .context("Failed to write workspace Cargo.toml")?;
+            changes.write(ws_path, ws_doc.to_string())?;
         }
 
         if !safe.is_empty() {
-            println!("Removed {} dependency(ies)", safe.len());
+            writeln!(out, "Removed {} dependency(ies)", safe.len())?;
         }
     }
 
-    std::fs::write(&user_manifest_path, user_doc.to_string())
-        .context("Failed to write Cargo.toml")?;
+    changes.write(&user_manifest_path, user_doc.to_string())?;
 
-    if let Err(e) = remove_battery_pack_state_entry(&user_manifest_path, &crate_name) {
+    if let Err(e) = remove_battery_pack_state_entry(&mut changes, &user_manifest_path, &crate_name)
+    {
         eprintln!("warning: failed to update battery-pack.toml: {e}");
     }
+    if let Err(e) = remove_locked_pack(&mut changes, &user_manifest_path, &crate_name) {
+        eprintln!("warning: failed to update battery-pack.lock: {e}");
+    }
 
     // Clean up build.rs
     let build_rs_path = user_manifest_path
         .parent()
         .unwrap_or(Path::new("."))
         .join("build.rs");
-    cleanup_build_rs(&build_rs_path, &crate_name)?;
+    cleanup_build_rs(&mut changes, &build_rs_path, &crate_name)?;
 
-    println!("Removed {}", crate_name);
+    dry_run.finish(changes, "rm", &plan_root(&user_manifest_path)?)?;
+    writeln!(out, "Removed {}", crate_name)?;
     Ok(())
 }
 
 /// Remove a validate() call from build.rs. If the file becomes an empty main,
-/// delete it entirely.
-fn cleanup_build_rs(build_rs_path: &Path, crate_name: &str) -> Result<()> {
-    if !build_rs_path.exists() {
+/// delete it entirely. The edit is staged in `changes`.
+fn cleanup_build_rs(changes: &mut Changeset, build_rs_path: &Path, crate_name: &str) -> Result<()> {
+    let Some(content) = changes
+        .read(build_rs_path)
+        .context("Failed to read build.rs")?
+    else {
         return Ok(());
-    }
-
-    let content = std::fs::read_to_string(build_rs_path).context("Failed to read build.rs")?;
+    };
     let crate_ident = crate_name.replace('-', "_");
     let validate_call = format!("{}::validate();", crate_ident);
 
@@ -1305,14 +2057,42 @@ fn cleanup_build_rs(build_rs_path: &Path, crate_name: &str) -> Result<()> {
     // Check if the remaining content is just an empty main
     let trimmed = new_content.replace(char::is_whitespace, "");
     if trimmed == "fnmain(){}" {
-        std::fs::remove_file(build_rs_path).context("Failed to delete build.rs")?;
+        changes.remove(build_rs_path)?;
     } else {
-        std::fs::write(build_rs_path, new_content).context("Failed to write build.rs")?;
+        changes.write(build_rs_path, new_content)?;
     }
 
     Ok(())
 }
 
+/// The directory whose `target/bp-undo/` holds snapshots for commands run in
+/// `project_dir`: the workspace root, or the crate directory outside a
+/// workspace.
+fn undo_root(project_dir: &Path) -> PathBuf {
+    find_user_manifest(project_dir)
+        .and_then(|manifest| plan_root(&manifest))
+        .unwrap_or_else(|_| project_dir.to_path_buf())
+}
+
+/// Put back the files saved by the last file-changing command.
+// [impl cli.undo.restore]
+fn undo_last_command(project_dir: &Path) -> Result<()> {
+    let undone = crate::undo::undo_latest(&undo_root(project_dir))?;
+    for path in &undone.removed {
+        println!("  removed {path}");
+    }
+    for path in &undone.restored {
+        println!("  restored {path}");
+    }
+    println!(
+        "Undid `{}`: restored {} file(s), removed {} created file(s).",
+        undone.command,
+        undone.restored.len(),
+        undone.removed.len()
+    );
+    Ok(())
+}
+
 fn show_add_help(project_dir: &Path) -> Result<()> {
     let manifest_path = find_user_manifest(project_dir);
     let installed = manifest_path.ok().and_then(|p| {
@@ -1342,21 +2122,267 @@ fn show_add_help(project_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Raise the version pins of battery packs and sync the project.
+// [impl cli.update.raise]
+// [impl cli.update.explicit]
+// [impl cli.update.sync]
+pub(crate) fn update_battery_packs(
+    battery_pack: Option<&str>,
+    source: &CrateSource,
+    project_dir: &Path,
+) -> Result<()> {
+    let CrateSource::Registry(registry) = source else {
+        bail!(
+            "`cargo bp update` resolves versions from a registry and can't be used with --crate-source"
+        );
+    };
+    let user_manifest_path = find_user_manifest(project_dir)?;
+    let pins: BTreeMap<String, String> = read_pinned_packs_from_state(&user_manifest_path)?
+        .into_iter()
+        .map(|(name, req)| (resolve_crate_name(&name), req))
+        .collect();
+
+    // (crate name, explicit requirement)
+    let targets: Vec<(String, Option<&str>)> = match battery_pack {
+        Some(arg) => {
+            let (name, req) = parse_pack_arg(arg)?;
+            vec![(resolve_crate_name(name), req)]
+        }
+        None => pins.keys().map(|name| (name.clone(), None)).collect(),
+    };
+    if targets.is_empty() {
+        println!("No pinned battery packs to update.");
+        return Ok(());
+    }
+
+    let mut changes = Changeset::new();
+    let mut bumped = Vec::new();
+    for (crate_name, explicit) in targets {
+        let current = pins.get(&crate_name);
+        let new_req = match (explicit, current) {
+            (Some(req), _) => req.to_string(),
+            (None, Some(current)) => {
+                let latest = crate::registry::lookup_crate(registry, &crate_name, None)?;
+                let latest = semver::Version::parse(&latest.version).with_context(|| {
+                    format!("'{crate_name}' has an invalid version {}", latest.version)
+                })?;
+                raise_version_req(current, &latest)
+            }
+            (None, None) => {
+                println!("{crate_name} is not pinned; it already follows the latest version");
+                continue;
+            }
+        };
+        let resolved = crate::registry::lookup_crate(
+            registry,
+            &crate_name,
+            Some(&semver::VersionReq::parse(&new_req)?),
+        )?
+        .version;
+
+        write_version_req_to_state(&mut changes, &user_manifest_path, &crate_name, &new_req)?;
+        match current {
+            Some(old) if *old == new_req => {
+                println!("{crate_name} {new_req} is up to date ({resolved})");
+            }
+            Some(old) => println!("Updated {crate_name}: {old} → {new_req} ({resolved})"),
+            None => println!("Pinned {crate_name} to {new_req} ({resolved})"),
+        }
+        bumped.push((crate_name, resolved));
+    }
+
+    set_battery_pack_dep_versions(&mut changes, &user_manifest_path, &bumped)?;
+    // The new pins and the sync they lead to are committed together.
+    // [impl manifest.lock.update]
+    sync_battery_packs(
+        project_dir,
+        &MemberSelection::default(),
+        None,
+        source,
+        LockMode::Refresh,
+        DryRun::Off,
+        changes,
+    )
+}
+
+/// Raise a version requirement so it admits `latest`, keeping its operator
+/// and precision: `0.4` becomes `0.6` and `~1.2.3` becomes `~1.4.0`. A
+/// requirement that already admits `latest`, like `>=0.4`, is kept as is;
+/// any other one that can't be raised (compound, wildcard, `<`) is replaced
+/// by `latest` itself.
+fn raise_version_req(req: &str, latest: &semver::Version) -> String {
+    let req = req.trim();
+    let parsed = semver::VersionReq::parse(req).ok();
+    if parsed.as_ref().is_some_and(|r| r.matches(latest)) {
+        return req.to_string();
+    }
+    match parsed.as_ref().map(|r| r.comparators.as_slice()) {
+        Some([c])
+            if matches!(
+                c.op,
+                semver::Op::Caret | semver::Op::Tilde | semver::Op::Exact
+            ) && c.pre.is_empty() =>
+        {
+            let op = &req[..req.find(|ch: char| ch.is_ascii_digit()).unwrap_or(0)];
+            match (c.minor, c.patch) {
+                (None, _) => format!("{op}{}", latest.major),
+                (Some(_), None) => format!("{op}{}.{}", latest.major, latest.minor),
+                (Some(_), Some(_)) => {
+                    format!("{op}{}.{}.{}", latest.major, latest.minor, latest.patch)
+                }
+            }
+        }
+        _ => latest.to_string(),
+    }
+}
+
+/// Point each battery pack's own dependency entry at its newly resolved
+/// version, in `[build-dependencies]` and `[workspace.dependencies]`.
+// [impl cli.update.dependency]
+fn set_battery_pack_dep_versions(
+    changes: &mut Changeset,
+    user_manifest_path: &Path,
+    versions: &[(String, String)],
+) -> Result<()> {
+    if versions.is_empty() {
+        return Ok(());
+    }
+
+    let mut manifests = vec![user_manifest_path.to_path_buf()];
+    manifests.extend(find_workspace_manifest(user_manifest_path)?);
+    for manifest_path in manifests {
+        let content = changes.read_to_string(&manifest_path)?;
+        // [impl manifest.toml.preserve]
+        let mut doc: toml_edit::DocumentMut = content
+            .parse()
+            .with_context(|| format!("Failed to parse {}", manifest_path.display()))?;
+        let mut changed = false;
+        for section in [&["build-dependencies"][..], &["workspace", "dependencies"]] {
+            let mut item = Some(doc.as_item_mut());
+            for key in section {
+                item = item.and_then(|i| i.get_mut(key));
+            }
+            let Some(table) = item.and_then(|i| i.as_table_mut()) else {
+                continue;
+            };
+            for (crate_name, version) in versions {
+                changed |= set_dep_version(table, crate_name, version);
+            }
+        }
+        if changed {
+            changes.write(&manifest_path, doc.to_string())?;
+        }
+    }
+    Ok(())
+}
+
 // [impl cli.sync.update-versions]
 // [impl cli.sync.add-features]
 // [impl cli.sync.add-crates]
 // [impl cli.source.subcommands]
 
-fn sync_battery_packs(project_dir: &Path, path: Option<&str>, source: &CrateSource) -> Result<()> {
-    let user_manifest_path = find_user_manifest(project_dir)?;
-    let user_manifest_content =
-        std::fs::read_to_string(&user_manifest_path).context("Failed to read Cargo.toml")?;
+// [impl cli.workspace.sync]
+/// Sync the selected crates with their installed battery packs.
+///
+/// Every edit is staged in `changes`, which may already hold edits made by
+/// the caller, and committed in one go once every pack has resolved.
+// [impl manifest.write.atomic]
+fn sync_battery_packs(
+    project_dir: &Path,
+    members: &MemberSelection,
+    path: Option<&str>,
+    source: &CrateSource,
+    lock_mode: LockMode,
+    dry_run: DryRun,
+    mut changes: Changeset,
+) -> Result<()> {
+    let mut out = dry_run.progress();
+    let targets = match select_members(project_dir, members)? {
+        Some(selected) if selected.is_empty() => {
+            writeln!(out, "No battery packs installed in any workspace member.")?;
+            return dry_run.finish(changes, "sync", project_dir);
+        }
+        Some(selected) => selected
+            .into_iter()
+            .map(|m| (Some(m.name), m.manifest_path))
+            .collect(),
+        None => vec![(None, find_user_manifest(project_dir)?)],
+    };
 
-    let bp_names = find_installed_bp_names(&user_manifest_content)?;
+    // Every member shares the workspace root's [workspace.dependencies]. A
+    // root that is itself a package syncs its own dependencies directly, as
+    // `cargo bp sync` run in it does.
+    let workspace_manifest = if targets[0].0.is_some() {
+        Some(crate::manifest::find_workspace_root_manifest(project_dir)?)
+    } else {
+        find_workspace_manifest(&targets[0].1)?
+    };
+    let plan_root = plan_root(&targets[0].1)?;
+
+    let mut total_changes = 0;
+    let mut any_packs = false;
+    for (member, user_manifest_path) in &targets {
+        if let Some(member) = member {
+            writeln!(out, "{member}:")?;
+        }
+        let member_workspace = workspace_manifest
+            .as_deref()
+            .filter(|ws_path| *ws_path != user_manifest_path.as_path());
+        if let Some(count) = sync_member(
+            &mut changes,
+            user_manifest_path,
+            member_workspace,
+            path,
+            source,
+            lock_mode,
+            &mut out,
+        )? {
+            any_packs = true;
+            total_changes += count;
+        } else if member.is_some() {
+            writeln!(out, "  No battery packs installed.")?;
+        }
+    }
 
+    if !any_packs {
+        if targets.len() == 1 && targets[0].0.is_none() {
+            writeln!(out, "No battery packs installed.")?;
+        }
+        return dry_run.finish(changes, "sync", &plan_root);
+    }
+
+    dry_run.finish(changes, "sync", &plan_root)?;
+    if total_changes == 0 {
+        writeln!(out, "All dependencies are up to date.")?;
+    } else {
+        writeln!(out, "Synced {} change(s).", total_changes)?;
+    }
+
+    Ok(())
+}
+
+/// Sync one crate's dependencies with its installed battery packs.
+///
+/// Dependency updates for a workspace crate go into the
+/// `[workspace.dependencies]` of `workspace_manifest`. Every edit is staged
+/// in `changes`. Returns the number of changes made, or `None` if the crate
+/// has no battery packs installed.
+fn sync_member(
+    changes: &mut Changeset,
+    user_manifest_path: &Path,
+    workspace_manifest: Option<&Path>,
+    path: Option<&str>,
+    source: &CrateSource,
+    lock_mode: LockMode,
+    out: &mut dyn std::io::Write,
+) -> Result<Option<usize>> {
+    let user_manifest_content = changes
+        .read_to_string(user_manifest_path)
+        .context("Failed to read Cargo.toml")?;
+
+    let bp_names = find_installed_bp_names(&user_manifest_content)?;
     if bp_names.is_empty() {
-        println!("No battery packs installed.");
-        return Ok(());
+        return Ok(None);
     }
 
     // [impl manifest.toml.preserve]
@@ -1364,43 +2390,70 @@ fn sync_battery_packs(project_dir: &Path, path: Option<&str>, source: &CrateSour
         .parse()
         .context("Failed to parse Cargo.toml")?;
 
-    let workspace_manifest = find_workspace_manifest(&user_manifest_path)?;
     let mut total_changes = 0;
+    let mut resolver = PackResolver::for_manifest(source, changes, user_manifest_path, lock_mode)?;
 
     for bp_name in &bp_names {
         // Get the battery pack spec
-        let bp_spec = load_installed_bp_spec(bp_name, path, source)?;
+        let version_req = read_staged_version_req(changes, user_manifest_path, bp_name)?;
+        // [impl manifest.lock.reuse]
+        let bp_spec = resolver.installed_spec(bp_name, path, version_req.as_ref())?;
 
         let active_features =
-            read_active_features_for_project(&user_manifest_path, &user_manifest_content, bp_name);
+            read_active_features_for_project(user_manifest_path, &user_manifest_content, bp_name);
 
         // [impl format.hidden.effect]
-        let expected = bp_spec.resolve_for_features(&active_features);
+        // [impl cli.add.extends]
+        let mut expected = resolver.expand_extended_packs(
+            &bp_spec.name,
+            bp_spec.resolve_for_features(&active_features),
+        )?;
+
+        // Crates the user moved to another kind stay there; one still in
+        // the pack's section (e.g. after editing battery-pack.toml) is moved.
+        // [impl cli.sync.dep-kind]
+        let pack_kinds: BTreeMap<String, bphelper_manifest::DepKind> = expected
+            .iter()
+            .map(|(name, spec)| (name.clone(), spec.dep_kind))
+            .collect();
+        apply_dep_kind_overrides(
+            &mut expected,
+            &read_dep_kind_overrides(changes, user_manifest_path, bp_name)?,
+        );
+        for dep_name in move_deps_to_kind(&mut user_doc, &expected, &pack_kinds) {
+            total_changes += 1;
+            writeln!(
+                out,
+                "  ~ {} (moved to {})",
+                dep_name, expected[&dep_name].dep_kind
+            )?;
+        }
 
         // [impl manifest.deps.workspace]
         // Sync each crate
-        if let Some(ref ws_path) = workspace_manifest {
-            let ws_content =
-                std::fs::read_to_string(ws_path).context("Failed to read workspace Cargo.toml")?;
+        if let Some(ws_path) = workspace_manifest {
+            let ws_content = changes
+                .read_to_string(ws_path)
+                .context("Failed to read workspace Cargo.toml")?;
             // [impl manifest.toml.preserve]
             let mut ws_doc: toml_edit::DocumentMut = ws_content
                 .parse()
                 .context("Failed to parse workspace Cargo.toml")?;
-
+            let mut ws_changed = false;
             let ws_deps = ws_doc["workspace"]["dependencies"]
                 .or_insert(toml_edit::Item::Table(toml_edit::Table::new()));
             if let Some(ws_table) = ws_deps.as_table_mut() {
                 for (dep_name, dep_spec) in &expected {
                     if sync_dep_in_table(ws_table, dep_name, dep_spec) {
+                        ws_changed = true;
                         total_changes += 1;
-                        println!("  ~ {} (updated in workspace)", dep_name);
+                        writeln!(out, "  ~ {} (updated in workspace)", dep_name)?;
                     }
                 }
             }
-
-            // [impl manifest.toml.preserve]
-            std::fs::write(ws_path, ws_doc.to_string())
-                .context("Failed to write workspace Cargo.toml")?;
+            if ws_changed {
+                changes.write(ws_path, ws_doc.to_string())?;
+            }
 
             // Ensure crate-level references exist in the correct sections
             // [impl cli.add.dep-kind]
@@ -1410,35 +2463,69 @@ fn sync_battery_packs(project_dir: &Path, path: Option<&str>, source: &CrateSour
             // [impl manifest.deps.no-workspace]
             // [impl cli.add.dep-kind]
             for (dep_name, dep_spec) in &expected {
-                let section = dep_kind_section(dep_spec.dep_kind);
-                let table =
-                    user_doc[section].or_insert(toml_edit::Item::Table(toml_edit::Table::new()));
-                if let Some(table) = table.as_table_mut() {
-                    if !table.contains_key(dep_name) {
-                        add_dep_to_table(table, dep_name, dep_spec);
-                        total_changes += 1;
-                        println!("  + {}", dep_name);
-                    } else if sync_dep_in_table(table, dep_name, dep_spec) {
-                        total_changes += 1;
-                        println!("  ~ {}", dep_name);
+                for dep_spec in specs_by_kind(dep_spec) {
+                    if let Some(table) = dep_table_mut(&mut user_doc, &dep_spec) {
+                        if !table.contains_key(dep_name) {
+                            add_dep_to_table(table, dep_name, &dep_spec);
+                            total_changes += 1;
+                            writeln!(out, "  + {}", dep_name)?;
+                        } else if sync_dep_in_table(table, dep_name, &dep_spec) {
+                            total_changes += 1;
+                            writeln!(out, "  ~ {}", dep_name)?;
+                        }
                     }
                 }
             }
         }
-        write_battery_pack_state(&user_manifest_path, bp_name, &active_features, &expected)?;
+        write_battery_pack_state(
+            changes,
+            user_manifest_path,
+            bp_name,
+            &active_features,
+            &expected,
+        )?;
     }
 
     // [impl manifest.toml.preserve]
-    std::fs::write(&user_manifest_path, user_doc.to_string())
-        .context("Failed to write Cargo.toml")?;
+    changes.write(user_manifest_path, user_doc.to_string())?;
+    // [impl manifest.lock.record]
+    resolver.finish(changes, user_manifest_path)?;
 
-    if total_changes == 0 {
-        println!("All dependencies are up to date.");
-    } else {
-        println!("Synced {} change(s).", total_changes);
-    }
+    Ok(Some(total_changes))
+}
 
-    Ok(())
+/// Directory that `--dry-run` shows paths relative to: the workspace root,
+/// or the crate's own directory outside a workspace.
+fn plan_root(user_manifest_path: &Path) -> Result<PathBuf> {
+    let manifest = find_workspace_manifest(user_manifest_path)?
+        .unwrap_or_else(|| user_manifest_path.to_path_buf());
+    Ok(manifest.parent().unwrap_or(Path::new(".")).to_path_buf())
+}
+
+/// Resolve `--workspace` / `-p` into the member crates to operate on.
+///
+/// Returns `None` when neither flag was given, meaning "just the crate in
+/// `project_dir`". With `--workspace` alone, only members that have battery
+/// packs installed are returned; members named with `-p` are always returned.
+// [impl cli.workspace.flag]
+// [impl cli.workspace.package]
+fn select_members(
+    project_dir: &Path,
+    selection: &MemberSelection,
+) -> Result<Option<Vec<crate::manifest::WorkspaceMember>>> {
+    if !selection.workspace && selection.package.is_empty() {
+        return Ok(None);
+    }
+    let mut members = crate::manifest::find_workspace_members(project_dir, &selection.package)?;
+    if selection.package.is_empty() {
+        members.retain(|m| {
+            std::fs::read_to_string(&m.manifest_path)
+                .ok()
+                .and_then(|content| find_installed_bp_names(&content).ok())
+                .is_some_and(|names| !names.is_empty())
+        });
+    }
+    Ok(Some(members))
 }
 
 // ============================================================================
@@ -1483,6 +2570,18 @@ enum PickRow {
     Template(String),
 }
 
+impl PickRow {
+    /// The picker key linking this row to its copies in other sections. The
+    /// kind is part of it: a feature is often named after the crate it adds.
+    fn key(&self) -> String {
+        match self {
+            PickRow::Feature(name) => format!("feature:{name}"),
+            PickRow::Crate(name) => format!("crate:{name}"),
+            PickRow::Template(name) => format!("template:{name}"),
+        }
+    }
+}
+
 /// Represents the result of an interactive crate selection.
 pub(crate) struct PickerResult {
     /// The resolved crates to install (name -> dep spec with merged features).
@@ -1491,6 +2590,8 @@ pub(crate) struct PickerResult {
     pub active_features: BTreeSet<String>,
     /// Template names selected by the user for application.
     pub selected_templates: Vec<String>,
+    /// The dependency kind chosen for each crate the user changed it for.
+    pub dep_kinds: BTreeMap<String, bphelper_manifest::DepKind>,
 }
 
 /// Context needed for template preview in the interactive picker.
@@ -1546,9 +2647,33 @@ fn dep_description(spec: &bphelper_manifest::BatteryPackSpec, dep: &str) -> Opti
 fn pick_crates_interactive(
     bp_spec: &bphelper_manifest::BatteryPackSpec,
     pre_selected: &BTreeSet<String>,
+    dep_kinds: &BTreeMap<String, bphelper_manifest::DepKind>,
+    preview_ctx: Option<PickerPreviewContext>,
+) -> Result<Option<PickerResult>> {
+    pick_crates_with(
+        bp_spec,
+        pre_selected,
+        dep_kinds,
+        preview_ctx,
+        sectioned_picker::run_picker,
+    )
+}
+
+/// [`pick_crates_interactive`] with the picker run by `run`, which gets the
+/// title, sections and actions. Tests pass a scripted
+/// [`sectioned_picker::run_picker_with`] here.
+fn pick_crates_with(
+    bp_spec: &bphelper_manifest::BatteryPackSpec,
+    pre_selected: &BTreeSet<String>,
+    dep_kinds: &BTreeMap<String, bphelper_manifest::DepKind>,
     preview_ctx: Option<PickerPreviewContext>,
+    run: impl FnOnce(
+        &str,
+        Vec<sectioned_picker::Section>,
+        Vec<sectioned_picker::PickerAction<'_>>,
+    ) -> Result<sectioned_picker::PickerOutcome>,
 ) -> Result<Option<PickerResult>> {
-    use sectioned_picker::{PickerAction, PickerOutcome, Section, SectionItem, run_picker};
+    use sectioned_picker::{PickerAction, PickerOutcome, Section, SectionItem};
 
     // Collect non-default features with their member crates.
     let features: Vec<(&String, &BTreeSet<bphelper_manifest::FeatureRef>)> = bp_spec
@@ -1607,8 +2732,20 @@ fn pick_crates_interactive(
             .join(", ");
         format!("✦ {feat_name} [{member_list}]")
     };
-    let crate_label = |crate_name: &str, spec: &bphelper_manifest::CrateSpec| {
-        if spec.features.is_empty() {
+    // The kind each crate will be added as; `d` cycles it for the crate
+    // under the cursor.
+    let kinds = std::cell::RefCell::new(dep_kinds.clone());
+    let kind_of = |crate_name: &str, spec: &bphelper_manifest::CrateSpec| {
+        kinds
+            .borrow()
+            .get(crate_name)
+            .copied()
+            .unwrap_or(spec.dep_kind)
+    };
+    let crate_label = |crate_name: &str,
+                       spec: &bphelper_manifest::CrateSpec,
+                       kind: bphelper_manifest::DepKind| {
+        let mut label = if spec.features.is_empty() {
             format!("{crate_name} ({})", spec.version)
         } else {
             format!(
@@ -1620,7 +2757,11 @@ fn pick_crates_interactive(
                     .collect::<Vec<_>>()
                     .join(", ")
             )
+        };
+        if kind != bphelper_manifest::DepKind::Normal {
+            label.push_str(&format!(" [{}]", crate::manifest::dep_kind_name(kind)));
         }
+        label
     };
 
     // Build sections and a parallel `section_rows` that records what each row
@@ -1637,6 +2778,9 @@ fn pick_crates_interactive(
     let mut categorized_templates: BTreeSet<&str> = BTreeSet::new();
 
     // -- One section per category (radio for at-most-one, checkbox for any) --
+    // An item in several categories gets a row in each, keyed so the picker
+    // keeps the rows' selection in step.
+    // [impl cli.picker-item-in-multiple-categories]
     for (cat_name, cat_spec) in &bp_spec.categories {
         let mut items: Vec<SectionItem> = Vec::new();
         let mut rows: Vec<PickRow> = Vec::new();
@@ -1652,8 +2796,9 @@ fn pick_crates_interactive(
                 if let Some(desc) = feature_description(bp_spec, feat_name) {
                     item = item.with_description(desc);
                 }
-                items.push(item);
-                rows.push(PickRow::Feature((*feat_name).clone()));
+                let row = PickRow::Feature((*feat_name).clone());
+                items.push(item.with_key(row.key()));
+                rows.push(row);
             }
         }
 
@@ -1666,12 +2811,16 @@ fn pick_crates_interactive(
                 } else {
                     pre_selected.contains(crate_name.as_str())
                 };
-                let mut item = SectionItem::new(crate_label(crate_name, spec), checked);
+                let mut item = SectionItem::new(
+                    crate_label(crate_name, spec, kind_of(crate_name, spec)),
+                    checked,
+                );
                 if let Some(desc) = dep_description(bp_spec, crate_name) {
                     item = item.with_description(desc);
                 }
-                items.push(item);
-                rows.push(PickRow::Crate((*crate_name).clone()));
+                let row = PickRow::Crate((*crate_name).clone());
+                items.push(item.with_key(row.key()));
+                rows.push(row);
             }
         }
 
@@ -1683,8 +2832,9 @@ fn pick_crates_interactive(
                     Some(desc) => format!("Add `{tmpl_name}` template — {desc}"),
                     None => format!("Add `{tmpl_name}` template"),
                 };
-                items.push(SectionItem::new(label, false));
-                rows.push(PickRow::Template(tmpl_name.clone()));
+                let row = PickRow::Template(tmpl_name.clone());
+                items.push(SectionItem::new(label, false).with_key(row.key()));
+                rows.push(row);
             }
         }
 
@@ -1739,7 +2889,10 @@ fn pick_crates_interactive(
             } else {
                 pre_selected.contains(crate_name.as_str())
             };
-            let mut item = SectionItem::new(crate_label(crate_name, spec), checked);
+            let mut item = SectionItem::new(
+                crate_label(crate_name, spec, kind_of(crate_name, spec)),
+                checked,
+            );
             if let Some(desc) = dep_description(bp_spec, crate_name) {
                 item = item.with_description(desc);
             }
@@ -1794,7 +2947,35 @@ fn pick_crates_interactive(
         })
         .collect();
 
+    // A flat (section_idx, item_idx) -> crate name map for the dep-kind handler.
+    let crate_targets: BTreeMap<(usize, usize), String> = section_rows
+        .iter()
+        .enumerate()
+        .flat_map(|(s, rows)| {
+            rows.iter()
+                .enumerate()
+                .filter_map(move |(i, row)| match row {
+                    PickRow::Crate(name) => Some(((s, i), name.clone())),
+                    _ => None,
+                })
+        })
+        .collect();
+
     let mut actions: Vec<PickerAction<'_>> = Vec::new();
+    // [impl cli.add.dep-kind-override]
+    actions.push(PickerAction {
+        key: 'd',
+        label: "Dep kind",
+        handler: Box::new(|ctx: &mut sectioned_picker::ActionContext<'_>| {
+            let Some(name) = crate_targets.get(&(ctx.section(), ctx.item())) else {
+                return;
+            };
+            let spec = &bp_spec.crates[name];
+            let kind = crate::manifest::next_dep_kind(kind_of(name, spec));
+            kinds.borrow_mut().insert(name.clone(), kind);
+            ctx.set_label(crate_label(name, spec, kind));
+        }),
+    });
     if let Some(ref ctx) = preview_ctx
         && has_templates
     {
@@ -1828,6 +3009,7 @@ fn pick_crates_interactive(
                             defines: std::collections::BTreeMap::new(),
                             active_features: std::collections::BTreeSet::new(),
                             interactive_override: Some(false),
+                            source: source.clone(),
                         };
                         match crate::template_engine::preview(opts) {
                             Ok(files) => crate::tui::highlight_preview(&files),
@@ -1837,15 +3019,18 @@ fn pick_crates_interactive(
                     Err(e) => ratatui::text::Text::from(format!("Preview unavailable: {e:#}")),
                 };
 
-                let title = format!("Preview: {template_name}");
-                crate::tui::show_preview(ctx.terminal(), &title, content);
+                // Scripted runs have no terminal to show the preview on.
+                if let Some(terminal) = ctx.terminal() {
+                    let title = format!("Preview: {template_name}");
+                    crate::tui::show_preview(terminal, &title, content);
+                }
             }),
         });
     }
 
     // Run the picker.
     let title = format!("{} v{}", bp_spec.name, bp_spec.version);
-    let outcome = run_picker(&title, sections, actions)?;
+    let outcome = run(&title, sections, actions)?;
 
     let section_results = match outcome {
         PickerOutcome::Confirmed(results) => results,
@@ -1853,12 +3038,13 @@ fn pick_crates_interactive(
     };
 
     // Decode results by name via `section_rows`. An item that appears in several
-    // sections (multiple categories) is simply collected into a set.
+    // sections (multiple categories) reports the same state in each, and is
+    // collected into a set.
     let mut picked_features: BTreeSet<String> = BTreeSet::new();
     let mut picked_crates: BTreeSet<String> = BTreeSet::new();
     let mut selected_templates: Vec<String> = Vec::new();
 
-    for (checks, rows) in section_results.iter().zip(section_rows.iter()) {
+    for (checks, rows) in section_results.sections.iter().zip(section_rows.iter()) {
         for (checked, row) in checks.iter().zip(rows.iter()) {
             if !*checked {
                 continue;
@@ -1879,6 +3065,50 @@ fn pick_crates_interactive(
         }
     }
 
+    let (crates, active_features) = resolve_picked(bp_spec, &picked_features, &picked_crates);
+
+    Ok(Some(PickerResult {
+        crates,
+        active_features,
+        selected_templates,
+        dep_kinds: kinds.into_inner(),
+    }))
+}
+
+/// Run the crate picker for the pack at `crate_root` on `keys` in a
+/// `width` x `height` test terminal, and return the last frame drawn.
+// [impl tui.picker.scripted]
+pub(crate) fn render_picker_snapshot(
+    crate_root: &Path,
+    keys: Vec<crossterm::event::KeyEvent>,
+    width: u16,
+    height: u16,
+) -> Result<String> {
+    let bp_spec = parse_battery_pack_from_path(&crate_root.join("Cargo.toml"))
+        .context("Failed to parse Cargo.toml")?;
+    let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(width, height))?;
+    pick_crates_with(
+        &bp_spec,
+        &BTreeSet::new(),
+        &BTreeMap::new(),
+        None,
+        |title, sections, actions| {
+            sectioned_picker::run_picker_with(title, sections, actions, keys, &mut terminal)
+        },
+    )?;
+    Ok(terminal.backend().to_string())
+}
+
+/// Turn the features and crates picked for a pack into the crates to install,
+/// and the features whose visible crates are all among them.
+pub(crate) fn resolve_picked(
+    bp_spec: &bphelper_manifest::BatteryPackSpec,
+    picked_features: &BTreeSet<String>,
+    picked_crates: &BTreeSet<String>,
+) -> (
+    BTreeMap<String, bphelper_manifest::CrateSpec>,
+    BTreeSet<String>,
+) {
     // Route picked features through the main resolver so per-dep features (`serde/derive`),
     // weak refs (`serde?/derive`), and nested refs (`bundle = ["fancy"]`) reach the
     // result with correct CrateSpec.features.
@@ -1895,7 +3125,7 @@ fn pick_crates_interactive(
 
     // Layer in directly-picked crates (their declared spec, picked by name, not via
     // a feature gate, so no per-dep feature accumulation).
-    for name in &picked_crates {
+    for name in picked_crates {
         if bp_spec.is_hidden(name) {
             continue;
         }
@@ -1936,11 +3166,7 @@ fn pick_crates_interactive(
         }
     }
 
-    Ok(Some(PickerResult {
-        crates,
-        active_features,
-        selected_templates,
-    }))
+    (crates, active_features)
 }
 
 // ============================================================================
@@ -1953,6 +3179,11 @@ struct NewOpts {
     name: Option<String>,
     defines: BTreeMap<String, String>,
     interactive: bool,
+    hooks: HookPolicy,
+    /// Written as the new project's `battery-pack.lock`.
+    lock: Option<Lockfile>,
+    /// Where packs the template's bp-managed dependencies need are fetched from.
+    source: CrateSource,
 }
 
 fn generate_from_local(opts: NewOpts, local_path: &str, template: Option<String>) -> Result<()> {
@@ -1977,6 +3208,7 @@ fn prompt_project_name(name: Option<String>) -> Result<String> {
         Some(n) => Ok(n),
         None => dialoguer::Input::<String>::new()
             .with_prompt("Project name")
+            .validate_with(|name: &String| crate::template_engine::check_crate_name(name))
             .interact_text()
             .context("Failed to read project name"),
     }
@@ -2016,25 +3248,65 @@ fn generate_from_path(
             defines: opts.defines,
             active_features: std::collections::BTreeSet::new(),
             interactive_override,
+            source: opts.source.clone(),
         },
         destination: None,
         git_init: true,
+        hooks: Some(opts.hooks),
     };
 
-    let project_dir = crate::template_engine::generate(gen_opts)?;
+    let (project_dir, placeholders) = crate::template_engine::generate_with_values(gen_opts)?;
 
     // Record the applied template in the new project's battery-pack.toml.
     let user_manifest_path = project_dir.join("Cargo.toml");
     if user_manifest_path.exists() {
         let bp_name = resolve_crate_name(&opts.battery_pack);
-        if let Err(e) = record_applied_template(&user_manifest_path, &bp_name, template_name) {
+        let applied = AppliedTemplate {
+            name: template_name.to_string(),
+            version: read_pack_version(&crate_path.join("Cargo.toml")),
+            placeholders,
+        };
+        if let Err(e) = record_applied_template(&user_manifest_path, &bp_name, applied) {
             eprintln!("warning: failed to record template in state: {e}");
         }
+        // [impl manifest.lock.record]
+        if let Some(lock) = &opts.lock
+            && let Err(e) = write_lockfile(&user_manifest_path, lock)
+        {
+            eprintln!("warning: failed to write battery-pack.lock: {e}");
+        }
     }
 
     Ok(())
 }
 
+/// Collect `--as-normal`, `--as-dev` and `--as-build` into the kind chosen
+/// for each crate, rejecting a crate given two different kinds.
+// [impl cli.add.dep-kind-override]
+fn dep_kind_choices(
+    as_normal: &[String],
+    as_dev: &[String],
+    as_build: &[String],
+) -> Result<BTreeMap<String, bphelper_manifest::DepKind>> {
+    use bphelper_manifest::DepKind;
+
+    let mut choices = BTreeMap::new();
+    for (names, kind) in [
+        (as_normal, DepKind::Normal),
+        (as_dev, DepKind::Dev),
+        (as_build, DepKind::Build),
+    ] {
+        for name in names {
+            if let Some(other) = choices.insert(name.clone(), kind)
+                && other != kind
+            {
+                bail!("'{name}' can't be added as both {other} and {kind}");
+            }
+        }
+    }
+    Ok(choices)
+}
+
 /// Parse a `key=value` string for clap's `value_parser`.
 fn parse_define(s: &str) -> Result<(String, String), String> {
     match s.split_once('=') {
@@ -2575,7 +3847,7 @@ fn print_template_preview(opts: &crate::template_engine::PreviewOpts<'_>) -> Res
 
     for file in &files {
         println!("── {} ──", file.path);
-        println!("{}", file.content);
+        println!("{}", file.display_content());
         println!();
     }
 
@@ -2599,15 +3871,38 @@ fn print_template_preview(opts: &crate::template_engine::PreviewOpts<'_>) -> Res
 // [impl cli.status.json]
 // [impl cli.source.subcommands]
 // [impl cli.path.subcommands]
+// [impl cli.workspace.status]
 fn status_battery_packs(
     project_dir: &Path,
+    members: &MemberSelection,
     path: Option<&str>,
     source: &CrateSource,
     json: bool,
 ) -> Result<()> {
-    let report = build_status_report(project_dir, path, source)?;
     let stdout = std::io::stdout();
     let mut out = stdout.lock();
+
+    if let Some(selected) = select_members(project_dir, members)? {
+        let report = cargo_bp_script::WorkspaceStatusReport::new().with_members(
+            selected
+                .into_iter()
+                .map(|member| {
+                    let mut report =
+                        build_manifest_status_report(&member.manifest_path, path, source)?;
+                    report.project = report.project.with_package_name(member.name);
+                    Ok(report)
+                })
+                .collect::<Result<Vec<_>>>()?,
+        );
+        if json {
+            render_status_json(&report, &mut out).context("Failed to write status JSON")?;
+        } else {
+            render_workspace_status_text(&report, &mut out).context("Failed to render status")?;
+        }
+        return Ok(());
+    }
+
+    let report = build_status_report(project_dir, path, source)?;
     if json {
         render_status_json(&report, &mut out).context("Failed to write status JSON")?;
     } else {
@@ -2622,7 +3917,7 @@ fn status_battery_packs(
 /// the schema lives in `cargo-bp-script`.
 // [impl cli.status.json]
 fn render_status_json(
-    report: &cargo_bp_script::StatusReport,
+    report: &impl serde::Serialize,
     w: &mut impl std::io::Write,
 ) -> std::io::Result<()> {
     serde_json::to_writer(&mut *w, report)?;
@@ -2647,25 +3942,90 @@ fn render_status_text(
         return Ok(());
     }
 
+    if render_status_packs(&report.packs, "", w)? {
+        writeln!(w)?;
+        writeln!(w, "Run {} to update.", style("cargo bp sync").bold())?;
+    }
+
+    Ok(())
+}
+
+/// Pretty-print a [`cargo_bp_script::WorkspaceStatusReport`]: one block per
+/// member, headed by its package name, and a single sync hint at the end.
+// [impl cli.workspace.status]
+fn render_workspace_status_text(
+    report: &cargo_bp_script::WorkspaceStatusReport,
+    w: &mut impl std::io::Write,
+) -> std::io::Result<()> {
+    use console::style;
+
+    if report.members.is_empty() {
+        writeln!(w, "No battery packs installed in any workspace member.")?;
+        return Ok(());
+    }
+
     let mut any_warnings = false;
-    for pack in &report.packs {
+    for (i, member) in report.members.iter().enumerate() {
+        if i > 0 {
+            writeln!(w)?;
+        }
+        let name = member
+            .project
+            .package_name
+            .clone()
+            .unwrap_or_else(|| member.project.manifest_path.display().to_string());
+        writeln!(w, "{}", style(name).bold().underlined())?;
+        if member.packs.is_empty() {
+            writeln!(w, "  No battery packs installed.")?;
+            continue;
+        }
+        any_warnings |= render_status_packs(&member.packs, "  ", w)?;
+    }
+
+    if any_warnings {
+        writeln!(w)?;
+        writeln!(
+            w,
+            "Run {} to update.",
+            style("cargo bp sync --workspace").bold()
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Print each pack's version line and dependency warnings, indented by
+/// `indent`. Returns whether any warnings were printed.
+fn render_status_packs(
+    packs: &[cargo_bp_script::InstalledPackStatus],
+    indent: &str,
+    w: &mut impl std::io::Write,
+) -> std::io::Result<bool> {
+    use console::style;
+
+    let mut any_warnings = false;
+    for pack in packs {
         // [impl cli.status.list]
         writeln!(
             w,
-            "{} ({})",
+            "{indent}{} ({})",
             style(&pack.short_name).bold(),
             style(&pack.version).dim(),
         )?;
 
         if pack.warnings.is_empty() {
-            writeln!(w, "  {} all dependencies up to date", style("✓").green())?;
+            writeln!(
+                w,
+                "{indent}  {} all dependencies up to date",
+                style("✓").green()
+            )?;
         } else {
             any_warnings = true;
             for warning in &pack.warnings {
                 // [impl cli.status.version-warn]
                 writeln!(
                     w,
-                    "  {} {}: {} → {} recommended",
+                    "{indent}  {} {}: {} → {} recommended",
                     style("⚠").yellow(),
                     warning.crate_name,
                     style(&warning.current_version).red(),
@@ -2674,13 +4034,7 @@ fn render_status_text(
             }
         }
     }
-
-    if any_warnings {
-        writeln!(w)?;
-        writeln!(w, "Run {} to update.", style("cargo bp sync").bold())?;
-    }
-
-    Ok(())
+    Ok(any_warnings)
 }
 
 /// Build a [`cargo_bp_script::StatusReport`] for the project rooted at
@@ -2697,17 +4051,61 @@ fn build_status_report(
     // [impl cli.status.no-project]
     let user_manifest_path =
         find_user_manifest(project_dir).context("are you inside a Rust project?")?;
+    build_manifest_status_report(&user_manifest_path, path, source)
+}
+
+/// Build a [`cargo_bp_script::StatusReport`] for the crate whose manifest is
+/// at `user_manifest_path`.
+fn build_manifest_status_report(
+    user_manifest_path: &Path,
+    path: Option<&str>,
+    source: &CrateSource,
+) -> Result<cargo_bp_script::StatusReport> {
+    let packs = installed_pack_statuses(user_manifest_path, path, source)?;
+    Ok(
+        cargo_bp_script::StatusReport::new(cargo_bp_script::ProjectInfo::new(
+            user_manifest_path.to_path_buf(),
+        ))
+        .with_packs(packs.into_iter().map(|view| view.status)),
+    )
+}
+
+/// A pack installed in the project, as the TUI's installed-packs view shows it.
+pub(crate) struct InstalledPackView {
+    pub pack: InstalledPack,
+    /// Version and drift warnings, as `cargo bp status` reports them.
+    pub status: cargo_bp_script::InstalledPackStatus,
+    /// The project's version of each of the pack's crates it depends on.
+    pub present: BTreeMap<String, String>,
+    /// Crates the user moved to a different dependency kind than the pack's.
+    pub dep_kinds: BTreeMap<String, bphelper_manifest::DepKind>,
+}
+
+/// Load the packs installed in the crate at `user_manifest_path`, each with
+/// its status and the project's versions of its crates.
+fn installed_pack_statuses(
+    user_manifest_path: &Path,
+    path: Option<&str>,
+    source: &CrateSource,
+) -> Result<Vec<InstalledPackView>> {
     let user_manifest_content =
-        std::fs::read_to_string(&user_manifest_path).context("Failed to read Cargo.toml")?;
+        std::fs::read_to_string(user_manifest_path).context("Failed to read Cargo.toml")?;
 
     // Inline the load_installed_packs logic to avoid re-reading the manifest.
     let bp_names = find_installed_bp_names(&user_manifest_content)?;
+    let mut resolver = PackResolver::for_manifest(
+        source,
+        &Changeset::new(),
+        user_manifest_path,
+        LockMode::Reuse,
+    )?;
     let packs: Vec<InstalledPack> = bp_names
         .into_iter()
         .map(|bp_name| {
-            let spec = load_installed_bp_spec(&bp_name, path, source)?;
+            let version_req = read_version_req_from_state(user_manifest_path, &bp_name)?;
+            let spec = resolver.installed_spec(&bp_name, path, version_req.as_ref())?;
             let active_features = read_active_features_for_project(
-                &user_manifest_path,
+                user_manifest_path,
                 &user_manifest_content,
                 &bp_name,
             );
@@ -2722,14 +4120,17 @@ fn build_status_report(
 
     // Build a map of the user's actual dependency versions so we can compare.
     // (Cheap to compute even when packs is empty; keeps the structure simple.)
-    let user_versions = collect_user_dep_versions(&user_manifest_path, &user_manifest_content)?;
+    let user_versions = collect_user_dep_versions(user_manifest_path, &user_manifest_content)?;
 
     // --- Map each installed pack into its `InstalledPackStatus`.
-    let pack_statuses: Vec<cargo_bp_script::InstalledPackStatus> = packs
-        .iter()
+    let statuses = packs
+        .into_iter()
         .map(|pack| {
             // Resolve which crates are expected for this pack's active features.
-            let expected = pack.spec.resolve_for_features(&pack.active_features);
+            let expected = resolver.expand_extended_packs(
+                &pack.spec.name,
+                pack.spec.resolve_for_features(&pack.active_features),
+            )?;
 
             // [impl cli.status.version-warn]
             let warnings = expected.iter().filter_map(|(dep_name, dep_spec)| {
@@ -2753,104 +4154,374 @@ fn build_status_report(
             };
 
             let applied_templates =
-                read_applied_templates_from_state(&user_manifest_path, &pack.spec.name);
+                read_applied_templates_from_state(user_manifest_path, &pack.spec.name);
 
-            cargo_bp_script::InstalledPackStatus::new(
+            let status = cargo_bp_script::InstalledPackStatus::new(
                 &pack.short_name,
                 &pack.spec.name,
                 &pack.version,
             )
             .with_active_features(feature_strings)
             .with_applied_templates(applied_templates)
-            .with_warnings(warnings)
+            .with_warnings(warnings);
+            let present = pack
+                .spec
+                .crates
+                .keys()
+                .filter_map(|name| Some((name.clone(), user_versions.get(name)?.clone())))
+                .collect();
+            Ok(InstalledPackView {
+                dep_kinds: read_dep_kind_overrides(
+                    &Changeset::new(),
+                    user_manifest_path,
+                    &pack.spec.name,
+                )?,
+                pack,
+                status,
+                present,
+            })
         })
-        .collect();
+        .collect::<Result<_>>()?;
 
-    Ok(
-        cargo_bp_script::StatusReport::new(cargo_bp_script::ProjectInfo::new(user_manifest_path))
-            .with_packs(pack_statuses),
-    )
+    Ok(statuses)
 }
 
-fn check_battery_packs(
+/// Load the packs installed in the crate in `project_dir` for the TUI.
+// [impl tui.installed.list-packs]
+pub(crate) fn load_installed_pack_views(
+    project_dir: &Path,
+    source: &CrateSource,
+) -> Result<Vec<InstalledPackView>> {
+    let user_manifest_path =
+        find_user_manifest(project_dir).context("are you inside a Rust project?")?;
+    installed_pack_statuses(&user_manifest_path, None, source)
+}
+
+/// Write the features and crates picked for an installed pack in the TUI,
+/// the same way `cargo bp add` writes a picker selection.
+// [impl tui.nav.exit]
+pub(crate) fn apply_installed_selection(
     project_dir: &Path,
-    _path: Option<&str>,
     source: &CrateSource,
+    bp_spec: &bphelper_manifest::BatteryPackSpec,
+    picked_features: &BTreeSet<String>,
+    picked_crates: &BTreeSet<String>,
+    dep_kinds: &BTreeMap<String, bphelper_manifest::DepKind>,
 ) -> Result<()> {
     let user_manifest_path = find_user_manifest(project_dir)?;
-    let user_manifest_content =
-        std::fs::read_to_string(&user_manifest_path).context("Failed to read Cargo.toml")?;
+    let (crates_to_sync, active_features) = resolve_picked(bp_spec, picked_features, picked_crates);
+    if crates_to_sync.is_empty() {
+        bail!(
+            "No crates selected. To remove the pack, run `cargo bp rm {}`.",
+            short_name(&bp_spec.name)
+        );
+    }
 
-    // For now, use build-dependencies to find battery packs (this will be updated when metadata reading is improved)
-    let bp_names = find_installed_bp_names(&user_manifest_content)?;
+    let mut resolver = PackResolver::for_manifest(
+        source,
+        &Changeset::new(),
+        &user_manifest_path,
+        LockMode::Reuse,
+    )?;
+    // [impl cli.add.extends]
+    let crates_to_sync = resolver.expand_extended_packs(&bp_spec.name, crates_to_sync)?;
+
+    let mut out = DryRun::Off.progress();
+    let mut changes = Changeset::new();
+    stage_pack_crates(
+        &mut changes,
+        &mut out,
+        &user_manifest_path,
+        bp_spec,
+        Some(&bp_spec.version),
+        None,
+        source,
+        &active_features,
+        &crates_to_sync,
+        dep_kinds,
+    )?;
+    resolver.finish(&mut changes, &user_manifest_path)?;
+    DryRun::Off.finish(changes, "add", &plan_root(&user_manifest_path)?)
+}
 
-    if bp_names.is_empty() {
-        println!("No battery packs installed.");
-        return Ok(());
+// [impl cli.workspace.check]
+// [impl cli.check.exit-code]
+fn check_battery_packs(
+    project_dir: &Path,
+    members: &MemberSelection,
+    path: Option<&str>,
+    source: &CrateSource,
+    json: bool,
+) -> Result<()> {
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+
+    let problems = if let Some(selected) = select_members(project_dir, members)? {
+        let report = cargo_bp_script::WorkspaceCheckReport::new().with_members(
+            selected
+                .into_iter()
+                .map(|member| {
+                    let mut report = build_check_report(&member.manifest_path, path, source)?;
+                    report.project = report.project.with_package_name(member.name);
+                    Ok(report)
+                })
+                .collect::<Result<Vec<_>>>()?,
+        );
+        if json {
+            render_status_json(&report, &mut out).context("Failed to write check JSON")?;
+        } else {
+            render_workspace_check_text(&report, &mut out).context("Failed to render check")?;
+        }
+        report.members.iter().map(count_check_problems).sum()
+    } else {
+        let user_manifest_path = find_user_manifest(project_dir)?;
+        let report = build_check_report(&user_manifest_path, path, source)?;
+        if json {
+            render_status_json(&report, &mut out).context("Failed to write check JSON")?;
+        } else {
+            render_check_text(&report, &mut out).context("Failed to render check")?;
+        }
+        count_check_problems(&report)
+    };
+
+    if problems > 0 {
+        bail!("cargo bp check found {problems} problem(s)");
     }
+    Ok(())
+}
+
+/// Issues plus packs that failed to load.
+fn count_check_problems(report: &cargo_bp_script::CheckReport) -> usize {
+    report
+        .packs
+        .iter()
+        .map(|pack| pack.issues.len() + usize::from(pack.error.is_some()))
+        .sum()
+}
+
+/// Check every battery pack installed in the crate at `user_manifest_path`
+/// against its dependencies. A pack that fails to load is recorded in the
+/// report rather than aborting the check.
+// [impl cli.check.purpose]
+// [impl cli.check.json]
+fn build_check_report(
+    user_manifest_path: &Path,
+    path: Option<&str>,
+    source: &CrateSource,
+) -> Result<cargo_bp_script::CheckReport> {
+    let user_manifest_content =
+        std::fs::read_to_string(user_manifest_path).context("Failed to read Cargo.toml")?;
+    let raw: toml::Value =
+        toml::from_str(&user_manifest_content).context("Failed to parse Cargo.toml")?;
 
-    println!("Checking {} installed battery pack(s)...", bp_names.len());
+    let ws_deps = match find_workspace_manifest(user_manifest_path)? {
+        Some(ws_path) => {
+            let ws_content =
+                std::fs::read_to_string(&ws_path).context("Failed to read workspace Cargo.toml")?;
+            let ws_raw: toml::Value =
+                toml::from_str(&ws_content).context("Failed to parse workspace Cargo.toml")?;
+            ws_raw
+                .get("workspace")
+                .and_then(|w| w.get("dependencies"))
+                .and_then(|d| d.as_table())
+                .cloned()
+        }
+        None => None,
+    };
 
-    // Get user's current dependency versions
-    let user_versions = collect_user_dep_versions(&user_manifest_path, &user_manifest_content)?;
+    let mut resolver = PackResolver::for_manifest(
+        source,
+        &Changeset::new(),
+        user_manifest_path,
+        LockMode::Reuse,
+    )?;
+    let packs = find_installed_bp_names(&user_manifest_content)?
+        .into_iter()
+        .map(|bp_name| {
+            let pack = cargo_bp_script::PackCheck::new(short_name(&bp_name), &bp_name);
+            // [impl cli.check.path]
+            let spec = read_version_req_from_state(user_manifest_path, &bp_name)
+                .and_then(|req| resolver.installed_spec(&bp_name, path, req.as_ref()));
+            let spec = match spec {
+                Ok(spec) => spec,
+                Err(e) => return pack.with_error(format!("{e:#}")),
+            };
+            let active_features = read_active_features_for_project(
+                user_manifest_path,
+                &user_manifest_content,
+                &bp_name,
+            );
+            let expected = match resolver
+                .expand_extended_packs(&spec.name, spec.resolve_for_features(&active_features))
+            {
+                Ok(expected) => expected,
+                Err(e) => return pack.with_error(format!("{e:#}")),
+            };
+            pack.with_version(&spec.version)
+                .with_issues(check_expected_deps(&expected, &raw, ws_deps.as_ref()))
+        })
+        .collect::<Vec<_>>();
 
-    let mut all_valid = true;
+    Ok(
+        cargo_bp_script::CheckReport::new(cargo_bp_script::ProjectInfo::new(user_manifest_path))
+            .with_packs(packs),
+    )
+}
 
-    for bp_name in &bp_names {
-        print!("  {} ... ", bp_name);
+/// Compare the crates a pack expects against the user's manifest (`raw`)
+/// and, in a workspace, its `[workspace.dependencies]` (`ws_deps`).
+// [impl cli.check.version-drift]
+// [impl cli.check.missing-deps]
+// [impl cli.check.missing-features]
+// [impl cli.check.workspace-refs]
+fn check_expected_deps(
+    expected: &BTreeMap<String, bphelper_manifest::CrateSpec>,
+    raw: &toml::Value,
+    ws_deps: Option<&toml::map::Map<String, toml::Value>>,
+) -> Vec<cargo_bp_script::CheckIssue> {
+    use cargo_bp_script::CheckIssue;
+
+    let mut issues = Vec::new();
+    for (dep_name, dep_spec) in expected {
+        // Look in the section the pack puts the crate in first.
+        let expected_section = dep_kind_section(dep_spec.dep_kind);
+        let expected_table = match &dep_spec.target {
+            Some(cfg) => raw.get("target").and_then(|t| t.get(cfg)),
+            None => Some(raw),
+        }
+        .and_then(|parent| parent.get(expected_section)?.as_table());
+        let entry = expected_table
+            .into_iter()
+            .chain(dep_tables(raw))
+            .find_map(|table| table.get(dep_name));
+        let Some(entry) = entry else {
+            issues.push(CheckIssue::missing_dependency(dep_name, &dep_spec.version));
+            continue;
+        };
 
-        // Get the battery pack spec
-        let (_version, spec) = match crate::registry::fetch_bp_spec(source, bp_name) {
-            Ok(result) => result,
-            Err(e) => {
-                println!("❌ Failed to load spec: {}", e);
-                all_valid = false;
+        let ws_entry = ws_deps.and_then(|deps| deps.get(dep_name));
+        let (version, features) = if is_workspace_ref(entry) {
+            let Some(ws_entry) = ws_entry else {
+                issues.push(CheckIssue::workspace_ref_mismatch(
+                    dep_name,
+                    "uses `workspace = true` but [workspace.dependencies] has no entry",
+                ));
                 continue;
+            };
+            let mut features = dep_features(ws_entry);
+            features.extend(dep_features(entry));
+            (extract_version_from_dep(ws_entry), features)
+        } else {
+            if ws_entry.is_some() && extract_version_from_dep(entry).is_some() {
+                issues.push(CheckIssue::workspace_ref_mismatch(
+                    dep_name,
+                    "declares its own version instead of `workspace = true`",
+                ));
             }
+            (extract_version_from_dep(entry), dep_features(entry))
         };
 
-        // Check for version drift
-        let mut warnings = Vec::new();
-        for (crate_name, crate_spec) in &spec.crates {
-            if let Some(user_version) = user_versions.get(crate_name)
-                && is_older_version(user_version, &crate_spec.version)
-            {
-                warnings.push(format!(
-                    "{}: {} → {}",
-                    crate_name, user_version, crate_spec.version
-                ));
-            }
+        if let Some(current) = version
+            && !dep_spec.version.is_empty()
+            && should_upgrade_version(&current, &dep_spec.version)
+        {
+            issues.push(CheckIssue::outdated_version(
+                dep_name,
+                current,
+                &dep_spec.version,
+            ));
+        }
+        let missing: Vec<&String> = dep_spec
+            .features
+            .iter()
+            .filter(|f| !features.contains(*f))
+            .collect();
+        if !missing.is_empty() {
+            issues.push(CheckIssue::missing_features(dep_name, missing));
         }
+    }
+    issues
+}
+
+/// The Cargo features listed on a dependency entry.
+fn dep_features(value: &toml::Value) -> BTreeSet<String> {
+    value
+        .get("features")
+        .and_then(|f| f.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Print a [`cargo_bp_script::CheckReport`] for humans.
+// [impl cli.check.output]
+// [impl cli.check.no-packs]
+fn render_check_text(
+    report: &cargo_bp_script::CheckReport,
+    w: &mut impl std::io::Write,
+) -> std::io::Result<()> {
+    if report.packs.is_empty() {
+        writeln!(w, "No battery packs installed.")?;
+        return Ok(());
+    }
 
-        if warnings.is_empty() {
-            println!("✅ OK");
+    writeln!(
+        w,
+        "Checking {} installed battery pack(s)...",
+        report.packs.len()
+    )?;
+    for pack in &report.packs {
+        write!(w, "  {} ... ", pack.name)?;
+        if let Some(error) = &pack.error {
+            writeln!(w, "❌ Failed to load spec: {error}")?;
+        } else if pack.issues.is_empty() {
+            writeln!(w, "✅ OK")?;
         } else {
-            println!("⚠️  Outdated versions:");
-            for warning in warnings {
-                println!("    {}", warning);
+            writeln!(w, "⚠️  Out of sync:")?;
+            for issue in &pack.issues {
+                writeln!(w, "    {}", issue.message)?;
             }
-            all_valid = false;
         }
     }
 
-    if all_valid {
-        println!("\nAll battery packs are up to date! ✅");
+    if report.is_ok() {
+        writeln!(w, "\nAll battery packs are up to date! ✅")?;
     } else {
-        println!("\nSome dependencies are outdated. Run `cargo bp sync` to update. ⚠️");
+        writeln!(
+            w,
+            "\nSome dependencies are out of sync. Run `cargo bp sync` to update. ⚠️"
+        )?;
     }
-
     Ok(())
 }
 
-fn is_older_version(user_version: &str, recommended_version: &str) -> bool {
-    // Simple version comparison - parse as semver if possible
-    match (
-        semver::Version::parse(user_version),
-        semver::Version::parse(recommended_version),
-    ) {
-        (Ok(user), Ok(recommended)) => user < recommended,
-        _ => false, // If we can't parse, assume it's fine
+/// Print a [`cargo_bp_script::WorkspaceCheckReport`]: one block per member,
+/// headed by its package name.
+// [impl cli.workspace.check]
+fn render_workspace_check_text(
+    report: &cargo_bp_script::WorkspaceCheckReport,
+    w: &mut impl std::io::Write,
+) -> std::io::Result<()> {
+    if report.members.is_empty() {
+        writeln!(w, "No battery packs installed in any workspace member.")?;
+        return Ok(());
+    }
+    for (i, member) in report.members.iter().enumerate() {
+        if i > 0 {
+            writeln!(w)?;
+        }
+        let name = member
+            .project
+            .package_name
+            .clone()
+            .unwrap_or_else(|| member.project.manifest_path.display().to_string());
+        writeln!(w, "{name}:")?;
+        render_check_text(member, w)?;
     }
+    Ok(())
 }
 
 /// Collect the user's actual dependency versions from Cargo.toml (and workspace deps if applicable).
@@ -2882,9 +4553,7 @@ pub(crate) fn collect_user_dep_versions(
     };
 
     // Collect from each dependency section.
-    for section in ["dependencies", "dev-dependencies", "build-dependencies"] {
-        let table = raw.get(section).and_then(|d| d.as_table());
-        let Some(table) = table else { continue };
+    for table in dep_tables(&raw) {
         for (name, value) in table {
             if versions.contains_key(name) {
                 continue; // first section wins
@@ -51,6 +51,17 @@ fn bare_cargo_bp_is_parse_error() {
     );
 }
 
+// ============================================================================
+// cli.tui.menu — `cargo bp tui` opens the TUI
+// ============================================================================
+
+// [verify cli.tui.menu]
+#[test]
+fn tui_subcommand_is_parsed() {
+    let cli = super::Cli::try_parse_from(["cargo", "bp", "tui"]).unwrap();
+    assert!(matches!(unwrap_bp_command(cli), super::BpCommands::Tui));
+}
+
 // ============================================================================
 // cli.new.name-flag — --name flag is accepted by the `new` subcommand
 // ============================================================================
@@ -230,6 +241,7 @@ fn add_dep_twice_no_duplicate() {
         features: BTreeSet::new(),
         dep_kind: bphelper_manifest::DepKind::Normal,
         optional: false,
+        ..Default::default()
     };
 
     crate::manifest::add_dep_to_table(&mut table, "anyhow", &spec);
@@ -241,6 +253,7 @@ fn add_dep_twice_no_duplicate() {
         features: BTreeSet::new(),
         dep_kind: bphelper_manifest::DepKind::Normal,
         optional: false,
+        ..Default::default()
     };
 
     crate::manifest::add_dep_to_table(&mut table, "anyhow", &spec_v2);
@@ -262,6 +275,7 @@ fn add_dep_twice_with_features_no_duplicate() {
         features: BTreeSet::from(["derive".to_string()]),
         dep_kind: bphelper_manifest::DepKind::Normal,
         optional: false,
+        ..Default::default()
     };
 
     crate::manifest::add_dep_to_table(&mut table, "clap", &spec1);
@@ -272,6 +286,7 @@ fn add_dep_twice_with_features_no_duplicate() {
         features: BTreeSet::from(["derive".to_string(), "env".to_string()]),
         dep_kind: bphelper_manifest::DepKind::Normal,
         optional: false,
+        ..Default::default()
     };
 
     crate::manifest::add_dep_to_table(&mut table, "clap", &spec2);
@@ -484,15 +499,18 @@ fn new_non_interactive_flag_is_parsed() {
 // cargo bp new without --name in non-interactive mode
 #[test]
 fn new_non_interactive_requires_name() {
-    let source = crate::registry::CrateSource::Registry;
+    let source = crate::registry::CrateSource::crates_io();
     let result = super::new_from_battery_pack(super::NewFromBpOpts {
         battery_pack: "cli",
         name: None,
         template: None,
         path_override: None,
         source: &source,
+        project_dir: std::path::Path::new("."),
         define: &[],
         interactive: false,
+        hooks: super::HookPolicy::Skip,
+        lock_mode: super::LockMode::Reuse,
     });
     let err = result.unwrap_err();
     assert_data_eq!(
@@ -609,6 +627,7 @@ fn parse_add_command(args: &[&str]) -> ParsedAdd {
             template,
             define,
             overwrite,
+            ..
         } => ParsedAdd {
             _battery_pack: battery_pack,
             crates,
@@ -1215,9 +1234,11 @@ fn add(
         no_default_features,
         all_features,
         &specific,
+        &BTreeMap::new(),
         Some(fixture_path.to_str().unwrap()),
-        &crate::registry::CrateSource::Registry,
+        &crate::registry::CrateSource::crates_io(),
         project_dir,
+        super::DryRun::Off,
     )
     .unwrap();
 }
@@ -1641,6 +1662,177 @@ clap = { version = "4", features = ["derive"] }
     );
 }
 
+// ============================================================================
+// tui.nav.exit — the installed-pack screen writes its selection
+// ============================================================================
+
+// [verify tui.nav.exit]
+#[test]
+fn apply_installed_selection_adds_and_removes_crates() {
+    let tmp = make_temp_project();
+    add(
+        "fancy",
+        "fancy-battery-pack",
+        &[],
+        FeatureMode::Default,
+        &[],
+        tmp.path(),
+    );
+
+    let set = |items: &[&str]| items.iter().map(|s| s.to_string()).collect();
+    super::apply_installed_selection(
+        tmp.path(),
+        &crate::registry::CrateSource::crates_io(),
+        &load_fancy_spec(),
+        &set(&["indicators"]),
+        &set(&["clap", "console", "indicatif"]),
+        &BTreeMap::from([("clap".to_string(), bphelper_manifest::DepKind::Dev)]),
+    )
+    .unwrap();
+
+    let content = read_cargo_toml(&tmp);
+    assert_data_eq!(
+        extract_section(&content, "[dependencies]"),
+        str![[r#"
+[dependencies]
+console = "0.15"
+indicatif = "0.17"
+
+"#]]
+    );
+    // [verify tui.installed.dep-kind]
+    assert_data_eq!(
+        extract_section(&content, "[dev-dependencies]"),
+        str![[r#"
+[dev-dependencies]
+assert_cmd = "2.0"
+predicates = "3.0"
+clap = { version = "4", features = ["derive"] }
+
+
+"#]]
+    );
+    let entry = extract_state_entry(&read_bp_state_toml(&tmp), "fancy").unwrap();
+    assert_eq!(
+        entry["features"],
+        toml::Value::Array(vec!["indicators".into()])
+    );
+    assert_eq!(entry["dep-kinds"]["clap"].as_str(), Some("dev"));
+}
+
+#[test]
+fn apply_installed_selection_rejects_empty_selection() {
+    let tmp = make_temp_project();
+    let err = super::apply_installed_selection(
+        tmp.path(),
+        &crate::registry::CrateSource::crates_io(),
+        &load_fancy_spec(),
+        &BTreeSet::new(),
+        &BTreeSet::new(),
+        &BTreeMap::new(),
+    )
+    .unwrap_err();
+    assert_data_eq!(
+        err.to_string(),
+        str!["No crates selected. To remove the pack, run `cargo bp rm fancy`."]
+    );
+}
+
+// ============================================================================
+// cli.registry.dependency — packs from another registry name it
+// ============================================================================
+
+// [verify cli.registry.dependency]
+#[test]
+fn battery_pack_dep_names_alternative_registry() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(tmp.path().join(".cargo")).unwrap();
+    std::fs::write(
+        tmp.path().join(".cargo/config.toml"),
+        "[registries.company]\nindex = \"sparse+https://example.com/index/\"\n",
+    )
+    .unwrap();
+    let registry = crate::registry::Registry::from_cargo_config("company", tmp.path()).unwrap();
+
+    let item =
+        super::battery_pack_dep_item("0.2.0", &crate::registry::CrateSource::Registry(registry));
+    assert_data_eq!(
+        item.to_string(),
+        str![[r#"{ version = "0.2.0", registry = "company" }"#]]
+    );
+
+    let item = super::battery_pack_dep_item("0.2.0", &crate::registry::CrateSource::crates_io());
+    assert_data_eq!(item.to_string(), str![[r#""0.2.0""#]]);
+}
+
+// ============================================================================
+// cli.add.pin / cli.update.raise — version requirements
+// ============================================================================
+
+// [verify cli.add.pin]
+#[test]
+fn parse_pack_arg_splits_requirement() {
+    assert_eq!(super::parse_pack_arg("cli").unwrap(), ("cli", None));
+    assert_eq!(
+        super::parse_pack_arg("cli@0.4").unwrap(),
+        ("cli", Some("0.4"))
+    );
+    assert_eq!(
+        super::parse_pack_arg("cli-battery-pack@>=0.4, <0.6").unwrap(),
+        ("cli-battery-pack", Some(">=0.4, <0.6"))
+    );
+
+    let err = super::parse_pack_arg("cli@latest").unwrap_err();
+    assert_data_eq!(
+        err.to_string(),
+        str!["invalid version requirement 'latest' for 'cli'"]
+    );
+}
+
+// [verify cli.update.raise]
+#[test]
+fn raise_version_req_keeps_operator_and_precision() {
+    let latest = semver::Version::parse("1.4.2").unwrap();
+    let cases = [
+        ("0.4", "1.4"),
+        ("1", "1"),
+        ("^0.4.1", "^1.4.2"),
+        ("~1.2.3", "~1.4.2"),
+        ("=0.4", "=1.4"),
+        (">=0.4, <0.6", "1.4.2"),
+        ("0.4.*", "1.4.2"),
+        // Requirements that already admit the latest version are kept.
+        (">=0.4", ">=0.4"),
+        ("<2", "<2"),
+        ("*", "*"),
+        ("1.2", "1.2"),
+        // An upper bound below it can't be raised, so it's replaced.
+        ("<0.6", "1.4.2"),
+    ];
+    for (req, expected) in cases {
+        assert_eq!(super::raise_version_req(req, &latest), expected, "{req}");
+    }
+}
+
+// [verify cli.update.raise]
+// [verify cli.update.explicit]
+#[test]
+fn cli_parses_update_with_optional_pack() {
+    let cli = super::Cli::try_parse_from(["cargo", "bp", "update"]).unwrap();
+    match unwrap_bp_command(cli) {
+        super::BpCommands::Update { battery_pack } => assert_eq!(battery_pack, None),
+        other => panic!("expected Update, got {:?}", std::mem::discriminant(&other)),
+    }
+
+    let cli = super::Cli::try_parse_from(["cargo", "bp", "update", "cli@0.5"]).unwrap();
+    match unwrap_bp_command(cli) {
+        super::BpCommands::Update { battery_pack } => {
+            assert_eq!(battery_pack.as_deref(), Some("cli@0.5"));
+        }
+        other => panic!("expected Update, got {:?}", std::mem::discriminant(&other)),
+    }
+}
+
 // ============================================================================
 // cli.add — metadata written to battery-pack.toml
 // ============================================================================
@@ -2555,7 +2747,7 @@ fn build_show_report_populates_categories_from_fixture() {
     let report = super::build_show_report(
         "category-battery-pack",
         Some(fixture.to_str().unwrap()),
-        &super::CrateSource::Registry,
+        &super::CrateSource::crates_io(),
         &fixture,
     )
     .unwrap();
@@ -2580,3 +2772,277 @@ fn build_show_report_populates_categories_from_fixture() {
     assert!(utils.members.contains(&"heapless".to_string()));
     assert!(utils.members.contains(&"logging".to_string()));
 }
+
+// --- cargo bp check ---
+
+fn check_spec(version: &str, features: &[&str]) -> CrateSpec {
+    CrateSpec {
+        version: version.to_string(),
+        features: features.iter().map(|f| f.to_string()).collect(),
+        dep_kind: bphelper_manifest::DepKind::Normal,
+        optional: false,
+        ..Default::default()
+    }
+}
+
+fn check_kinds(issues: &[cargo_bp_script::CheckIssue]) -> Vec<cargo_bp_script::CheckIssueKind> {
+    issues.iter().map(|i| i.kind).collect()
+}
+
+// [verify cli.check.version-drift]
+#[test]
+fn check_reports_outdated_version() {
+    let raw: toml::Value = toml::from_str("[dependencies]\nclap = \"4.1\"\n").unwrap();
+    let expected = BTreeMap::from([("clap".to_string(), check_spec("4.5", &[]))]);
+
+    let issues = super::check_expected_deps(&expected, &raw, None);
+    assert_eq!(issues.len(), 1);
+    assert_eq!(
+        issues[0].kind,
+        cargo_bp_script::CheckIssueKind::OutdatedVersion
+    );
+    assert_eq!(issues[0].current_version.as_deref(), Some("4.1"));
+    assert_eq!(issues[0].recommended_version.as_deref(), Some("4.5"));
+    assert_eq!(issues[0].message, "clap: 4.1 → 4.5");
+}
+
+// [verify cli.check.version-drift]
+#[test]
+fn check_compares_partial_versions() {
+    // "1" is older than "1.2", even though neither is a full semver version.
+    let raw: toml::Value = toml::from_str("[dependencies]\nanyhow = \"1\"\n").unwrap();
+    let expected = BTreeMap::from([("anyhow".to_string(), check_spec("1.2", &[]))]);
+    let issues = super::check_expected_deps(&expected, &raw, None);
+    assert_eq!(
+        check_kinds(&issues),
+        [cargo_bp_script::CheckIssueKind::OutdatedVersion]
+    );
+
+    // A newer version than recommended is fine.
+    let raw: toml::Value = toml::from_str("[dependencies]\nanyhow = \"2\"\n").unwrap();
+    assert!(super::check_expected_deps(&expected, &raw, None).is_empty());
+}
+
+// [verify cli.check.missing-deps]
+#[test]
+fn check_reports_missing_dependency() {
+    let raw: toml::Value = toml::from_str("[dependencies]\n").unwrap();
+    let expected = BTreeMap::from([("dialoguer".to_string(), check_spec("0.11", &[]))]);
+
+    let issues = super::check_expected_deps(&expected, &raw, None);
+    assert_eq!(
+        check_kinds(&issues),
+        [cargo_bp_script::CheckIssueKind::MissingDependency]
+    );
+    assert_eq!(issues[0].recommended_version.as_deref(), Some("0.11"));
+}
+
+// [verify cli.check.missing-deps]
+#[test]
+fn check_finds_dep_in_another_section() {
+    // A crate moved to [dev-dependencies] by the user is still present.
+    let raw: toml::Value = toml::from_str("[dev-dependencies]\nclap = \"4.5\"\n").unwrap();
+    let expected = BTreeMap::from([("clap".to_string(), check_spec("4.5", &[]))]);
+    assert!(super::check_expected_deps(&expected, &raw, None).is_empty());
+}
+
+// [verify cli.check.missing-features]
+#[test]
+fn check_reports_missing_features() {
+    let raw: toml::Value =
+        toml::from_str("[dependencies]\nclap = { version = \"4.5\", features = [\"derive\"] }\n")
+            .unwrap();
+    let expected = BTreeMap::from([(
+        "clap".to_string(),
+        check_spec("4.5", &["derive", "color", "env"]),
+    )]);
+
+    let issues = super::check_expected_deps(&expected, &raw, None);
+    assert_eq!(
+        check_kinds(&issues),
+        [cargo_bp_script::CheckIssueKind::MissingFeatures]
+    );
+    assert_eq!(issues[0].missing_features, ["color", "env"]);
+}
+
+// [verify cli.check.workspace-refs]
+#[test]
+fn check_reports_workspace_ref_without_entry() {
+    let raw: toml::Value = toml::from_str("[dependencies]\nclap = { workspace = true }\n").unwrap();
+    let ws_deps = toml::map::Map::new();
+    let expected = BTreeMap::from([("clap".to_string(), check_spec("4.5", &[]))]);
+
+    let issues = super::check_expected_deps(&expected, &raw, Some(&ws_deps));
+    assert_eq!(
+        check_kinds(&issues),
+        [cargo_bp_script::CheckIssueKind::WorkspaceRefMismatch]
+    );
+}
+
+// [verify cli.check.workspace-refs]
+#[test]
+fn check_reports_member_version_shadowing_workspace() {
+    let raw: toml::Value = toml::from_str("[dependencies]\nclap = \"4.5\"\n").unwrap();
+    let ws: toml::Value = toml::from_str("clap = \"4.5\"\n").unwrap();
+    let expected = BTreeMap::from([("clap".to_string(), check_spec("4.5", &[]))]);
+
+    let issues = super::check_expected_deps(&expected, &raw, ws.as_table());
+    assert_eq!(
+        check_kinds(&issues),
+        [cargo_bp_script::CheckIssueKind::WorkspaceRefMismatch]
+    );
+}
+
+// [verify cli.check.workspace-refs]
+#[test]
+fn check_merges_workspace_and_member_features() {
+    let raw: toml::Value =
+        toml::from_str("[dependencies]\nclap = { workspace = true, features = [\"env\"] }\n")
+            .unwrap();
+    let ws: toml::Value =
+        toml::from_str("clap = { version = \"4.5\", features = [\"derive\"] }\n").unwrap();
+    let expected = BTreeMap::from([("clap".to_string(), check_spec("4.5", &["derive", "env"]))]);
+
+    assert!(super::check_expected_deps(&expected, &raw, ws.as_table()).is_empty());
+}
+
+// ============================================================================
+// Scripted crate picker
+// ============================================================================
+
+/// Run the crate picker for `spec` on `keys`, returning what was picked and
+/// the last frame drawn.
+fn scripted_pick(
+    spec: &bphelper_manifest::BatteryPackSpec,
+    dep_kinds: &BTreeMap<String, bphelper_manifest::DepKind>,
+    keys: &str,
+) -> (Option<super::PickerResult>, String) {
+    let keys = sectioned_picker::parse_keys(keys).unwrap();
+    let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 20)).unwrap();
+    let result = super::pick_crates_with(
+        spec,
+        &BTreeSet::new(),
+        dep_kinds,
+        None,
+        |title, sections, actions| {
+            sectioned_picker::run_picker_with(title, sections, actions, keys, &mut terminal)
+        },
+    )
+    .unwrap();
+    (result, terminal.backend().to_string())
+}
+
+fn picked_names(result: &super::PickerResult) -> Vec<&str> {
+    result.crates.keys().map(String::as_str).collect()
+}
+
+// [verify tui.picker.scripted]
+#[test]
+fn scripted_pick_toggles_a_crate_and_confirms() {
+    let (result, _) = scripted_pick(&load_fancy_spec(), &BTreeMap::new(), "jjjj <enter>");
+    let result = result.expect("picker was confirmed");
+    assert_eq!(
+        picked_names(&result),
+        ["assert_cmd", "clap", "console", "dialoguer", "predicates"]
+    );
+    assert!(result.dep_kinds.is_empty());
+}
+
+// [verify tui.picker.scripted]
+// [verify cli.add.dep-kind-override]
+#[test]
+fn scripted_pick_changes_a_dep_kind() {
+    let (result, frame) = scripted_pick(&load_fancy_spec(), &BTreeMap::new(), "jjjd");
+    assert!(result.is_none());
+    assert!(
+        frame.contains("> [x] clap (4, features: derive) [dev]"),
+        "{frame}"
+    );
+
+    let (result, _) = scripted_pick(&load_fancy_spec(), &BTreeMap::new(), "jjjd<enter>");
+    assert_eq!(
+        result.expect("picker was confirmed").dep_kinds,
+        BTreeMap::from([("clap".to_string(), bphelper_manifest::DepKind::Dev)])
+    );
+}
+
+// [verify tui.picker.scripted]
+// [verify tui.picker.filter]
+#[test]
+fn scripted_pick_filters_before_toggling() {
+    let (result, _) = scripted_pick(&load_fancy_spec(), &BTreeMap::new(), "/0.15<enter> <enter>");
+    assert_eq!(
+        picked_names(&result.expect("picker was confirmed")),
+        ["assert_cmd", "clap", "console", "dialoguer", "predicates"]
+    );
+}
+
+// [verify tui.picker.scripted]
+#[test]
+fn scripted_pick_without_confirming_is_cancelled() {
+    let (result, frame) = scripted_pick(&load_fancy_spec(), &BTreeMap::new(), "");
+    assert!(result.is_none());
+    assert_data_eq!(
+        frame,
+        str![[r#"
+"────────────────────────────────────────────────────────────────────────────────"
+" ▼ Features: (pick any number)                                                  "
+" > [ ] ✦ fancy [clap, console, dialoguer, indicatif]                            "
+"   [ ] ✦ indicators [console, indicatif]                                        "
+"                                                                                "
+" ▼ Dependencies: (4 items selected)                                             "
+"   [x] assert_cmd (2.0) [dev]                                                   "
+"   [x] clap (4, features: derive)                                               "
+"   [ ] console (0.15)                                                           "
+"   [x] dialoguer (0.11)                                                         "
+"   [ ] indicatif (0.17)                                                         "
+"   [x] predicates (3.0) [dev]                                                   "
+"                                                                                "
+" ▼ Actions: (pick any number)                                                   "
+"   [ ] Add `default` template — Basic CLI app                                   "
+"   [ ] Add `full` template — Full-featured CLI with indicators                  "
+"                                                                                "
+"                                                                                "
+"                                                                                "
+" fancy-battery-pack v0.2.0  ↑↓/jk Navigate | Space Toggle | ←/→ Collapse/expand "
+
+"#]]
+    );
+}
+
+// [verify tui.picker.scripted]
+#[test]
+fn picker_snapshot_renders_the_last_frame() {
+    let frame = crate::PickerSnapshot::new(fixtures_dir().join("fancy-battery-pack"))
+        .keys("jjjj <c-c>")
+        .size(60, 12)
+        .render()
+        .unwrap();
+    assert_data_eq!(
+        frame,
+        str![[r#"
+"────────────────────────────────────────────────────────────"
+" ▼ Features: (pick any number)                              "
+"   [ ] ✦ fancy [clap, console, dialoguer, indicatif]        "
+"   [ ] ✦ indicators [console, indicatif]                    "
+"                                                            "
+" ▼ Dependencies: (5 items selected)                         "
+"   [x] assert_cmd (2.0) [dev]                               "
+"   [x] clap (4, features: derive)                           "
+" > [x] console (0.15)                                       "
+"   [x] dialoguer (0.11)                                     "
+"   [ ] indicatif (0.17)                                     "
+" fancy-battery-pack v0.2.0  ↑↓/jk Navigate | Space Toggle | "
+
+"#]]
+    );
+
+    let err = crate::PickerSnapshot::new(fixtures_dir().join("fancy-battery-pack"))
+        .keys("<pgdn>")
+        .render()
+        .unwrap_err();
+    assert_data_eq!(
+        err.to_string(),
+        str![[r#"unknown key `<pgdn>` in key sequence "<pgdn>""#]]
+    );
+}
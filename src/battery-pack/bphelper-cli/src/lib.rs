@@ -1,12 +1,15 @@
 //! CLI for battery-pack: create and manage battery packs.
 
+pub(crate) mod changeset;
 mod commands;
 mod completions;
+pub(crate) mod lockfile;
 pub(crate) mod manifest;
 pub(crate) mod merge;
 pub(crate) mod registry;
 pub(crate) mod template_engine;
 mod tui;
+pub(crate) mod undo;
 mod validate;
 
 // The only true public API
@@ -71,14 +74,62 @@ impl PreviewBuilder {
             defines: self.defines,
             active_features: std::collections::BTreeSet::new(),
             interactive_override: Some(false),
+            source: registry::CrateSource::crates_io(),
         };
         let files = template_engine::preview(opts)?;
         Ok(files
             .into_iter()
             .map(|f| PreviewFile {
+                content: f.display_content().into_owned(),
                 path: f.path,
-                content: f.content,
             })
             .collect())
     }
 }
+
+/// Builder for snapshotting the `cargo bp add` picker for a battery pack.
+///
+/// Runs the picker on the pack's own `Cargo.toml` with a recorded key
+/// sequence (see [`sectioned_picker::parse_keys`]) and returns the last frame
+/// drawn, one quoted line per terminal row.
+///
+/// ```rust,ignore
+/// let frame = battery_pack::testing::PickerSnapshot::new(env!("CARGO_MANIFEST_DIR"))
+///     .keys("jj ")
+///     .render()
+///     .unwrap();
+/// assert!(frame.contains("> [x] clap"));
+/// ```
+pub struct PickerSnapshot {
+    crate_root: std::path::PathBuf,
+    keys: String,
+    width: u16,
+    height: u16,
+}
+
+impl PickerSnapshot {
+    pub fn new(crate_root: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            crate_root: crate_root.into(),
+            keys: String::new(),
+            width: 80,
+            height: 24,
+        }
+    }
+
+    pub fn keys(mut self, keys: impl Into<String>) -> Self {
+        self.keys = keys.into();
+        self
+    }
+
+    pub fn size(mut self, width: u16, height: u16) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn render(self) -> anyhow::Result<String> {
+        let keys = sectioned_picker::parse_keys(&self.keys)?;
+        commands::render_picker_snapshot(&self.crate_root, keys, self.width, self.height)
+    }
+}
@@ -0,0 +1,371 @@
+//! `battery-pack.lock`: the exact battery pack releases a crate was synced
+//! against.
+//!
+//! `battery-pack.toml` records what the user chose; the lock file records
+//! what that resolved to — each pack's version, the registry it came from and
+//! the SHA-256 of its `.crate` tarball — so `sync` gives the same result on
+//! every machine until the lock is deliberately moved forward. Packs read
+//! from a local directory (`--path`, `--crate-source`) have no tarball and
+//! are not locked.
+
+use anyhow::{Context, Result, bail};
+use bphelper_manifest::{BatteryPackSpec, CrateSpec, parse_battery_pack_from_path};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::changeset::Changeset;
+use crate::registry::{
+    CrateSource, Registry, fetch_bp_spec, fetch_verified_crate_dir, find_local_battery_pack_dir,
+    load_installed_bp_spec, lookup_crate, resolve_crate_name,
+};
+
+const LOCK_FILE_NAME: &str = "battery-pack.lock";
+
+const LOCK_FORMAT_VERSION: u32 = 1;
+
+const LOCK_HEADER: &str =
+    "# This file is generated by cargo-bp. It is not intended for manual editing.\n";
+
+/// The contents of `battery-pack.lock`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Lockfile {
+    version: u32,
+    #[serde(default, rename = "pack")]
+    packs: Vec<LockedPack>,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self {
+            version: LOCK_FORMAT_VERSION,
+            packs: Vec::new(),
+        }
+    }
+}
+
+/// One resolved battery pack.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct LockedPack {
+    /// Full crate name, e.g. `cli-battery-pack`.
+    pub name: String,
+    pub version: String,
+    /// The registry, as [`Registry::source_id`].
+    pub source: String,
+    /// SHA-256 of the `.crate` tarball.
+    pub checksum: String,
+}
+
+/// Return the lock file path for a user `Cargo.toml`.
+// [impl manifest.lock.location]
+pub(crate) fn lock_file_path(user_manifest_path: &Path) -> PathBuf {
+    user_manifest_path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(LOCK_FILE_NAME)
+}
+
+/// Read `battery-pack.lock` including any edits staged in `changes`. A
+/// missing file is an empty lock.
+pub(crate) fn read_lockfile(changes: &Changeset, lock_path: &Path) -> Result<Lockfile> {
+    let Some(content) = changes.read(lock_path)? else {
+        return Ok(Lockfile::default());
+    };
+    let lock: Lockfile = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", lock_path.display()))?;
+    if lock.version > LOCK_FORMAT_VERSION {
+        bail!(
+            "{} has version {}, but this tool only supports version {}. Please upgrade cargo-bp.",
+            lock_path.display(),
+            lock.version,
+            LOCK_FORMAT_VERSION,
+        );
+    }
+    Ok(lock)
+}
+
+/// Stage `lock` as the new content of `lock_path`.
+// [impl manifest.lock.format]
+pub(crate) fn stage_lockfile(
+    changes: &mut Changeset,
+    lock_path: &Path,
+    lock: &Lockfile,
+) -> Result<()> {
+    let body = toml::to_string_pretty(lock).context("Failed to serialize battery-pack lock")?;
+    changes.write(lock_path, format!("{LOCK_HEADER}{body}"))
+}
+
+/// Write `lock` as the `battery-pack.lock` of the crate at
+/// `user_manifest_path`.
+pub(crate) fn write_lockfile(user_manifest_path: &Path, lock: &Lockfile) -> Result<()> {
+    let mut changes = Changeset::new();
+    stage_lockfile(&mut changes, &lock_file_path(user_manifest_path), lock)?;
+    changes.commit()
+}
+
+/// Drop `bp_name`'s entry from `battery-pack.lock`, if it has one.
+pub(crate) fn remove_locked_pack(
+    changes: &mut Changeset,
+    user_manifest_path: &Path,
+    bp_name: &str,
+) -> Result<()> {
+    let lock_path = lock_file_path(user_manifest_path);
+    let mut lock = read_lockfile(changes, &lock_path)?;
+    let crate_name = resolve_crate_name(bp_name);
+    let before = lock.packs.len();
+    lock.packs.retain(|p| p.name != crate_name);
+    if lock.packs.len() != before {
+        stage_lockfile(changes, &lock_path, &lock)?;
+    }
+    Ok(())
+}
+
+/// How resolution treats `battery-pack.lock`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum LockMode {
+    /// Reuse locked versions that still satisfy the pack's requirement, and
+    /// lock whatever else is resolved.
+    #[default]
+    Reuse,
+    /// Like `Reuse`, but fail if the lock would change (`--locked`).
+    Locked,
+    /// Resolve afresh and overwrite the lock (`cargo bp update`).
+    Refresh,
+}
+
+impl LockMode {
+    pub(crate) fn from_flags(locked: bool) -> Self {
+        if locked {
+            LockMode::Locked
+        } else {
+            LockMode::Reuse
+        }
+    }
+}
+
+/// Resolves battery packs from a source, reproducing the versions recorded in
+/// a lock file and recording the ones it resolves.
+pub(crate) struct PackResolver<'a> {
+    source: &'a CrateSource,
+    mode: LockMode,
+    /// The lock as read.
+    lock: Lockfile,
+    /// Packs resolved so far, by crate name.
+    resolved: BTreeMap<String, LockedPack>,
+}
+
+impl<'a> PackResolver<'a> {
+    pub(crate) fn new(source: &'a CrateSource, lock: Lockfile, mode: LockMode) -> Self {
+        Self {
+            source,
+            mode,
+            lock,
+            resolved: BTreeMap::new(),
+        }
+    }
+
+    /// A resolver for the crate at `user_manifest_path`, reading its
+    /// `battery-pack.lock` (with any staged edits) if there is one.
+    pub(crate) fn for_manifest(
+        source: &'a CrateSource,
+        changes: &Changeset,
+        user_manifest_path: &Path,
+        mode: LockMode,
+    ) -> Result<Self> {
+        let lock = read_lockfile(changes, &lock_file_path(user_manifest_path))?;
+        Ok(Self::new(source, lock, mode))
+    }
+
+    /// The locked entry for `crate_name` from `registry`, unless refreshing.
+    fn locked(&self, registry: &Registry, crate_name: &str) -> Option<&LockedPack> {
+        if self.mode == LockMode::Refresh {
+            return None;
+        }
+        let source = registry.source_id();
+        self.lock
+            .packs
+            .iter()
+            .find(|p| p.name == crate_name && p.source == source)
+    }
+
+    /// Fetch `version` of `crate_name`, checking it against the lock when the
+    /// lock has that version, and record it.
+    fn fetch_locked(
+        &mut self,
+        registry: &Registry,
+        crate_name: &str,
+        version: &str,
+    ) -> Result<PathBuf> {
+        let expected = self
+            .locked(registry, crate_name)
+            .filter(|p| p.version == version)
+            .map(|p| p.checksum.clone());
+        let (dir, checksum) =
+            fetch_verified_crate_dir(registry, crate_name, version, expected.as_deref())?;
+        if let Some(checksum) = checksum.or(expected) {
+            self.resolved.insert(
+                crate_name.to_string(),
+                LockedPack {
+                    name: crate_name.to_string(),
+                    version: version.to_string(),
+                    source: registry.source_id(),
+                    checksum,
+                },
+            );
+        }
+        Ok(dir)
+    }
+
+    /// Resolve a battery pack to its crate directory: the locked version if
+    /// it still matches `req`, otherwise the newest version that does.
+    /// Returns the version too, except for a local workspace.
+    // [impl manifest.lock.reuse]
+    pub(crate) fn fetch_dir(
+        &mut self,
+        name: &str,
+        req: Option<&semver::VersionReq>,
+    ) -> Result<(Option<String>, PathBuf)> {
+        let crate_name = resolve_crate_name(name);
+        let registry = match self.source {
+            CrateSource::Registry(registry) => registry,
+            CrateSource::Local(workspace_dir) => {
+                return Ok((
+                    None,
+                    find_local_battery_pack_dir(workspace_dir, &crate_name)?,
+                ));
+            }
+        };
+        let locked = self.locked(registry, &crate_name).filter(|p| {
+            req.is_none_or(|req| {
+                semver::Version::parse(&p.version).is_ok_and(|version| req.matches(&version))
+            })
+        });
+        let version = match locked {
+            Some(locked) => locked.version.clone(),
+            None => lookup_crate(registry, &crate_name, req)?.version,
+        };
+        let dir = self.fetch_locked(registry, &crate_name, &version)?;
+        Ok((Some(version), dir))
+    }
+
+    /// Like [`fetch_bp_spec`], but through the lock.
+    pub(crate) fn fetch_spec(
+        &mut self,
+        name: &str,
+        req: Option<&semver::VersionReq>,
+    ) -> Result<(Option<String>, BatteryPackSpec)> {
+        if matches!(self.source, CrateSource::Local(_)) {
+            return fetch_bp_spec(self.source, name, req);
+        }
+        let (version, dir) = self.fetch_dir(name, req)?;
+        let spec = parse_battery_pack_from_path(&dir.join("Cargo.toml"))
+            .with_context(|| format!("Failed to parse battery pack '{}'", name))?;
+        Ok((version, spec))
+    }
+
+    /// Like [`load_installed_bp_spec`], but through the lock.
+    ///
+    /// A pinned pack is resolved from the registry, reusing its locked
+    /// version. An unpinned pack follows the version cargo resolved for the
+    /// build-dependency, which is then locked with its checksum.
+    pub(crate) fn installed_spec(
+        &mut self,
+        bp_name: &str,
+        path: Option<&str>,
+        req: Option<&semver::VersionReq>,
+    ) -> Result<BatteryPackSpec> {
+        let registry = match self.source {
+            CrateSource::Registry(registry) if path.is_none() => registry,
+            _ => return load_installed_bp_spec(bp_name, path, self.source, req),
+        };
+        if req.is_some() {
+            return self.fetch_spec(bp_name, req).map(|(_, spec)| spec);
+        }
+        let spec = load_installed_bp_spec(bp_name, None, self.source, None)?;
+        self.fetch_locked(registry, &resolve_crate_name(bp_name), &spec.version)?;
+        Ok(spec)
+    }
+
+    /// Expand the battery packs in a resolved crate set into their own
+    /// crates, fetching each at the version its entry requires (or its
+    /// locked version, if that still matches).
+    ///
+    /// `root` is the pack `crates` was resolved from.
+    // [impl cli.add.extends]
+    pub(crate) fn expand_extended_packs(
+        &mut self,
+        root: &str,
+        crates: BTreeMap<String, CrateSpec>,
+    ) -> Result<BTreeMap<String, CrateSpec>> {
+        bphelper_manifest::expand_extended_packs(root, crates, &mut |name, entry| {
+            let req = semver::VersionReq::parse(&entry.version).ok();
+            self.fetch_spec(name, req.as_ref())
+                .map(|(_version, spec)| spec)
+                .with_context(|| format!("Failed to load extended battery pack '{name}'"))
+        })
+    }
+
+    /// Just the packs resolved so far, as the lock of a new project; `None`
+    /// if none was locked.
+    pub(crate) fn resolved_lock(&self) -> Option<Lockfile> {
+        (!self.resolved.is_empty()).then(|| Lockfile {
+            version: LOCK_FORMAT_VERSION,
+            packs: self.resolved.values().cloned().collect(),
+        })
+    }
+
+    /// The lock with every pack resolved so far recorded in it.
+    pub(crate) fn updated_lock(&self) -> Lockfile {
+        let mut packs: Vec<LockedPack> = self
+            .lock
+            .packs
+            .iter()
+            .filter(|p| !self.resolved.contains_key(&p.name))
+            .cloned()
+            .chain(self.resolved.values().cloned())
+            .collect();
+        packs.sort_by(|a, b| a.name.cmp(&b.name));
+        Lockfile {
+            version: LOCK_FORMAT_VERSION,
+            packs,
+        }
+    }
+
+    /// With `--locked`, fail if resolving changed the lock.
+    // [impl cli.sync.locked]
+    pub(crate) fn ensure_unchanged(&self, lock_path: &Path) -> Result<()> {
+        if self.mode != LockMode::Locked {
+            return Ok(());
+        }
+        let updated = self.updated_lock();
+        let changed: Vec<String> = updated
+            .packs
+            .iter()
+            .filter(|p| !self.lock.packs.contains(p))
+            .map(|p| format!("{} {}", p.name, p.version))
+            .collect();
+        if !changed.is_empty() {
+            bail!(
+                "{} needs to be updated ({}), but --locked was passed",
+                lock_path.display(),
+                changed.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// Stage the updated lock next to `user_manifest_path`, if it changed.
+    // [impl manifest.lock.record]
+    pub(crate) fn finish(self, changes: &mut Changeset, user_manifest_path: &Path) -> Result<()> {
+        let lock_path = lock_file_path(user_manifest_path);
+        self.ensure_unchanged(&lock_path)?;
+        let updated = self.updated_lock();
+        if updated == self.lock {
+            return Ok(());
+        }
+        stage_lockfile(changes, &lock_path, &updated)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,166 @@
+use super::*;
+use snapbox::{assert_data_eq, str};
+
+fn pack(name: &str, version: &str, checksum: &str) -> LockedPack {
+    LockedPack {
+        name: name.to_string(),
+        version: version.to_string(),
+        source: "sparse+https://index.crates.io/".to_string(),
+        checksum: checksum.to_string(),
+    }
+}
+
+fn lock(packs: Vec<LockedPack>) -> Lockfile {
+    Lockfile {
+        version: LOCK_FORMAT_VERSION,
+        packs,
+    }
+}
+
+// [verify manifest.lock.location]
+// [verify manifest.lock.format]
+#[test]
+fn lockfile_round_trips_next_to_the_manifest() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manifest = tmp.path().join("Cargo.toml");
+    let original = lock(vec![pack("cli-battery-pack", "0.4.1", "ab12")]);
+
+    write_lockfile(&manifest, &original).unwrap();
+
+    let path = tmp.path().join("battery-pack.lock");
+    assert_data_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        str![[r#"
+# This file is generated by cargo-bp. It is not intended for manual editing.
+version = 1
+
+[[pack]]
+name = "cli-battery-pack"
+version = "0.4.1"
+source = "sparse+https://index.crates.io/"
+checksum = "ab12"
+
+"#]]
+    );
+    assert_eq!(read_lockfile(&Changeset::new(), &path).unwrap(), original);
+}
+
+#[test]
+fn missing_lockfile_is_empty() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("battery-pack.lock");
+    assert_eq!(
+        read_lockfile(&Changeset::new(), &path).unwrap(),
+        Lockfile::default()
+    );
+}
+
+#[test]
+fn newer_lockfile_version_is_rejected() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("battery-pack.lock");
+    std::fs::write(&path, "version = 2\n").unwrap();
+
+    let err = read_lockfile(&Changeset::new(), &path).unwrap_err();
+    assert!(
+        err.to_string().ends_with(
+            "has version 2, but this tool only supports version 1. Please upgrade cargo-bp."
+        ),
+        "{err}"
+    );
+}
+
+// [verify manifest.lock.record]
+#[test]
+fn updated_lock_keeps_untouched_packs_and_replaces_resolved_ones() {
+    let source = CrateSource::crates_io();
+    let old = lock(vec![
+        pack("cli-battery-pack", "0.4.0", "aa"),
+        pack("error-battery-pack", "0.2.0", "bb"),
+    ]);
+    let mut resolver = PackResolver::new(&source, old, LockMode::Reuse);
+    let ci = pack("ci-battery-pack", "0.1.0", "cc");
+    let cli = pack("cli-battery-pack", "0.4.1", "dd");
+    resolver.resolved.insert(ci.name.clone(), ci.clone());
+    resolver.resolved.insert(cli.name.clone(), cli.clone());
+
+    assert_eq!(
+        resolver.updated_lock(),
+        lock(vec![
+            ci.clone(),
+            cli.clone(),
+            pack("error-battery-pack", "0.2.0", "bb"),
+        ])
+    );
+    assert_eq!(resolver.resolved_lock(), Some(lock(vec![ci, cli])));
+}
+
+// [verify cli.sync.locked]
+#[test]
+fn locked_mode_rejects_a_changed_lock() {
+    let source = CrateSource::crates_io();
+    let old = lock(vec![pack("cli-battery-pack", "0.4.0", "aa")]);
+    let path = Path::new("battery-pack.lock");
+
+    let mut resolver = PackResolver::new(&source, old.clone(), LockMode::Locked);
+    let same = pack("cli-battery-pack", "0.4.0", "aa");
+    resolver.resolved.insert(same.name.clone(), same);
+    resolver.ensure_unchanged(path).unwrap();
+
+    let newer = pack("cli-battery-pack", "0.4.1", "dd");
+    resolver.resolved.insert(newer.name.clone(), newer);
+    let err = resolver.ensure_unchanged(path).unwrap_err();
+    assert_data_eq!(
+        err.to_string(),
+        str![
+            "battery-pack.lock needs to be updated (cli-battery-pack 0.4.1), but --locked was passed"
+        ]
+    );
+
+    // Without --locked the same change is simply recorded.
+    let mut resolver = PackResolver::new(&source, old, LockMode::Reuse);
+    resolver
+        .resolved
+        .insert("x".to_string(), pack("x-battery-pack", "1.0.0", "ee"));
+    resolver.ensure_unchanged(path).unwrap();
+}
+
+#[test]
+fn unchanged_lock_is_not_rewritten() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manifest = tmp.path().join("Cargo.toml");
+    let source = CrateSource::crates_io();
+
+    let mut changes = Changeset::new();
+    PackResolver::for_manifest(&source, &changes, &manifest, LockMode::Reuse)
+        .unwrap()
+        .finish(&mut changes, &manifest)
+        .unwrap();
+    changes.commit().unwrap();
+
+    assert!(!tmp.path().join("battery-pack.lock").exists());
+}
+
+#[test]
+fn removing_a_pack_drops_its_entry() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manifest = tmp.path().join("Cargo.toml");
+    write_lockfile(
+        &manifest,
+        &lock(vec![
+            pack("cli-battery-pack", "0.4.0", "aa"),
+            pack("error-battery-pack", "0.2.0", "bb"),
+        ]),
+    )
+    .unwrap();
+
+    let mut changes = Changeset::new();
+    remove_locked_pack(&mut changes, &manifest, "cli").unwrap();
+    changes.commit().unwrap();
+
+    let path = lock_file_path(&manifest);
+    assert_eq!(
+        read_lockfile(&Changeset::new(), &path).unwrap(),
+        lock(vec![pack("error-battery-pack", "0.2.0", "bb")])
+    );
+}
@@ -79,6 +79,66 @@ pub(crate) fn find_workspace_manifest(crate_manifest: &Path) -> Result<Option<Pa
     Ok(None)
 }
 
+/// The root `Cargo.toml` of the workspace containing `project_dir`, as
+/// reported by `cargo metadata`. Unlike [`find_workspace_manifest`], this
+/// finds a root that is also a package from inside that package.
+pub(crate) fn find_workspace_root_manifest(project_dir: &Path) -> Result<PathBuf> {
+    let metadata = cargo_metadata::MetadataCommand::new()
+        .current_dir(project_dir)
+        .no_deps()
+        .exec()
+        .context("Failed to run `cargo metadata`")?;
+    Ok(metadata.workspace_root.join("Cargo.toml").into())
+}
+
+/// A workspace member package, as reported by `cargo metadata`.
+#[derive(Debug, Clone)]
+pub(crate) struct WorkspaceMember {
+    pub(crate) name: String,
+    pub(crate) manifest_path: PathBuf,
+}
+
+/// List the members of the workspace containing `project_dir`, sorted by name.
+///
+/// With an empty `packages` filter every member is returned. Otherwise only
+/// the named members are, and a name that isn't a member is an error, like
+/// `cargo -p`.
+pub(crate) fn find_workspace_members(
+    project_dir: &Path,
+    packages: &[String],
+) -> Result<Vec<WorkspaceMember>> {
+    let metadata = cargo_metadata::MetadataCommand::new()
+        .current_dir(project_dir)
+        .no_deps()
+        .exec()
+        .context("Failed to run `cargo metadata`")?;
+
+    let mut members: Vec<WorkspaceMember> = metadata
+        .workspace_packages()
+        .into_iter()
+        .map(|pkg| WorkspaceMember {
+            name: pkg.name.to_string(),
+            manifest_path: pkg.manifest_path.clone().into(),
+        })
+        .collect();
+    members.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if packages.is_empty() {
+        return Ok(members);
+    }
+    for requested in packages {
+        if !members.iter().any(|m| &m.name == requested) {
+            let names: Vec<_> = members.iter().map(|m| m.name.as_str()).collect();
+            bail!(
+                "package '{requested}' is not a member of the workspace. Members: {}",
+                names.join(", ")
+            );
+        }
+    }
+    members.retain(|m| packages.contains(&m.name));
+    Ok(members)
+}
+
 // ============================================================================
 // Dependency section helpers
 // ============================================================================
@@ -92,6 +152,87 @@ pub(crate) fn dep_kind_section(kind: bphelper_manifest::DepKind) -> &'static str
     }
 }
 
+/// Every dependency table in a parsed Cargo.toml: the unconditional
+/// `[dependencies]`, `[dev-dependencies]` and `[build-dependencies]` first,
+/// then their `[target.'<cfg>'.*]` mirrors.
+pub(crate) fn dep_tables(raw: &toml::Value) -> Vec<&toml::map::Map<String, toml::Value>> {
+    const SECTIONS: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+    let targets = raw
+        .get("target")
+        .and_then(|t| t.as_table())
+        .into_iter()
+        .flat_map(|t| t.values());
+    std::iter::once(raw)
+        .chain(targets)
+        .flat_map(|parent| SECTIONS.iter().filter_map(|s| parent.get(*s)?.as_table()))
+        .collect()
+}
+
+/// Return the table a crate's entry lives in, creating it if needed:
+/// `[target.'<cfg>'.<section>]` for platform-gated crates, `[<section>]`
+/// otherwise.
+// [impl manifest.deps.target]
+pub(crate) fn dep_table_mut<'a>(
+    doc: &'a mut toml_edit::DocumentMut,
+    spec: &bphelper_manifest::CrateSpec,
+) -> Option<&'a mut toml_edit::Table> {
+    let section = dep_kind_section(spec.dep_kind);
+    let parent = match &spec.target {
+        Some(cfg) => {
+            let mut implicit = toml_edit::Table::new();
+            implicit.set_implicit(true);
+            let targets = doc["target"]
+                .or_insert(toml_edit::Item::Table(implicit.clone()))
+                .as_table_mut()?;
+            targets
+                .entry(cfg)
+                .or_insert(toml_edit::Item::Table(implicit))
+                .as_table_mut()?
+        }
+        None => doc.as_table_mut(),
+    };
+    parent
+        .entry(section)
+        .or_insert(toml_edit::Item::Table(toml_edit::Table::new()))
+        .as_table_mut()
+}
+
+/// Like [`dep_table_mut`], but never creates the table.
+fn existing_dep_table_mut<'a>(
+    doc: &'a mut toml_edit::DocumentMut,
+    spec: &bphelper_manifest::CrateSpec,
+) -> Option<&'a mut toml_edit::Table> {
+    // `Table::get_mut` rather than `Item::get_mut`: indexing an item inserts
+    // an empty placeholder for a missing key, which would then block
+    // `dep_table_mut` from creating the real table.
+    let section = dep_kind_section(spec.dep_kind);
+    let parent = match &spec.target {
+        Some(cfg) => doc
+            .as_table_mut()
+            .get_mut("target")?
+            .as_table_mut()?
+            .get_mut(cfg)?
+            .as_table_mut()?,
+        None => doc.as_table_mut(),
+    };
+    parent.get_mut(section)?.as_table_mut()
+}
+
+/// One copy of `spec` per section it goes in (see
+/// [`CrateSpec::dep_kinds`](bphelper_manifest::CrateSpec::dep_kinds)), each
+/// with that section's `dep_kind`.
+// [impl manifest.merge.dep-kind]
+pub(crate) fn specs_by_kind(
+    spec: &bphelper_manifest::CrateSpec,
+) -> impl Iterator<Item = bphelper_manifest::CrateSpec> + '_ {
+    spec.dep_kinds()
+        .map(|dep_kind| bphelper_manifest::CrateSpec {
+            dep_kind,
+            extra_dep_kinds: Vec::new(),
+            ..spec.clone()
+        })
+}
+
 /// Write dependencies (with full version+features) to the correct sections by `dep_kind`.
 ///
 /// When `if_missing` is true, only inserts crates that don't already exist in
@@ -104,13 +245,13 @@ pub(crate) fn write_deps_by_kind(
 ) -> usize {
     let mut written = 0;
     for (dep_name, dep_spec) in crates {
-        let section = dep_kind_section(dep_spec.dep_kind);
-        let table = doc[section].or_insert(toml_edit::Item::Table(toml_edit::Table::new()));
-        if let Some(table) = table.as_table_mut()
-            && (!if_missing || !table.contains_key(dep_name))
-        {
-            add_dep_to_table(table, dep_name, dep_spec);
-            written += 1;
+        for dep_spec in specs_by_kind(dep_spec) {
+            if let Some(table) = dep_table_mut(doc, &dep_spec)
+                && (!if_missing || !table.contains_key(dep_name))
+            {
+                add_dep_to_table(table, dep_name, &dep_spec);
+                written += 1;
+            }
         }
     }
     written
@@ -129,26 +270,34 @@ pub(crate) fn write_workspace_refs_by_kind(
 ) -> usize {
     let mut written = 0;
     for (dep_name, dep_spec) in crates {
-        let section = dep_kind_section(dep_spec.dep_kind);
-        let table = doc[section].or_insert(toml_edit::Item::Table(toml_edit::Table::new()));
-        if let Some(table) = table.as_table_mut()
-            && (!if_missing || !table.contains_key(dep_name))
-        {
-            let mut dep = toml_edit::InlineTable::new();
-            dep.insert("workspace", toml_edit::Value::from(true));
-            table.insert(
-                dep_name,
-                toml_edit::Item::Value(toml_edit::Value::InlineTable(dep)),
-            );
-            written += 1;
+        for dep_spec in specs_by_kind(dep_spec) {
+            if let Some(table) = dep_table_mut(doc, &dep_spec)
+                && (!if_missing || !table.contains_key(dep_name))
+            {
+                let mut dep = toml_edit::InlineTable::new();
+                dep.insert("workspace", toml_edit::Value::from(true));
+                table.insert(
+                    dep_name,
+                    toml_edit::Item::Value(toml_edit::Value::InlineTable(dep)),
+                );
+                written += 1;
+            }
         }
     }
     written
 }
 
 /// Add a dependency to a toml_edit table (non-workspace mode).
+///
+/// A plain version string is written when that's all the spec needs;
+/// otherwise an inline table carrying the rename, `default-features`,
+/// features and source. The platform gate is not written here: it decides
+/// which table the entry goes in (see [`dep_table_mut`]).
 // [impl manifest.deps.add]
 // [impl manifest.deps.version-features]
+// [impl manifest.deps.rename]
+// [impl manifest.deps.default-features]
+// [impl manifest.deps.source]
 // [impl manifest.toml.style]
 // [impl cli.add.idempotent]
 pub(crate) fn add_dep_to_table(
@@ -156,21 +305,55 @@ pub(crate) fn add_dep_to_table(
     name: &str,
     spec: &bphelper_manifest::CrateSpec,
 ) {
-    if spec.features.is_empty() {
+    let is_simple = spec.features.is_empty()
+        && spec.package.is_none()
+        && spec.default_features
+        && spec.source == bphelper_manifest::DepSource::CratesIo;
+    if is_simple {
         table.insert(name, toml_edit::value(&spec.version));
-    } else {
-        let mut dep = toml_edit::InlineTable::new();
+        return;
+    }
+
+    let mut dep = toml_edit::InlineTable::new();
+    if !spec.version.is_empty() {
         dep.insert("version", toml_edit::Value::from(spec.version.as_str()));
+    }
+    if let Some(package) = &spec.package {
+        dep.insert("package", toml_edit::Value::from(package.as_str()));
+    }
+    match &spec.source {
+        bphelper_manifest::DepSource::CratesIo => {}
+        bphelper_manifest::DepSource::Registry { index } => {
+            dep.insert("registry-index", toml_edit::Value::from(index.as_str()));
+        }
+        bphelper_manifest::DepSource::Git {
+            url,
+            branch,
+            tag,
+            rev,
+        } => {
+            dep.insert("git", toml_edit::Value::from(url.as_str()));
+            for (key, value) in [("branch", branch), ("tag", tag), ("rev", rev)] {
+                if let Some(value) = value {
+                    dep.insert(key, toml_edit::Value::from(value.as_str()));
+                }
+            }
+        }
+    }
+    if !spec.default_features {
+        dep.insert("default-features", toml_edit::Value::from(false));
+    }
+    if !spec.features.is_empty() {
         let mut features = toml_edit::Array::new();
         for feat in &spec.features {
             features.push(feat.as_str());
         }
         dep.insert("features", toml_edit::Value::Array(features));
-        table.insert(
-            name,
-            toml_edit::Item::Value(toml_edit::Value::InlineTable(dep)),
-        );
     }
+    table.insert(
+        name,
+        toml_edit::Item::Value(toml_edit::Value::InlineTable(dep)),
+    );
 }
 
 /// Remove dependencies from the correct sections by `dep_kind`.
@@ -182,16 +365,93 @@ pub(crate) fn remove_deps_by_kind(
 ) -> usize {
     let mut removed = 0;
     for (dep_name, dep_spec) in crates {
-        let section = dep_kind_section(dep_spec.dep_kind);
-        if let Some(table) = doc.get_mut(section).and_then(|t| t.as_table_mut())
-            && table.remove(dep_name).is_some()
-        {
-            removed += 1;
+        for dep_spec in specs_by_kind(dep_spec) {
+            if let Some(table) = existing_dep_table_mut(doc, &dep_spec)
+                && table.remove(dep_name).is_some()
+            {
+                removed += 1;
+            }
         }
     }
     removed
 }
 
+/// Remove `name` from the first dependency table holding it, in the order of
+/// [`dep_tables`], for a crate whose kind and target aren't known (one that
+/// came from an extended pack). Returns whether it was found.
+pub(crate) fn remove_dep_from_any_table(doc: &mut toml_edit::DocumentMut, name: &str) -> bool {
+    const SECTIONS: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+    let root = doc.as_table_mut();
+    let target_names: Vec<String> = root
+        .get("target")
+        .and_then(|t| t.as_table())
+        .map(|t| t.iter().map(|(cfg, _)| cfg.to_string()).collect())
+        .unwrap_or_default();
+    for section in SECTIONS {
+        if let Some(table) = root.get_mut(section).and_then(|t| t.as_table_mut())
+            && table.remove(name).is_some()
+        {
+            return true;
+        }
+    }
+    let Some(targets) = root.get_mut("target").and_then(|t| t.as_table_mut()) else {
+        return false;
+    };
+    for cfg in target_names {
+        for section in SECTIONS {
+            if let Some(table) = targets
+                .get_mut(&cfg)
+                .and_then(|t| t.as_table_mut())
+                .and_then(|t| t.get_mut(section))
+                .and_then(|t| t.as_table_mut())
+                && table.remove(name).is_some()
+            {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Move each crate in `crates` out of the section for the kind it had before,
+/// as given by `previous`, into the section for its kind now. The entry is
+/// moved as written, so user edits to it are kept. Crates that were not in
+/// their previous section are left alone.
+///
+/// Returns the names of the crates moved.
+// [impl manifest.deps.move-kind]
+pub(crate) fn move_deps_to_kind(
+    doc: &mut toml_edit::DocumentMut,
+    crates: &BTreeMap<String, bphelper_manifest::CrateSpec>,
+    previous: &BTreeMap<String, bphelper_manifest::DepKind>,
+) -> Vec<String> {
+    let mut moved = Vec::new();
+    for (dep_name, dep_spec) in crates {
+        let Some(&old_kind) = previous.get(dep_name) else {
+            continue;
+        };
+        if old_kind == dep_spec.dep_kind {
+            continue;
+        }
+        let old_spec = bphelper_manifest::CrateSpec {
+            dep_kind: old_kind,
+            ..dep_spec.clone()
+        };
+        let Some(item) =
+            existing_dep_table_mut(doc, &old_spec).and_then(|table| table.remove(dep_name))
+        else {
+            continue;
+        };
+        if let Some(table) = dep_table_mut(doc, dep_spec)
+            && !table.contains_key(dep_name)
+        {
+            table.insert(dep_name, item);
+        }
+        moved.push(dep_name.clone());
+    }
+    moved
+}
+
 /// Return true when `recommended` is strictly newer than `current` (semver).
 ///
 /// Falls back to string equality when either side is not a valid semver
@@ -247,11 +507,14 @@ pub(crate) fn sync_dep_in_table(
                 } else {
                     current.clone()
                 };
+                // Only version and features are synced; the user's choice of
+                // source, rename and default features is left alone.
                 let patched = bphelper_manifest::CrateSpec {
                     version: keep_version,
                     features: spec.features.clone(),
                     dep_kind: spec.dep_kind,
                     optional: spec.optional,
+                    ..Default::default()
                 };
                 add_dep_to_table(table, name, &patched);
                 changed = true;
@@ -352,6 +615,33 @@ pub(crate) fn sync_dep_in_table(
     changed
 }
 
+/// Set the version of an existing dependency, whatever its form, leaving
+/// other keys untouched. Entries without a version (`workspace = true`,
+/// `path = ...`) are left alone. Returns true if the version changed.
+pub(crate) fn set_dep_version(table: &mut toml_edit::Table, name: &str, version: &str) -> bool {
+    let slot = match table.get_mut(name) {
+        Some(toml_edit::Item::Value(toml_edit::Value::String(v))) => v,
+        Some(toml_edit::Item::Value(toml_edit::Value::InlineTable(inline))) => {
+            match inline.get_mut("version") {
+                Some(toml_edit::Value::String(v)) => v,
+                _ => return false,
+            }
+        }
+        Some(toml_edit::Item::Table(tbl)) => match tbl.get_mut("version") {
+            Some(toml_edit::Item::Value(toml_edit::Value::String(v))) => v,
+            _ => return false,
+        },
+        _ => return false,
+    };
+    if slot.value() == version {
+        return false;
+    }
+    let decor = slot.decor().clone();
+    *slot = toml_edit::Formatted::new(version.to_string());
+    *slot.decor_mut() = decor;
+    true
+}
+
 // ============================================================================
 // Feature reading / writing
 // ============================================================================
@@ -399,9 +689,13 @@ impl Default for BatteryPackStateFile {
 /// `features = [...]` for a specific subset. When `all-features` is true the
 /// `features` array is omitted. v1 files that used `features = ["all"]` as a
 /// sentinel are transparently upgraded on read.
+///
+/// `version` is the semver requirement the pack is pinned to, if any.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct BatteryPackStateEntry {
     name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
     #[serde(rename = "all-features", default, skip_serializing_if = "is_false")]
     all_features: bool,
     #[serde(default = "default_feature_set", skip_serializing_if = "skip_features")]
@@ -413,7 +707,147 @@ struct BatteryPackStateEntry {
         default,
         skip_serializing_if = "Vec::is_empty"
     )]
-    applied_templates: Vec<String>,
+    applied_templates: Vec<AppliedTemplate>,
+    /// Crates the user moved to a different dependency kind than the pack's.
+    #[serde(
+        rename = "dep-kinds",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        with = "dep_kind_names"
+    )]
+    dep_kinds: BTreeMap<String, bphelper_manifest::DepKind>,
+}
+
+/// The name of a dependency kind in `battery-pack.toml` and on the command
+/// line: `normal`, `dev` or `build`.
+pub(crate) fn dep_kind_name(kind: bphelper_manifest::DepKind) -> &'static str {
+    match kind {
+        bphelper_manifest::DepKind::Normal => "normal",
+        bphelper_manifest::DepKind::Dev => "dev",
+        bphelper_manifest::DepKind::Build => "build",
+    }
+}
+
+/// The kind after `kind` when cycling through them: normal, dev, build.
+pub(crate) fn next_dep_kind(kind: bphelper_manifest::DepKind) -> bphelper_manifest::DepKind {
+    match kind {
+        bphelper_manifest::DepKind::Normal => bphelper_manifest::DepKind::Dev,
+        bphelper_manifest::DepKind::Dev => bphelper_manifest::DepKind::Build,
+        bphelper_manifest::DepKind::Build => bphelper_manifest::DepKind::Normal,
+    }
+}
+
+/// Parse a name written by [`dep_kind_name`].
+pub(crate) fn parse_dep_kind(name: &str) -> Option<bphelper_manifest::DepKind> {
+    match name {
+        "normal" => Some(bphelper_manifest::DepKind::Normal),
+        "dev" => Some(bphelper_manifest::DepKind::Dev),
+        "build" => Some(bphelper_manifest::DepKind::Build),
+        _ => None,
+    }
+}
+
+/// (De)serialize a crate → kind map as `{ crate = "dev" }`.
+mod dep_kind_names {
+    use bphelper_manifest::DepKind;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+    use std::collections::BTreeMap;
+
+    pub(super) fn serialize<S: Serializer>(
+        kinds: &BTreeMap<String, DepKind>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        kinds
+            .iter()
+            .map(|(name, kind)| (name, super::dep_kind_name(*kind)))
+            .collect::<BTreeMap<_, _>>()
+            .serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<String, DepKind>, D::Error> {
+        BTreeMap::<String, String>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(name, kind)| match super::parse_dep_kind(&kind) {
+                Some(kind) => Ok((name, kind)),
+                None => Err(D::Error::custom(format!(
+                    "unknown dependency kind '{kind}' for '{name}' (expected normal, dev or build)"
+                ))),
+            })
+            .collect()
+    }
+}
+
+/// A template that was applied from a battery pack.
+///
+/// Records the pack version and placeholder values used at apply time so
+/// `cargo bp upgrade-template` can re-render the original output as the base
+/// of a three-way merge. Entries written before this was tracked are bare
+/// template names and read back with no version or placeholders.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "AppliedTemplateRepr", into = "AppliedTemplateRepr")]
+pub(crate) struct AppliedTemplate {
+    pub(crate) name: String,
+    pub(crate) version: Option<String>,
+    pub(crate) placeholders: BTreeMap<String, String>,
+}
+
+impl AppliedTemplate {
+    /// A template applied with no recorded version or placeholders.
+    pub(crate) fn named(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: None,
+            placeholders: BTreeMap::new(),
+        }
+    }
+}
+
+/// On-disk form of an `applied-templates` entry: a bare name (legacy) or a
+/// table with the version and placeholder values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum AppliedTemplateRepr {
+    Name(String),
+    Detailed {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        version: Option<String>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        placeholders: BTreeMap<String, String>,
+    },
+}
+
+impl From<AppliedTemplateRepr> for AppliedTemplate {
+    fn from(repr: AppliedTemplateRepr) -> Self {
+        match repr {
+            AppliedTemplateRepr::Name(name) => Self::named(name),
+            AppliedTemplateRepr::Detailed {
+                name,
+                version,
+                placeholders,
+            } => Self {
+                name,
+                version,
+                placeholders,
+            },
+        }
+    }
+}
+
+impl From<AppliedTemplate> for AppliedTemplateRepr {
+    fn from(applied: AppliedTemplate) -> Self {
+        if applied.version.is_none() && applied.placeholders.is_empty() {
+            Self::Name(applied.name)
+        } else {
+            Self::Detailed {
+                name: applied.name,
+                version: applied.version,
+                placeholders: applied.placeholders,
+            }
+        }
+    }
 }
 
 fn is_false(v: &bool) -> bool {
@@ -447,17 +881,21 @@ impl BatteryPackStateEntry {
         match active {
             bphelper_manifest::ActiveFeatures::All => Self {
                 name,
+                version: None,
                 all_features: true,
                 features: BTreeSet::new(),
                 managed_deps,
                 applied_templates: Vec::new(),
+                dep_kinds: BTreeMap::new(),
             },
             bphelper_manifest::ActiveFeatures::Subset(set) => Self {
                 name,
+                version: None,
                 all_features: false,
                 features: normalized_feature_set(set),
                 managed_deps,
                 applied_templates: Vec::new(),
+                dep_kinds: BTreeMap::new(),
             },
         }
     }
@@ -469,6 +907,7 @@ struct ManagedDepEntry {
     version: String,
 }
 
+use crate::changeset::Changeset;
 use crate::registry::short_name;
 
 fn state_name_matches(name: &str, bp_name: &str) -> bool {
@@ -491,7 +930,19 @@ fn read_state_file(state_path: &Path) -> Result<BatteryPackStateFile> {
     }
     let content = std::fs::read_to_string(state_path)
         .with_context(|| format!("Failed to read {}", state_path.display()))?;
-    let state: BatteryPackStateFile = toml::from_str(&content)
+    parse_state_file(state_path, &content)
+}
+
+/// Read `battery-pack.toml` including any edits staged in `changes`.
+fn read_staged_state_file(changes: &Changeset, state_path: &Path) -> Result<BatteryPackStateFile> {
+    match changes.read(state_path)? {
+        Some(content) => parse_state_file(state_path, &content),
+        None => Ok(BatteryPackStateFile::default()),
+    }
+}
+
+fn parse_state_file(state_path: &Path, content: &str) -> Result<BatteryPackStateFile> {
+    let state: BatteryPackStateFile = toml::from_str(content)
         .with_context(|| format!("Failed to parse {}", state_path.display()))?;
     if state.version > STATE_FORMAT_VERSION {
         bail!(
@@ -513,13 +964,26 @@ pub(crate) fn state_file_path(user_manifest_path: &Path) -> PathBuf {
 }
 
 fn write_state_file(state_path: &Path, state: &BatteryPackStateFile) -> Result<()> {
+    let mut changes = Changeset::new();
+    stage_state_file(&mut changes, state_path, state)?;
+    changes.commit()
+}
+
+fn stage_state_file(
+    changes: &mut Changeset,
+    state_path: &Path,
+    state: &BatteryPackStateFile,
+) -> Result<()> {
+    changes.write(state_path, serialize_state_file(state)?)
+}
+
+fn serialize_state_file(state: &BatteryPackStateFile) -> Result<String> {
     let mut serialized =
         toml::to_string_pretty(state).context("Failed to serialize battery-pack state")?;
     if !serialized.ends_with('\n') {
         serialized.push('\n');
     }
-    std::fs::write(state_path, serialized)
-        .with_context(|| format!("Failed to write {}", state_path.display()))
+    Ok(serialized)
 }
 
 /// Read active features for a battery pack from `battery-pack.toml` if present.
@@ -553,6 +1017,18 @@ pub(crate) fn read_applied_templates_from_state(
     user_manifest_path: &Path,
     bp_name: &str,
 ) -> Vec<String> {
+    read_applied_template_records(user_manifest_path, bp_name)
+        .into_iter()
+        .map(|applied| applied.name)
+        .collect()
+}
+
+/// Read the full applied-template records (name, version, placeholder values)
+/// for a battery pack from `battery-pack.toml`.
+pub(crate) fn read_applied_template_records(
+    user_manifest_path: &Path,
+    bp_name: &str,
+) -> Vec<AppliedTemplate> {
     let state_path = state_file_path(user_manifest_path);
     let state = match read_state_file(&state_path) {
         Ok(s) => s,
@@ -608,15 +1084,17 @@ pub(crate) fn read_managed_deps_for_project(
     read_managed_deps_from_state(user_manifest_path, bp_name)
 }
 
-/// Upsert battery-pack state in sibling `battery-pack.toml`.
+/// Upsert battery-pack state in sibling `battery-pack.toml`, staged in
+/// `changes`.
 pub(crate) fn write_battery_pack_state(
+    changes: &mut Changeset,
     user_manifest_path: &Path,
     bp_name: &str,
     active_features: &bphelper_manifest::ActiveFeatures,
     managed_crates: &BTreeMap<String, bphelper_manifest::CrateSpec>,
 ) -> Result<()> {
     let state_path = state_file_path(user_manifest_path);
-    let mut state = read_state_file(&state_path)?;
+    let mut state = read_staged_state_file(changes, &state_path)?;
     let managed_deps = managed_crates
         .iter()
         .map(|(name, spec)| ManagedDepEntry {
@@ -625,17 +1103,18 @@ pub(crate) fn write_battery_pack_state(
         })
         .collect::<Vec<_>>();
 
-    // Preserve previously applied templates when only updating deps/features.
-    let prev_templates = state_entry_for(&state, bp_name)
-        .map(|e| e.applied_templates.clone())
-        .unwrap_or_default();
-
     let mut updated = BatteryPackStateEntry::from_active_features(
         short_name(bp_name).to_string(),
         active_features,
         managed_deps,
     );
-    updated.applied_templates = prev_templates;
+    // Preserve the version pin, previously applied templates and dep-kind
+    // overrides when only updating deps/features.
+    if let Some(prev) = state_entry_for(&state, bp_name) {
+        updated.version = prev.version.clone();
+        updated.applied_templates = prev.applied_templates.clone();
+        updated.dep_kinds = prev.dep_kinds.clone();
+    }
 
     if let Some(entry) = state
         .battery_pack
@@ -647,17 +1126,70 @@ pub(crate) fn write_battery_pack_state(
         state.battery_pack.push(updated);
     }
 
-    write_state_file(&state_path, &state)?;
+    stage_state_file(changes, &state_path, &state)?;
     Ok(())
 }
 
+/// Read the dependency kinds the user chose for a pack's crates, including
+/// edits staged in `changes`. Crates without an override are absent.
+// [impl manifest.state.dep-kinds]
+pub(crate) fn read_dep_kind_overrides(
+    changes: &Changeset,
+    user_manifest_path: &Path,
+    bp_name: &str,
+) -> Result<BTreeMap<String, bphelper_manifest::DepKind>> {
+    let state = read_staged_state_file(changes, &state_file_path(user_manifest_path))?;
+    Ok(state_entry_for(&state, bp_name)
+        .map(|entry| entry.dep_kinds.clone())
+        .unwrap_or_default())
+}
+
+/// Replace a pack's dependency-kind overrides in `battery-pack.toml`,
+/// staged in `changes`. The pack's entry must already exist.
+// [impl manifest.state.dep-kinds]
+pub(crate) fn write_dep_kind_overrides(
+    changes: &mut Changeset,
+    user_manifest_path: &Path,
+    bp_name: &str,
+    overrides: &BTreeMap<String, bphelper_manifest::DepKind>,
+) -> Result<()> {
+    let state_path = state_file_path(user_manifest_path);
+    let mut state = read_staged_state_file(changes, &state_path)?;
+    let Some(entry) = state
+        .battery_pack
+        .iter_mut()
+        .find(|entry| state_name_matches(&entry.name, bp_name))
+    else {
+        bail!("'{}' is not recorded in {}", bp_name, state_path.display());
+    };
+    if entry.dep_kinds == *overrides {
+        return Ok(());
+    }
+    entry.dep_kinds = overrides.clone();
+    stage_state_file(changes, &state_path, &state)
+}
+
+/// Give each crate in `crates` the dependency kind the user chose for it.
+pub(crate) fn apply_dep_kind_overrides(
+    crates: &mut BTreeMap<String, bphelper_manifest::CrateSpec>,
+    overrides: &BTreeMap<String, bphelper_manifest::DepKind>,
+) {
+    for (name, spec) in crates.iter_mut() {
+        if let Some(kind) = overrides.get(name) {
+            spec.dep_kind = *kind;
+            spec.extra_dep_kinds.clear();
+        }
+    }
+}
+
 /// Record that a template was applied for a battery pack.
 ///
-/// Appends the template name if not already present.
+/// Appends the template if not already present, or replaces the existing
+/// record (version and placeholder values) when it is re-applied or upgraded.
 pub(crate) fn record_applied_template(
     user_manifest_path: &Path,
     bp_name: &str,
-    template_name: &str,
+    applied: AppliedTemplate,
 ) -> Result<()> {
     let state_path = state_file_path(user_manifest_path);
     let mut state = read_state_file(&state_path)?;
@@ -667,17 +1199,24 @@ pub(crate) fn record_applied_template(
         .iter_mut()
         .find(|entry| state_name_matches(&entry.name, bp_name))
     {
-        if !entry.applied_templates.contains(&template_name.to_string()) {
-            entry.applied_templates.push(template_name.to_string());
+        match entry
+            .applied_templates
+            .iter_mut()
+            .find(|existing| existing.name == applied.name)
+        {
+            Some(existing) => *existing = applied,
+            None => entry.applied_templates.push(applied),
         }
     } else {
         // No existing entry for this pack — create a minimal one.
         state.battery_pack.push(BatteryPackStateEntry {
             name: short_name(bp_name).to_string(),
+            version: None,
             all_features: false,
             features: default_feature_set(),
             managed_deps: Vec::new(),
-            applied_templates: vec![template_name.to_string()],
+            applied_templates: vec![applied],
+            dep_kinds: BTreeMap::new(),
         });
     }
 
@@ -685,13 +1224,92 @@ pub(crate) fn record_applied_template(
     Ok(())
 }
 
-/// Remove one pack entry from `battery-pack.toml` if it exists.
+/// Read the version requirement a battery pack is pinned to in
+/// `battery-pack.toml`, if any.
+// [impl cli.add.pin]
+pub(crate) fn read_version_req_from_state(
+    user_manifest_path: &Path,
+    bp_name: &str,
+) -> Result<Option<semver::VersionReq>> {
+    read_staged_version_req(&Changeset::new(), user_manifest_path, bp_name)
+}
+
+/// Like [`read_version_req_from_state`], but sees edits staged in
+/// `changes`.
+pub(crate) fn read_staged_version_req(
+    changes: &Changeset,
+    user_manifest_path: &Path,
+    bp_name: &str,
+) -> Result<Option<semver::VersionReq>> {
+    let state_path = state_file_path(user_manifest_path);
+    let state = read_staged_state_file(changes, &state_path)?;
+    let Some(req) = state_entry_for(&state, bp_name).and_then(|e| e.version.as_deref()) else {
+        return Ok(None);
+    };
+    semver::VersionReq::parse(req).map(Some).with_context(|| {
+        format!(
+            "invalid version requirement '{req}' for '{}' in {}",
+            short_name(bp_name),
+            state_path.display()
+        )
+    })
+}
+
+/// Every pinned battery pack in `battery-pack.toml`, as `(name, requirement)`.
+pub(crate) fn read_pinned_packs_from_state(
+    user_manifest_path: &Path,
+) -> Result<Vec<(String, String)>> {
+    let state = read_state_file(&state_file_path(user_manifest_path))?;
+    Ok(state
+        .battery_pack
+        .into_iter()
+        .filter_map(|entry| Some((entry.name, entry.version?)))
+        .collect())
+}
+
+/// Pin a battery pack to a version requirement in `battery-pack.toml`,
+/// creating its entry if needed. The edit is staged in `changes`.
+// [impl cli.add.pin]
+pub(crate) fn write_version_req_to_state(
+    changes: &mut Changeset,
+    user_manifest_path: &Path,
+    bp_name: &str,
+    req: &str,
+) -> Result<()> {
+    let state_path = state_file_path(user_manifest_path);
+    let mut state = read_staged_state_file(changes, &state_path)?;
+
+    if let Some(entry) = state
+        .battery_pack
+        .iter_mut()
+        .find(|entry| state_name_matches(&entry.name, bp_name))
+    {
+        entry.version = Some(req.to_string());
+    } else {
+        state.battery_pack.push(BatteryPackStateEntry {
+            name: short_name(bp_name).to_string(),
+            version: Some(req.to_string()),
+            all_features: false,
+            features: default_feature_set(),
+            managed_deps: Vec::new(),
+            applied_templates: Vec::new(),
+            dep_kinds: BTreeMap::new(),
+        });
+    }
+
+    stage_state_file(changes, &state_path, &state)?;
+    Ok(())
+}
+
+/// Remove one pack entry from `battery-pack.toml` if it exists, staging
+/// the edit in `changes`.
 pub(crate) fn remove_battery_pack_state_entry(
+    changes: &mut Changeset,
     user_manifest_path: &Path,
     bp_name: &str,
 ) -> Result<bool> {
     let state_path = state_file_path(user_manifest_path);
-    let mut state = read_state_file(&state_path)?;
+    let mut state = read_staged_state_file(changes, &state_path)?;
     let original_len = state.battery_pack.len();
     state
         .battery_pack
@@ -700,7 +1318,7 @@ pub(crate) fn remove_battery_pack_state_entry(
         return Ok(false);
     }
 
-    write_state_file(&state_path, &state)?;
+    stage_state_file(changes, &state_path, &state)?;
     Ok(true)
 }
 
@@ -720,10 +1338,8 @@ pub(crate) fn prune_state_managed_deps_for_manifest(
     let raw: toml::Value =
         toml::from_str(user_manifest_content).context("Failed to parse Cargo.toml")?;
     let mut present_deps: BTreeSet<String> = BTreeSet::new();
-    for section in ["dependencies", "dev-dependencies", "build-dependencies"] {
-        if let Some(table) = raw.get(section).and_then(|v| v.as_table()) {
-            present_deps.extend(table.keys().cloned());
-        }
+    for table in dep_tables(&raw) {
+        present_deps.extend(table.keys().cloned());
     }
 
     let mut state = read_state_file(&state_path)?;
@@ -754,10 +1370,12 @@ pub(crate) fn prune_state_managed_deps_for_manifest(
 ///
 /// Works for any dependency source: path deps, registry deps, git deps.
 /// The battery pack must already be in [build-dependencies].
-pub(crate) fn resolve_battery_pack_manifest(bp_name: &str) -> Result<PathBuf> {
-    let metadata = cargo_metadata::MetadataCommand::new()
-        .exec()
-        .context("Failed to run `cargo metadata`")?;
+pub(crate) fn resolve_battery_pack_manifest(bp_name: &str, offline: bool) -> Result<PathBuf> {
+    let mut command = cargo_metadata::MetadataCommand::new();
+    if offline {
+        command.other_options(vec!["--offline".to_string()]);
+    }
+    let metadata = command.exec().context("Failed to run `cargo metadata`")?;
 
     let package = metadata
         .packages
@@ -86,6 +86,7 @@ fn deps_add_simple_version() {
         features: BTreeSet::new(),
         dep_kind: bphelper_manifest::DepKind::Normal,
         optional: false,
+        ..Default::default()
     };
 
     super::add_dep_to_table(&mut table, "anyhow", &spec);
@@ -105,6 +106,7 @@ fn deps_add_does_not_add_to_wrong_key() {
         features: BTreeSet::from(["derive".to_string()]),
         dep_kind: bphelper_manifest::DepKind::Normal,
         optional: false,
+        ..Default::default()
     };
 
     super::add_dep_to_table(&mut table, "clap", &spec);
@@ -127,6 +129,7 @@ fn deps_version_features_included() {
         features: BTreeSet::from(["derive".to_string(), "env".to_string()]),
         dep_kind: bphelper_manifest::DepKind::Normal,
         optional: false,
+        ..Default::default()
     };
 
     super::add_dep_to_table(&mut table, "clap", &spec);
@@ -150,6 +153,7 @@ fn deps_version_features_empty_features_uses_simple_string() {
         features: BTreeSet::new(),
         dep_kind: bphelper_manifest::DepKind::Normal,
         optional: false,
+        ..Default::default()
     };
 
     super::add_dep_to_table(&mut table, "anyhow", &spec);
@@ -182,6 +186,7 @@ fn deps_workspace_adds_to_workspace_deps_table() {
         features: BTreeSet::from(["derive".to_string()]),
         dep_kind: bphelper_manifest::DepKind::Normal,
         optional: false,
+        ..Default::default()
     };
 
     super::add_dep_to_table(&mut ws_table, "serde", &spec);
@@ -217,6 +222,7 @@ fn deps_no_workspace_adds_directly() {
         features: BTreeSet::new(),
         dep_kind: bphelper_manifest::DepKind::Normal,
         optional: false,
+        ..Default::default()
     };
 
     super::add_dep_to_table(&mut table, "thiserror", &spec);
@@ -233,6 +239,7 @@ fn deps_no_workspace_adds_with_features() {
         features: BTreeSet::from(["derive".to_string()]),
         dep_kind: bphelper_manifest::DepKind::Normal,
         optional: false,
+        ..Default::default()
     };
 
     super::add_dep_to_table(&mut table, "serde", &spec);
@@ -262,6 +269,7 @@ fn deps_existing_does_not_overwrite_version() {
         features: BTreeSet::new(),
         dep_kind: bphelper_manifest::DepKind::Normal,
         optional: false,
+        ..Default::default()
     };
 
     let changed = super::sync_dep_in_table(&mut table, "anyhow", &spec);
@@ -284,6 +292,7 @@ fn deps_existing_adds_missing_features() {
         features: BTreeSet::from(["derive".to_string(), "env".to_string()]),
         dep_kind: bphelper_manifest::DepKind::Normal,
         optional: false,
+        ..Default::default()
     };
 
     let changed = super::sync_dep_in_table(&mut table, "clap", &spec);
@@ -310,6 +319,7 @@ fn deps_existing_preserves_user_features() {
         features: BTreeSet::from(["derive".to_string()]),
         dep_kind: bphelper_manifest::DepKind::Normal,
         optional: false,
+        ..Default::default()
     };
 
     let changed = super::sync_dep_in_table(&mut table, "clap", &spec);
@@ -340,6 +350,7 @@ fn deps_existing_no_change_when_up_to_date() {
         features: BTreeSet::new(),
         dep_kind: bphelper_manifest::DepKind::Normal,
         optional: false,
+        ..Default::default()
     };
 
     let changed = super::sync_dep_in_table(&mut table, "anyhow", &spec);
@@ -367,6 +378,7 @@ fn deps_add_respects_dep_kind_in_spec() {
             features: BTreeSet::new(),
             dep_kind: kind,
             optional: false,
+            ..Default::default()
         };
 
         super::add_dep_to_table(&mut table, "some-crate", &spec);
@@ -378,6 +390,215 @@ fn deps_add_respects_dep_kind_in_spec() {
     }
 }
 
+// ============================================================================
+// manifest.deps.{rename,default-features,source,target} — dep details
+// ============================================================================
+
+// [verify manifest.deps.rename]
+// [verify manifest.deps.default-features]
+// [verify manifest.deps.source]
+#[test]
+fn deps_add_emits_rename_default_features_and_source() {
+    let mut table = toml_edit::Table::new();
+    let renamed = bphelper_manifest::CrateSpec {
+        version: "1".to_string(),
+        package: Some("serde_json".to_string()),
+        ..Default::default()
+    };
+    let no_defaults = bphelper_manifest::CrateSpec {
+        version: "0.6".to_string(),
+        features: BTreeSet::from(["trace".to_string()]),
+        default_features: false,
+        ..Default::default()
+    };
+    let git = bphelper_manifest::CrateSpec {
+        source: bphelper_manifest::DepSource::Git {
+            url: "https://github.com/example/tracey".to_string(),
+            branch: None,
+            tag: Some("v1.0.0".to_string()),
+            rev: None,
+        },
+        ..Default::default()
+    };
+    let registry = bphelper_manifest::CrateSpec {
+        version: "2".to_string(),
+        source: bphelper_manifest::DepSource::Registry {
+            index: "sparse+https://registry.example.com/index/".to_string(),
+        },
+        ..Default::default()
+    };
+
+    super::add_dep_to_table(&mut table, "json", &renamed);
+    super::add_dep_to_table(&mut table, "tower-http", &no_defaults);
+    super::add_dep_to_table(&mut table, "tracey", &git);
+    super::add_dep_to_table(&mut table, "internal", &registry);
+
+    let mut doc = DocumentMut::new();
+    doc.insert("dependencies", toml_edit::Item::Table(table));
+    snapbox::assert_data_eq!(
+        doc.to_string(),
+        snapbox::str![[r#"
+[dependencies]
+json = { version = "1", package = "serde_json" }
+tower-http = { version = "0.6", default-features = false, features = ["trace"] }
+tracey = { git = "https://github.com/example/tracey", tag = "v1.0.0" }
+internal = { version = "2", registry-index = "sparse+https://registry.example.com/index/" }
+
+"#]]
+    );
+}
+
+// [verify manifest.deps.target]
+#[test]
+fn deps_target_gated_crates_go_in_target_tables() {
+    let mut doc = parse_doc("[package]\nname = \"app\"\n");
+    let crates = std::collections::BTreeMap::from([
+        ("anyhow".to_string(), simple_spec("1")),
+        (
+            "tikv-jemallocator".to_string(),
+            CrateSpec {
+                target: Some(r#"cfg(not(target_env = "msvc"))"#.to_string()),
+                ..simple_spec("0.7")
+            },
+        ),
+    ]);
+
+    assert_eq!(super::write_deps_by_kind(&mut doc, &crates, false), 2);
+    snapbox::assert_data_eq!(
+        doc.to_string(),
+        snapbox::str![[r#"
+[package]
+name = "app"
+
+[dependencies]
+anyhow = "1"
+
+[target.'cfg(not(target_env = "msvc"))'.dependencies]
+tikv-jemallocator = "0.7"
+
+"#]]
+    );
+
+    // Removal finds the gated entry in its target table.
+    assert_eq!(super::remove_deps_by_kind(&mut doc, &crates), 2);
+    assert!(!doc.to_string().contains("jemallocator"));
+}
+
+// [verify manifest.merge.dep-kind]
+#[test]
+fn deps_with_an_extra_kind_go_in_both_sections() {
+    let mut doc = parse_doc("[package]\nname = \"app\"\n");
+    let crates = std::collections::BTreeMap::from([(
+        "serde".to_string(),
+        CrateSpec {
+            dep_kind: DepKind::Dev,
+            extra_dep_kinds: vec![DepKind::Build],
+            ..simple_spec("1")
+        },
+    )]);
+
+    assert_eq!(super::write_deps_by_kind(&mut doc, &crates, false), 2);
+    let text = doc.to_string();
+    assert!(text.contains("[dev-dependencies]\nserde = \"1\""), "{text}");
+    assert!(
+        text.contains("[build-dependencies]\nserde = \"1\""),
+        "{text}"
+    );
+
+    assert_eq!(super::remove_deps_by_kind(&mut doc, &crates), 2);
+    assert!(!doc.to_string().contains("serde"));
+}
+
+#[test]
+fn removing_a_dep_of_unknown_kind_searches_every_table() {
+    let mut doc = parse_doc(
+        r#"[package]
+name = "app"
+
+[dependencies]
+anyhow = "1"
+
+[dev-dependencies]
+insta = "1"
+
+[target.'cfg(unix)'.build-dependencies]
+cc = "1"
+"#,
+    );
+
+    assert!(super::remove_dep_from_any_table(&mut doc, "insta"));
+    assert!(super::remove_dep_from_any_table(&mut doc, "cc"));
+    assert!(!super::remove_dep_from_any_table(&mut doc, "serde"));
+    let text = doc.to_string();
+    assert!(text.contains("anyhow"), "{text}");
+    assert!(!text.contains("insta") && !text.contains("cc ="), "{text}");
+}
+
+// [verify manifest.deps.move-kind]
+#[test]
+fn moving_a_dep_keeps_its_entry_and_leaves_missing_sections_alone() {
+    let mut doc = parse_doc(
+        "[package]\nname = \"app\"\n\n[dependencies]\nclap = { version = \"4\", features = [\"env\"] }\n",
+    );
+    let previous = std::collections::BTreeMap::from([
+        ("clap".to_string(), DepKind::Normal),
+        ("insta".to_string(), DepKind::Build),
+    ]);
+    let crates = std::collections::BTreeMap::from([
+        (
+            "clap".to_string(),
+            CrateSpec {
+                dep_kind: DepKind::Dev,
+                ..simple_spec("4")
+            },
+        ),
+        (
+            "insta".to_string(),
+            CrateSpec {
+                dep_kind: DepKind::Dev,
+                ..simple_spec("1")
+            },
+        ),
+    ]);
+
+    assert_eq!(
+        super::move_deps_to_kind(&mut doc, &crates, &previous),
+        ["clap"]
+    );
+    // The crate never in [build-dependencies] doesn't leave a stub behind.
+    assert_eq!(super::write_deps_by_kind(&mut doc, &crates, true), 1);
+    snapbox::assert_data_eq!(
+        doc.to_string(),
+        snapbox::str![[r#"
+[package]
+name = "app"
+
+[dependencies]
+
+[dev-dependencies]
+clap = { version = "4", features = ["env"] }
+insta = "1"
+
+"#]]
+    );
+}
+
+// [verify manifest.deps.existing]
+#[test]
+fn sync_leaves_existing_default_features_alone() {
+    let mut doc = parse_deps("[dependencies]\ntower-http = \"0.5\"\n");
+    let pack_spec = CrateSpec {
+        default_features: false,
+        ..spec("0.6", &["trace"])
+    };
+    let table = doc["dependencies"].as_table_mut().unwrap();
+    assert!(super::sync_dep_in_table(table, "tower-http", &pack_spec));
+
+    assert_eq!(read_version(&doc, "tower-http"), "0.6");
+    assert_eq!(read_features(&doc, "tower-http"), vec!["trace"]);
+    assert!(!doc.to_string().contains("default-features"));
+}
+
 // ============================================================================
 // Integration: parse fixture + add deps to a fresh Cargo.toml
 // ============================================================================
@@ -479,6 +700,7 @@ fn sync_adds_missing_dep() {
         features: BTreeSet::new(),
         dep_kind: bphelper_manifest::DepKind::Normal,
         optional: false,
+        ..Default::default()
     };
 
     let changed = super::sync_dep_in_table(&mut table, "anyhow", &spec);
@@ -501,6 +723,7 @@ fn sync_converts_simple_string_to_table_when_adding_features() {
         features: BTreeSet::from(["backtrace".to_string()]),
         dep_kind: bphelper_manifest::DepKind::Normal,
         optional: false,
+        ..Default::default()
     };
 
     let changed = super::sync_dep_in_table(&mut table, "anyhow", &spec);
@@ -541,6 +764,7 @@ fn spec(version: &str, features: &[&str]) -> CrateSpec {
         features: features.iter().map(|s| s.to_string()).collect(),
         dep_kind: DepKind::Normal,
         optional: false,
+        ..Default::default()
     }
 }
 
@@ -979,6 +1203,7 @@ fn simple_spec(version: &str) -> CrateSpec {
         features: BTreeSet::new(),
         dep_kind: DepKind::Normal,
         optional: false,
+        ..Default::default()
     }
 }
 
@@ -989,6 +1214,7 @@ fn spec_with_features(version: &str, features: &[&str]) -> CrateSpec {
         features: features.iter().map(|s| s.to_string()).collect(),
         dep_kind: DepKind::Normal,
         optional: false,
+        ..Default::default()
     }
 }
 
@@ -1445,15 +1671,19 @@ fn write_all_features_then_read_round_trips() {
             features: BTreeSet::new(),
             dep_kind: bphelper_manifest::DepKind::Normal,
             optional: false,
+            ..Default::default()
         },
     )]);
+    let mut changes = crate::changeset::Changeset::new();
     super::write_battery_pack_state(
+        &mut changes,
         &manifest_path,
         "cli-battery-pack",
         &bphelper_manifest::ActiveFeatures::All,
         &crates,
     )
     .unwrap();
+    changes.commit().unwrap();
 
     // Verify the file uses `all-features = true`
     let content = std::fs::read_to_string(tmp.path().join("battery-pack.toml")).unwrap();
@@ -1489,10 +1719,19 @@ fn write_subset_features_then_read_round_trips() {
             features: BTreeSet::new(),
             dep_kind: bphelper_manifest::DepKind::Normal,
             optional: false,
+            ..Default::default()
         },
     )]);
-    super::write_battery_pack_state(&manifest_path, "cli-battery-pack", &features, &crates)
-        .unwrap();
+    let mut changes = crate::changeset::Changeset::new();
+    super::write_battery_pack_state(
+        &mut changes,
+        &manifest_path,
+        "cli-battery-pack",
+        &features,
+        &crates,
+    )
+    .unwrap();
+    changes.commit().unwrap();
 
     // Verify the file uses features array (no all-features flag)
     let content = std::fs::read_to_string(tmp.path().join("battery-pack.toml")).unwrap();
@@ -1509,3 +1748,180 @@ fn write_subset_features_then_read_round_trips() {
     let read = super::read_active_features_from_state(&manifest_path, "cli-battery-pack").unwrap();
     assert_eq!(read, features);
 }
+
+// ============================================================================
+// Applied templates: version and placeholder values
+// ============================================================================
+
+/// Bare template names written by older versions read back with no version
+/// or placeholders.
+#[test]
+fn read_applied_templates_accepts_bare_names() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let manifest_path = tmp.path().join("Cargo.toml");
+    std::fs::write(&manifest_path, "[package]\nname = \"test\"\n").unwrap();
+    std::fs::write(
+        tmp.path().join("battery-pack.toml"),
+        indoc::indoc! {r#"
+            version = 2
+
+            [[battery-pack]]
+            name = "ci"
+            applied-templates = ["spellcheck"]
+        "#},
+    )
+    .unwrap();
+
+    let records = super::read_applied_template_records(&manifest_path, "ci-battery-pack");
+    assert_eq!(records, vec![super::AppliedTemplate::named("spellcheck")]);
+}
+
+// [verify cli.add.template-record]
+/// Recording a template keeps the version and placeholder values, and
+/// re-recording the same template replaces them instead of duplicating it.
+#[test]
+fn record_applied_template_round_trips_version_and_placeholders() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let manifest_path = tmp.path().join("Cargo.toml");
+    std::fs::write(&manifest_path, "[package]\nname = \"test\"\n").unwrap();
+
+    let applied = |version: &str| super::AppliedTemplate {
+        name: "full".to_string(),
+        version: Some(version.to_string()),
+        placeholders: std::collections::BTreeMap::from([
+            ("ci_platform".to_string(), "github".to_string()),
+            ("fuzzing".to_string(), "true".to_string()),
+        ]),
+    };
+
+    super::record_applied_template(&manifest_path, "ci-battery-pack", applied("0.1.0")).unwrap();
+    super::record_applied_template(
+        &manifest_path,
+        "ci-battery-pack",
+        super::AppliedTemplate::named("spellcheck"),
+    )
+    .unwrap();
+    super::record_applied_template(&manifest_path, "ci-battery-pack", applied("0.2.0")).unwrap();
+
+    let records = super::read_applied_template_records(&manifest_path, "ci-battery-pack");
+    assert_eq!(
+        records,
+        vec![
+            applied("0.2.0"),
+            super::AppliedTemplate::named("spellcheck")
+        ]
+    );
+    assert_eq!(
+        super::read_applied_templates_from_state(&manifest_path, "ci"),
+        vec!["full".to_string(), "spellcheck".to_string()]
+    );
+}
+
+// ============================================================================
+// Version pins
+// ============================================================================
+
+// [verify cli.add.pin]
+/// A pin survives later state writes that only change deps and features.
+#[test]
+fn version_pin_survives_state_rewrite() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let manifest_path = tmp.path().join("Cargo.toml");
+    std::fs::write(&manifest_path, "[package]\nname = \"test\"\n").unwrap();
+
+    let mut changes = crate::changeset::Changeset::new();
+    super::write_version_req_to_state(&mut changes, &manifest_path, "cli-battery-pack", "0.4")
+        .unwrap();
+    super::write_battery_pack_state(
+        &mut changes,
+        &manifest_path,
+        "cli-battery-pack",
+        &bphelper_manifest::ActiveFeatures::All,
+        &std::collections::BTreeMap::new(),
+    )
+    .unwrap();
+    changes.commit().unwrap();
+
+    let content = std::fs::read_to_string(tmp.path().join("battery-pack.toml")).unwrap();
+    assert!(
+        content.contains("version = \"0.4\""),
+        "expected pin in state file, got:\n{content}"
+    );
+    let req = super::read_version_req_from_state(&manifest_path, "cli").unwrap();
+    assert_eq!(req, Some(semver::VersionReq::parse("0.4").unwrap()));
+    assert_eq!(
+        super::read_pinned_packs_from_state(&manifest_path).unwrap(),
+        vec![("cli".to_string(), "0.4".to_string())]
+    );
+}
+
+// [verify cli.add.pin]
+#[test]
+fn read_version_req_rejects_invalid_pin() {
+    let tmp = tempfile::TempDir::new().unwrap();
+    let manifest_path = tmp.path().join("Cargo.toml");
+    std::fs::write(&manifest_path, "[package]\nname = \"test\"\n").unwrap();
+    std::fs::write(
+        tmp.path().join("battery-pack.toml"),
+        indoc::indoc! {r#"
+            version = 2
+
+            [[battery-pack]]
+            name = "cli"
+            version = "not a version"
+
+            [[battery-pack]]
+            name = "ci"
+        "#},
+    )
+    .unwrap();
+
+    let err = super::read_version_req_from_state(&manifest_path, "cli").unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("invalid version requirement 'not a version' for 'cli'"),
+        "got: {err}"
+    );
+    assert_eq!(
+        super::read_version_req_from_state(&manifest_path, "ci").unwrap(),
+        None
+    );
+}
+
+// [verify cli.update.dependency]
+// [verify manifest.toml.preserve]
+#[test]
+fn set_dep_version_updates_every_form() {
+    let mut doc: toml_edit::DocumentMut = indoc::indoc! {r#"
+        [build-dependencies]
+        a = "0.1.0" # pinned by bp
+        b = { version = "0.1.0", registry = "company" }
+        c = { workspace = true }
+
+        [build-dependencies.d]
+        version = "0.1.0"
+    "#}
+    .parse()
+    .unwrap();
+    let table = doc["build-dependencies"].as_table_mut().unwrap();
+
+    for name in ["a", "b", "d"] {
+        assert!(super::set_dep_version(table, name, "0.2.0"), "{name}");
+    }
+    assert!(!super::set_dep_version(table, "a", "0.2.0"), "unchanged");
+    assert!(!super::set_dep_version(table, "c", "0.2.0"), "no version");
+    assert!(!super::set_dep_version(table, "missing", "0.2.0"));
+
+    assert_eq!(
+        doc.to_string(),
+        indoc::indoc! {r#"
+            [build-dependencies]
+            a = "0.2.0" # pinned by bp
+            b = { version = "0.2.0", registry = "company" }
+            c = { workspace = true }
+
+            [build-dependencies.d]
+            version = "0.2.0"
+        "#}
+    );
+}
@@ -6,24 +6,56 @@
 //!
 //! - `Cargo.toml`: TOML-aware merge (deps get version upgrade + feature union,
 //!   other sections inserted if absent)
-//! - `*.yml` / `*.yaml`: YAML-aware merge (top-level map keys merged additively)
-//! - Everything else: write if new, prompt if exists
+//! - `*.yml` / `*.yaml`: YAML-aware merge (top-level map keys merged additively,
+//!   spliced in as text so comments and formatting survive)
+//! - `*.json`: JSON-aware merge (object members merged additively, spliced in)
+//! - `.gitignore`-style files and `CODEOWNERS`: union of lines, order kept
+//! - `*.md`: missing `##` sections inserted
+//! - Paths listed in the template's `[merge]` table: the strategy named there
+//! - Everything else, and verbatim (binary) files: write if new, prompt if exists
+//!
+//! `cargo bp upgrade-template` additionally re-renders the version of the
+//! template that was originally applied and three-way merges the user's files
+//! against it, so local edits survive a template upgrade.
 
 use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 
 use crate::template_engine::RenderedFile;
 
+mod json;
+mod lines;
+mod markdown;
+mod yaml;
+
+pub(crate) use json::merge_json;
+pub(crate) use lines::merge_lines;
+pub(crate) use markdown::merge_markdown;
+pub(crate) use yaml::merge_yaml;
+
 // ============================================================================
 // Merge strategy dispatch
 // ============================================================================
 
 /// How to handle a file that already exists in the target project.
-enum MergeStrategy {
+///
+/// Picked from the file name by default; a template can override it per path
+/// in its `bp-template.toml` `[merge]` table, using the kebab-case names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum MergeStrategy {
     /// TOML-aware merge (Cargo.toml files).
     Toml,
     /// YAML-aware merge (workflow files, etc.).
     Yaml,
+    /// JSON object merge (editor settings, bot configs).
+    Json,
+    /// Union of lines (`.gitignore` and friends).
+    Lines,
+    /// Union of lines keyed by their first field (`CODEOWNERS`).
+    Patterns,
+    /// Missing `##` sections inserted (Markdown).
+    Markdown,
     /// Plain file: skip or overwrite.
     Plain,
 }
@@ -31,12 +63,15 @@ enum MergeStrategy {
 /// Determine the merge strategy for a file based on its path.
 fn strategy_for(path: &str) -> MergeStrategy {
     let filename = path.rsplit('/').next().unwrap_or(path);
-    if filename.ends_with(".toml") {
-        MergeStrategy::Toml
-    } else if filename.ends_with(".yml") || filename.ends_with(".yaml") {
-        MergeStrategy::Yaml
-    } else {
-        MergeStrategy::Plain
+    let extension = filename.rsplit_once('.').map(|(_, ext)| ext);
+    match extension {
+        Some("toml") => MergeStrategy::Toml,
+        Some("yml" | "yaml") => MergeStrategy::Yaml,
+        Some("json" | "jsonc") => MergeStrategy::Json,
+        Some("md" | "markdown") => MergeStrategy::Markdown,
+        _ if filename == "CODEOWNERS" || filename == ".gitattributes" => MergeStrategy::Patterns,
+        _ if filename.starts_with('.') && filename.ends_with("ignore") => MergeStrategy::Lines,
+        _ => MergeStrategy::Plain,
     }
 }
 
@@ -49,7 +84,7 @@ fn strategy_for(path: &str) -> MergeStrategy {
 pub(crate) enum FileResult {
     /// File was written (new file, no conflict).
     Created(String),
-    /// File was merged (structured merge for TOML/YAML).
+    /// File was merged (structured merge, e.g. TOML/YAML).
     Merged(String),
     /// File was skipped (user chose to skip, or non-interactive default).
     Skipped(String),
@@ -57,6 +92,8 @@ pub(crate) enum FileResult {
     Overwritten(String),
     /// File was unchanged (merge produced identical content).
     Unchanged(String),
+    /// File was written with conflict markers left for the user to resolve.
+    Conflicted(String),
 }
 
 /// Options for applying rendered template files to an existing project.
@@ -67,11 +104,29 @@ pub(crate) struct ApplyOpts {
     pub(crate) overwrite: bool,
     /// Whether interactive prompts are allowed.
     pub(crate) interactive: bool,
+    /// Per-path strategies from the template's `[merge]` table, tried in
+    /// order before the file-name defaults.
+    pub(crate) merge_overrides: Vec<(glob::Pattern, MergeStrategy)>,
+}
+
+impl ApplyOpts {
+    /// The merge strategy for a project-relative path.
+    // [impl format.templates.merge]
+    fn strategy_for(&self, path: &str) -> MergeStrategy {
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        self.merge_overrides
+            .iter()
+            .find(|(pattern, _)| pattern.matches_with(path, options))
+            .map_or_else(|| strategy_for(path), |(_, strategy)| *strategy)
+    }
 }
 
 /// Batch decision set by "accept all" or "skip all" during interactive prompts.
-/// This is shared across both TOML/YAML and other-file prompts: if the user
-/// picks "overwrite all" on a plain file, subsequent TOML/YAML merges are also
+/// This is shared across both structured and other-file prompts: if the user
+/// picks "overwrite all" on a plain file, subsequent structured merges are also
 /// auto-accepted (and vice versa). This is intentional: the user has signaled
 /// they want to stop being prompted.
 #[derive(Clone, Copy)]
@@ -91,85 +146,111 @@ pub(crate) fn apply_rendered_files(
     files: &[RenderedFile],
     opts: &ApplyOpts,
 ) -> Result<Vec<FileResult>> {
-    let mut results = Vec::new();
     let mut batch = BatchDecision::None;
+    files
+        .iter()
+        .map(|file| apply_rendered_file(file, opts, &mut batch))
+        .collect()
+}
 
-    for file in files {
-        let dest = opts.project_dir.join(&file.path);
-        let result = if !dest.exists() {
-            // New file: always write it.
-            write_new_file(&dest, &file.content)?;
-            FileResult::Created(file.path.clone())
-        } else {
-            // Existing file: dispatch by strategy.
-            // Handle binary/non-UTF-8 files as plain file conflicts.
-            let existing = match std::fs::read_to_string(&dest) {
-                Ok(content) => content,
-                Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
-                    let ctx = ConflictContext {
-                        dest: &dest,
-                        rel_path: &file.path,
-                        existing: "",
-                        new_content: &file.content,
-                        opts,
-                    };
-                    results.push(resolve_plain_conflict(&ctx, &mut batch)?);
-                    continue;
-                }
-                Err(e) => {
-                    return Err(e).with_context(|| format!("failed to read {}", dest.display()));
-                }
-            };
+/// Apply one rendered file: write it if new, otherwise merge by strategy.
+fn apply_rendered_file(
+    file: &RenderedFile,
+    opts: &ApplyOpts,
+    batch: &mut BatchDecision,
+) -> Result<FileResult> {
+    let dest = opts.project_dir.join(&file.path);
+    if !dest.exists() {
+        // New file: always write it.
+        write_new_file(&dest, file.data())?;
+        return Ok(FileResult::Created(file.path.clone()));
+    }
 
-            match strategy_for(&file.path) {
-                MergeStrategy::Toml => {
-                    let merged = merge_toml(&existing, &file.content)?;
-                    let ctx = ConflictContext {
-                        dest: &dest,
-                        rel_path: &file.path,
-                        existing: &existing,
-                        new_content: &merged,
-                        opts,
-                    };
-                    resolve_structured_merge(&ctx, &mut batch)?
-                }
-                MergeStrategy::Yaml => {
-                    let merged = merge_yaml(&existing, &file.content)?;
-                    let ctx = ConflictContext {
-                        dest: &dest,
-                        rel_path: &file.path,
-                        existing: &existing,
-                        new_content: &merged,
-                        opts,
-                    };
-                    resolve_structured_merge(&ctx, &mut batch)?
-                }
-                MergeStrategy::Plain => {
-                    let ctx = ConflictContext {
-                        dest: &dest,
-                        rel_path: &file.path,
-                        existing: &existing,
-                        new_content: &file.content,
-                        opts,
-                    };
-                    resolve_plain_conflict(&ctx, &mut batch)?
-                }
-            }
+    // A verbatim file can't be merged, only kept or replaced.
+    // [impl format.templates.binary]
+    if let Some(bytes) = &file.bytes {
+        let existing =
+            std::fs::read(&dest).with_context(|| format!("failed to read {}", dest.display()))?;
+        if existing == *bytes {
+            return Ok(FileResult::Unchanged(file.path.clone()));
+        }
+        let ctx = ConflictContext {
+            dest: &dest,
+            rel_path: &file.path,
+            existing: "",
+            new_content: "",
+            new_bytes: Some(bytes),
+            opts,
         };
-        results.push(result);
+        return resolve_plain_conflict(&ctx, batch);
     }
 
-    Ok(results)
+    // Existing file: dispatch by strategy.
+    let Some(existing) = read_existing(&dest)? else {
+        // Handle binary/non-UTF-8 files as plain file conflicts.
+        let ctx = ConflictContext {
+            dest: &dest,
+            rel_path: &file.path,
+            existing: "",
+            new_content: &file.content,
+            new_bytes: None,
+            opts,
+        };
+        return resolve_plain_conflict(&ctx, batch);
+    };
+
+    let merged = match opts.strategy_for(&file.path) {
+        MergeStrategy::Toml => merge_toml(&existing, &file.content)?,
+        MergeStrategy::Yaml => merge_yaml(&existing, &file.content)?,
+        MergeStrategy::Json => merge_json(&existing, &file.content)?,
+        MergeStrategy::Lines => merge_lines(&existing, &file.content, false),
+        MergeStrategy::Patterns => merge_lines(&existing, &file.content, true),
+        MergeStrategy::Markdown => merge_markdown(&existing, &file.content),
+        MergeStrategy::Plain => {
+            let ctx = ConflictContext {
+                dest: &dest,
+                rel_path: &file.path,
+                existing: &existing,
+                new_content: &file.content,
+                new_bytes: None,
+                opts,
+            };
+            return resolve_plain_conflict(&ctx, batch);
+        }
+    };
+    let ctx = ConflictContext {
+        dest: &dest,
+        rel_path: &file.path,
+        existing: &existing,
+        new_content: &merged,
+        new_bytes: None,
+        opts,
+    };
+    resolve_structured_merge(&ctx, batch)
+}
+
+/// Read an existing project file, returning `None` if it is not valid UTF-8.
+fn read_existing(dest: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(dest) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::InvalidData => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("failed to read {}", dest.display())),
+    }
 }
 
 /// Write a new file, creating parent directories as needed.
-fn write_new_file(dest: &Path, content: &str) -> Result<()> {
+fn write_new_file(dest: &Path, content: &[u8]) -> Result<()> {
     if let Some(parent) = dest.parent() {
         std::fs::create_dir_all(parent)
             .with_context(|| format!("failed to create {}", parent.display()))?;
     }
-    std::fs::write(dest, content).with_context(|| format!("failed to write {}", dest.display()))?;
-    Ok(())
+    write_file(dest, content)
+}
+
+/// Write a project file, first recording it for `cargo bp undo`.
+fn write_file(dest: &Path, content: impl AsRef<[u8]>) -> Result<()> {
+    crate::undo::record(dest)?;
+    std::fs::write(dest, content).with_context(|| format!("failed to write {}", dest.display()))
 }
 
 /// Shared context for conflict resolution (plain and structured).
@@ -178,17 +259,28 @@ struct ConflictContext<'a> {
     rel_path: &'a str,
     existing: &'a str,
     new_content: &'a str,
+    /// Written instead of `new_content` for a verbatim file, which has no diff.
+    new_bytes: Option<&'a [u8]>,
     opts: &'a ApplyOpts,
 }
 
+impl ConflictContext<'_> {
+    /// Write the new content over the existing file.
+    fn overwrite(&self) -> Result<()> {
+        write_file(
+            self.dest,
+            self.new_bytes.unwrap_or(self.new_content.as_bytes()),
+        )
+    }
+}
+
 /// Handle a plain file conflict: prompt, overwrite, or skip.
 fn resolve_plain_conflict(
     ctx: &ConflictContext<'_>,
     batch: &mut BatchDecision,
 ) -> Result<FileResult> {
     if ctx.opts.overwrite || matches!(batch, BatchDecision::AcceptAll) {
-        std::fs::write(ctx.dest, ctx.new_content)
-            .with_context(|| format!("failed to write {}", ctx.dest.display()))?;
+        ctx.overwrite()?;
         return Ok(FileResult::Overwritten(ctx.rel_path.to_string()));
     }
 
@@ -205,10 +297,10 @@ fn resolve_plain_conflict(
         match read_single_key()? {
             's' => return Ok(FileResult::Skipped(ctx.rel_path.to_string())),
             'o' => {
-                std::fs::write(ctx.dest, ctx.new_content)
-                    .with_context(|| format!("failed to write {}", ctx.dest.display()))?;
+                ctx.overwrite()?;
                 return Ok(FileResult::Overwritten(ctx.rel_path.to_string()));
             }
+            'd' if ctx.new_bytes.is_some() => eprintln!("(binary files differ)"),
             'd' => {
                 let diff = unified_diff(ctx.existing, ctx.new_content, ctx.rel_path);
                 eprintln!("{diff}");
@@ -219,8 +311,7 @@ fn resolve_plain_conflict(
             }
             'O' => {
                 *batch = BatchDecision::AcceptAll;
-                std::fs::write(ctx.dest, ctx.new_content)
-                    .with_context(|| format!("failed to write {}", ctx.dest.display()))?;
+                ctx.overwrite()?;
                 return Ok(FileResult::Overwritten(ctx.rel_path.to_string()));
             }
             _ => {} // Unrecognized key, re-prompt.
@@ -228,7 +319,8 @@ fn resolve_plain_conflict(
     }
 }
 
-/// Handle a structured file merge (TOML/YAML): show diff, prompt accept/skip/edit.
+/// Handle a structured file merge (TOML, YAML, JSON, line files, Markdown):
+/// show diff, prompt accept/skip/edit.
 ///
 /// `ctx.new_content` is the already-merged result. In non-interactive mode,
 /// the merge is applied automatically (structured merges are additive and
@@ -249,8 +341,7 @@ fn resolve_structured_merge(
         if !diff.is_empty() {
             eprintln!("merging {}:\n{}", ctx.rel_path, diff);
         }
-        std::fs::write(ctx.dest, ctx.new_content)
-            .with_context(|| format!("failed to write {}", ctx.dest.display()))?;
+        write_file(ctx.dest, ctx.new_content)?;
         return Ok(FileResult::Merged(ctx.rel_path.to_string()));
     }
 
@@ -271,8 +362,7 @@ fn resolve_structured_merge(
         );
         match read_single_key()? {
             'a' => {
-                std::fs::write(ctx.dest, &content_to_write)
-                    .with_context(|| format!("failed to write {}", ctx.dest.display()))?;
+                write_file(ctx.dest, &content_to_write)?;
                 return Ok(FileResult::Merged(ctx.rel_path.to_string()));
             }
             's' => return Ok(FileResult::Skipped(ctx.rel_path.to_string())),
@@ -287,8 +377,7 @@ fn resolve_structured_merge(
             }
             'A' => {
                 *batch = BatchDecision::AcceptAll;
-                std::fs::write(ctx.dest, &content_to_write)
-                    .with_context(|| format!("failed to write {}", ctx.dest.display()))?;
+                write_file(ctx.dest, &content_to_write)?;
                 return Ok(FileResult::Merged(ctx.rel_path.to_string()));
             }
             'S' => {
@@ -424,7 +513,7 @@ fn parse_dep_as_spec(value: &toml_edit::Item, section: &str) -> bphelper_manifes
             version: version.to_string(),
             features: std::collections::BTreeSet::new(),
             dep_kind,
-            optional: false,
+            ..Default::default()
         };
     }
 
@@ -458,89 +547,288 @@ fn parse_dep_as_spec(value: &toml_edit::Item, section: &str) -> bphelper_manifes
         })
         .unwrap_or_default();
     let optional = get_bool("optional").unwrap_or(false);
+    let source = if let Some(url) = get_str("git") {
+        bphelper_manifest::DepSource::Git {
+            url: url.to_string(),
+            branch: get_str("branch").map(String::from),
+            tag: get_str("tag").map(String::from),
+            rev: get_str("rev").map(String::from),
+        }
+    } else if let Some(index) = get_str("registry-index") {
+        bphelper_manifest::DepSource::Registry {
+            index: index.to_string(),
+        }
+    } else {
+        bphelper_manifest::DepSource::CratesIo
+    };
 
     bphelper_manifest::CrateSpec {
         version,
         features,
         dep_kind,
+        extra_dep_kinds: Vec::new(),
         optional,
+        package: get_str("package").map(String::from),
+        default_features: get_bool("default-features").unwrap_or(true),
+        target: None,
+        source,
     }
 }
 
 // ============================================================================
-// YAML merge
+// Template upgrade (three-way merge)
 // ============================================================================
 
-/// Merge a template YAML file into an existing one.
-///
-/// Top-level mapping keys are merged additively: new keys from the template
-/// are inserted, existing keys in the user's file are left alone.
-/// For known GitHub Actions keys (`jobs`, `on`, `permissions`), child maps
-/// are also merged additively.
-///
-/// Note: YAML round-tripping through the parser/emitter may reformat the file
-/// (normalize indentation, remove comments, reorder keys). This is a known
-/// limitation. When the merge changes content, a diff is shown so the user
-/// can review the result.
-pub(crate) fn merge_yaml(existing: &str, template: &str) -> Result<String> {
-    use yaml_rust2::{Yaml, YamlEmitter, YamlLoader};
+/// Marker opening the user's side of a conflict.
+const CONFLICT_START: &str = "<<<<<<< current";
+/// Marker separating the user's side from the template's side.
+const CONFLICT_SEP: &str = "=======";
+
+/// Outcome of a line-based three-way merge.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ThreeWayMerge {
+    /// Both sides' changes applied without overlapping.
+    Clean(String),
+    /// Some changes overlapped; the content contains conflict markers.
+    Conflict(String),
+}
 
-    let existing_docs =
-        YamlLoader::load_from_str(existing).context("failed to parse existing YAML")?;
-    let template_docs =
-        YamlLoader::load_from_str(template).context("failed to parse template YAML")?;
+/// A run of changed lines relative to the base: `base[start..end]` is
+/// replaced by `lines`.
+struct LineEdit<'a> {
+    start: usize,
+    end: usize,
+    lines: Vec<&'a str>,
+}
 
-    // Work with the first document in each file.
-    let existing_doc = existing_docs.first().cloned().unwrap_or(Yaml::Null);
-    let template_doc = template_docs.first().cloned().unwrap_or(Yaml::Null);
+/// Collect the changed regions of `other` relative to `base`, coalescing
+/// adjacent delete/insert operations into a single replacement.
+fn line_edits<'a>(base: &'a str, other: &'a str) -> Vec<LineEdit<'a>> {
+    let diff = similar::TextDiff::from_lines(base, other);
+    let new_lines = diff.new_slices();
+    let mut edits: Vec<LineEdit<'a>> = Vec::new();
+    let mut last_was_edit = false;
+
+    for op in diff.ops() {
+        let (tag, old, new) = op.as_tag_tuple();
+        if tag == similar::DiffTag::Equal {
+            last_was_edit = false;
+            continue;
+        }
+        match edits.last_mut() {
+            Some(prev) if last_was_edit => {
+                prev.end = old.end;
+                prev.lines.extend_from_slice(&new_lines[new]);
+            }
+            _ => edits.push(LineEdit {
+                start: old.start,
+                end: old.end,
+                lines: new_lines[new].to_vec(),
+            }),
+        }
+        last_was_edit = true;
+    }
+    edits
+}
+
+/// Apply one side's edits to `base[lo..hi]`.
+fn apply_line_edits(base: &[&str], lo: usize, hi: usize, edits: &[LineEdit<'_>]) -> String {
+    let mut out = String::new();
+    let mut cur = lo;
+    for edit in edits {
+        out.extend(base[cur..edit.start].iter().copied());
+        out.extend(edit.lines.iter().copied());
+        cur = edit.end;
+    }
+    out.extend(base[cur..hi].iter().copied());
+    out
+}
+
+/// Push `text` and make sure it ends with a newline, so a conflict marker
+/// that follows starts on its own line.
+fn push_line_terminated(out: &mut String, text: &str) {
+    out.push_str(text);
+    if !text.is_empty() && !text.ends_with('\n') {
+        out.push('\n');
+    }
+}
 
-    let merged = merge_yaml_values(&existing_doc, &template_doc);
+/// Line-based three-way merge of `ours` and `theirs` against their common `base`.
+///
+/// Changes made on only one side are taken as-is; identical changes on both
+/// sides are taken once. Overlapping (or touching) changes that differ are
+/// emitted between `<<<<<<< current` / `=======` / `>>>>>>> {theirs_label}`
+/// markers, the same layout `git merge` uses.
+// [impl cli.upgrade-template.conflict]
+pub(crate) fn three_way_merge(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    theirs_label: &str,
+) -> ThreeWayMerge {
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let ours_edits = line_edits(base, ours);
+    let theirs_edits = line_edits(base, theirs);
 
     let mut out = String::new();
-    let mut emitter = YamlEmitter::new(&mut out);
-    emitter
-        .dump(&merged)
-        .context("failed to emit merged YAML")?;
+    let mut conflict = false;
+    let mut pos = 0;
+    let (mut i, mut j) = (0, 0);
+
+    while i < ours_edits.len() || j < theirs_edits.len() {
+        let start = match (ours_edits.get(i), theirs_edits.get(j)) {
+            (Some(o), Some(t)) => o.start.min(t.start),
+            (Some(o), None) => o.start,
+            (None, Some(t)) => t.start,
+            (None, None) => unreachable!("loop condition"),
+        };
+        out.extend(base_lines[pos..start].iter().copied());
+
+        // Grow the group until no edit on either side starts inside it.
+        let (group_i, group_j) = (i, j);
+        let mut hi = start;
+        loop {
+            let mut grew = false;
+            while let Some(edit) = ours_edits.get(i).filter(|e| e.start <= hi) {
+                hi = hi.max(edit.end);
+                i += 1;
+                grew = true;
+            }
+            while let Some(edit) = theirs_edits.get(j).filter(|e| e.start <= hi) {
+                hi = hi.max(edit.end);
+                j += 1;
+                grew = true;
+            }
+            if !grew {
+                break;
+            }
+        }
 
-    // YamlEmitter prepends "---\n"; strip it to match typical workflow files.
-    let out = out.strip_prefix("---\n").unwrap_or(&out).to_string();
+        let ours_group = &ours_edits[group_i..i];
+        let theirs_group = &theirs_edits[group_j..j];
+        if theirs_group.is_empty() {
+            out.push_str(&apply_line_edits(&base_lines, start, hi, ours_group));
+        } else if ours_group.is_empty() {
+            out.push_str(&apply_line_edits(&base_lines, start, hi, theirs_group));
+        } else {
+            let ours_text = apply_line_edits(&base_lines, start, hi, ours_group);
+            let theirs_text = apply_line_edits(&base_lines, start, hi, theirs_group);
+            if ours_text == theirs_text {
+                out.push_str(&ours_text);
+            } else {
+                conflict = true;
+                out.push_str(CONFLICT_START);
+                out.push('\n');
+                push_line_terminated(&mut out, &ours_text);
+                out.push_str(CONFLICT_SEP);
+                out.push('\n');
+                push_line_terminated(&mut out, &theirs_text);
+                out.push_str(&format!(">>>>>>> {theirs_label}\n"));
+            }
+        }
+        pos = hi;
+    }
+    out.extend(base_lines[pos..].iter().copied());
 
-    Ok(out)
+    if conflict {
+        ThreeWayMerge::Conflict(out)
+    } else {
+        ThreeWayMerge::Clean(out)
+    }
 }
 
-/// Recursively merge two YAML values. Existing keys take precedence.
-fn merge_yaml_values(existing: &yaml_rust2::Yaml, template: &yaml_rust2::Yaml) -> yaml_rust2::Yaml {
-    use yaml_rust2::Yaml;
-
-    match (existing, template) {
-        (Yaml::Hash(existing_map), Yaml::Hash(template_map)) => {
-            let mut merged = existing_map.clone();
-            for (key, template_value) in template_map {
-                if let Some(existing_value) = existing_map.get(key) {
-                    // Both have this key: recurse for known deep-merge keys.
-                    let key_str = key.as_str().unwrap_or("");
-                    // Deep-merge known GitHub Actions keys so new jobs,
-                    // triggers, and permissions are added without replacing
-                    // existing ones. Other keys are treated as atomic.
-                    if matches!(key_str, "jobs" | "on" | "permissions") {
-                        merged.insert(
-                            key.clone(),
-                            merge_yaml_values(existing_value, template_value),
-                        );
-                    }
-                    // For other keys, existing value wins (no change).
-                } else {
-                    // New key from template: insert it.
-                    merged.insert(key.clone(), template_value.clone());
-                }
-            }
-            Yaml::Hash(merged)
+/// Whether `content` still contains an unresolved conflict block.
+fn has_conflict_markers(content: &str) -> bool {
+    let mut lines = content.lines();
+    lines.any(|l| l == CONFLICT_START) && lines.any(|l| l == CONFLICT_SEP)
+}
+
+/// Apply an upgraded template to a project by three-way merging each file.
+///
+/// `base` is the template as it was originally applied (re-rendered at the
+/// recorded version and placeholder values) and `files` is the new version.
+/// For each new file:
+///
+/// - present in `base` and in the project: three-way merge, so the user's
+///   edits and the template's changes are both kept. Overlapping edits are
+///   written with conflict markers and reported as [`FileResult::Conflicted`].
+/// - present in `base` but deleted from the project: skipped, since the user
+///   removed it on purpose.
+/// - a verbatim (binary) file: replaced when the project still has the base
+///   bytes, otherwise handled like [`apply_rendered_files`].
+/// - not in `base`: handled like [`apply_rendered_files`].
+///
+/// Files the new version no longer ships are left in place.
+// [impl cli.upgrade-template.deleted]
+pub(crate) fn apply_template_upgrade(
+    base: &[RenderedFile],
+    files: &[RenderedFile],
+    opts: &ApplyOpts,
+    theirs_label: &str,
+) -> Result<Vec<FileResult>> {
+    let mut results = Vec::new();
+    let mut batch = BatchDecision::None;
+
+    for file in files {
+        let Some(base_file) = base.iter().find(|b| b.path == file.path) else {
+            results.push(apply_rendered_file(file, opts, &mut batch)?);
+            continue;
+        };
+
+        let dest = opts.project_dir.join(&file.path);
+        if !dest.exists() {
+            results.push(FileResult::Skipped(file.path.clone()));
+            continue;
         }
-        // Existing is empty/null: use the template value.
-        (Yaml::Null | Yaml::BadValue, _) => template.clone(),
-        // Non-mapping types: existing value wins.
-        _ => existing.clone(),
+        // Verbatim files have no lines to merge: take the new bytes if the
+        // user never changed the file, and treat it as a conflict otherwise.
+        if file.bytes.is_some() {
+            let on_disk = std::fs::read(&dest)
+                .with_context(|| format!("failed to read {}", dest.display()))?;
+            let result = if on_disk == file.data() {
+                FileResult::Unchanged(file.path.clone())
+            } else if on_disk == base_file.data() {
+                std::fs::write(&dest, file.data())
+                    .with_context(|| format!("failed to write {}", dest.display()))?;
+                FileResult::Merged(file.path.clone())
+            } else {
+                apply_rendered_file(file, opts, &mut batch)?
+            };
+            results.push(result);
+            continue;
+        }
+        let Some(existing) = read_existing(&dest)? else {
+            results.push(apply_rendered_file(file, opts, &mut batch)?);
+            continue;
+        };
+
+        let (merged, conflict) =
+            match three_way_merge(&base_file.content, &existing, &file.content, theirs_label) {
+                ThreeWayMerge::Clean(merged) => (merged, false),
+                ThreeWayMerge::Conflict(merged) => (merged, true),
+            };
+        let ctx = ConflictContext {
+            dest: &dest,
+            rel_path: &file.path,
+            existing: &existing,
+            new_content: &merged,
+            new_bytes: None,
+            opts,
+        };
+        let result = resolve_structured_merge(&ctx, &mut batch)?;
+        // The user may have resolved the markers in the editor before accepting.
+        let result = match result {
+            FileResult::Merged(path)
+                if conflict && has_conflict_markers(&std::fs::read_to_string(&dest)?) =>
+            {
+                FileResult::Conflicted(path)
+            }
+            other => other,
+        };
+        results.push(result);
     }
+
+    Ok(results)
 }
 
 // ============================================================================
@@ -600,6 +888,7 @@ pub(crate) fn print_summary(results: &[FileResult]) {
     let mut merged = 0;
     let mut skipped = 0;
     let mut overwritten = 0;
+    let mut conflicted = 0;
 
     for result in results {
         match result {
@@ -622,6 +911,10 @@ pub(crate) fn print_summary(results: &[FileResult]) {
             FileResult::Unchanged(path) => {
                 eprintln!("  {} {}", style("unchanged").dim(), path);
             }
+            FileResult::Conflicted(path) => {
+                eprintln!("  {} {}", style("conflict").red().bold(), path);
+                conflicted += 1;
+            }
         }
     }
 
@@ -639,6 +932,9 @@ pub(crate) fn print_summary(results: &[FileResult]) {
     if overwritten > 0 {
         parts.push(format!("{overwritten} overwritten"));
     }
+    if conflicted > 0 {
+        parts.push(format!("{conflicted} with conflicts"));
+    }
     if !parts.is_empty() {
         eprintln!("{}", parts.join(", "));
     }
@@ -0,0 +1,328 @@
+//! Format-preserving JSON merge.
+//!
+//! Like the YAML merge, the user's file is never re-serialized: members the
+//! template adds are spliced into the existing objects as text. Comments and
+//! trailing commas are accepted, so JSONC files such as
+//! `.vscode/settings.json` merge too.
+
+use anyhow::{Context, Result, anyhow, bail};
+
+/// Merge a template JSON file into an existing one.
+///
+/// Objects are merged recursively: members missing from the user's file are
+/// added after its last member, existing members are left alone. Arrays and
+/// other values are atomic; the existing value wins.
+// [impl cli.add.template-merge-json]
+pub(crate) fn merge_json(existing: &str, template: &str) -> Result<String> {
+    let tmpl = parse(template).context("failed to parse template JSON")?;
+    if existing.trim().is_empty() {
+        return Ok(template.to_string());
+    }
+    let base = parse(existing).context("failed to parse existing JSON")?;
+    let (Value::Object(base), Value::Object(tmpl)) = (base, tmpl) else {
+        return Ok(existing.to_string());
+    };
+
+    let mut splicer = Splicer {
+        base: existing,
+        tmpl: template,
+        tmpl_unit: indent_unit(template, &tmpl),
+        inserts: Vec::new(),
+    };
+    splicer.merge_objects(&base, &tmpl);
+
+    let mut inserts = splicer.inserts;
+    inserts.sort_by_key(|(at, _)| *at);
+    let mut out = String::with_capacity(existing.len());
+    let mut copied = 0;
+    for (at, text) in inserts {
+        out.push_str(&existing[copied..at]);
+        out.push_str(&text);
+        copied = at;
+    }
+    out.push_str(&existing[copied..]);
+    Ok(out)
+}
+
+/// A parsed JSON value, keeping only what splicing needs: the byte spans of
+/// objects and their members.
+enum Value {
+    Object(Object),
+    Other,
+}
+
+struct Object {
+    /// Byte offset of the opening `{`.
+    open: usize,
+    /// Byte offset of the closing `}`.
+    close: usize,
+    members: Vec<Member>,
+}
+
+struct Member {
+    key: String,
+    /// Byte offset of the key's opening quote.
+    start: usize,
+    /// Byte offset just past the value.
+    value_end: usize,
+    value: Value,
+}
+
+struct Splicer<'a> {
+    base: &'a str,
+    tmpl: &'a str,
+    /// One level of the template's indentation.
+    tmpl_unit: String,
+    /// Text to insert at a byte offset of the user's file.
+    inserts: Vec<(usize, String)>,
+}
+
+impl Splicer<'_> {
+    fn merge_objects(&mut self, base: &Object, tmpl: &Object) {
+        let mut added = Vec::new();
+        for member in &tmpl.members {
+            match base.members.iter().find(|m| m.key == member.key) {
+                Some(existing) => {
+                    if let (Value::Object(base), Value::Object(tmpl)) =
+                        (&existing.value, &member.value)
+                    {
+                        self.merge_objects(base, tmpl);
+                    }
+                }
+                None => added.push(member),
+            }
+        }
+        if added.is_empty() {
+            return;
+        }
+
+        // Lay new members out like the existing ones, or one level deeper
+        // than the object's own line when it is empty. Indentation is copied
+        // as text, so tab-indented files stay tab-indented.
+        let open_indent = line_indent(self.base, base.open);
+        let indent = match base.members.first() {
+            Some(first) => member_indent(self.base, first.start),
+            None if open_indent.contains('\t') => format!("{open_indent}\t"),
+            None => format!("{open_indent}  "),
+        };
+        let rendered: Vec<String> = added
+            .iter()
+            .map(|member| {
+                let from = member_indent(self.tmpl, member.start);
+                let text = &self.tmpl[member.start..member.value_end];
+                let text = reindent(text, &from, &indent, &self.tmpl_unit);
+                format!("{indent}{text}")
+            })
+            .collect();
+
+        match base.members.last() {
+            None => {
+                let text = format!("\n{}\n{open_indent}", rendered.join(",\n"));
+                let at = self.base[..base.close].trim_end().len();
+                self.inserts.push((at, text));
+            }
+            Some(last) => {
+                // Keep a trailing comma after the last member if there is one.
+                let after = skip_trivia(self.base, last.value_end);
+                if self.base[after..].starts_with(',') {
+                    let text: String = rendered.iter().map(|m| format!("\n{m},")).collect();
+                    self.inserts.push((after + 1, text));
+                } else {
+                    let text: String = rendered.iter().map(|m| format!(",\n{m}")).collect();
+                    self.inserts.push((last.value_end, text));
+                }
+            }
+        }
+    }
+}
+
+/// The column of byte offset `at`: its distance from the start of its line.
+fn column(text: &str, at: usize) -> usize {
+    at - text[..at].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// The text before byte offset `at` on its line, when that is whitespace,
+/// so a member inserted next to it is indented the same way. Otherwise (a
+/// member sharing its line with the `{`) as many spaces as that text is wide.
+fn member_indent(text: &str, at: usize) -> String {
+    let prefix = &text[text[..at].rfind('\n').map_or(0, |i| i + 1)..at];
+    if prefix.trim_start_matches([' ', '\t']).is_empty() {
+        prefix.to_string()
+    } else {
+        " ".repeat(prefix.chars().count())
+    }
+}
+
+/// The indentation of the line holding byte offset `at`.
+fn line_indent(text: &str, at: usize) -> &str {
+    let line = &text[text[..at].rfind('\n').map_or(0, |i| i + 1)..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// One level of indentation in `text`: how much deeper the first member of
+/// the top-level `object` is indented than its `{`, or two spaces.
+fn indent_unit(text: &str, object: &Object) -> String {
+    object
+        .members
+        .first()
+        .map(|first| member_indent(text, first.start))
+        .and_then(|indent| {
+            indent
+                .strip_prefix(line_indent(text, object.open))
+                .map(str::to_string)
+        })
+        .filter(|unit| !unit.is_empty())
+        .unwrap_or_else(|| "  ".to_string())
+}
+
+/// Move the continuation lines of `text` from indentation `from` to `to`,
+/// keeping how much deeper each is. In a tab-indented file, each `unit` of
+/// the template's extra indentation becomes a tab.
+fn reindent(text: &str, from: &str, to: &str, unit: &str) -> String {
+    let tabs = to.contains('\t');
+    let mut lines = text.split('\n');
+    let mut out = lines.next().unwrap_or_default().to_string();
+    for line in lines {
+        let content = line.trim_start_matches([' ', '\t']);
+        let mut extra = line[..line.len() - content.len()]
+            .strip_prefix(from)
+            .unwrap_or_default();
+        out.push('\n');
+        out.push_str(to);
+        while tabs && extra.starts_with(unit) {
+            out.push('\t');
+            extra = &extra[unit.len()..];
+        }
+        out.push_str(extra);
+        out.push_str(content);
+    }
+    out
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+/// Parse a JSON document, allowing `//` and `/* */` comments and trailing
+/// commas.
+fn parse(text: &str) -> Result<Value> {
+    let start = skip_trivia(text, 0);
+    let (value, end) = parse_value(text, start)?;
+    let end = skip_trivia(text, end);
+    if end != text.len() {
+        bail!("unexpected content at {}", position(text, end));
+    }
+    Ok(value)
+}
+
+/// Parse the value starting at byte offset `at`, returning it and the offset
+/// just past it.
+fn parse_value(text: &str, at: usize) -> Result<(Value, usize)> {
+    let bytes = text.as_bytes();
+    match bytes.get(at) {
+        Some(b'{') => {
+            let mut members = Vec::new();
+            let mut pos = skip_trivia(text, at + 1);
+            while bytes.get(pos) != Some(&b'}') {
+                if bytes.get(pos) != Some(&b'"') {
+                    bail!("expected a key at {}", position(text, pos));
+                }
+                let key_end = string_end(text, pos)?;
+                let key: String = serde_json::from_str(&text[pos..key_end])
+                    .map_err(|e| anyhow!("invalid key at {}: {e}", position(text, pos)))?;
+                let colon = skip_trivia(text, key_end);
+                if bytes.get(colon) != Some(&b':') {
+                    bail!("expected ':' at {}", position(text, colon));
+                }
+                let value_start = skip_trivia(text, colon + 1);
+                let (value, value_end) = parse_value(text, value_start)?;
+                members.push(Member {
+                    key,
+                    start: pos,
+                    value_end,
+                    value,
+                });
+                pos = skip_separator(text, value_end, b'}')?;
+            }
+            let object = Object {
+                open: at,
+                close: pos,
+                members,
+            };
+            Ok((Value::Object(object), pos + 1))
+        }
+        Some(b'[') => {
+            let mut pos = skip_trivia(text, at + 1);
+            while bytes.get(pos) != Some(&b']') {
+                let (_, end) = parse_value(text, pos)?;
+                pos = skip_separator(text, end, b']')?;
+            }
+            Ok((Value::Other, pos + 1))
+        }
+        Some(b'"') => Ok((Value::Other, string_end(text, at)?)),
+        Some(_) => {
+            let len = text[at..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-')))
+                .unwrap_or(text.len() - at);
+            let literal = &text[at..at + len];
+            let valid = matches!(literal, "true" | "false" | "null")
+                || serde_json::from_str::<serde_json::Number>(literal).is_ok();
+            if !valid {
+                bail!("unexpected '{literal}' at {}", position(text, at));
+            }
+            Ok((Value::Other, at + len))
+        }
+        None => bail!("unexpected end of input"),
+    }
+}
+
+/// After a member or element ending at `at`: skip a `,` (trailing ones
+/// allowed) and return the offset of the next item or the closing bracket.
+fn skip_separator(text: &str, at: usize, close: u8) -> Result<usize> {
+    let pos = skip_trivia(text, at);
+    match text.as_bytes().get(pos) {
+        Some(b',') => Ok(skip_trivia(text, pos + 1)),
+        Some(&c) if c == close => Ok(pos),
+        _ => bail!(
+            "expected ',' or '{}' at {}",
+            close as char,
+            position(text, pos)
+        ),
+    }
+}
+
+/// The offset just past the string starting at `at`.
+fn string_end(text: &str, at: usize) -> Result<usize> {
+    let mut escaped = false;
+    for (i, c) in text[at + 1..].char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Ok(at + 1 + i + 1),
+            _ => {}
+        }
+    }
+    bail!("unterminated string at {}", position(text, at))
+}
+
+/// Skip whitespace and comments from `at`.
+fn skip_trivia(text: &str, mut at: usize) -> usize {
+    loop {
+        let rest = &text[at..];
+        let trimmed = rest.trim_start();
+        at += rest.len() - trimmed.len();
+        if trimmed.starts_with("//") {
+            at += trimmed.find('\n').unwrap_or(trimmed.len());
+        } else if let Some(comment) = trimmed.strip_prefix("/*") {
+            at += comment.find("*/").map_or(trimmed.len(), |i| i + 4);
+        } else {
+            return at;
+        }
+    }
+}
+
+/// `line:column` of a byte offset, for errors.
+fn position(text: &str, at: usize) -> String {
+    let line = text[..at].matches('\n').count() + 1;
+    format!("{line}:{}", column(text, at) + 1)
+}
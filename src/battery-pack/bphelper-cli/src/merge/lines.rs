@@ -0,0 +1,74 @@
+//! Set-union merge for line-based files such as `.gitignore` and
+//! `CODEOWNERS`.
+
+use std::collections::HashSet;
+
+/// Merge a template line file into an existing one.
+///
+/// Template lines missing from the user's file are appended, in template
+/// order, together with the comments that introduce them; nothing is removed
+/// or reordered. With `by_pattern`, lines are matched on their first field
+/// only, for files like `CODEOWNERS` and `.gitattributes` where a later line
+/// for the same pattern would override the user's.
+// [impl cli.add.template-merge-lines]
+pub(crate) fn merge_lines(existing: &str, template: &str, by_pattern: bool) -> String {
+    let key = |line: &'_ str| -> String {
+        let line = line.trim();
+        if by_pattern {
+            line.split_whitespace().next().unwrap_or(line).to_string()
+        } else {
+            line.to_string()
+        }
+    };
+    let present: HashSet<&str> = existing.lines().map(str::trim).collect();
+    let mut seen: HashSet<String> = existing
+        .lines()
+        .filter(|line| !is_trivia(line))
+        .map(key)
+        .collect();
+
+    // Comments wait for the first new line of their group; blank lines
+    // separate groups.
+    let mut added: Vec<&str> = Vec::new();
+    let mut comments: Vec<&str> = Vec::new();
+    let mut gap = false;
+    for line in template.lines() {
+        if line.trim().is_empty() {
+            comments.clear();
+            gap = !added.is_empty();
+        } else if line.trim_start().starts_with('#') {
+            comments.push(line);
+        } else if seen.insert(key(line)) {
+            if gap {
+                added.push("");
+                gap = false;
+            }
+            added.extend(comments.drain(..).filter(|c| !present.contains(c.trim())));
+            added.push(line);
+        }
+    }
+
+    if added.is_empty() {
+        return existing.to_string();
+    }
+    let mut out = existing.to_string();
+    if !out.is_empty() {
+        if !out.ends_with('\n') {
+            out.push('\n');
+        }
+        if !out.ends_with("\n\n") {
+            out.push('\n');
+        }
+    }
+    for line in added {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Blank lines and comments, which aren't entries.
+fn is_trivia(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
@@ -0,0 +1,130 @@
+//! Section merge for Markdown files.
+
+/// Merge a template Markdown file into an existing one.
+///
+/// Each `## ` section of the template whose heading the user's file lacks
+/// is inserted, heading and body, after the section that precedes it in the
+/// template (or before the one that follows it, or at the end). Everything
+/// before the template's first `## ` heading, such as its title, is left
+/// out: the user's file already has its own.
+// [impl cli.add.template-merge-markdown]
+pub(crate) fn merge_markdown(existing: &str, template: &str) -> String {
+    let base: Vec<&str> = existing.lines().collect();
+    let tmpl: Vec<&str> = template.lines().collect();
+    let base_sections = sections(&base);
+    let find = |heading: &str| base_sections.iter().find(|s| s.heading == heading);
+
+    // Line index to insert at, with a flag for "before an existing heading"
+    // (the text then needs a blank line after it rather than before).
+    let mut inserts: Vec<(usize, bool, String)> = Vec::new();
+    let tmpl_sections = sections(&tmpl);
+    for (i, section) in tmpl_sections.iter().enumerate() {
+        if find(&section.heading).is_some() {
+            continue;
+        }
+        let previous = tmpl_sections[..i]
+            .iter()
+            .rev()
+            .find_map(|s| find(&s.heading));
+        let next = tmpl_sections[i + 1..].iter().find_map(|s| find(&s.heading));
+        let (at, before) = match (previous, next) {
+            (Some(previous), _) => (previous.end, false),
+            (None, Some(next)) => (next.start, true),
+            (None, None) => (base.len(), false),
+        };
+        let mut text: String = tmpl[section.start..section.end]
+            .iter()
+            .map(|line| format!("{line}\n"))
+            .collect();
+        if before {
+            text.push('\n');
+        } else {
+            text.insert(0, '\n');
+        }
+        match inserts.last_mut() {
+            Some((last_at, last_before, last_text)) if *last_at == at && *last_before == before => {
+                last_text.push_str(&text);
+            }
+            _ => inserts.push((at, before, text)),
+        }
+    }
+
+    if inserts.is_empty() {
+        return existing.to_string();
+    }
+    inserts.sort_by_key(|(at, before, _)| (*at, *before));
+    let mut out = String::with_capacity(existing.len());
+    let mut inserts = inserts.into_iter().peekable();
+    for (i, line) in base.iter().enumerate() {
+        while let Some((_, _, text)) = inserts.next_if(|(at, _, _)| *at == i) {
+            out.push_str(&text);
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    for (_, _, text) in inserts {
+        out.push_str(&text);
+    }
+    out
+}
+
+/// A `## ` section: its heading and line range, without trailing blank
+/// lines.
+struct Section {
+    heading: String,
+    start: usize,
+    end: usize,
+}
+
+/// The `## ` sections of a Markdown file. A section runs until the next
+/// heading of level one or two; headings inside code fences don't count.
+fn sections(lines: &[&str]) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut fence: Option<&str> = None;
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(open) = fence {
+            if trimmed.starts_with(open) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            continue;
+        }
+        let level = line.len() - line.trim_start_matches('#').len();
+        let is_heading = matches!(level, 1 | 2) && line[level..].starts_with(' ');
+        if !is_heading {
+            continue;
+        }
+        if let Some(last) = sections.last_mut()
+            && last.end == usize::MAX
+        {
+            last.end = i;
+        }
+        if level == 2 {
+            sections.push(Section {
+                heading: normalize_heading(&line[level..]),
+                start: i,
+                end: usize::MAX,
+            });
+        }
+    }
+    if let Some(last) = sections.last_mut()
+        && last.end == usize::MAX
+    {
+        last.end = lines.len();
+    }
+    for section in &mut sections {
+        while section.end > section.start + 1 && lines[section.end - 1].trim().is_empty() {
+            section.end -= 1;
+        }
+    }
+    sections
+}
+
+/// Compare headings without case, surrounding space, or closing `#`s.
+fn normalize_heading(text: &str) -> String {
+    text.trim().trim_end_matches('#').trim().to_lowercase()
+}
@@ -311,6 +311,398 @@ fn merge_yaml_adds_permissions() {
     assert!(result.contains("contents"));
 }
 
+// [verify cli.add.template-merge-yaml]
+#[test]
+fn merge_yaml_keeps_comments_order_and_anchors() {
+    let existing = indoc! {r#"
+        # Our CI. Keep the jobs in pipeline order.
+        name: "CI"
+
+        on:
+          push:
+            branches: [main] # release branch too, eventually
+
+        env: &env
+          RUST_BACKTRACE: 1
+
+        jobs:
+          # Fast checks first.
+          test:
+            runs-on: ubuntu-latest
+            env: *env
+            steps:
+              - uses: actions/checkout@v4
+
+          build:
+            runs-on: ubuntu-latest
+
+          # Commented-out until the runners are back.
+          # miri:
+          #   runs-on: ubuntu-latest
+    "#};
+
+    let template = indoc! {r#"
+        name: CI
+        on:
+          push:
+            branches: [main]
+          pull_request:
+        jobs:
+          # Catch typos in code and docs.
+          spellcheck:
+            runs-on: ubuntu-latest
+            steps:
+              - uses: crate-ci/typos@v1 # pinned by the template
+    "#};
+
+    let result = merge_yaml(existing, template).unwrap();
+    snapbox::assert_data_eq!(
+        result,
+        snapbox::str![[r#"
+# Our CI. Keep the jobs in pipeline order.
+name: "CI"
+
+on:
+  push:
+    branches: [main] # release branch too, eventually
+  pull_request:
+
+env: &env
+  RUST_BACKTRACE: 1
+
+jobs:
+  # Fast checks first.
+  test:
+    runs-on: ubuntu-latest
+    env: *env
+    steps:
+      - uses: actions/checkout@v4
+
+  build:
+    runs-on: ubuntu-latest
+
+  # Catch typos in code and docs.
+  spellcheck:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: crate-ci/typos@v1 # pinned by the template
+
+  # Commented-out until the runners are back.
+  # miri:
+  #   runs-on: ubuntu-latest
+
+"#]]
+    );
+}
+
+// [verify cli.add.template-merge-yaml]
+#[test]
+fn merge_yaml_reindents_and_fills_empty_keys() {
+    let existing = indoc! {"
+        permissions:
+        jobs:
+            test:
+                runs-on: ubuntu-latest
+    "};
+
+    let template = indoc! {"
+        permissions:
+          contents: read
+        jobs:
+          lint:
+            steps:
+              - run: |
+                  cargo clippy
+        concurrency: ci
+    "};
+
+    let result = merge_yaml(existing, template).unwrap();
+    snapbox::assert_data_eq!(
+        result,
+        snapbox::str![[r#"
+permissions:
+  contents: read
+jobs:
+    test:
+        runs-on: ubuntu-latest
+    lint:
+      steps:
+        - run: |
+            cargo clippy
+concurrency: ci
+
+"#]]
+    );
+}
+
+#[test]
+fn merge_yaml_unchanged_keeps_file_verbatim() {
+    let existing = "on: [push]   # just push\njobs:\n  test: {runs-on: ubuntu-latest}\n";
+    let template = "on: [push]\njobs:\n  test:\n    runs-on: windows-latest\n";
+    assert_eq!(merge_yaml(existing, template).unwrap(), existing);
+}
+
+#[test]
+fn merge_yaml_flow_mapping_falls_back_to_reemitting() {
+    // A flow map can't take a spliced-in key, so the merged document is
+    // re-emitted; it still holds everything.
+    let existing = "# dropped\npermissions: {contents: read}\n";
+    let template = "permissions:\n  checks: write\n";
+    let result = merge_yaml(existing, template).unwrap();
+    snapbox::assert_data_eq!(
+        result,
+        snapbox::str![[r#"
+permissions:
+  contents: read
+  checks: write
+
+"#]]
+    );
+}
+
+// ============================================================================
+// JSON, line file, and Markdown merge tests
+// ============================================================================
+
+#[test]
+fn strategy_dispatch_json_lines_markdown() {
+    assert_eq!(strategy_for(".vscode/settings.json"), MergeStrategy::Json);
+    assert_eq!(strategy_for("renovate.json"), MergeStrategy::Json);
+    assert_eq!(strategy_for(".gitignore"), MergeStrategy::Lines);
+    assert_eq!(strategy_for("docker/.dockerignore"), MergeStrategy::Lines);
+    assert_eq!(strategy_for(".github/CODEOWNERS"), MergeStrategy::Patterns);
+    assert_eq!(strategy_for("README.md"), MergeStrategy::Markdown);
+}
+
+// [verify cli.add.template-merge-json]
+#[test]
+fn merge_json_splices_members_and_keeps_comments() {
+    let existing = indoc! {r#"
+        {
+            // Keep rust-analyzer fast.
+            "rust-analyzer.cargo.features": ["cli"],
+            "[rust]": {
+                "editor.formatOnSave": true,
+            },
+        }
+    "#};
+    let template = indoc! {r#"
+        {
+          "rust-analyzer.cargo.features": "all",
+          "[rust]": {
+            "editor.formatOnSave": false,
+            "editor.defaultFormatter": "rust-lang.rust-analyzer"
+          },
+          "files.exclude": {
+            "target": true
+          }
+        }
+    "#};
+
+    let result = merge_json(existing, template).unwrap();
+    snapbox::assert_data_eq!(
+        result,
+        snapbox::str![[r#"
+{
+    // Keep rust-analyzer fast.
+    "rust-analyzer.cargo.features": ["cli"],
+    "[rust]": {
+        "editor.formatOnSave": true,
+        "editor.defaultFormatter": "rust-lang.rust-analyzer",
+    },
+    "files.exclude": {
+      "target": true
+    },
+}
+
+"#]]
+    );
+}
+
+// [verify cli.add.template-merge-json]
+#[test]
+fn merge_json_keeps_tab_indentation() {
+    let existing = "{\n\t\"editor.tabSize\": 4,\n\t\"[rust]\": {\n\t\t\"editor.formatOnSave\": true\n\t},\n\t\"files.watcherExclude\": {}\n}\n";
+    let template = indoc! {r#"
+        {
+          "[rust]": {
+            "editor.defaultFormatter": "rust-lang.rust-analyzer"
+          },
+          "files.watcherExclude": {
+            "**/target": true
+          },
+          "files.exclude": {
+            "target": true
+          }
+        }
+    "#};
+
+    let result = merge_json(existing, template).unwrap();
+    assert_eq!(
+        result,
+        "{\n\t\"editor.tabSize\": 4,\n\t\"[rust]\": {\n\t\t\"editor.formatOnSave\": true,\n\t\t\"editor.defaultFormatter\": \"rust-lang.rust-analyzer\"\n\t},\n\t\"files.watcherExclude\": {\n\t\t\"**/target\": true\n\t},\n\t\"files.exclude\": {\n\t\t\"target\": true\n\t}\n}\n"
+    );
+    serde_json::from_str::<serde_json::Value>(&result).unwrap();
+}
+
+// [verify cli.add.template-merge-json]
+#[test]
+fn merge_json_fills_empty_objects_and_rejects_invalid_input() {
+    let result = merge_json(
+        "{\"extends\": [\"config:base\"], \"packageRules\": {}}\n",
+        "{\"packageRules\": {\"automerge\": true}, \"extends\": []}",
+    )
+    .unwrap();
+    assert_eq!(
+        result,
+        "{\"extends\": [\"config:base\"], \"packageRules\": {\n  \"automerge\": true\n}}\n"
+    );
+    serde_json::from_str::<serde_json::Value>(&result).unwrap();
+
+    let err = merge_json("{\"a\": 1 \"b\": 2}", "{}").unwrap_err();
+    assert_eq!(
+        format!("{err:#}"),
+        "failed to parse existing JSON: expected ',' or '}' at 1:9"
+    );
+    assert_eq!(merge_json("", "{\"a\": 1}\n").unwrap(), "{\"a\": 1}\n");
+}
+
+// [verify cli.add.template-merge-lines]
+#[test]
+fn merge_lines_appends_missing_entries_in_order() {
+    let existing = "/target\n*.swp\n";
+    let template = indoc! {"
+        # Build output
+        /target
+
+        # Fuzzing
+        fuzz/corpus
+        fuzz/artifacts
+        *.swp
+    "};
+
+    assert_eq!(
+        merge_lines(existing, template, false),
+        "/target\n*.swp\n\n# Fuzzing\nfuzz/corpus\nfuzz/artifacts\n"
+    );
+    // Merging again changes nothing.
+    let merged = merge_lines(existing, template, false);
+    assert_eq!(merge_lines(&merged, template, false), merged);
+}
+
+// [verify cli.add.template-merge-lines]
+#[test]
+fn merge_lines_by_pattern_keeps_existing_owners() {
+    let existing = "* @me\n";
+    let template = "* @OWNER\n/.github/ @OWNER\n";
+    assert_eq!(
+        merge_lines(existing, template, true),
+        "* @me\n\n/.github/ @OWNER\n"
+    );
+}
+
+// [verify cli.add.template-merge-markdown]
+#[test]
+fn merge_markdown_inserts_missing_sections_in_template_order() {
+    let existing = indoc! {"
+        # my-app
+
+        Does things.
+
+        ## Installation
+
+        cargo install my-app
+
+        ## License
+
+        MIT
+    "};
+    let template = indoc! {"
+        # {{ project_name }}
+
+        ## Usage
+
+        ```sh
+        ## not a heading
+        my-app --help
+        ```
+
+        ## Installation
+
+        cargo install
+
+        ## Contributing
+
+        PRs welcome.
+
+        ## license
+    "};
+
+    let result = merge_markdown(existing, template);
+    snapbox::assert_data_eq!(
+        &result,
+        snapbox::str![[r#"
+# my-app
+
+Does things.
+
+## Usage
+
+```sh
+## not a heading
+my-app --help
+```
+
+## Installation
+
+cargo install my-app
+
+## Contributing
+
+PRs welcome.
+
+## License
+
+MIT
+
+"#]]
+    );
+    assert_eq!(merge_markdown(&result, template), result);
+}
+
+// [verify format.templates.merge]
+#[test]
+fn apply_uses_merge_overrides() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(tmp.path().join("NOTES"), "one\n").unwrap();
+    std::fs::write(tmp.path().join("README.md"), "# Mine\n").unwrap();
+
+    let mut opts = non_interactive(tmp.path());
+    opts.merge_overrides = vec![
+        (glob::Pattern::new("NOTES").unwrap(), MergeStrategy::Lines),
+        (glob::Pattern::new("*.md").unwrap(), MergeStrategy::Plain),
+    ];
+    let files = vec![
+        RenderedFile {
+            path: "NOTES".to_string(),
+            content: "one\ntwo\n".to_string(),
+            bytes: None,
+        },
+        RenderedFile {
+            path: "README.md".to_string(),
+            content: "# Theirs\n\n## Usage\n".to_string(),
+            bytes: None,
+        },
+    ];
+    let results = apply_rendered_files(&files, &opts).unwrap();
+    assert!(matches!(&results[0], FileResult::Merged(p) if p == "NOTES"));
+    assert!(matches!(&results[1], FileResult::Skipped(p) if p == "README.md"));
+    assert_eq!(
+        std::fs::read_to_string(tmp.path().join("NOTES")).unwrap(),
+        "one\n\ntwo\n"
+    );
+}
+
 // ============================================================================
 // Diff display tests
 // ============================================================================
@@ -362,7 +754,7 @@ fn strategy_dispatch_yaml() {
 #[test]
 fn strategy_dispatch_plain() {
     assert!(matches!(strategy_for("src/main.rs"), MergeStrategy::Plain));
-    assert!(matches!(strategy_for("README.md"), MergeStrategy::Plain));
+    assert!(matches!(strategy_for("LICENSE-MIT"), MergeStrategy::Plain));
     assert!(matches!(
         strategy_for(".github/dependabot.yml"),
         MergeStrategy::Yaml
@@ -380,10 +772,12 @@ fn apply_creates_new_files() {
         RenderedFile {
             path: "src/main.rs".to_string(),
             content: "fn main() {}".to_string(),
+            bytes: None,
         },
         RenderedFile {
             path: ".github/workflows/ci.yml".to_string(),
             content: "name: CI".to_string(),
+            bytes: None,
         },
     ];
 
@@ -391,6 +785,7 @@ fn apply_creates_new_files() {
         project_dir: tmp.path().to_path_buf(),
         overwrite: false,
         interactive: false,
+        merge_overrides: Vec::new(),
     };
 
     let results = apply_rendered_files(&files, &opts).unwrap();
@@ -408,25 +803,27 @@ fn apply_skips_existing_plain_non_interactive() {
     let tmp = tempfile::tempdir().unwrap();
 
     // Pre-create a file.
-    let readme = tmp.path().join("README.md");
-    std::fs::write(&readme, "existing content").unwrap();
+    let notes = tmp.path().join("notes.txt");
+    std::fs::write(&notes, "existing content").unwrap();
 
     let files = vec![RenderedFile {
-        path: "README.md".to_string(),
+        path: "notes.txt".to_string(),
         content: "template content".to_string(),
+        bytes: None,
     }];
 
     let opts = ApplyOpts {
         project_dir: tmp.path().to_path_buf(),
         overwrite: false,
         interactive: false,
+        merge_overrides: Vec::new(),
     };
 
     let results = apply_rendered_files(&files, &opts).unwrap();
     assert!(matches!(&results[0], FileResult::Skipped(_)));
 
     // Verify original content is preserved.
-    let content = std::fs::read_to_string(&readme).unwrap();
+    let content = std::fs::read_to_string(&notes).unwrap();
     assert_eq!(content, "existing content");
 }
 
@@ -435,25 +832,27 @@ fn apply_overwrites_existing_plain_with_flag() {
     let tmp = tempfile::tempdir().unwrap();
 
     // Pre-create a file.
-    let readme = tmp.path().join("README.md");
-    std::fs::write(&readme, "existing content").unwrap();
+    let notes = tmp.path().join("notes.txt");
+    std::fs::write(&notes, "existing content").unwrap();
 
     let files = vec![RenderedFile {
-        path: "README.md".to_string(),
+        path: "notes.txt".to_string(),
         content: "template content".to_string(),
+        bytes: None,
     }];
 
     let opts = ApplyOpts {
         project_dir: tmp.path().to_path_buf(),
         overwrite: true,
         interactive: false,
+        merge_overrides: Vec::new(),
     };
 
     let results = apply_rendered_files(&files, &opts).unwrap();
     assert!(matches!(&results[0], FileResult::Overwritten(_)));
 
     // Verify content was replaced.
-    let content = std::fs::read_to_string(&readme).unwrap();
+    let content = std::fs::read_to_string(&notes).unwrap();
     assert_eq!(content, "template content");
 }
 
@@ -485,12 +884,14 @@ fn apply_merges_existing_toml() {
             clap = "4"
         "#}
         .to_string(),
+        bytes: None,
     }];
 
     let opts = ApplyOpts {
         project_dir: tmp.path().to_path_buf(),
         overwrite: false,
         interactive: false,
+        merge_overrides: Vec::new(),
     };
 
     let results = apply_rendered_files(&files, &opts).unwrap();
@@ -531,12 +932,14 @@ fn apply_merges_existing_yaml() {
                 runs-on: ubuntu-latest
         "}
         .to_string(),
+        bytes: None,
     }];
 
     let opts = ApplyOpts {
         project_dir: tmp.path().to_path_buf(),
         overwrite: false,
         interactive: false,
+        merge_overrides: Vec::new(),
     };
 
     let results = apply_rendered_files(&files, &opts).unwrap();
@@ -798,12 +1201,14 @@ fn apply_skips_binary_file_conflict_non_interactive() {
     let files = vec![RenderedFile {
         path: "icon.png".to_string(),
         content: "replacement content".to_string(),
+        bytes: None,
     }];
 
     let opts = ApplyOpts {
         project_dir: tmp.path().to_path_buf(),
         overwrite: false,
         interactive: false,
+        merge_overrides: Vec::new(),
     };
 
     let results = apply_rendered_files(&files, &opts).unwrap();
@@ -825,14 +1230,333 @@ fn apply_overwrites_binary_file_with_flag() {
     let files = vec![RenderedFile {
         path: "icon.png".to_string(),
         content: "replacement content".to_string(),
+        bytes: None,
     }];
 
     let opts = ApplyOpts {
         project_dir: tmp.path().to_path_buf(),
         overwrite: true,
         interactive: false,
+        merge_overrides: Vec::new(),
     };
 
     let results = apply_rendered_files(&files, &opts).unwrap();
     assert!(matches!(&results[0], FileResult::Overwritten(_)));
 }
+
+// ============================================================================
+// Three-way merge tests (template upgrade)
+// ============================================================================
+
+// [verify cli.upgrade-template.merge]
+#[test]
+fn three_way_merge_keeps_both_sides_when_disjoint() {
+    let base = "a\nb\nc\nd\ne\n";
+    let ours = "a\nB (user)\nc\nd\ne\n";
+    let theirs = "a\nb\nc\nd\nE (template)\n";
+
+    let merged = three_way_merge(base, ours, theirs, "ci-battery-pack 0.2.0");
+    assert_eq!(
+        merged,
+        ThreeWayMerge::Clean("a\nB (user)\nc\nd\nE (template)\n".to_string())
+    );
+}
+
+// [verify cli.upgrade-template.merge]
+#[test]
+fn three_way_merge_takes_identical_changes_once() {
+    let base = "a\nb\nc\n";
+    let changed = "a\nX\nc\n";
+
+    let merged = three_way_merge(base, changed, changed, "pack");
+    assert_eq!(merged, ThreeWayMerge::Clean(changed.to_string()));
+}
+
+#[test]
+fn three_way_merge_applies_template_insertions_and_deletions() {
+    let base = "one\ntwo\nthree\n";
+    let ours = "zero\none\ntwo\nthree\n";
+    let theirs = "one\nthree\nfour\n";
+
+    let merged = three_way_merge(base, ours, theirs, "pack");
+    assert_eq!(
+        merged,
+        ThreeWayMerge::Clean("zero\none\nthree\nfour\n".to_string())
+    );
+}
+
+// [verify cli.upgrade-template.conflict]
+#[test]
+fn three_way_merge_marks_overlapping_changes() {
+    let base = "a\nb\nc\n";
+    let ours = "a\nmine\nc\n";
+    let theirs = "a\ntheirs\nc\n";
+
+    let merged = three_way_merge(base, ours, theirs, "ci-battery-pack 0.2.0");
+    assert_eq!(
+        merged,
+        ThreeWayMerge::Conflict(
+            indoc! {"
+                a
+                <<<<<<< current
+                mine
+                =======
+                theirs
+                >>>>>>> ci-battery-pack 0.2.0
+                c
+            "}
+            .to_string()
+        )
+    );
+}
+
+#[test]
+fn three_way_merge_terminates_last_line_before_markers() {
+    let merged = three_way_merge("a\nb", "a\nmine", "a\ntheirs", "pack");
+    assert_eq!(
+        merged,
+        ThreeWayMerge::Conflict(
+            "a\n<<<<<<< current\nmine\n=======\ntheirs\n>>>>>>> pack\n".to_string()
+        )
+    );
+}
+
+fn rendered(path: &str, content: &str) -> RenderedFile {
+    RenderedFile {
+        path: path.to_string(),
+        content: content.to_string(),
+        bytes: None,
+    }
+}
+
+fn non_interactive(dir: &Path) -> ApplyOpts {
+    ApplyOpts {
+        project_dir: dir.to_path_buf(),
+        overwrite: false,
+        interactive: false,
+        merge_overrides: Vec::new(),
+    }
+}
+
+/// A verbatim file holding `bytes`, as rendered from a binary template file.
+fn binary(path: &str, bytes: &[u8]) -> RenderedFile {
+    RenderedFile {
+        path: path.to_string(),
+        content: String::new(),
+        bytes: Some(bytes.to_vec()),
+    }
+}
+
+// [verify format.templates.binary]
+#[test]
+fn apply_writes_binary_files_byte_for_byte() {
+    let tmp = tempfile::tempdir().unwrap();
+    let logo: &[u8] = b"\x89PNG\r\n\x1a\n\0\xff";
+    let files = vec![binary("assets/logo.png", logo)];
+
+    let results = apply_rendered_files(&files, &non_interactive(tmp.path())).unwrap();
+    assert!(matches!(&results[0], FileResult::Created(_)), "{results:?}");
+    assert_eq!(
+        std::fs::read(tmp.path().join("assets/logo.png")).unwrap(),
+        logo
+    );
+
+    // Applying the same bytes again changes nothing.
+    let results = apply_rendered_files(&files, &non_interactive(tmp.path())).unwrap();
+    assert!(
+        matches!(&results[0], FileResult::Unchanged(_)),
+        "{results:?}"
+    );
+}
+
+// [verify format.templates.binary]
+#[test]
+fn apply_treats_changed_binary_files_as_plain_conflicts() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(tmp.path().join("memory.x"), b"old\0").unwrap();
+    let files = vec![binary("memory.x", b"new\0")];
+
+    let results = apply_rendered_files(&files, &non_interactive(tmp.path())).unwrap();
+    assert!(matches!(&results[0], FileResult::Skipped(_)), "{results:?}");
+    assert_eq!(
+        std::fs::read(tmp.path().join("memory.x")).unwrap(),
+        b"old\0"
+    );
+
+    let overwrite = ApplyOpts {
+        overwrite: true,
+        ..non_interactive(tmp.path())
+    };
+    let results = apply_rendered_files(&files, &overwrite).unwrap();
+    assert!(
+        matches!(&results[0], FileResult::Overwritten(_)),
+        "{results:?}"
+    );
+    assert_eq!(
+        std::fs::read(tmp.path().join("memory.x")).unwrap(),
+        b"new\0"
+    );
+}
+
+#[test]
+fn upgrade_merges_template_changes_into_edited_file() {
+    let tmp = tempfile::tempdir().unwrap();
+    let workflow = ".github/workflows/ci.yml";
+    std::fs::create_dir_all(tmp.path().join(".github/workflows")).unwrap();
+    // The user added a comment and a step to the originally applied workflow.
+    std::fs::write(
+        tmp.path().join(workflow),
+        indoc! {"
+            # Our CI
+            name: CI
+            jobs:
+              test:
+                steps:
+                  - run: cargo test
+                  - run: cargo test --doc
+        "},
+    )
+    .unwrap();
+
+    let base = vec![rendered(
+        workflow,
+        indoc! {"
+            name: CI
+            jobs:
+              test:
+                steps:
+                  - run: cargo test
+        "},
+    )];
+    let new = vec![rendered(
+        workflow,
+        indoc! {"
+            name: CI
+            jobs:
+              test:
+                runs-on: ubuntu-latest
+                steps:
+                  - run: cargo test
+        "},
+    )];
+
+    let results =
+        apply_template_upgrade(&base, &new, &non_interactive(tmp.path()), "ci 0.2.0").unwrap();
+    assert!(matches!(&results[0], FileResult::Merged(_)), "{results:?}");
+
+    let content = std::fs::read_to_string(tmp.path().join(workflow)).unwrap();
+    assert_eq!(
+        content,
+        indoc! {"
+            # Our CI
+            name: CI
+            jobs:
+              test:
+                runs-on: ubuntu-latest
+                steps:
+                  - run: cargo test
+                  - run: cargo test --doc
+        "}
+    );
+}
+
+// [verify cli.upgrade-template.conflict]
+#[test]
+fn upgrade_writes_conflict_markers_non_interactive() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(tmp.path().join("deny.toml"), "level = \"warn\"\n").unwrap();
+
+    let base = vec![rendered("deny.toml", "level = \"deny\"\n")];
+    let new = vec![rendered("deny.toml", "level = \"forbid\"\n")];
+
+    let results =
+        apply_template_upgrade(&base, &new, &non_interactive(tmp.path()), "ci 0.2.0").unwrap();
+    assert!(
+        matches!(&results[0], FileResult::Conflicted(_)),
+        "{results:?}"
+    );
+
+    let content = std::fs::read_to_string(tmp.path().join("deny.toml")).unwrap();
+    assert!(content.contains("<<<<<<< current"), "{content}");
+    assert!(content.contains(">>>>>>> ci 0.2.0"), "{content}");
+}
+
+#[test]
+fn upgrade_leaves_files_the_template_did_not_change() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(tmp.path().join("README.md"), "# Edited by user\n").unwrap();
+
+    let base = vec![rendered("README.md", "# {{ project_name }}\n")];
+    let new = base
+        .iter()
+        .map(|f| rendered(&f.path, &f.content))
+        .collect::<Vec<_>>();
+
+    let results =
+        apply_template_upgrade(&base, &new, &non_interactive(tmp.path()), "pack").unwrap();
+    assert!(
+        matches!(&results[0], FileResult::Unchanged(_)),
+        "{results:?}"
+    );
+    assert_eq!(
+        std::fs::read_to_string(tmp.path().join("README.md")).unwrap(),
+        "# Edited by user\n"
+    );
+}
+
+// [verify cli.upgrade-template.deleted]
+#[test]
+fn upgrade_does_not_recreate_deleted_files() {
+    let tmp = tempfile::tempdir().unwrap();
+
+    let base = vec![rendered("typos.toml", "[default]\n")];
+    let new = vec![rendered("typos.toml", "[default]\nlocale = \"en-us\"\n")];
+
+    let results =
+        apply_template_upgrade(&base, &new, &non_interactive(tmp.path()), "pack").unwrap();
+    assert!(matches!(&results[0], FileResult::Skipped(_)), "{results:?}");
+    assert!(!tmp.path().join("typos.toml").exists());
+}
+
+// [verify format.templates.binary]
+#[test]
+fn upgrade_replaces_unmodified_binary_files() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(tmp.path().join("logo.png"), [0x89, b'P', 0x00, 1]).unwrap();
+    std::fs::write(tmp.path().join("icon.png"), [0x89, b'P', 0x00, 9]).unwrap();
+
+    let base = vec![
+        binary("logo.png", &[0x89, b'P', 0x00, 1]),
+        binary("icon.png", &[0x89, b'P', 0x00, 1]),
+    ];
+    let new = vec![
+        binary("logo.png", &[0x89, b'P', 0x00, 2]),
+        binary("icon.png", &[0x89, b'P', 0x00, 2]),
+    ];
+
+    let results =
+        apply_template_upgrade(&base, &new, &non_interactive(tmp.path()), "pack").unwrap();
+    // The untouched file takes the new bytes; the edited one is a conflict,
+    // which non-interactively keeps the user's bytes.
+    assert!(matches!(&results[0], FileResult::Merged(_)), "{results:?}");
+    assert!(matches!(&results[1], FileResult::Skipped(_)), "{results:?}");
+    assert_eq!(
+        std::fs::read(tmp.path().join("logo.png")).unwrap(),
+        [0x89, b'P', 0x00, 2]
+    );
+    assert_eq!(
+        std::fs::read(tmp.path().join("icon.png")).unwrap(),
+        [0x89, b'P', 0x00, 9]
+    );
+}
+
+#[test]
+fn upgrade_creates_files_new_in_template() {
+    let tmp = tempfile::tempdir().unwrap();
+
+    let new = vec![rendered(".github/workflows/audit.yml", "name: Audit\n")];
+
+    let results = apply_template_upgrade(&[], &new, &non_interactive(tmp.path()), "pack").unwrap();
+    assert!(matches!(&results[0], FileResult::Created(_)), "{results:?}");
+    assert!(tmp.path().join(".github/workflows/audit.yml").exists());
+}
@@ -0,0 +1,438 @@
+//! Format-preserving YAML merge.
+//!
+//! The user's file is never re-emitted. Keys the template adds are spliced
+//! into it as text, copied from the template with their comments, quoting
+//! and anchors, and re-indented to fit. The same approach `toml_edit` takes
+//! for `merge_toml`, done by hand for the block-style YAML that workflow
+//! files are written in.
+
+use anyhow::{Context, Result};
+use std::cmp::Reverse;
+use yaml_rust2::{Yaml, YamlEmitter, YamlLoader};
+
+/// Keys whose child maps are merged additively, so new jobs, triggers, and
+/// permissions are added without replacing existing ones. Any other key the
+/// user's file already has is left alone.
+const DEEP_MERGE_KEYS: [&str; 3] = ["jobs", "on", "permissions"];
+
+/// Merge a template YAML file into an existing one.
+///
+/// Template keys missing from the user's file are inserted after its last
+/// key, existing keys are left alone. For known GitHub Actions keys (`jobs`,
+/// `on`, `permissions`), child maps are also merged additively.
+///
+/// Comments, key order, quoting, and anchors in the user's file are kept.
+/// Where the text can't be spliced, e.g. a flow-style `{ ... }` map that
+/// needs a new key, the merged document is re-emitted instead, which loses
+/// comments and formatting; the diff shown before writing makes that visible.
+// [impl cli.add.template-merge-yaml]
+pub(crate) fn merge_yaml(existing: &str, template: &str) -> Result<String> {
+    let existing_docs =
+        YamlLoader::load_from_str(existing).context("failed to parse existing YAML")?;
+    let template_docs =
+        YamlLoader::load_from_str(template).context("failed to parse template YAML")?;
+
+    // Work with the first document in each file.
+    let existing_doc = existing_docs.first().cloned().unwrap_or(Yaml::Null);
+    let template_doc = template_docs.first().cloned().unwrap_or(Yaml::Null);
+
+    let merged = merge_yaml_values(&existing_doc, &template_doc);
+    if merged == existing_doc {
+        return Ok(existing.to_string());
+    }
+    if matches!(existing_doc, Yaml::Null | Yaml::BadValue) {
+        return Ok(template.to_string());
+    }
+
+    // Keep the splice only if it means exactly what the merge should.
+    if let Some(spliced) = splice(existing, template) {
+        let reparsed = YamlLoader::load_from_str(&spliced).ok();
+        if reparsed.as_ref().and_then(|docs| docs.first()) == Some(&merged) {
+            return Ok(spliced);
+        }
+    }
+    emit(&merged)
+}
+
+/// Re-emit a YAML document, dropping comments and formatting.
+fn emit(doc: &Yaml) -> Result<String> {
+    let mut out = String::new();
+    let mut emitter = YamlEmitter::new(&mut out);
+    emitter.dump(doc).context("failed to emit merged YAML")?;
+
+    // YamlEmitter prepends "---\n" and leaves out the final newline; match
+    // typical workflow files instead.
+    let mut out = out.strip_prefix("---\n").unwrap_or(&out).to_string();
+    out.push('\n');
+    Ok(out)
+}
+
+/// Recursively merge two YAML values. Existing keys take precedence.
+fn merge_yaml_values(existing: &Yaml, template: &Yaml) -> Yaml {
+    match (existing, template) {
+        (Yaml::Hash(existing_map), Yaml::Hash(template_map)) => {
+            let mut merged = existing_map.clone();
+            for (key, template_value) in template_map {
+                if let Some(existing_value) = existing_map.get(key) {
+                    // Both have this key: recurse for known deep-merge keys,
+                    // in place so the key keeps its position. Other keys are
+                    // treated as atomic; the existing value wins.
+                    let key_str = key.as_str().unwrap_or("");
+                    if DEEP_MERGE_KEYS.contains(&key_str)
+                        && let Some(slot) = merged.get_mut(key)
+                    {
+                        *slot = merge_yaml_values(existing_value, template_value);
+                    }
+                } else {
+                    // New key from template: insert it.
+                    merged.insert(key.clone(), template_value.clone());
+                }
+            }
+            Yaml::Hash(merged)
+        }
+        // Existing is empty/null: use the template value.
+        (Yaml::Null | Yaml::BadValue, _) => template.clone(),
+        // Non-mapping types: existing value wins.
+        _ => existing.clone(),
+    }
+}
+
+// ============================================================================
+// Text splicing
+// ============================================================================
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum LineKind {
+    Blank,
+    Comment,
+    Content,
+}
+
+/// One line of a YAML file, classified for splicing.
+struct Line<'a> {
+    /// The line, including its terminator.
+    text: &'a str,
+    /// Leading spaces.
+    indent: usize,
+    kind: LineKind,
+}
+
+impl<'a> Line<'a> {
+    fn new(text: &'a str) -> Self {
+        let body = text.trim_end_matches(['\n', '\r']);
+        let trimmed = body.trim_start_matches(' ');
+        let kind = if trimmed.trim().is_empty() {
+            LineKind::Blank
+        } else if trimmed.starts_with('#') {
+            LineKind::Comment
+        } else {
+            LineKind::Content
+        };
+        Line {
+            text,
+            indent: body.len() - trimmed.len(),
+            kind,
+        }
+    }
+
+    /// The line without its indentation and terminator.
+    fn body(&self) -> &'a str {
+        self.text[self.indent..].trim_end_matches(['\n', '\r'])
+    }
+}
+
+/// One `key: value` entry of a block mapping, as line indices.
+struct Entry {
+    key: String,
+    /// First line of the entry: its leading comments, or else the key line.
+    start: usize,
+    /// The line holding the key.
+    line: usize,
+    /// One past the last line of the value, leaving out trailing blank lines
+    /// and comments at the mapping's own indentation.
+    end: usize,
+    /// Whether the value is a block collection on the following lines (or
+    /// empty), rather than written after the key.
+    block: bool,
+}
+
+/// A block mapping: its indentation and entries in file order.
+struct Mapping {
+    indent: usize,
+    entries: Vec<Entry>,
+}
+
+/// Text to insert before line `at` of the user's file. Deeper insertions go
+/// first where they share a line, so they stay inside their parent.
+struct Insert {
+    at: usize,
+    depth: usize,
+    text: String,
+}
+
+/// Splice the template's missing keys into `existing`. Returns `None` when
+/// either file's top level is not a block mapping.
+fn splice(existing: &str, template: &str) -> Option<String> {
+    let base: Vec<Line<'_>> = existing.split_inclusive('\n').map(Line::new).collect();
+    let tmpl: Vec<Line<'_>> = template.split_inclusive('\n').map(Line::new).collect();
+    let (base_start, base_end) = document_body(&base);
+    let (tmpl_start, tmpl_end) = document_body(&tmpl);
+    let base_root = mapping(&base, base_start, base_end)?;
+    let tmpl_root = mapping(&tmpl, tmpl_start, tmpl_end)?;
+
+    let mut splicer = Splicer {
+        base: &base,
+        tmpl: &tmpl,
+        inserts: Vec::new(),
+    };
+    splicer.merge_mapping(&base_root, &tmpl_root, 0);
+
+    let mut inserts = splicer.inserts;
+    inserts.sort_by_key(|insert| (insert.at, Reverse(insert.depth)));
+    let mut inserts = inserts.into_iter().peekable();
+    let mut out = String::with_capacity(existing.len());
+    for (i, line) in base.iter().enumerate() {
+        while let Some(insert) = inserts.next_if(|insert| insert.at == i) {
+            out.push_str(&insert.text);
+        }
+        out.push_str(line.text);
+    }
+    for insert in inserts {
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(&insert.text);
+    }
+    Some(out)
+}
+
+/// The line range of the first document, past any directives and `---`.
+fn document_body(lines: &[Line<'_>]) -> (usize, usize) {
+    let is_marker = |line: &Line<'_>, marker: &str| {
+        line.indent == 0 && line.body().split_whitespace().next() == Some(marker)
+    };
+    let mut start = 0;
+    while start < lines.len()
+        && (lines[start].kind != LineKind::Content || lines[start].body().starts_with('%'))
+    {
+        start += 1;
+    }
+    if start < lines.len() && is_marker(&lines[start], "---") {
+        start += 1;
+    }
+    let end = (start..lines.len())
+        .find(|&i| is_marker(&lines[i], "---") || is_marker(&lines[i], "..."))
+        .unwrap_or(lines.len());
+    (start, end)
+}
+
+/// Read the block mapping in `lines[start..end]`, or `None` if it holds
+/// anything else.
+fn mapping(lines: &[Line<'_>], start: usize, end: usize) -> Option<Mapping> {
+    let first = (start..end).find(|&i| lines[i].kind == LineKind::Content)?;
+    let indent = lines[first].indent;
+    let mut entries: Vec<Entry> = Vec::new();
+    for (i, line) in lines.iter().enumerate().take(end).skip(first) {
+        if line.kind != LineKind::Content || line.indent > indent {
+            continue;
+        }
+        if line.indent < indent {
+            return None;
+        }
+        let body = line.body();
+        if body == "-" || body.starts_with("- ") {
+            // A sequence may sit at its key's indentation.
+            if entries.is_empty() {
+                return None;
+            }
+            continue;
+        }
+        let (key, rest) = parse_key(body)?;
+        entries.push(Entry {
+            key,
+            start: i,
+            line: i,
+            end: i + 1,
+            block: opens_block(rest),
+        });
+    }
+
+    for k in 0..entries.len() {
+        let limit = entries.get(k + 1).map_or(end, |next| next.line);
+        let line = entries[k].line;
+        entries[k].end = (line + 1..limit)
+            .rev()
+            .find(|&i| {
+                lines[i].kind == LineKind::Content
+                    || (lines[i].kind == LineKind::Comment && lines[i].indent > indent)
+            })
+            .map_or(line + 1, |i| i + 1);
+
+        // Comments right above the key, at its indentation, belong to it.
+        let floor = if k == 0 { start } else { entries[k - 1].end };
+        let mut entry_start = line;
+        while entry_start > floor
+            && lines[entry_start - 1].kind == LineKind::Comment
+            && lines[entry_start - 1].indent == indent
+        {
+            entry_start -= 1;
+        }
+        entries[k].start = entry_start;
+    }
+    Some(Mapping { indent, entries })
+}
+
+/// Split a `key: value` line into its (unquoted) key and what follows the
+/// colon. `None` if the line isn't a simple key.
+fn parse_key(body: &str) -> Option<(String, &str)> {
+    let (key, rest) = if let Some(quote) = body.chars().next().filter(|c| matches!(c, '"' | '\'')) {
+        let close = closing_quote(body, quote)?;
+        let rest = body[close + 1..]
+            .trim_start_matches(' ')
+            .strip_prefix(':')?;
+        let key = YamlLoader::load_from_str(&body[..=close]).ok()?;
+        (key.first()?.as_str()?.to_string(), rest)
+    } else {
+        if body.starts_with(['?', '{', '[', '&', '*', '!', '|', '>', '#']) {
+            return None;
+        }
+        let colon = body
+            .match_indices(':')
+            .map(|(i, _)| i)
+            .find(|&i| body[i + 1..].is_empty() || body[i + 1..].starts_with(' '))?;
+        (body[..colon].trim_end().to_string(), &body[colon + 1..])
+    };
+    (rest.is_empty() || rest.starts_with(' ')).then_some((key, rest))
+}
+
+/// Byte index of the quote closing the quoted scalar that starts `body`.
+fn closing_quote(body: &str, quote: char) -> Option<usize> {
+    let mut chars = body.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if quote == '"' => {
+                chars.next();
+            }
+            // A doubled single quote is an escaped one.
+            '\'' if quote == '\'' && chars.peek().is_some_and(|&(_, c)| c == '\'') => {
+                chars.next();
+            }
+            c if c == quote => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Whether a key's value starts on the next line: nothing follows the colon
+/// but anchors, tags, and a comment.
+fn opens_block(rest: &str) -> bool {
+    rest.split_whitespace()
+        .take_while(|token| !token.starts_with('#'))
+        .all(|token| token.starts_with('&') || token.starts_with('!'))
+}
+
+struct Splicer<'s, 'a> {
+    base: &'s [Line<'a>],
+    tmpl: &'s [Line<'a>],
+    inserts: Vec<Insert>,
+}
+
+impl Splicer<'_, '_> {
+    /// Append the template entries missing from `base` after its last entry,
+    /// and recurse into the deep-merge keys both have.
+    fn merge_mapping(&mut self, base: &Mapping, tmpl: &Mapping, depth: usize) {
+        let Some(last) = base.entries.last() else {
+            return;
+        };
+        let separated = is_separated(self.base, base);
+        let mut text = String::new();
+        for entry in &tmpl.entries {
+            match base.entries.iter().find(|e| e.key == entry.key) {
+                Some(existing) if DEEP_MERGE_KEYS.contains(&entry.key.as_str()) => {
+                    self.merge_entry(existing, entry, depth);
+                }
+                Some(_) => {}
+                None => {
+                    let blank = match separated {
+                        Some(separated) => separated,
+                        None => {
+                            entry.start > 0 && self.tmpl[entry.start - 1].kind == LineKind::Blank
+                        }
+                    };
+                    if blank {
+                        text.push('\n');
+                    }
+                    text.push_str(&reindent(
+                        &self.tmpl[entry.start..entry.end],
+                        tmpl.indent,
+                        base.indent,
+                    ));
+                }
+            }
+        }
+        if !text.is_empty() {
+            self.inserts.push(Insert {
+                at: last.end,
+                depth,
+                text,
+            });
+        }
+    }
+
+    /// Merge the value of a deep-merge key present on both sides.
+    fn merge_entry(&mut self, base: &Entry, tmpl: &Entry, depth: usize) {
+        if !base.block || !tmpl.block {
+            return;
+        }
+        let Some(tmpl_map) = mapping(self.tmpl, tmpl.line + 1, tmpl.end) else {
+            return;
+        };
+        if base.end == base.line + 1 {
+            // The key is there with no value: give it the template's.
+            let key_indent = self.base[base.line].indent;
+            let step = tmpl_map.indent - self.tmpl[tmpl.line].indent;
+            let (first, last) = (&tmpl_map.entries[0], &tmpl_map.entries.last().unwrap());
+            self.inserts.push(Insert {
+                at: base.end,
+                depth: depth + 1,
+                text: reindent(
+                    &self.tmpl[first.start..last.end],
+                    tmpl_map.indent,
+                    key_indent + step,
+                ),
+            });
+        } else if let Some(base_map) = mapping(self.base, base.line + 1, base.end) {
+            self.merge_mapping(&base_map, &tmpl_map, depth + 1);
+        }
+    }
+}
+
+/// Whether a mapping's entries are separated by blank lines, or `None` if it
+/// has too few entries to tell.
+fn is_separated(lines: &[Line<'_>], map: &Mapping) -> Option<bool> {
+    if map.entries.len() < 2 {
+        return None;
+    }
+    Some(
+        map.entries[1..]
+            .iter()
+            .all(|e| lines[e.start - 1].kind == LineKind::Blank),
+    )
+}
+
+/// Shift lines from indentation `from` to `to`, keeping their relative
+/// layout. Every returned line ends with a newline.
+fn reindent(lines: &[Line<'_>], from: usize, to: usize) -> String {
+    let mut out = String::new();
+    for line in lines {
+        let body = line.text.trim_end_matches(['\n', '\r']);
+        if line.kind != LineKind::Blank {
+            let indent = (line.indent + to).saturating_sub(from);
+            out.push_str(&" ".repeat(indent));
+            out.push_str(&body[line.indent..]);
+        }
+        out.push('\n');
+    }
+    out
+}
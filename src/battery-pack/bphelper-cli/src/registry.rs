@@ -1,26 +1,26 @@
 //! Crates.io / local-source registry, API types, and shared data types.
 //!
 //! This module handles looking up, downloading, and inspecting battery packs
-//! from crates.io or a local workspace. It also defines the shared data types
+//! from crates.io, an alternative cargo registry, or a local workspace. It also defines the shared data types
 //! used by both the TUI and text output paths.
 
 use anyhow::{Context, Result, bail};
 use bphelper_manifest::{
     BatteryPackSpec, CrateSpec, discover_battery_packs, parse_battery_pack_from_path,
 };
-use flate2::read::GzDecoder;
 use serde::Deserialize;
 use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use tar::Archive;
 use toml_edit::{Array, DocumentMut, InlineTable, Item, TableLike, Value};
 
 use crate::completions::get_cache_dir;
 use crate::manifest::{self, resolve_battery_pack_manifest};
 
-const CRATES_IO_API: &str = "https://crates.io/api/v1/crates";
-const CRATES_IO_CDN: &str = "https://static.crates.io/crates";
+mod cache;
+mod sparse;
+
+pub(crate) use sparse::Registry;
 
 fn http_client() -> &'static reqwest::blocking::Client {
     static CLIENT: std::sync::OnceLock<reqwest::blocking::Client> = std::sync::OnceLock::new();
@@ -34,27 +34,53 @@ fn http_client() -> &'static reqwest::blocking::Client {
 
 // [impl cli.source.flag]
 // [impl cli.source.replace]
+// [impl cli.registry.flag]
 #[derive(Debug, Clone)]
 pub(crate) enum CrateSource {
-    Registry,
+    Registry(Registry),
     Local(PathBuf),
 }
 
-// ============================================================================
-// crates.io API types
-// ============================================================================
+impl CrateSource {
+    /// The default source: crates.io.
+    pub(crate) fn crates_io() -> Self {
+        CrateSource::Registry(Registry::crates_io())
+    }
 
-#[derive(Deserialize)]
-struct CratesIoResponse {
-    versions: Vec<VersionInfo>,
-}
+    /// Disable network access when this is a registry source.
+    pub(crate) fn with_offline(self, offline: bool) -> Self {
+        match self {
+            CrateSource::Registry(registry) => {
+                CrateSource::Registry(registry.with_offline(offline))
+            }
+            local => local,
+        }
+    }
 
-#[derive(Deserialize)]
-struct VersionInfo {
-    num: String,
-    yanked: bool,
+    /// Arguments that select this source on a `cargo bp` command line.
+    pub(crate) fn cli_args(&self) -> Vec<String> {
+        match self {
+            CrateSource::Registry(registry) => {
+                let mut args = match registry.name() {
+                    Some(name) => vec!["--registry".to_string(), name.to_string()],
+                    None => Vec::new(),
+                };
+                if registry.is_offline() {
+                    args.push("--offline".to_string());
+                }
+                args
+            }
+            CrateSource::Local(path) => {
+                vec!["--crate-source".to_string(), path.display().to_string()]
+            }
+        }
+    }
 }
 
+// ============================================================================
+// crates.io API types
+// ============================================================================
+
 #[derive(Deserialize)]
 struct SearchResponse {
     crates: Vec<SearchCrate>,
@@ -177,90 +203,110 @@ pub(crate) struct CrateMetadata {
     pub(crate) version: String,
 }
 
-/// Look up a crate on crates.io and return its metadata
-pub(crate) fn lookup_crate(crate_name: &str) -> Result<CrateMetadata> {
-    let client = http_client();
-
-    let url = format!("{}/{}", CRATES_IO_API, crate_name);
-    let response = client
-        .get(&url)
-        .send()
-        .with_context(|| format!("Failed to query crates.io for '{}'", crate_name))?;
-
-    if !response.status().is_success() {
-        bail!(
-            "Crate '{}' not found on crates.io (status: {})",
-            crate_name,
-            response.status()
-        );
+/// Look up a crate's latest version in a registry index, or in the local
+/// caches when offline. With `req`, the latest version matching it.
+// [impl cli.add.pin-resolve]
+pub(crate) fn lookup_crate(
+    registry: &Registry,
+    crate_name: &str,
+    req: Option<&semver::VersionReq>,
+) -> Result<CrateMetadata> {
+    if registry.is_offline() {
+        let (version, _) =
+            cache::find_offline(registry, crate_name, |v| req.is_none_or(|r| r.matches(v)))
+                .ok_or_else(|| {
+                    let wanted = req.map(|r| r.to_string());
+                    not_cached_error(registry, crate_name, wanted.as_deref())
+                })?;
+        return Ok(CrateMetadata { version });
     }
-
-    let parsed: CratesIoResponse = response
-        .json()
-        .with_context(|| format!("Failed to parse crates.io response for '{}'", crate_name))?;
-
-    // Find the latest non-yanked version
-    let version = parsed
-        .versions
-        .iter()
-        .find(|v| !v.yanked)
-        .map(|v| v.num.clone())
-        .ok_or_else(|| anyhow::anyhow!("No non-yanked versions found for '{}'", crate_name))?;
-
-    Ok(CrateMetadata { version })
+    let entry = registry.latest_version(crate_name, req)?;
+    Ok(CrateMetadata {
+        version: entry.vers,
+    })
 }
 
-/// Download a crate tarball and extract it to a temp directory
-pub(crate) fn download_and_extract_crate(
+/// Return the extracted directory of a crate version, downloading it into
+/// the on-disk cache if it isn't there yet
+// [impl cli.registry.sparse]
+// [impl cli.offline.cache]
+pub(crate) fn fetch_crate_dir(
+    registry: &Registry,
     crate_name: &str,
     version: &str,
-) -> Result<tempfile::TempDir> {
-    let client = http_client();
+) -> Result<PathBuf> {
+    fetch_verified_crate_dir(registry, crate_name, version, None).map(|(dir, _)| dir)
+}
 
-    // Download from CDN: https://static.crates.io/crates/{name}/{name}-{version}.crate
-    let url = format!(
-        "{}/{}/{}-{}.crate",
-        CRATES_IO_CDN, crate_name, crate_name, version
-    );
+/// Like [`fetch_crate_dir`], but also return the SHA-256 of the version's
+/// `.crate` tarball, and fail if it isn't `expected`.
+///
+/// Online, the registry index's checksum is compared with `expected` before
+/// the tarball is downloaded, and the download is checked against it.
+/// Offline, the checksum recorded when the crate was cached is used; a
+/// crate found only in cargo's own `registry/src` has none.
+// [impl manifest.lock.verify]
+pub(crate) fn fetch_verified_crate_dir(
+    registry: &Registry,
+    crate_name: &str,
+    version: &str,
+    expected: Option<&str>,
+) -> Result<(PathBuf, Option<String>)> {
+    let check = |actual: &str| match expected {
+        Some(expected) if !actual.eq_ignore_ascii_case(expected) => Err(anyhow::anyhow!(
+            "checksum mismatch for '{crate_name}' version {version}: \
+             battery-pack.lock has {expected}, {} has {actual}",
+            registry.display_name()
+        )),
+        _ => Ok(()),
+    };
 
-    let response = client
-        .get(&url)
-        .send()
-        .with_context(|| format!("Failed to download crate from {}", url))?;
+    if registry.is_offline() {
+        let (_, dir) = cache::find_offline(registry, crate_name, |v| v.to_string() == version)
+            .ok_or_else(|| not_cached_error(registry, crate_name, Some(version)))?;
+        let checksum = cache::cached_checksum(registry, crate_name, version)
+            .filter(|_| dir.starts_with(registry.cache_dir()));
+        if let Some(checksum) = &checksum {
+            check(checksum)?;
+        }
+        return Ok((dir, checksum));
+    }
 
-    if !response.status().is_success() {
-        bail!(
-            "Failed to download '{}' version {} (status: {})",
+    let entry = registry.find_version(crate_name, version)?;
+    check(&entry.cksum)?;
+    if let Some(dir) = cache::lookup(registry, &entry) {
+        return Ok((dir, Some(entry.cksum)));
+    }
+    let bytes = registry.download(&entry).with_context(|| {
+        format!(
+            "Failed to download '{}' version {} from {}",
             crate_name,
             version,
-            response.status()
-        );
-    }
-
-    let bytes = response
-        .bytes()
-        .with_context(|| "Failed to read crate tarball")?;
-
-    // Create temp directory and extract
-    let temp_dir = tempfile::tempdir().with_context(|| "Failed to create temp directory")?;
-
-    let decoder = GzDecoder::new(&bytes[..]);
-    let mut archive = Archive::new(decoder);
-    archive
-        .unpack(temp_dir.path())
-        .with_context(|| "Failed to extract crate tarball")?;
+            registry.display_name()
+        )
+    })?;
+    let dir = cache::store(registry, &entry, &bytes)?;
+    Ok((dir, Some(entry.cksum)))
+}
 
-    Ok(temp_dir)
+fn not_cached_error(registry: &Registry, crate_name: &str, version: Option<&str>) -> anyhow::Error {
+    let what = match version {
+        Some(v) => format!("'{crate_name}' version {v}"),
+        None => format!("'{crate_name}'"),
+    };
+    anyhow::anyhow!(
+        "{what} from {} is not available offline; run once with network access to cache it",
+        registry.display_name()
+    )
 }
 
 pub(crate) fn fetch_bp_spec_from_registry(
+    registry: &Registry,
     crate_name: &str,
+    req: Option<&semver::VersionReq>,
 ) -> Result<(String, bphelper_manifest::BatteryPackSpec)> {
-    let crate_info = lookup_crate(crate_name)?;
-    let temp_dir = download_and_extract_crate(crate_name, &crate_info.version)?;
-    let crate_dir = temp_dir
-        .path()
-        .join(format!("{}-{}", crate_name, crate_info.version));
+    let crate_info = lookup_crate(registry, crate_name, req)?;
+    let crate_dir = fetch_crate_dir(registry, crate_name, &crate_info.version)?;
 
     let manifest_path = crate_dir.join("Cargo.toml");
     let spec = parse_battery_pack_from_path(&manifest_path)
@@ -329,10 +375,24 @@ fn active_packs_from_state(state: &str) -> Vec<(String, std::collections::BTreeS
         .unwrap_or_default()
 }
 
+/// Rewrite the bp-managed dependencies of a rendered `Cargo.toml` to concrete
+/// versions, fetching battery packs that aren't local from crates.io.
 pub fn resolve_bp_managed_content(
     content: &str,
     bp_crate_root: &Path,
     bp_state: Option<&str>,
+) -> Result<String> {
+    resolve_bp_managed_content_with(content, bp_crate_root, bp_state, &CrateSource::crates_io())
+}
+
+/// Like [`resolve_bp_managed_content`], but battery packs that aren't in
+/// `bp_crate_root`'s workspace -- including the packs they extend -- are
+/// fetched from `source` rather than crates.io.
+pub(crate) fn resolve_bp_managed_content_with(
+    content: &str,
+    bp_crate_root: &Path,
+    bp_state: Option<&str>,
+    source: &CrateSource,
 ) -> Result<String> {
     let mut doc: DocumentMut = content.parse().context("failed to parse Cargo.toml")?;
 
@@ -395,24 +455,28 @@ pub fn resolve_bp_managed_content(
         {
             bspec.clone()
         } else {
-            // Not local; fetch from crates.io. State entries use the short name, so restore the
-            // full `*-battery-pack` crate name for the registry lookup.
-            let full_name = if bp_name == "battery-pack" || bp_name.ends_with("-battery-pack") {
-                bp_name.clone()
-            } else {
-                format!("{bp_name}-battery-pack")
-            };
+            // Not local; fetch from `source`. State entries use the short name, so restore the
+            // full `*-battery-pack` crate name for the lookup.
+            let full_name = resolve_crate_name(bp_name);
             let (_version, bpspec) =
-                fetch_bp_spec_from_registry(&full_name).with_context(|| {
-                    format!("battery pack '{full_name}' not found locally or on crates.io")
+                fetch_bp_spec(source, &full_name, None).with_context(|| {
+                    format!("battery pack '{full_name}' not found locally or in the crate source")
                 })?;
             bpspec
         };
 
         bp_versions.insert(spec.name.clone(), spec.version.clone());
-        for (crate_name, crate_spec) in spec.resolve_for_features(&active_features.into()) {
-            resolved.insert(crate_name, crate_spec);
-        }
+        // [impl cli.add.extends]
+        let crates = spec.resolve_extended(&active_features.into(), &mut |name, entry| {
+            if let Some(local) = all_specs.iter().find(|pack_spec| pack_spec.name == name) {
+                return Ok(local.clone());
+            }
+            let req = semver::VersionReq::parse(&entry.version).ok();
+            fetch_bp_spec(source, name, req.as_ref())
+                .map(|(_version, spec)| spec)
+                .with_context(|| format!("Failed to load extended battery pack '{name}'"))
+        })?;
+        resolved.extend(crates);
     }
 
     // Allow battery packs to reference their own version as bp-managed
@@ -640,17 +704,25 @@ fn extra_keys_on_bp_managed(table: &dyn TableLike) -> Vec<String> {
         .collect()
 }
 
-pub(crate) fn fetch_battery_pack_spec(bp_name: &str) -> Result<bphelper_manifest::BatteryPackSpec> {
-    let manifest_path = resolve_battery_pack_manifest(bp_name)?;
+pub(crate) fn fetch_battery_pack_spec(
+    bp_name: &str,
+    offline: bool,
+) -> Result<bphelper_manifest::BatteryPackSpec> {
+    let manifest_path = resolve_battery_pack_manifest(bp_name, offline)?;
 
     parse_battery_pack_from_path(&manifest_path)
         .with_context(|| format!("Failed to parse battery pack '{}'", bp_name))
 }
 
+/// Load the spec of an installed battery pack.
+///
+/// A pack pinned to `req` is resolved against the registry; otherwise cargo
+/// resolves the build-dependency.
 pub(crate) fn load_installed_bp_spec(
     bp_name: &str,
     path: Option<&str>,
     source: &CrateSource,
+    req: Option<&semver::VersionReq>,
 ) -> Result<bphelper_manifest::BatteryPackSpec> {
     if let Some(local_path) = path {
         let manifest_path = Path::new(local_path).join("Cargo.toml");
@@ -658,9 +730,21 @@ pub(crate) fn load_installed_bp_spec(
             .with_context(|| format!("Failed to parse battery pack '{}'", bp_name));
     }
     match source {
-        CrateSource::Registry => fetch_battery_pack_spec(bp_name),
+        // [impl cli.add.pin-resolve]
+        CrateSource::Registry(_) if req.is_some() => {
+            let (_version, spec) = fetch_bp_spec(source, bp_name, req)?;
+            Ok(spec)
+        }
+        // [impl cli.offline.resolve]
+        CrateSource::Registry(registry) if registry.is_offline() => {
+            // Cargo resolves the build-dependency from its own registry/src; if it
+            // can't, fall back to the bp cache.
+            fetch_battery_pack_spec(bp_name, true)
+                .or_else(|_| fetch_bp_spec(source, bp_name, None).map(|(_, spec)| spec))
+        }
+        CrateSource::Registry(_) => fetch_battery_pack_spec(bp_name, false),
         CrateSource::Local(_) => {
-            let (_version, spec) = fetch_bp_spec(source, bp_name)?;
+            let (_version, spec) = fetch_bp_spec(source, bp_name, None)?;
             Ok(spec)
         }
     }
@@ -678,27 +762,41 @@ pub(crate) fn fetch_battery_pack_list(
     filter: Option<&str>,
 ) -> Result<Vec<BatteryPackSummary>> {
     match source {
-        CrateSource::Registry => fetch_battery_pack_list_from_registry(filter),
+        CrateSource::Registry(registry) => fetch_battery_pack_list_from_registry(registry, filter),
         CrateSource::Local(path) => discover_local_battery_packs(path, filter),
     }
 }
 
-fn fetch_battery_pack_list_from_registry(filter: Option<&str>) -> Result<Vec<BatteryPackSummary>> {
-    let client = http_client();
+fn fetch_battery_pack_list_from_registry(
+    registry: &Registry,
+    filter: Option<&str>,
+) -> Result<Vec<BatteryPackSummary>> {
+    if registry.is_offline() {
+        bail!(
+            "cannot search {} for battery packs in offline mode",
+            registry.display_name()
+        );
+    }
+    let Some(api) = registry.crates_api_url("")? else {
+        bail!(
+            "registry '{}' has no search API; name the battery pack directly",
+            registry.display_name()
+        );
+    };
 
     // Build the search URL with keyword filter
     let url = match filter {
         Some(q) => format!(
-            "{CRATES_IO_API}?q={}&keyword=battery-pack&per_page=50",
+            "{api}?q={}&keyword=battery-pack&per_page=50",
             urlencoding::encode(q)
         ),
-        None => format!("{CRATES_IO_API}?keyword=battery-pack&per_page=50"),
+        None => format!("{api}?keyword=battery-pack&per_page=50"),
     };
 
-    let response = client
+    let response = registry
         .get(&url)
         .send()
-        .context("Failed to query crates.io")?;
+        .with_context(|| format!("Failed to query {}", registry.display_name()))?;
 
     if !response.status().is_success() {
         bail!(
@@ -726,7 +824,7 @@ fn fetch_battery_pack_list_from_registry(filter: Option<&str>) -> Result<Vec<Bat
 }
 
 pub(crate) fn update_cache() -> Result<()> {
-    let packs = fetch_battery_pack_list_from_registry(None)?;
+    let packs = fetch_battery_pack_list_from_registry(&Registry::crates_io(), None)?;
     let pack_names: Vec<String> = packs.into_iter().map(|p| p.name).collect();
 
     let cache_dir = crate::completions::get_cache_dir();
@@ -803,14 +901,18 @@ pub(crate) fn find_local_battery_pack_dir(
         .into())
 }
 
+/// Fetch a battery pack's spec from `source`. For a registry this is the
+/// latest version matching `req`; a local workspace only has the version on
+/// disk, so `req` is not consulted.
 pub(crate) fn fetch_bp_spec(
     source: &CrateSource,
     name: &str,
+    req: Option<&semver::VersionReq>,
 ) -> Result<(Option<String>, bphelper_manifest::BatteryPackSpec)> {
     let crate_name = resolve_crate_name(name);
     match source {
-        CrateSource::Registry => {
-            let (version, spec) = fetch_bp_spec_from_registry(&crate_name)?;
+        CrateSource::Registry(registry) => {
+            let (version, spec) = fetch_bp_spec_from_registry(registry, &crate_name, req)?;
             Ok((Some(version), spec))
         }
         CrateSource::Local(workspace_dir) => {
@@ -830,7 +932,7 @@ pub(crate) fn fetch_battery_pack_detail_from_source(
     name: &str,
 ) -> Result<BatteryPackDetail> {
     match source {
-        CrateSource::Registry => fetch_battery_pack_detail(name, None),
+        CrateSource::Registry(registry) => fetch_battery_pack_detail_from_registry(registry, name),
         CrateSource::Local(workspace_dir) => {
             let crate_name = resolve_crate_name(name);
             let crate_dir = find_local_battery_pack_dir(workspace_dir, &crate_name)?;
@@ -866,22 +968,27 @@ pub(crate) fn fetch_battery_pack_detail(
         return fetch_battery_pack_detail_from_path(local_path);
     }
 
+    fetch_battery_pack_detail_from_registry(&Registry::crates_io(), name)
+}
+
+/// Fetch detailed battery pack info by downloading it from a registry
+fn fetch_battery_pack_detail_from_registry(
+    registry: &Registry,
+    name: &str,
+) -> Result<BatteryPackDetail> {
     let crate_name = resolve_crate_name(name);
 
     // Look up crate info and download
-    let crate_info = lookup_crate(&crate_name)?;
-    let temp_dir = download_and_extract_crate(&crate_name, &crate_info.version)?;
-    let crate_dir = temp_dir
-        .path()
-        .join(format!("{}-{}", crate_name, crate_info.version));
+    let crate_info = lookup_crate(registry, &crate_name, None)?;
+    let crate_dir = fetch_crate_dir(registry, &crate_name, &crate_info.version)?;
 
     // Parse the battery pack spec
     let manifest_path = crate_dir.join("Cargo.toml");
     let spec =
         parse_battery_pack_from_path(&manifest_path).context("Failed to parse battery pack")?;
 
-    // Fetch owners from crates.io
-    let owners = fetch_owners(&crate_name)?;
+    // Fetch owners from the registry's web API
+    let owners = fetch_owners(registry, &crate_name)?;
 
     build_battery_pack_detail(&crate_dir, &spec, owners)
 }
@@ -1004,11 +1111,14 @@ pub(crate) fn build_battery_pack_detail(
     })
 }
 
-fn fetch_owners(crate_name: &str) -> Result<Vec<Owner>> {
-    let client = http_client();
-
-    let url = format!("{}/{}/owners", CRATES_IO_API, crate_name);
-    let response = client
+fn fetch_owners(registry: &Registry, crate_name: &str) -> Result<Vec<Owner>> {
+    if registry.is_offline() {
+        return Ok(Vec::new());
+    }
+    let Some(url) = registry.crates_api_url(&format!("/{crate_name}/owners"))? else {
+        return Ok(Vec::new());
+    };
+    let response = registry
         .get(&url)
         .send()
         .with_context(|| format!("Failed to fetch owners for '{}'", crate_name))?;
@@ -1109,10 +1219,9 @@ pub(crate) fn find_example_path(tree: &[String], example_name: &str) -> Option<S
     tree.iter().find(|path| path.ends_with(&suffix)).cloned()
 }
 
-/// A resolved battery pack crate directory. Owns the temp dir (if any) to keep it alive.
+/// A resolved battery pack crate directory.
 pub(crate) struct ResolvedCrate {
     pub dir: PathBuf,
-    _temp: Option<tempfile::TempDir>,
 }
 
 /// Resolve a battery pack name to a local crate directory.
@@ -1127,24 +1236,52 @@ pub(crate) fn resolve_crate_dir(
     if let Some(path) = path_override {
         return Ok(ResolvedCrate {
             dir: PathBuf::from(path),
-            _temp: None,
         });
     }
 
     let crate_name = resolve_crate_name(battery_pack);
     match source {
-        CrateSource::Registry => {
-            let info = lookup_crate(&crate_name)?;
-            let temp = download_and_extract_crate(&crate_name, &info.version)?;
-            let dir = temp.path().join(format!("{}-{}", crate_name, info.version));
-            Ok(ResolvedCrate {
-                dir,
-                _temp: Some(temp),
-            })
+        CrateSource::Registry(registry) => {
+            let info = lookup_crate(registry, &crate_name, None)?;
+            let dir = fetch_crate_dir(registry, &crate_name, &info.version)?;
+            Ok(ResolvedCrate { dir })
         }
         CrateSource::Local(workspace_dir) => {
             let dir = find_local_battery_pack_dir(workspace_dir, &crate_name)?;
-            Ok(ResolvedCrate { dir, _temp: None })
+            Ok(ResolvedCrate { dir })
+        }
+    }
+}
+
+/// Resolve a battery pack's crate directory at an exact version.
+///
+/// Registry sources download that version. A local workspace only has the
+/// version currently checked out, so this fails if it doesn't match.
+pub(crate) fn resolve_crate_dir_at_version(
+    battery_pack: &str,
+    version: &str,
+    source: &CrateSource,
+) -> Result<ResolvedCrate> {
+    let crate_name = resolve_crate_name(battery_pack);
+    match source {
+        CrateSource::Registry(registry) => {
+            let dir = fetch_crate_dir(registry, &crate_name, version)?;
+            Ok(ResolvedCrate { dir })
+        }
+        CrateSource::Local(workspace_dir) => {
+            let dir = find_local_battery_pack_dir(workspace_dir, &crate_name)?;
+            let spec = bphelper_manifest::parse_battery_pack_from_path(&dir.join("Cargo.toml"))
+                .with_context(|| format!("Failed to parse {}", dir.display()))?;
+            if spec.version != version {
+                bail!(
+                    "{} is at version {} in {}, not {}",
+                    crate_name,
+                    spec.version,
+                    workspace_dir.display(),
+                    version
+                );
+            }
+            Ok(ResolvedCrate { dir })
         }
     }
 }
@@ -0,0 +1,119 @@
+//! On-disk cache of extracted battery pack crates.
+//!
+//! Registry downloads are unpacked once into
+//! `$CARGO_HOME/bp-cache/crates/<registry>/<name>-<version>/`, next to a
+//! `<name>-<version>.cksum` file holding the tarball's SHA-256, and reused
+//! while the index checksum still matches. In offline mode this cache and
+//! cargo's own `registry/src` are the only places crates are found.
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use std::fs;
+use std::path::PathBuf;
+use tar::Archive;
+
+use super::sparse::{IndexEntry, Registry};
+
+/// The cached crate directory for `entry`, if present with a matching checksum.
+// [impl cli.offline.cache]
+pub(crate) fn lookup(registry: &Registry, entry: &IndexEntry) -> Option<PathBuf> {
+    let dir = registry.cache_dir();
+    let stem = format!("{}-{}", entry.name, entry.vers);
+    let cksum = fs::read_to_string(dir.join(format!("{stem}.cksum"))).ok()?;
+    let crate_dir = dir.join(&stem);
+    (cksum.trim().eq_ignore_ascii_case(&entry.cksum) && crate_dir.is_dir()).then_some(crate_dir)
+}
+
+/// The checksum recorded for a cached crate, if it is in the bp cache.
+pub(crate) fn cached_checksum(
+    registry: &Registry,
+    crate_name: &str,
+    version: &str,
+) -> Option<String> {
+    let path = registry
+        .cache_dir()
+        .join(format!("{crate_name}-{version}.cksum"));
+    fs::read_to_string(path).ok().map(|c| c.trim().to_string())
+}
+
+/// Unpack a verified tarball into the cache and return the crate directory.
+// [impl cli.offline.cache]
+pub(crate) fn store(registry: &Registry, entry: &IndexEntry, tarball: &[u8]) -> Result<PathBuf> {
+    let dir = registry.cache_dir();
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create cache directory {}", dir.display()))?;
+    let stem = format!("{}-{}", entry.name, entry.vers);
+    let crate_dir = dir.join(&stem);
+    let cksum_path = dir.join(format!("{stem}.cksum"));
+
+    // Unpack beside the final location and move it into place, so an
+    // interrupted run never leaves a half-extracted crate in the cache.
+    let staging = tempfile::tempdir_in(&dir).context("Failed to create temp directory")?;
+    Archive::new(GzDecoder::new(tarball))
+        .unpack(staging.path())
+        .context("Failed to extract crate tarball")?;
+
+    if crate_dir.exists() {
+        if let Some(existing) = lookup(registry, entry) {
+            // Another run cached the same crate meanwhile.
+            return Ok(existing);
+        }
+        // A stale copy whose checksum no longer matches the index.
+        fs::remove_dir_all(&crate_dir)
+            .with_context(|| format!("Failed to remove {}", crate_dir.display()))?;
+    }
+    fs::write(&cksum_path, &entry.cksum).context("Failed to write cache checksum")?;
+    if let Err(err) = fs::rename(staging.path().join(&stem), &crate_dir) {
+        if let Some(existing) = lookup(registry, entry) {
+            return Ok(existing);
+        }
+        return Err(err).with_context(|| {
+            format!(
+                "crate tarball for '{}' has no {stem}/ directory",
+                entry.name
+            )
+        });
+    }
+    Ok(crate_dir)
+}
+
+/// Find a crate without touching the network: first in the bp cache, then in
+/// cargo's `registry/src`. Of the versions accepted by `wanted`, the newest
+/// wins, preferring stable releases. Returns the version and its directory.
+// [impl cli.offline.resolve]
+pub(crate) fn find_offline(
+    registry: &Registry,
+    crate_name: &str,
+    wanted: impl Fn(&semver::Version) -> bool,
+) -> Option<(String, PathBuf)> {
+    let mut roots = vec![registry.cache_dir()];
+    roots.extend(registry.cargo_src_dirs());
+
+    let prefix = format!("{crate_name}-");
+    let mut best: Option<(semver::Version, PathBuf)> = None;
+    for root in roots {
+        let Ok(entries) = fs::read_dir(&root) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let Some(vers) = file_name.to_str().and_then(|n| n.strip_prefix(&prefix)) else {
+                continue;
+            };
+            // Also rejects longer crate names sharing the prefix (`foo-bar-1.0.0` for `foo`).
+            let Ok(parsed) = semver::Version::parse(vers) else {
+                continue;
+            };
+            if !wanted(&parsed) || !entry.path().is_dir() {
+                continue;
+            }
+            let newer = best.as_ref().is_none_or(|(current, _)| {
+                (parsed.pre.is_empty(), &parsed) > (current.pre.is_empty(), current)
+            });
+            if newer {
+                best = Some((parsed, entry.path()));
+            }
+        }
+    }
+    best.map(|(version, dir)| (version.to_string(), dir))
+}
@@ -0,0 +1,472 @@
+//! Cargo registries reached through the sparse index protocol.
+//!
+//! crates.io and any registry configured under `[registries]` in cargo's
+//! config are read the same way: the index lists versions and checksums,
+//! and the index's `config.json` supplies the download template and the
+//! web API root used for search and owners.
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::http_client;
+use crate::completions::get_cache_dir;
+
+const CRATES_IO_INDEX: &str = "https://index.crates.io/";
+const CRATES_IO_DL: &str = "https://static.crates.io/crates";
+const CRATES_IO_API: &str = "https://crates.io";
+
+/// Markers that may appear in a registry's `dl` template. If none do, cargo
+/// appends `/{crate}/{version}/download`, and so do we.
+const DL_MARKERS: &[&str] = &[
+    "{crate}",
+    "{version}",
+    "{prefix}",
+    "{lowerprefix}",
+    "{sha256-checksum}",
+];
+
+/// A cargo registry with a sparse index.
+#[derive(Debug, Clone)]
+pub(crate) struct Registry {
+    /// Name under `[registries]`, or `None` for crates.io.
+    name: Option<String>,
+    /// Index root with the `sparse+` prefix stripped and a trailing slash.
+    index: String,
+    token: Option<String>,
+    config: OnceLock<IndexConfig>,
+    /// Refuse network access; only `file://` indexes can still be read.
+    offline: bool,
+    /// Where extracted crates are cached (`$CARGO_HOME/bp-cache/crates`).
+    cache_dir: PathBuf,
+    /// Cargo's home, whose `registry/src` is searched when offline.
+    cargo_home: Option<PathBuf>,
+}
+
+/// The index's `config.json`.
+#[derive(Debug, Clone, Deserialize)]
+struct IndexConfig {
+    dl: String,
+    #[serde(default)]
+    api: Option<String>,
+}
+
+/// One line of a crate's index file. Only the fields we use are parsed.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct IndexEntry {
+    pub(crate) name: String,
+    pub(crate) vers: String,
+    pub(crate) cksum: String,
+    #[serde(default)]
+    pub(crate) yanked: bool,
+}
+
+impl Registry {
+    /// The crates.io registry. Its index config is known, so it is never fetched.
+    pub(crate) fn crates_io() -> Self {
+        let config = OnceLock::new();
+        let _ = config.set(IndexConfig {
+            dl: CRATES_IO_DL.to_string(),
+            api: Some(CRATES_IO_API.to_string()),
+        });
+        Self {
+            name: None,
+            index: CRATES_IO_INDEX.to_string(),
+            token: None,
+            config,
+            offline: false,
+            cache_dir: get_cache_dir().join("crates"),
+            cargo_home: cargo_home(),
+        }
+    }
+
+    /// Look up a registry by name the way cargo does: the
+    /// `CARGO_REGISTRIES_<NAME>_INDEX` environment variable, then
+    /// `[registries.<name>]` in `.cargo/config.toml` files from `cwd` upwards
+    /// and in `$CARGO_HOME`. The token comes from `CARGO_REGISTRIES_<NAME>_TOKEN`,
+    /// the config files, or `$CARGO_HOME/credentials.toml`.
+    // [impl cli.registry.config]
+    // [impl cli.registry.auth]
+    pub(crate) fn from_cargo_config(name: &str, cwd: &Path) -> Result<Self> {
+        if name == "crates-io" {
+            return Ok(Self::crates_io());
+        }
+
+        let configs = read_cargo_configs(cwd)?;
+        let env_key = name.to_uppercase().replace('-', "_");
+
+        let index = match std::env::var(format!("CARGO_REGISTRIES_{env_key}_INDEX")) {
+            Ok(index) => index,
+            Err(_) => registry_key(&configs, name, "index").with_context(|| {
+                format!(
+                    "registry '{name}' is not configured; add `[registries.{name}]` with an \
+                     `index = \"sparse+https://...\"` to .cargo/config.toml"
+                )
+            })?,
+        };
+        // [impl cli.registry.sparse]
+        let Some(index) = index.strip_prefix("sparse+") else {
+            bail!(
+                "registry '{name}' uses a git index ({index}); only sparse (`sparse+`) indexes are supported"
+            );
+        };
+
+        let token = std::env::var(format!("CARGO_REGISTRIES_{env_key}_TOKEN"))
+            .ok()
+            .or_else(|| registry_key(&configs, name, "token"))
+            .or_else(|| read_credentials_token(name));
+
+        Ok(Self {
+            name: Some(name.to_string()),
+            index: format!("{}/", index.trim_end_matches('/')),
+            token,
+            config: OnceLock::new(),
+            offline: false,
+            cache_dir: get_cache_dir().join("crates"),
+            cargo_home: cargo_home(),
+        })
+    }
+
+    /// Disable network access for this registry.
+    // [impl cli.offline.flag]
+    pub(crate) fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    pub(crate) fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Cache directory for this registry's extracted crates.
+    pub(crate) fn cache_dir(&self) -> PathBuf {
+        self.cache_dir.join(self.display_name())
+    }
+
+    /// Cargo's extracted sources for this registry:
+    /// `$CARGO_HOME/registry/src/<host>-<hash>/`.
+    pub(crate) fn cargo_src_dirs(&self) -> Vec<PathBuf> {
+        let prefixes = self.cargo_index_dir_prefixes();
+        let Some(src) = self
+            .cargo_home
+            .as_ref()
+            .map(|home| home.join("registry/src"))
+        else {
+            return Vec::new();
+        };
+        let Ok(entries) = std::fs::read_dir(&src) else {
+            return Vec::new();
+        };
+        let mut dirs: Vec<PathBuf> = entries
+            .flatten()
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| prefixes.iter().any(|p| name.starts_with(p.as_str())))
+            })
+            .map(|entry| entry.path())
+            .collect();
+        dirs.sort();
+        dirs
+    }
+
+    /// The `[registries]` name, or `None` for crates.io.
+    pub(crate) fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Human-readable name for messages.
+    pub(crate) fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or("crates.io")
+    }
+
+    /// The registry's source as cargo writes it in `Cargo.lock`
+    /// (`sparse+<index url>`), recorded in `battery-pack.lock`.
+    pub(crate) fn source_id(&self) -> String {
+        format!("sparse+{}", self.index)
+    }
+
+    /// Directory-name prefixes cargo uses for this registry under
+    /// `$CARGO_HOME/registry/src` (`<host>-<hash>`). Empty for `file://` indexes.
+    fn cargo_index_dir_prefixes(&self) -> Vec<String> {
+        if self.name.is_none() {
+            // The sparse index, and the git index older cargo versions used.
+            return vec!["index.crates.io-".to_string(), "github.com-".to_string()];
+        }
+        reqwest::Url::parse(&self.index)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| format!("{host}-")))
+            .into_iter()
+            .collect()
+    }
+
+    /// All published versions of a crate, in index order.
+    pub(crate) fn index_entries(&self, crate_name: &str) -> Result<Vec<IndexEntry>> {
+        let url = format!("{}{}", self.index, index_path(crate_name));
+        let Some(bytes) = self.fetch(&url)? else {
+            bail!(
+                "Crate '{}' not found on {}",
+                crate_name,
+                self.display_name()
+            );
+        };
+        let text = String::from_utf8(bytes)
+            .with_context(|| format!("Index file for '{crate_name}' is not UTF-8"))?;
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .with_context(|| format!("Failed to parse index entry for '{crate_name}'"))
+            })
+            .collect()
+    }
+
+    /// The newest non-yanked version, preferring stable releases over
+    /// pre-releases. With `req`, only versions matching it are considered.
+    pub(crate) fn latest_version(
+        &self,
+        crate_name: &str,
+        req: Option<&semver::VersionReq>,
+    ) -> Result<IndexEntry> {
+        self.index_entries(crate_name)?
+            .into_iter()
+            .filter(|e| !e.yanked)
+            .filter_map(|e| semver::Version::parse(&e.vers).ok().map(|v| (v, e)))
+            .filter(|(v, _)| req.is_none_or(|req| req.matches(v)))
+            .max_by(|(a, _), (b, _)| (a.pre.is_empty(), a).cmp(&(b.pre.is_empty(), b)))
+            .map(|(_, e)| e)
+            .ok_or_else(|| match req {
+                Some(req) => anyhow::anyhow!(
+                    "No non-yanked version of '{}' matching {} found on {}",
+                    crate_name,
+                    req,
+                    self.display_name()
+                ),
+                None => anyhow::anyhow!(
+                    "No non-yanked versions found for '{}' on {}",
+                    crate_name,
+                    self.display_name()
+                ),
+            })
+    }
+
+    /// The index entry for an exact version, yanked or not.
+    pub(crate) fn find_version(&self, crate_name: &str, version: &str) -> Result<IndexEntry> {
+        self.index_entries(crate_name)?
+            .into_iter()
+            .find(|e| e.vers == version)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "'{}' has no version {} on {}",
+                    crate_name,
+                    version,
+                    self.display_name()
+                )
+            })
+    }
+
+    /// Download a `.crate` tarball and check it against the index checksum.
+    pub(crate) fn download(&self, entry: &IndexEntry) -> Result<Vec<u8>> {
+        let url = dl_url(&self.config()?.dl, entry);
+        let bytes = self
+            .fetch(&url)?
+            .with_context(|| format!("Failed to download crate from {url} (not found)"))?;
+
+        let actual = hex(&Sha256::digest(&bytes));
+        if !actual.eq_ignore_ascii_case(&entry.cksum) {
+            bail!(
+                "checksum mismatch for '{}' version {}: index has {}, download has {}",
+                entry.name,
+                entry.vers,
+                entry.cksum,
+                actual
+            );
+        }
+        Ok(bytes)
+    }
+
+    /// URL of a crates API endpoint (`<api>/api/v1/crates<path>`), or `None`
+    /// if the registry has no web API.
+    pub(crate) fn crates_api_url(&self, path: &str) -> Result<Option<String>> {
+        Ok(self
+            .config()?
+            .api
+            .as_ref()
+            .map(|api| format!("{}/api/v1/crates{}", api.trim_end_matches('/'), path)))
+    }
+
+    /// Start a GET request, attaching the registry token if there is one.
+    pub(crate) fn get(&self, url: &str) -> reqwest::blocking::RequestBuilder {
+        let request = http_client().get(url);
+        match &self.token {
+            Some(token) => request.header(reqwest::header::AUTHORIZATION, token),
+            None => request,
+        }
+    }
+
+    fn config(&self) -> Result<&IndexConfig> {
+        if let Some(config) = self.config.get() {
+            return Ok(config);
+        }
+        let url = format!("{}config.json", self.index);
+        let bytes = self.fetch(&url)?.with_context(|| {
+            format!(
+                "registry '{}' has no config.json at {url}",
+                self.display_name()
+            )
+        })?;
+        let config: IndexConfig =
+            serde_json::from_slice(&bytes).with_context(|| format!("Failed to parse {url}"))?;
+        let _ = self.config.set(config);
+        Ok(self.config.get().expect("config was just set"))
+    }
+
+    /// Fetch a URL from the index or download location. `file://` URLs are read
+    /// from disk. Returns `None` when the resource does not exist.
+    fn fetch(&self, url: &str) -> Result<Option<Vec<u8>>> {
+        if url.starts_with("file://") {
+            let path = reqwest::Url::parse(url)
+                .ok()
+                .and_then(|u| u.to_file_path().ok())
+                .with_context(|| format!("invalid file URL: {url}"))?;
+            return match std::fs::read(&path) {
+                Ok(bytes) => Ok(Some(bytes)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+            };
+        }
+
+        // [impl cli.offline.no-network]
+        if self.offline {
+            bail!(
+                "cannot reach {} ({url}) in offline mode",
+                self.display_name()
+            );
+        }
+
+        let response = self
+            .get(url)
+            .send()
+            .with_context(|| format!("Failed to query {} at {url}", self.display_name()))?;
+        let status = response.status();
+        // The sparse protocol treats 404, 410 and 451 as "does not exist".
+        if matches!(status.as_u16(), 404 | 410 | 451) {
+            return Ok(None);
+        }
+        if status == reqwest::StatusCode::UNAUTHORIZED
+            && self.token.is_none()
+            && let Some(name) = &self.name
+        {
+            bail!(
+                "registry '{name}' requires authentication; run `cargo login --registry {name}` \
+                 or set CARGO_REGISTRIES_{}_TOKEN",
+                name.to_uppercase().replace('-', "_")
+            );
+        }
+        if !status.is_success() {
+            bail!("Request to {url} failed (status: {status})");
+        }
+        let bytes = response
+            .bytes()
+            .with_context(|| format!("Failed to read response from {url}"))?;
+        Ok(Some(bytes.to_vec()))
+    }
+}
+
+/// Path of a crate's file within the index, e.g. `se/rd/serde` or `3/s/syn`.
+pub(crate) fn index_path(crate_name: &str) -> String {
+    let name = crate_name.to_lowercase();
+    format!("{}/{}", prefix(&name), name)
+}
+
+/// Directory part of the index path, keeping the crate name's case.
+fn prefix(name: &str) -> String {
+    match name.len() {
+        1 => "1".to_string(),
+        2 => "2".to_string(),
+        3 => format!("3/{}", &name[..1]),
+        _ => format!("{}/{}", &name[..2], &name[2..4]),
+    }
+}
+
+/// Expand a `dl` template for one crate version.
+pub(crate) fn dl_url(template: &str, entry: &IndexEntry) -> String {
+    if !DL_MARKERS.iter().any(|m| template.contains(m)) {
+        return format!(
+            "{}/{}/{}/download",
+            template.trim_end_matches('/'),
+            entry.name,
+            entry.vers
+        );
+    }
+    template
+        .replace("{crate}", &entry.name)
+        .replace("{version}", &entry.vers)
+        .replace("{prefix}", &prefix(&entry.name))
+        .replace("{lowerprefix}", &prefix(&entry.name.to_lowercase()))
+        .replace("{sha256-checksum}", &entry.cksum)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn cargo_home() -> Option<PathBuf> {
+    std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cargo")))
+}
+
+/// Cargo config files in precedence order: `cwd` and its ancestors first,
+/// then `$CARGO_HOME`. Missing files are skipped.
+fn read_cargo_configs(cwd: &Path) -> Result<Vec<toml::Table>> {
+    let mut dirs: Vec<PathBuf> = cwd.ancestors().map(|d| d.join(".cargo")).collect();
+    if let Some(home) = cargo_home()
+        && !dirs.contains(&home)
+    {
+        dirs.push(home);
+    }
+
+    let mut configs = Vec::new();
+    for dir in dirs {
+        for file in ["config.toml", "config"] {
+            let path = dir.join(file);
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let table = content
+                .parse::<toml::Table>()
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            configs.push(table);
+            break;
+        }
+    }
+    Ok(configs)
+}
+
+/// First `[registries.<name>].<key>` string found in the given config tables.
+fn registry_key(configs: &[toml::Table], name: &str, key: &str) -> Option<String> {
+    configs.iter().find_map(|config| {
+        config
+            .get("registries")?
+            .get(name)?
+            .get(key)?
+            .as_str()
+            .map(str::to_string)
+    })
+}
+
+fn read_credentials_token(name: &str) -> Option<String> {
+    let home = cargo_home()?;
+    ["credentials.toml", "credentials"].iter().find_map(|file| {
+        let content = std::fs::read_to_string(home.join(file)).ok()?;
+        let table = content.parse::<toml::Table>().ok()?;
+        registry_key(std::slice::from_ref(&table), name, "token")
+    })
+}
+
+#[cfg(test)]
+mod tests;
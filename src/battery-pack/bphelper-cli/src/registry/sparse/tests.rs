@@ -0,0 +1,683 @@
+//! Tests for registries read through a sparse index, and the crate cache.
+//!
+//! The index and tarballs live on disk and are reached through `file://`
+//! URLs, so these run without network access. Each test points the crate
+//! cache and cargo home into its own temp dir.
+
+use super::{IndexEntry, Registry, dl_url, index_path};
+use crate::lockfile::{LockMode, Lockfile, PackResolver, read_lockfile, write_lockfile};
+use crate::registry::{self, CrateSource};
+use sha2::{Digest, Sha256};
+use snapbox::{assert_data_eq, str};
+use std::path::{Path, PathBuf};
+
+fn fixtures_dir() -> PathBuf {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    manifest_dir
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("tests/fixtures")
+}
+
+/// Package the fancy fixture as `fancy-battery-pack-0.2.0.crate` and publish it
+/// into a file-based sparse index under `root`. `extra_lines` are appended to
+/// the crate's index file verbatim. Returns the `sparse+file://` index URL.
+fn publish_fancy_to_file_index(
+    root: &Path,
+    cksum_override: Option<&str>,
+    extra_lines: &[&str],
+) -> String {
+    let index_dir = root.join("index");
+    let dl_dir = root.join("dl");
+    std::fs::create_dir_all(index_dir.join("fa/nc")).unwrap();
+    std::fs::create_dir_all(&dl_dir).unwrap();
+
+    let mut tarball = Vec::new();
+    {
+        let encoder = flate2::write::GzEncoder::new(&mut tarball, flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        builder
+            .append_dir_all(
+                "fancy-battery-pack-0.2.0",
+                fixtures_dir().join("fancy-battery-pack"),
+            )
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+    std::fs::write(dl_dir.join("fancy-battery-pack-0.2.0.crate"), &tarball).unwrap();
+
+    let cksum = cksum_override
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{:x}", Sha256::digest(&tarball)));
+    let mut lines = vec![format!(
+        r#"{{"name":"fancy-battery-pack","vers":"0.2.0","deps":[],"cksum":"{cksum}","features":{{}},"yanked":false}}"#
+    )];
+    lines.extend(extra_lines.iter().map(|l| l.to_string()));
+    std::fs::write(
+        index_dir.join("fa/nc/fancy-battery-pack"),
+        lines.join("\n") + "\n",
+    )
+    .unwrap();
+
+    let dl_url = reqwest::Url::from_directory_path(&dl_dir).unwrap();
+    std::fs::write(
+        index_dir.join("config.json"),
+        format!(r#"{{"dl": "{dl_url}{{crate}}-{{version}}.crate"}}"#),
+    )
+    .unwrap();
+
+    format!(
+        "sparse+{}",
+        reqwest::Url::from_directory_path(&index_dir).unwrap()
+    )
+}
+
+/// Look up `company` from `project`, with the crate cache and cargo home
+/// isolated under `root`.
+fn company_registry(root: &Path, project: &Path) -> Registry {
+    Registry {
+        cache_dir: root.join("bp-cache"),
+        cargo_home: Some(root.join("cargo-home")),
+        ..Registry::from_cargo_config("company", project).unwrap()
+    }
+}
+
+/// Create a project directory whose `.cargo/config.toml` declares `company`.
+fn project_with_registry(root: &Path, index: &str, extra: &str) -> PathBuf {
+    let project = root.join("project");
+    std::fs::create_dir_all(project.join(".cargo")).unwrap();
+    std::fs::write(
+        project.join(".cargo/config.toml"),
+        format!("[registries.company]\nindex = \"{index}\"\n{extra}"),
+    )
+    .unwrap();
+    project
+}
+
+// [verify cli.registry.config]
+// [verify cli.registry.sparse]
+#[test]
+fn registry_fetches_spec_from_file_sparse_index() {
+    let tmp = tempfile::tempdir().unwrap();
+    let index = publish_fancy_to_file_index(tmp.path(), None, &[]);
+    let project = project_with_registry(tmp.path(), &index, "");
+
+    let registry = company_registry(tmp.path(), &project);
+    assert_eq!(registry.display_name(), "company");
+
+    let source = CrateSource::Registry(registry);
+    let (version, spec) = registry::fetch_bp_spec(&source, "fancy", None).unwrap();
+    assert_eq!(version.as_deref(), Some("0.2.0"));
+    assert_eq!(spec.name, "fancy-battery-pack");
+    assert!(spec.templates.contains_key("default"));
+
+    let resolved = registry::resolve_crate_dir("fancy", None, &source).unwrap();
+    assert!(resolved.dir.join("templates/default/_Cargo.toml").exists());
+}
+
+// [verify cli.registry.config]
+#[test]
+fn registry_config_is_found_in_parent_directories() {
+    let tmp = tempfile::tempdir().unwrap();
+    let index = publish_fancy_to_file_index(tmp.path(), None, &[]);
+    let project = project_with_registry(tmp.path(), &index, "");
+    let nested = project.join("crates/app");
+    std::fs::create_dir_all(&nested).unwrap();
+
+    let registry = company_registry(tmp.path(), &nested);
+    let source = CrateSource::Registry(registry);
+    let detail = registry::fetch_battery_pack_detail_from_source(&source, "fancy").unwrap();
+    assert_eq!(detail.version, "0.2.0");
+    assert!(detail.owners.is_empty(), "no API, so no owners");
+}
+
+#[test]
+fn registry_latest_version_skips_yanked_and_prereleases() {
+    let tmp = tempfile::tempdir().unwrap();
+    let index = publish_fancy_to_file_index(
+        tmp.path(),
+        None,
+        &[
+            r#"{"name":"fancy-battery-pack","vers":"0.1.0","deps":[],"cksum":"00","features":{}}"#,
+            r#"{"name":"fancy-battery-pack","vers":"0.3.0","deps":[],"cksum":"00","features":{},"yanked":true}"#,
+            r#"{"name":"fancy-battery-pack","vers":"0.4.0-beta.1","deps":[],"cksum":"00","features":{}}"#,
+        ],
+    );
+    let project = project_with_registry(tmp.path(), &index, "");
+    let registry = company_registry(tmp.path(), &project);
+
+    let latest = registry.latest_version("fancy-battery-pack", None).unwrap();
+    assert_eq!(latest.vers, "0.2.0");
+    assert_eq!(
+        registry
+            .find_version("fancy-battery-pack", "0.3.0")
+            .unwrap()
+            .vers,
+        "0.3.0",
+        "an exact version is found even if yanked"
+    );
+}
+
+// [verify cli.add.pin-resolve]
+#[test]
+fn registry_latest_version_honours_requirement() {
+    let tmp = tempfile::tempdir().unwrap();
+    let index = publish_fancy_to_file_index(
+        tmp.path(),
+        None,
+        &[
+            r#"{"name":"fancy-battery-pack","vers":"0.1.0","deps":[],"cksum":"00","features":{}}"#,
+            r#"{"name":"fancy-battery-pack","vers":"0.1.5","deps":[],"cksum":"00","features":{}}"#,
+            r#"{"name":"fancy-battery-pack","vers":"0.3.0","deps":[],"cksum":"00","features":{},"yanked":true}"#,
+        ],
+    );
+    let project = project_with_registry(tmp.path(), &index, "");
+    let registry = company_registry(tmp.path(), &project);
+    let latest = |req: &str| {
+        registry.latest_version(
+            "fancy-battery-pack",
+            Some(&semver::VersionReq::parse(req).unwrap()),
+        )
+    };
+
+    assert_eq!(latest("0.1").unwrap().vers, "0.1.5");
+    assert_eq!(latest("~0.1.0").unwrap().vers, "0.1.5");
+    assert_eq!(latest(">=0.1, <0.2").unwrap().vers, "0.1.5");
+    assert_eq!(latest("=0.1.0").unwrap().vers, "0.1.0");
+    assert_data_eq!(
+        latest("0.3").unwrap_err().to_string(),
+        str!["No non-yanked version of 'fancy-battery-pack' matching ^0.3 found on company"]
+    );
+}
+
+#[test]
+fn registry_rejects_checksum_mismatch() {
+    let tmp = tempfile::tempdir().unwrap();
+    let index = publish_fancy_to_file_index(tmp.path(), Some(&"0".repeat(64)), &[]);
+    let project = project_with_registry(tmp.path(), &index, "");
+    let source = CrateSource::Registry(company_registry(tmp.path(), &project));
+
+    let err = registry::fetch_bp_spec(&source, "fancy", None).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("checksum mismatch"),
+        "unexpected error: {err:#}"
+    );
+}
+
+#[test]
+fn registry_missing_crate_errors() {
+    let tmp = tempfile::tempdir().unwrap();
+    let index = publish_fancy_to_file_index(tmp.path(), None, &[]);
+    let project = project_with_registry(tmp.path(), &index, "");
+    let source = CrateSource::Registry(company_registry(tmp.path(), &project));
+
+    let err = registry::fetch_bp_spec(&source, "missing", None).unwrap_err();
+    assert_data_eq!(
+        err.to_string(),
+        str!["Crate 'missing-battery-pack' not found on company"]
+    );
+}
+
+#[test]
+fn registry_rejects_git_index() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = project_with_registry(tmp.path(), "https://example.com/index.git", "");
+
+    let err = Registry::from_cargo_config("company", &project).unwrap_err();
+    assert_data_eq!(
+        err.to_string(),
+        str![
+            "registry 'company' uses a git index (https://example.com/index.git); only sparse (`sparse+`) indexes are supported"
+        ]
+    );
+}
+
+#[test]
+fn registry_unconfigured_errors() {
+    let tmp = tempfile::tempdir().unwrap();
+    let err = Registry::from_cargo_config("nowhere-configured", tmp.path()).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("registry 'nowhere-configured' is not configured"),
+        "unexpected error: {err}"
+    );
+}
+
+// [verify cli.registry.auth]
+#[test]
+fn registry_sends_configured_token() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = project_with_registry(
+        tmp.path(),
+        "sparse+https://example.com/index/",
+        "token = \"Bearer s3cret\"\n",
+    );
+    let registry = company_registry(tmp.path(), &project);
+
+    let request = registry
+        .get("https://example.com/index/config.json")
+        .build()
+        .unwrap();
+    assert_eq!(
+        request.headers()[reqwest::header::AUTHORIZATION],
+        "Bearer s3cret"
+    );
+
+    let anonymous = Registry::crates_io()
+        .get("https://index.crates.io/config.json")
+        .build()
+        .unwrap();
+    assert!(
+        !anonymous
+            .headers()
+            .contains_key(reqwest::header::AUTHORIZATION)
+    );
+}
+
+#[test]
+fn index_path_follows_cargo_layout() {
+    assert_eq!(index_path("a"), "1/a");
+    assert_eq!(index_path("ab"), "2/ab");
+    assert_eq!(index_path("abc"), "3/a/abc");
+    assert_eq!(index_path("Serde"), "se/rd/serde");
+}
+
+#[test]
+fn dl_url_expands_markers_or_appends_default_path() {
+    let entry = IndexEntry {
+        name: "My-Pack".to_string(),
+        vers: "1.2.3".to_string(),
+        cksum: "abc".to_string(),
+        yanked: false,
+    };
+    assert_eq!(
+        dl_url("https://dl.example.com/api/v1/crates/", &entry),
+        "https://dl.example.com/api/v1/crates/My-Pack/1.2.3/download"
+    );
+    assert_eq!(
+        dl_url(
+            "https://dl.example.com/{lowerprefix}/{crate}-{version}.crate?sum={sha256-checksum}",
+            &entry
+        ),
+        "https://dl.example.com/my/-p/My-Pack-1.2.3.crate?sum=abc"
+    );
+}
+
+// --- crate cache and offline mode ---
+
+/// Copy a directory tree.
+fn copy_dir(from: &Path, to: &Path) {
+    for entry in walkdir::WalkDir::new(from) {
+        let entry = entry.unwrap();
+        let dest = to.join(entry.path().strip_prefix(from).unwrap());
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&dest).unwrap();
+        } else {
+            std::fs::copy(entry.path(), &dest).unwrap();
+        }
+    }
+}
+
+// [verify cli.offline.cache]
+#[test]
+fn registry_download_is_cached_and_reused() {
+    let tmp = tempfile::tempdir().unwrap();
+    let index = publish_fancy_to_file_index(tmp.path(), None, &[]);
+    let project = project_with_registry(tmp.path(), &index, "");
+    let source = CrateSource::Registry(company_registry(tmp.path(), &project));
+
+    let first = registry::resolve_crate_dir("fancy", None, &source).unwrap();
+    assert_eq!(
+        first.dir,
+        tmp.path().join("bp-cache/company/fancy-battery-pack-0.2.0")
+    );
+    assert!(
+        tmp.path()
+            .join("bp-cache/company/fancy-battery-pack-0.2.0.cksum")
+            .exists()
+    );
+
+    // With the tarball gone, the cached copy is still served.
+    std::fs::remove_dir_all(tmp.path().join("dl")).unwrap();
+    let second = registry::resolve_crate_dir("fancy", None, &source).unwrap();
+    assert_eq!(second.dir, first.dir);
+}
+
+// [verify cli.offline.cache]
+#[test]
+fn registry_replaces_cache_entry_with_stale_checksum() {
+    let tmp = tempfile::tempdir().unwrap();
+    let index = publish_fancy_to_file_index(tmp.path(), None, &[]);
+    let project = project_with_registry(tmp.path(), &index, "");
+    let source = CrateSource::Registry(company_registry(tmp.path(), &project));
+
+    let stale = tmp.path().join("bp-cache/company/fancy-battery-pack-0.2.0");
+    std::fs::create_dir_all(&stale).unwrap();
+    std::fs::write(stale.join("stale.txt"), "old").unwrap();
+    std::fs::write(
+        tmp.path()
+            .join("bp-cache/company/fancy-battery-pack-0.2.0.cksum"),
+        "0".repeat(64),
+    )
+    .unwrap();
+
+    let resolved = registry::resolve_crate_dir("fancy", None, &source).unwrap();
+    assert_eq!(resolved.dir, stale);
+    assert!(!stale.join("stale.txt").exists());
+    assert!(stale.join("Cargo.toml").exists());
+}
+
+// [verify cli.offline.resolve]
+// [verify cli.offline.no-network]
+#[test]
+fn offline_resolves_from_cache_without_the_index() {
+    let tmp = tempfile::tempdir().unwrap();
+    let index = publish_fancy_to_file_index(tmp.path(), None, &[]);
+    let project = project_with_registry(tmp.path(), &index, "");
+    let registry = company_registry(tmp.path(), &project);
+    registry::resolve_crate_dir("fancy", None, &CrateSource::Registry(registry.clone())).unwrap();
+
+    std::fs::remove_dir_all(tmp.path().join("index")).unwrap();
+    let source = CrateSource::Registry(registry.with_offline(true));
+    let (version, spec) = registry::fetch_bp_spec(&source, "fancy", None).unwrap();
+    assert_eq!(version.as_deref(), Some("0.2.0"));
+    assert_eq!(spec.name, "fancy-battery-pack");
+}
+
+// [verify cli.offline.resolve]
+#[test]
+fn offline_resolves_from_cargo_registry_src() {
+    let tmp = tempfile::tempdir().unwrap();
+    let src = tmp
+        .path()
+        .join("cargo-home/registry/src/index.crates.io-1949cf8c6b5b557f");
+    let fixture = fixtures_dir().join("fancy-battery-pack");
+    copy_dir(&fixture, &src.join("fancy-battery-pack-0.1.0"));
+    copy_dir(&fixture, &src.join("fancy-battery-pack-0.2.0"));
+    // A different crate sharing the name prefix must not be picked up.
+    copy_dir(&fixture, &src.join("fancy-battery-pack-extra-9.0.0"));
+
+    let registry = Registry {
+        cache_dir: tmp.path().join("bp-cache"),
+        cargo_home: Some(tmp.path().join("cargo-home")),
+        ..Registry::crates_io()
+    }
+    .with_offline(true);
+    let resolved =
+        registry::resolve_crate_dir("fancy", None, &CrateSource::Registry(registry)).unwrap();
+    assert_eq!(resolved.dir, src.join("fancy-battery-pack-0.2.0"));
+}
+
+// [verify cli.offline.no-network]
+#[test]
+fn offline_without_cached_copy_errors() {
+    let tmp = tempfile::tempdir().unwrap();
+    let index = publish_fancy_to_file_index(tmp.path(), None, &[]);
+    let project = project_with_registry(tmp.path(), &index, "");
+    let source = CrateSource::Registry(company_registry(tmp.path(), &project).with_offline(true));
+
+    let err = registry::fetch_bp_spec(&source, "fancy", None).unwrap_err();
+    assert_data_eq!(
+        err.to_string(),
+        str![
+            "'fancy-battery-pack' from company is not available offline; run once with network access to cache it"
+        ]
+    );
+
+    let Err(err) = registry::fetch_battery_pack_list(&source, None) else {
+        panic!("listing should fail offline");
+    };
+    assert_data_eq!(
+        err.to_string(),
+        str!["cannot search company for battery packs in offline mode"]
+    );
+}
+
+// [verify cli.add.pin-resolve]
+#[test]
+fn offline_lookup_honours_requirement() {
+    let tmp = tempfile::tempdir().unwrap();
+    let src = tmp
+        .path()
+        .join("cargo-home/registry/src/index.crates.io-1949cf8c6b5b557f");
+    let fixture = fixtures_dir().join("fancy-battery-pack");
+    copy_dir(&fixture, &src.join("fancy-battery-pack-0.1.0"));
+    copy_dir(&fixture, &src.join("fancy-battery-pack-0.2.0"));
+
+    let registry = Registry {
+        cache_dir: tmp.path().join("bp-cache"),
+        cargo_home: Some(tmp.path().join("cargo-home")),
+        ..Registry::crates_io()
+    }
+    .with_offline(true);
+
+    let req = semver::VersionReq::parse("0.1").unwrap();
+    let found = registry::lookup_crate(&registry, "fancy-battery-pack", Some(&req)).unwrap();
+    assert_eq!(found.version, "0.1.0");
+
+    let req = semver::VersionReq::parse("0.3").unwrap();
+    let err = registry::lookup_crate(&registry, "fancy-battery-pack", Some(&req))
+        .err()
+        .unwrap();
+    assert_data_eq!(
+        err.to_string(),
+        str![
+            "'fancy-battery-pack' version ^0.3 from crates.io is not available offline; run once with network access to cache it"
+        ]
+    );
+}
+
+// [verify cli.update.raise]
+// [verify cli.update.dependency]
+// [verify cli.update.sync]
+// [verify cli.add.pin-resolve]
+#[test]
+fn update_raises_pin_and_syncs_from_new_version() {
+    let tmp = tempfile::tempdir().unwrap();
+    let index = publish_fancy_to_file_index(
+        tmp.path(),
+        None,
+        &[r#"{"name":"fancy-battery-pack","vers":"0.1.0","deps":[],"cksum":"00","features":{}}"#],
+    );
+    let project = project_with_registry(tmp.path(), &index, "");
+    std::fs::write(
+        project.join("Cargo.toml"),
+        indoc::indoc! {r#"
+            [package]
+            name = "consumer"
+            version = "0.1.0"
+            edition = "2021"
+
+            [build-dependencies]
+            fancy-battery-pack = { version = "0.1.0", registry = "company" }
+        "#},
+    )
+    .unwrap();
+    std::fs::write(
+        project.join("battery-pack.toml"),
+        indoc::indoc! {r#"
+            version = 2
+
+            [[battery-pack]]
+            name = "fancy"
+            version = "0.1"
+            managed-deps = []
+        "#},
+    )
+    .unwrap();
+    let source = CrateSource::Registry(company_registry(tmp.path(), &project));
+
+    crate::commands::update_battery_packs(None, &source, &project).unwrap();
+
+    let manifest_path = project.join("Cargo.toml");
+    assert_eq!(
+        crate::manifest::read_pinned_packs_from_state(&manifest_path).unwrap(),
+        vec![("fancy".to_string(), "0.2".to_string())]
+    );
+    let manifest: toml::Value =
+        toml::from_str(&std::fs::read_to_string(&manifest_path).unwrap()).unwrap();
+    assert_eq!(
+        manifest["build-dependencies"]["fancy-battery-pack"]["version"].as_str(),
+        Some("0.2.0")
+    );
+    let deps = manifest["dependencies"].as_table().unwrap();
+    assert!(
+        deps.contains_key("clap") && deps.contains_key("dialoguer"),
+        "default crates of 0.2.0 should be synced, got {deps:?}"
+    );
+}
+
+// [verify cli.update.explicit]
+#[test]
+fn update_with_unmatched_requirement_fails_without_writing() {
+    let tmp = tempfile::tempdir().unwrap();
+    let index = publish_fancy_to_file_index(tmp.path(), None, &[]);
+    let project = project_with_registry(tmp.path(), &index, "");
+    std::fs::write(
+        project.join("Cargo.toml"),
+        "[package]\nname = \"consumer\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    let source = CrateSource::Registry(company_registry(tmp.path(), &project));
+
+    let err =
+        crate::commands::update_battery_packs(Some("fancy@0.5"), &source, &project).unwrap_err();
+    assert_data_eq!(
+        err.to_string(),
+        str!["No non-yanked version of 'fancy-battery-pack' matching ^0.5 found on company"]
+    );
+    assert!(!project.join("battery-pack.toml").exists());
+}
+
+/// SHA-256 of the tarball [`publish_fancy_to_file_index`] wrote under `root`.
+fn published_checksum(root: &Path) -> String {
+    let tarball = std::fs::read(root.join("dl/fancy-battery-pack-0.2.0.crate")).unwrap();
+    format!("{:x}", Sha256::digest(&tarball))
+}
+
+fn fancy_lock(registry: &Registry, version: &str, checksum: &str) -> Lockfile {
+    toml::from_str(&format!(
+        indoc::indoc! {r#"
+            version = 1
+
+            [[pack]]
+            name = "fancy-battery-pack"
+            version = "{}"
+            source = "{}"
+            checksum = "{}"
+        "#},
+        version,
+        registry.source_id(),
+        checksum
+    ))
+    .unwrap()
+}
+
+// [verify manifest.lock.reuse]
+#[test]
+fn resolver_reuses_locked_version_over_newer_release() {
+    let tmp = tempfile::tempdir().unwrap();
+    // 0.3.0 is listed but has no tarball: resolving it would fail.
+    let index = publish_fancy_to_file_index(
+        tmp.path(),
+        None,
+        &[r#"{"name":"fancy-battery-pack","vers":"0.3.0","deps":[],"cksum":"00","features":{}}"#],
+    );
+    let project = project_with_registry(tmp.path(), &index, "");
+    let registry = company_registry(tmp.path(), &project);
+    let lock = fancy_lock(&registry, "0.2.0", &published_checksum(tmp.path()));
+    let source = CrateSource::Registry(registry);
+
+    let mut resolver = PackResolver::new(&source, lock.clone(), LockMode::Locked);
+    let (version, spec) = resolver.fetch_spec("fancy", None).unwrap();
+    assert_eq!(version.as_deref(), Some("0.2.0"));
+    assert_eq!(spec.name, "fancy-battery-pack");
+    assert_eq!(resolver.updated_lock(), lock);
+
+    // Refreshing ignores the lock and picks the newest release.
+    let mut resolver = PackResolver::new(&source, lock, LockMode::Refresh);
+    let err = resolver.fetch_spec("fancy", None).unwrap_err();
+    assert!(format!("{err:#}").contains("0.3.0"), "{err:#}");
+}
+
+// [verify manifest.lock.verify]
+#[test]
+fn resolver_rejects_tarball_that_differs_from_lock() {
+    let tmp = tempfile::tempdir().unwrap();
+    let index = publish_fancy_to_file_index(tmp.path(), None, &[]);
+    let project = project_with_registry(tmp.path(), &index, "");
+    let registry = company_registry(tmp.path(), &project);
+    let lock = fancy_lock(&registry, "0.2.0", &"1".repeat(64));
+    let source = CrateSource::Registry(registry);
+
+    let mut resolver = PackResolver::new(&source, lock, LockMode::Reuse);
+    let err = resolver.fetch_spec("fancy", None).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "checksum mismatch for 'fancy-battery-pack' version 0.2.0: \
+             battery-pack.lock has {}, company has {}",
+            "1".repeat(64),
+            published_checksum(tmp.path())
+        )
+    );
+}
+
+// [verify manifest.lock.record]
+// [verify manifest.lock.update]
+#[test]
+fn update_records_resolved_pack_in_lock() {
+    let tmp = tempfile::tempdir().unwrap();
+    let index = publish_fancy_to_file_index(
+        tmp.path(),
+        None,
+        &[r#"{"name":"fancy-battery-pack","vers":"0.1.0","deps":[],"cksum":"00","features":{}}"#],
+    );
+    let project = project_with_registry(tmp.path(), &index, "");
+    std::fs::write(
+        project.join("Cargo.toml"),
+        indoc::indoc! {r#"
+            [package]
+            name = "consumer"
+            version = "0.1.0"
+            edition = "2021"
+
+            [build-dependencies]
+            fancy-battery-pack = { version = "0.1.0", registry = "company" }
+        "#},
+    )
+    .unwrap();
+    std::fs::write(
+        project.join("battery-pack.toml"),
+        indoc::indoc! {r#"
+            version = 2
+
+            [[battery-pack]]
+            name = "fancy"
+            version = "0.1"
+            managed-deps = []
+        "#},
+    )
+    .unwrap();
+    let registry = company_registry(tmp.path(), &project);
+    // The entry for the old release is replaced.
+    write_lockfile(
+        &project.join("Cargo.toml"),
+        &fancy_lock(&registry, "0.1.0", "00"),
+    )
+    .unwrap();
+    let expected = fancy_lock(&registry, "0.2.0", &published_checksum(tmp.path()));
+    let source = CrateSource::Registry(registry);
+
+    crate::commands::update_battery_packs(None, &source, &project).unwrap();
+
+    let lock_path = project.join("battery-pack.lock");
+    assert_eq!(
+        read_lockfile(&crate::changeset::Changeset::new(), &lock_path).unwrap(),
+        expected
+    );
+}
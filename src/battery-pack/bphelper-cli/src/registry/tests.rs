@@ -220,6 +220,47 @@ fn resolve_bp_managed_from_state_file() {
     );
 }
 
+#[test]
+fn resolve_bp_managed_fetches_non_local_packs_from_source() {
+    // The template's own crate has no battery packs, so `managed` must come
+    // from the given source rather than crates.io.
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(
+        tmp.path().join("Cargo.toml"),
+        "[package]\nname = \"tpl-host\"\nversion = \"0.1.0\"\nedition = \"2024\"\n\n[workspace]\n",
+    )
+    .unwrap();
+    std::fs::create_dir(tmp.path().join("src")).unwrap();
+    std::fs::write(tmp.path().join("src/lib.rs"), "").unwrap();
+    let cargo_toml = indoc! {r#"
+      [package]
+      name = "my-app"
+      version = "0.1.0"
+
+      [dependencies]
+      clap.bp-managed = true
+    "#};
+    let state = indoc! {r#"
+      version = 1
+
+      [[battery-pack]]
+      name = "managed"
+      features = ["default"]
+    "#};
+
+    let result = super::resolve_bp_managed_content_with(
+        cargo_toml,
+        tmp.path(),
+        Some(state),
+        &CrateSource::Local(fixtures_dir()),
+    )
+    .unwrap();
+    assert!(
+        result.contains(r#"clap = { version = "4", features = ["derive"] }"#),
+        "clap should resolve from the local source, got:\n{result}"
+    );
+}
+
 #[test]
 fn resolve_bp_managed_resolves_versions() {
     let bp_root = fixtures_dir().join("managed-battery-pack");
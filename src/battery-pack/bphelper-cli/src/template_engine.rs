@@ -4,7 +4,9 @@
 //! - `bp-template.toml` configuration (ignore, placeholders, file includes)
 //! - MiniJinja syntax (`{{ project_name }}`, `{% include %}`, etc.)
 //! - Pre-set variable overrides (for non-interactive / test usage)
+//! - Post-generate hooks, run in the generated project
 
+use crate::registry::CrateSource;
 use anyhow::{Context, Result, bail};
 use bphelper_manifest::parse_battery_pack_from_path;
 use serde::Deserialize;
@@ -15,9 +17,9 @@ use std::path::{Path, PathBuf};
 /// Parsed `bp-template.toml` configuration.
 #[derive(Debug, Deserialize, Default)]
 struct BpTemplateConfig {
-    /// Files/folders to exclude from output entirely.
+    /// Files/folders to exclude from output, always or under a condition.
     #[serde(default)]
-    ignore: Vec<String>,
+    ignore: Vec<IgnoreRule>,
 
     /// Placeholder definitions.
     #[serde(default)]
@@ -27,16 +29,48 @@ struct BpTemplateConfig {
     #[serde(default)]
     files: Vec<FileInclude>,
 
+    /// Globs of template files copied byte-for-byte, without rendering.
+    #[serde(default)]
+    copy: Vec<String>,
+
     /// Post-merge hints shown after `cargo bp add -t`.
     #[serde(default)]
     hints: Vec<Hint>,
+
+    /// Commands run in the project after the template is written.
+    #[serde(default)]
+    hooks: Vec<HookDef>,
+
+    /// Merge strategies for existing files, by glob over project paths, in
+    /// the order they are declared: the first matching glob wins.
+    #[serde(default)]
+    merge: indexmap::IndexMap<String, crate::merge::MergeStrategy>,
+
+    /// Placeholder combinations `cargo bp validate` builds besides the defaults.
+    #[serde(default)]
+    matrix: TestMatrix,
 }
 
-#[derive(Debug, Deserialize)]
+/// The `[matrix]` table of `bp-template.toml`.
+// [impl format.templates.matrix]
+#[derive(Debug, Deserialize, Default)]
+struct TestMatrix {
+    /// Vary one placeholder at a time from the defaults: every other option
+    /// of each `select`, and each `bool` flipped.
+    #[serde(default)]
+    each: bool,
+    /// Explicit placeholder sets, each layered over the defaults.
+    #[serde(default)]
+    include: Vec<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Deserialize, Default)]
 struct PlaceholderDef {
     #[serde(default)]
     prompt: Option<String>,
-    #[serde(default)]
+    /// The default value. A list default is stored comma-separated, the same
+    /// way `--define` spells it.
+    #[serde(default, deserialize_with = "deserialize_default")]
     default: Option<String>,
     #[serde(default, rename = "type")]
     placeholder_type: PlaceholderType,
@@ -45,6 +79,22 @@ struct PlaceholderDef {
     /// (`options.category = "allocator"`).
     #[serde(default)]
     options: Option<OptionsSource>,
+    /// A regex the whole value (each item, for a list) must match.
+    #[serde(default)]
+    pattern: Option<String>,
+    /// Reject an empty value (an empty list, for a list).
+    #[serde(default)]
+    required: bool,
+    /// Minimum value length, in characters (items, for a list).
+    #[serde(default)]
+    min_length: Option<usize>,
+    /// Maximum value length, in characters (items, for a list).
+    #[serde(default)]
+    max_length: Option<usize>,
+    /// A MiniJinja expression over other placeholders; the placeholder is
+    /// only prompted for when it holds (e.g. `ci_platform == "github"`).
+    #[serde(default)]
+    when: Option<String>,
 }
 
 /// Where a `select` placeholder's options come from.
@@ -64,12 +114,76 @@ enum PlaceholderType {
     String,
     Bool,
     Select,
+    /// A whole number, rendered as a MiniJinja integer.
+    Int,
+    /// Free-form strings, rendered as a MiniJinja sequence.
+    List,
+    /// Any subset of `options`, rendered as a MiniJinja sequence.
+    Multiselect,
+}
+
+impl PlaceholderType {
+    /// Whether values of this type are lists.
+    fn is_list(&self) -> bool {
+        matches!(self, PlaceholderType::List | PlaceholderType::Multiselect)
+    }
+}
+
+/// Accept a `default` as a string, or in its natural TOML form: an integer,
+/// a bool, or an array of strings (joined with `,`).
+fn deserialize_default<'de, D>(deserializer: D) -> std::result::Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        String(String),
+        Int(i64),
+        Bool(bool),
+        List(Vec<String>),
+    }
+    Ok(Some(match Raw::deserialize(deserializer)? {
+        Raw::String(s) => s,
+        Raw::Int(n) => n.to_string(),
+        Raw::Bool(b) => b.to_string(),
+        Raw::List(items) => items.join(","),
+    }))
+}
+
+/// Split a list value (`a,b,c`) into its items, dropping empty ones.
+fn list_items(value: &str) -> Vec<&str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .collect()
 }
 
 #[derive(Debug, Deserialize)]
 struct FileInclude {
     src: String,
     dest: String,
+    /// A MiniJinja expression; the file is only included when it holds.
+    #[serde(default)]
+    condition: Option<String>,
+}
+
+/// One `ignore` entry.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+enum IgnoreRule {
+    /// A file or folder name, excluded wherever it appears.
+    Name(String),
+    /// Glob patterns over paths relative to the template directory,
+    /// excluded when `condition` holds and `unless` does not.
+    Paths {
+        paths: Vec<String>,
+        #[serde(default)]
+        condition: Option<String>,
+        #[serde(default)]
+        unless: Option<String>,
+    },
 }
 
 /// A post-merge hint shown to the user after `cargo bp add -t`.
@@ -78,6 +192,69 @@ struct Hint {
     message: String,
 }
 
+/// A `[[hooks]]` entry of `bp-template.toml`.
+// [impl format.templates.hooks]
+#[derive(Debug, Deserialize)]
+struct HookDef {
+    /// The command, split on whitespace, or as an argument list. Each
+    /// argument is rendered with the template's variables.
+    run: HookCommand,
+    /// What the command is for, shown next to it.
+    #[serde(default)]
+    description: Option<String>,
+    /// A MiniJinja expression; the hook only runs when it holds.
+    #[serde(default)]
+    condition: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum HookCommand {
+    Line(String),
+    Args(Vec<String>),
+}
+
+/// A post-generate hook, with its arguments rendered.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Hook {
+    pub(crate) description: Option<String>,
+    pub(crate) args: Vec<String>,
+}
+
+impl std::fmt::Display for Hook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.args.join(" "))?;
+        if let Some(description) = &self.description {
+            write!(f, "  # {description}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether to run a template's post-generate hooks.
+// [impl cli.new.hooks]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HookPolicy {
+    /// Show the hooks and ask before running them.
+    Prompt,
+    /// Run the hooks without asking (`--allow-hooks`).
+    Allow,
+    /// List the hooks without running them.
+    Skip,
+}
+
+impl HookPolicy {
+    /// `--allow-hooks` runs hooks outright; otherwise they need a
+    /// confirmation, which is only possible interactively.
+    pub(crate) fn from_flags(allow_hooks: bool, interactive: bool) -> Self {
+        match (allow_hooks, interactive) {
+            (true, _) => HookPolicy::Allow,
+            (false, true) => HookPolicy::Prompt,
+            (false, false) => HookPolicy::Skip,
+        }
+    }
+}
+
 /// Options for template generation.
 pub(crate) struct GenerateOpts {
     /// Shared rendering options.
@@ -87,6 +264,9 @@ pub(crate) struct GenerateOpts {
     pub(crate) destination: Option<PathBuf>,
     /// Whether to run `git init` on the generated project.
     pub(crate) git_init: bool,
+    /// How to treat the template's post-generate hooks. `None` never runs
+    /// them, without listing them either.
+    pub(crate) hooks: Option<HookPolicy>,
 }
 
 /// Shared options for rendering a template (used by both preview and generate).
@@ -106,6 +286,9 @@ pub(crate) struct RenderOpts {
     /// Force treating the context as interactive or not, used to avoid prompting for input during tests,
     /// used to make sure we don't prompt for input during tests
     pub(crate) interactive_override: Option<bool>,
+    /// Where battery packs referenced by bp-managed dependencies are fetched
+    /// from when they aren't in `crate_root`'s workspace.
+    pub(crate) source: CrateSource,
 }
 
 /// A rendered file from a template preview.
@@ -113,33 +296,59 @@ pub(crate) struct RenderOpts {
 pub(crate) struct RenderedFile {
     /// Relative path within the generated project.
     pub(crate) path: String,
-    /// Rendered file content.
+    /// Rendered file content. Empty for a verbatim file.
     pub(crate) content: String,
+    /// The raw bytes of a file copied without rendering: a binary file, or
+    /// one matched by `copy` in `bp-template.toml`.
+    pub(crate) bytes: Option<Vec<u8>>,
 }
 
-/// Render a template and return the files in memory without writing to disk.
-pub(crate) fn preview(mut opts: RenderOpts) -> Result<Vec<RenderedFile>> {
-    let (template_dir, config) = load_config(&opts)?;
-
-    // A category-linked placeholder that the picker already resolved must not
-    // be shadowed by the synthesized fallback below, so skip those here; the
-    // prefill is applied inside `resolve_placeholders`.
-    let resolved = resolve_option_sources(&opts, &config)?;
-    let has_prefill = |name: &str| resolved.get(name).is_some_and(|r| r.prefill.is_some());
+impl RenderedFile {
+    /// The bytes to write for this file.
+    pub(crate) fn data(&self) -> &[u8] {
+        self.bytes.as_deref().unwrap_or(self.content.as_bytes())
+    }
 
-    // For preview, fall back to "<name>" for placeholders without a default
-    // so the preview always renders.
-    for (name, def) in &config.placeholders {
-        if has_prefill(name) {
-            continue;
+    /// The file's content for display: verbatim files that are not text
+    /// show as a one-line summary.
+    pub(crate) fn display_content(&self) -> std::borrow::Cow<'_, str> {
+        match &self.bytes {
+            None => self.content.as_str().into(),
+            Some(bytes) => match std::str::from_utf8(bytes) {
+                Ok(text) => text.into(),
+                Err(_) => format!("<binary file, {} bytes>", bytes.len()).into(),
+            },
         }
-        opts.defines
-            .entry(name.clone())
-            .or_insert_with(|| def.default.clone().unwrap_or_else(|| format!("<{name}>")));
     }
+}
+
+/// Render a template and return the files in memory without writing to disk.
+pub(crate) fn preview(opts: RenderOpts) -> Result<Vec<RenderedFile>> {
+    preview_with_values(&opts).map(|(files, _)| files)
+}
 
-    let variables = prepare_render(&opts, &config)?;
-    render(&opts.crate_root, &template_dir, &config, &variables)
+/// Like [`preview`], but also returns the value chosen for each placeholder
+/// declared in `bp-template.toml`, so the render can be reproduced later.
+pub(crate) fn preview_with_values(
+    opts: &RenderOpts,
+) -> Result<(Vec<RenderedFile>, BTreeMap<String, String>)> {
+    let (template_dir, config) = load_config(opts)?;
+    let variables = prepare_render(opts, &config, true)?;
+    let files = render(opts, &template_dir, &config, &variables)?;
+    Ok((files, placeholder_values(&config, &variables)))
+}
+
+/// Pick out the values of the placeholders declared in `bp-template.toml`,
+/// leaving out built-ins like `project_name`.
+fn placeholder_values(
+    config: &BpTemplateConfig,
+    variables: &BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    variables
+        .iter()
+        .filter(|(name, _)| config.placeholders.contains_key(*name))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
 }
 
 /// Load post-merge hints from a template's `bp-template.toml`.
@@ -156,14 +365,129 @@ pub(crate) fn load_template_hints(crate_root: &Path, template_path: &str) -> Vec
     config.hints.into_iter().map(|h| h.message).collect()
 }
 
+/// The `[merge]` strategy overrides of a template, with their globs compiled.
+// [impl format.templates.merge]
+pub(crate) fn merge_overrides(
+    opts: &RenderOpts,
+) -> Result<Vec<(glob::Pattern, crate::merge::MergeStrategy)>> {
+    let (_template_dir, config) = load_config(opts)?;
+    config
+        .merge
+        .into_iter()
+        .map(|(pattern, strategy)| {
+            let glob = glob::Pattern::new(&pattern)
+                .with_context(|| format!("invalid [merge] pattern '{pattern}'"))?;
+            Ok((glob, strategy))
+        })
+        .collect()
+}
+
+/// The post-generate hooks of a template applied with the given placeholder
+/// values, such as those returned by [`preview_with_values`].
+pub(crate) fn template_hooks(
+    opts: &RenderOpts,
+    placeholders: &BTreeMap<String, String>,
+) -> Result<Vec<Hook>> {
+    let (_template_dir, config) = load_config(opts)?;
+    let mut variables = builtin_variables(&opts.project_name);
+    variables.extend(placeholders.clone());
+    render_hooks(&opts.crate_root, &config, &variables)
+}
+
+/// The placeholder define sets to validate a template with, from its
+/// `[matrix]`: an empty set (all defaults) first, then one set per
+/// combination, without duplicates. A value equal to its placeholder's
+/// default is left out of a set, so a set that only restates the defaults
+/// isn't built twice.
+// [impl format.templates.matrix]
+pub(crate) fn test_matrix(
+    crate_root: &Path,
+    template_path: &str,
+) -> Result<Vec<BTreeMap<String, String>>> {
+    let opts = RenderOpts {
+        crate_root: crate_root.to_path_buf(),
+        template_path: template_path.to_string(),
+        project_name: "bp-validate-matrix".to_string(),
+        defines: BTreeMap::new(),
+        active_features: BTreeSet::new(),
+        interactive_override: Some(false),
+        source: CrateSource::crates_io(),
+    };
+    let (_template_dir, config) = load_config(&opts)?;
+    let mut sets = vec![BTreeMap::new()];
+
+    if config.matrix.each {
+        let resolved = resolve_option_sources(&opts, &config)?;
+        for (name, def) in &config.placeholders {
+            match def.placeholder_type {
+                PlaceholderType::String | PlaceholderType::Int | PlaceholderType::List => {}
+                PlaceholderType::Bool => {
+                    let default = def
+                        .default
+                        .as_deref()
+                        .is_some_and(|d| d.eq_ignore_ascii_case("true"));
+                    sets.push(BTreeMap::from([(name.clone(), (!default).to_string())]));
+                }
+                PlaceholderType::Select => {
+                    // Without a default, the defaults build takes the first option.
+                    let options = resolved
+                        .get(name)
+                        .map(|r| r.options.as_slice())
+                        .unwrap_or_default();
+                    let default = def.default.as_ref().or(options.first());
+                    for option in options {
+                        if default != Some(option) {
+                            sets.push(BTreeMap::from([(name.clone(), option.clone())]));
+                        }
+                    }
+                }
+                PlaceholderType::Multiselect => {
+                    // Toggle each option in or out of the default selection.
+                    let default = list_items(def.default.as_deref().unwrap_or_default());
+                    let options = resolved.get(name).map(|r| r.options.as_slice());
+                    for option in options.unwrap_or_default() {
+                        let toggled: Vec<&str> = if default.contains(&option.as_str()) {
+                            default.iter().copied().filter(|d| d != option).collect()
+                        } else {
+                            default.iter().copied().chain([option.as_str()]).collect()
+                        };
+                        sets.push(BTreeMap::from([(name.clone(), toggled.join(","))]));
+                    }
+                }
+            }
+        }
+    }
+
+    for set in &config.matrix.include {
+        if let Some(unknown) = set.keys().find(|k| !config.placeholders.contains_key(*k)) {
+            bail!("[matrix] include references unknown placeholder '{unknown}'");
+        }
+        let mut set = set.clone();
+        set.retain(|name, value| config.placeholders[name].default.as_ref() != Some(value));
+        if !sets.contains(&set) {
+            sets.push(set);
+        }
+    }
+    Ok(sets)
+}
+
 /// Generate a project from a battery pack template.
 ///
 /// Returns the path to the generated project directory.
 pub(crate) fn generate(opts: GenerateOpts) -> Result<PathBuf> {
+    generate_with_values(opts).map(|(project_dir, _)| project_dir)
+}
+
+/// Like [`generate`], but also returns the value chosen for each placeholder
+/// declared in `bp-template.toml`.
+pub(crate) fn generate_with_values(
+    opts: GenerateOpts,
+) -> Result<(PathBuf, BTreeMap<String, String>)> {
+    check_crate_name(&opts.render.project_name).map_err(anyhow::Error::msg)?;
     let (template_dir, config) = load_config(&opts.render)?;
-    let variables = prepare_render(&opts.render, &config)?;
+    let variables = prepare_render(&opts.render, &config, false)?;
 
-    let files = render(&opts.render.crate_root, &template_dir, &config, &variables)?;
+    let files = render(&opts.render, &template_dir, &config, &variables)?;
 
     // Write rendered files to disk
     let dest_base = opts.destination.unwrap_or_else(|| PathBuf::from("."));
@@ -171,43 +495,99 @@ pub(crate) fn generate(opts: GenerateOpts) -> Result<PathBuf> {
     if project_dir.exists() {
         bail!("destination already exists: {}", project_dir.display());
     }
+    std::fs::create_dir_all(&project_dir)
+        .with_context(|| format!("failed to create {}", project_dir.display()))?;
 
     for file in &files {
         let dest = project_dir.join(&file.path);
         if let Some(parent) = dest.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        std::fs::write(&dest, &file.content)
+        std::fs::write(&dest, file.data())
             .with_context(|| format!("failed to write {}", dest.display()))?;
     }
 
+    // Hooks run before `git init` so that what they create is staged too.
+    let hooks = render_hooks(&opts.render.crate_root, &config, &variables)?;
+    if let Some(policy) = opts.hooks {
+        run_hooks(&hooks, &project_dir, policy)?;
+    }
+
     if opts.git_init {
         git_init(&project_dir)?;
     }
 
-    Ok(project_dir)
+    Ok((project_dir, placeholder_values(&config, &variables)))
 }
 
 /// Shared rendering pipeline: resolves templates and file includes into memory.
 fn render(
-    crate_root: &Path,
+    opts: &RenderOpts,
     template_dir: &Path,
     config: &BpTemplateConfig,
     variables: &BTreeMap<String, String>,
 ) -> Result<Vec<RenderedFile>> {
-    let mut env = build_jinja_env(crate_root, variables)?;
+    let crate_root = opts.crate_root.as_path();
+    let mut env = build_jinja_env(crate_root, variables, &config.placeholders)?;
     prefetch_pin_github_actions(crate_root);
-    let ignore_set: Vec<&str> = config.ignore.iter().map(|s| s.as_str()).collect();
+
+    // [impl format.templates.conditional-ignore]
+    let mut ignore_names = Vec::new();
+    let mut ignore_globs = Vec::new();
+    for rule in &config.ignore {
+        match rule {
+            IgnoreRule::Name(name) => ignore_names.push(name.as_str()),
+            IgnoreRule::Paths {
+                paths,
+                condition,
+                unless,
+            } => {
+                let applies = match condition {
+                    Some(expr) => eval_condition(&env, expr, "ignore `condition`")?,
+                    None => true,
+                } && match unless {
+                    Some(expr) => !eval_condition(&env, expr, "ignore `unless`")?,
+                    None => true,
+                };
+                if applies {
+                    for path in paths {
+                        ignore_globs.push(glob::Pattern::new(path).with_context(|| {
+                            format!("invalid ignore pattern '{path}' in bp-template.toml")
+                        })?);
+                    }
+                }
+            }
+        }
+    }
+
+    // [impl format.templates.copy]
+    let copy_globs = config
+        .copy
+        .iter()
+        .map(|path| {
+            glob::Pattern::new(path)
+                .with_context(|| format!("invalid copy pattern '{path}' in bp-template.toml"))
+        })
+        .collect::<Result<Vec<_>>>()?;
 
     let mut files = Vec::new();
 
-    for entry in walkdir::WalkDir::new(template_dir) {
+    // Skipping ignored directories in `filter_entry` drops their whole subtree.
+    let walker = walkdir::WalkDir::new(template_dir)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry
+                .path()
+                .strip_prefix(template_dir)
+                .is_ok_and(|rel_path| {
+                    !should_ignore(rel_path, &ignore_names)
+                        && !matches_glob(rel_path, &ignore_globs)
+                })
+        });
+    for entry in walker {
         let entry = entry?;
         let rel_path = entry.path().strip_prefix(template_dir)?;
 
-        if should_ignore(rel_path, &ignore_set) {
-            continue;
-        }
         if rel_path == Path::new("bp-template.toml") {
             continue;
         }
@@ -229,8 +609,17 @@ fn render(
         } else {
             rendered_path.replace("_Cargo.toml", "Cargo.toml")
         };
-        let content = std::fs::read_to_string(entry.path())
+        let raw = std::fs::read(entry.path())
             .with_context(|| format!("failed to read {}", entry.path().display()))?;
+        if matches_glob(rel_path, &copy_globs) || is_binary(&raw) {
+            files.push(RenderedFile {
+                path: rendered_path,
+                content: String::new(),
+                bytes: Some(raw),
+            });
+            continue;
+        }
+        let content = String::from_utf8(raw).expect("checked by is_binary");
         // Whitespace trimming and trailing-newline keeping apply to Rust only, where rustfmt also
         // normalizes the result. Other formats (YAML, TOML) are newline-sensitive and keep the
         // legacy behavior, so their templates still use `{%- -%}` for whitespace control.
@@ -241,18 +630,28 @@ fn render(
         let rendered = env
             .render_str(&content, minijinja::context! {})
             .with_context(|| format!("failed to render template {}", rel_path.display()))?;
+        // [impl format.templates.empty-files]
+        // Drop files whose content rendered away (e.g. wrapped in {% if false %}...{% endif %}),
+        // but keep files that are empty in the template too.
+        if rendered.trim().is_empty() && !content.trim().is_empty() {
+            continue;
+        }
 
         files.push(RenderedFile {
             path: rendered_path,
             content: rendered,
+            bytes: None,
         });
     }
 
     // Process [[files]] includes
-    // TODO: support conditional file includes (e.g. a `condition` field evaluated as a
-    // MiniJinja expression, or grouped tables like [[files.fuzzing]]) so battery pack
-    // authors don't need to wrap entire file contents in {% if %}...{% endif %}.
     for file_include in &config.files {
+        // [impl format.templates.files-condition]
+        if let Some(condition) = &file_include.condition
+            && !eval_condition(&env, condition, "[[files]] `condition`")?
+        {
+            continue;
+        }
         let src_path = crate_root.join(&file_include.src);
         if !src_path.exists() {
             bail!("file include source not found: {}", src_path.display());
@@ -260,8 +659,17 @@ fn render(
         if files.iter().any(|f| f.path == file_include.dest) {
             continue;
         }
-        let content = std::fs::read_to_string(&src_path)
+        let raw = std::fs::read(&src_path)
             .with_context(|| format!("failed to read {}", src_path.display()))?;
+        if is_binary(&raw) {
+            files.push(RenderedFile {
+                path: file_include.dest.clone(),
+                content: String::new(),
+                bytes: Some(raw),
+            });
+            continue;
+        }
+        let content = String::from_utf8(raw).expect("checked by is_binary");
         let is_rust = file_include.dest.ends_with(".rs");
         env.set_trim_blocks(is_rust);
         env.set_lstrip_blocks(is_rust);
@@ -269,17 +677,18 @@ fn render(
         let rendered = env
             .render_str(&content, minijinja::context! {})
             .with_context(|| format!("failed to render {}", src_path.display()))?;
+        if rendered.trim().is_empty() && !content.trim().is_empty() {
+            continue;
+        }
         files.push(RenderedFile {
             path: file_include.dest.clone(),
             content: rendered,
+            bytes: None,
         });
     }
 
     files.sort_by(|a, b| a.path.cmp(&b.path));
 
-    // Filter out files whose rendered content is empty (e.g. wrapped in {% if false %}...{% endif %}).
-    files.retain(|f| !f.content.trim().is_empty());
-
     // Map each directory to its rendered battery-pack.toml, the active-pack/feature source for
     // resolution. Falls back to the Cargo.toml metadata section inside the resolver when absent.
     let parent_dir = |p: &str| -> String {
@@ -290,6 +699,7 @@ fn render(
     };
     let bp_states: std::collections::HashMap<String, String> = files
         .iter()
+        .filter(|f| f.bytes.is_none())
         .filter(|f| std::path::Path::new(&f.path).file_name() == Some("battery-pack.toml".as_ref()))
         .map(|f| (parent_dir(&f.path), f.content.clone()))
         .collect();
@@ -299,10 +709,16 @@ fn render(
     // that reference battery packs not yet published, so we warn instead of failing.
     for file in files
         .iter_mut()
+        .filter(|f| f.bytes.is_none())
         .filter(|f| std::path::Path::new(&f.path).file_name() == Some("Cargo.toml".as_ref()))
     {
         let state = bp_states.get(&parent_dir(&file.path)).map(String::as_str);
-        match crate::resolve_bp_managed_content(&file.content, crate_root, state) {
+        match crate::registry::resolve_bp_managed_content_with(
+            &file.content,
+            crate_root,
+            state,
+            &opts.source,
+        ) {
             Ok(resolved) => file.content = resolved,
             Err(e) => eprintln!(
                 "warning: failed to resolve bp-managed deps in {}: {e:#}",
@@ -313,7 +729,10 @@ fn render(
 
     // Normalize rendered Rust through rustfmt so generated output is formatting-stable regardless
     // of template whitespace. No-ops if rustfmt is missing or rejects the input.
-    for file in files.iter_mut().filter(|f| f.path.ends_with(".rs")) {
+    for file in files
+        .iter_mut()
+        .filter(|f| f.bytes.is_none() && f.path.ends_with(".rs"))
+    {
         file.content = rustfmt_rust(std::mem::take(&mut file.content));
     }
 
@@ -359,31 +778,44 @@ fn rustfmt_rust(source: String) -> String {
         _ => source,
     }
 }
+/// Resolve the built-in variables and every placeholder. A `preview` never
+/// prompts, and falls back to `"<name>"` for placeholders without a value so
+/// the preview always renders.
 fn prepare_render(
     opts: &RenderOpts,
     config: &BpTemplateConfig,
+    preview: bool,
 ) -> Result<BTreeMap<String, String>> {
-    let mut variables = BTreeMap::new();
-    variables.insert("project_name".to_string(), opts.project_name.clone());
-    variables.insert(
-        "crate_name".to_string(),
-        opts.project_name.replace('-', "_"),
-    );
+    let mut variables = builtin_variables(&opts.project_name);
 
     // Resolve category-linked options into concrete lists, and pre-fill any
     // placeholder whose category the user already chose from in the picker.
     let resolved = resolve_option_sources(opts, config)?;
 
+    let interactive_override = if preview {
+        Some(false)
+    } else {
+        opts.interactive_override
+    };
     resolve_placeholders(
         &config.placeholders,
         &resolved,
         &opts.defines,
         &mut variables,
-        opts.interactive_override,
+        interactive_override,
+        preview,
     )?;
     Ok(variables)
 }
 
+/// The variables every template gets: `project_name` and `crate_name`.
+fn builtin_variables(project_name: &str) -> BTreeMap<String, String> {
+    BTreeMap::from([
+        ("project_name".to_string(), project_name.to_string()),
+        ("crate_name".to_string(), project_name.replace('-', "_")),
+    ])
+}
+
 /// The concrete options and any picker-derived pre-fill for one placeholder.
 #[derive(Debug)]
 struct ResolvedOptions {
@@ -436,11 +868,15 @@ fn resolve_option_sources(
                     bail!("placeholder '{name}' references undefined category '{category}'");
                 }
                 let members = spec.items_in_category(category);
-                // Pre-fill from the picker: the first category member the user activated.
-                let prefill = members
-                    .iter()
-                    .find(|m| opts.active_features.contains(*m))
-                    .cloned();
+                // Pre-fill from the picker: the first category member the user
+                // activated, or every one of them for a multi-select.
+                let mut chosen = members.iter().filter(|m| opts.active_features.contains(*m));
+                let prefill = if def.placeholder_type == PlaceholderType::Multiselect {
+                    let chosen: Vec<&str> = chosen.map(String::as_str).collect();
+                    (!chosen.is_empty()).then(|| chosen.join(","))
+                } else {
+                    chosen.next().cloned()
+                };
                 resolved.insert(
                     name.clone(),
                     ResolvedOptions {
@@ -471,12 +907,14 @@ fn load_config(opts: &RenderOpts) -> Result<(PathBuf, BpTemplateConfig)> {
     Ok((template_dir, config))
 }
 
+// [impl format.templates.placeholder-when]
 fn resolve_placeholders(
     defs: &BTreeMap<String, PlaceholderDef>,
     resolved_options: &BTreeMap<String, ResolvedOptions>,
     defines: &BTreeMap<String, String>,
     variables: &mut BTreeMap<String, String>,
     interactive_override: Option<bool>,
+    preview: bool,
 ) -> Result<()> {
     let interactive = if let Some(interactive) = interactive_override {
         interactive
@@ -487,6 +925,7 @@ fn resolve_placeholders(
     // Empty option list for placeholders with no `options` (non-select types).
     let no_options: Vec<String> = Vec::new();
 
+    let mut pending = Vec::new();
     for (name, def) in defs {
         // MiniJinja parses `-` as the minus operator, so kebab-case names
         // would be silently unreachable in templates without extra user handling.
@@ -496,102 +935,375 @@ fn resolve_placeholders(
                 "placeholder '{name}' contains '-'; use snake_case (MiniJinja treats '-' as minus)"
             );
         }
+        let pattern = def
+            .pattern
+            .as_deref()
+            .map(|p| regex::Regex::new(&format!("^(?:{p})$")))
+            .transpose()
+            .with_context(|| format!("placeholder '{name}' has an invalid pattern"))?;
+        pending.push((name, def, pattern));
+    }
 
-        // Check pre-set overrides first
-        if let Some(value) = defines.get(name) {
-            variables.insert(name.clone(), value.clone());
-            continue;
+    // A `when` condition can only be evaluated once the placeholders it names
+    // are resolved, so keep sweeping until every placeholder is settled.
+    let env = minijinja::Environment::new();
+    while !pending.is_empty() {
+        let before = pending.len();
+        let mut deferred = Vec::new();
+
+        for (name, def, pattern) in pending {
+            let asked = match &def.when {
+                None => true,
+                Some(when) => {
+                    let expr = env.compile_expression(when).map_err(|e| {
+                        anyhow::anyhow!("placeholder '{name}' has an invalid `when`: {e}")
+                    })?;
+                    let refs = expr.undeclared_variables(false);
+                    if let Some(unknown) = refs
+                        .iter()
+                        .find(|r| !defs.contains_key(*r) && !variables.contains_key(*r))
+                    {
+                        bail!(
+                            "placeholder '{name}' `when` refers to unknown placeholder '{unknown}'"
+                        );
+                    }
+                    if refs.iter().any(|r| !variables.contains_key(r)) {
+                        deferred.push((name, def, pattern));
+                        continue;
+                    }
+                    let ctx: BTreeMap<&str, minijinja::Value> = variables
+                        .iter()
+                        .map(|(k, v)| (k.as_str(), jinja_value(defs.get(k), v)))
+                        .collect();
+                    expr.eval(ctx)
+                        .map_err(|e| {
+                            anyhow::anyhow!(
+                                "failed to evaluate `when` of placeholder '{name}': {e}"
+                            )
+                        })?
+                        .is_true()
+                }
+            };
+
+            let options = resolved_options
+                .get(name)
+                .map(|r| &r.options)
+                .unwrap_or(&no_options);
+            let prefill = resolved_options.get(name).and_then(|r| r.prefill.as_ref());
+            let define = defines.get(name);
+            let value = if !asked && !preview && define.is_none() && prefill.is_none() {
+                // Not asked: the default, or an empty/false value.
+                def.default
+                    .clone()
+                    .unwrap_or_else(|| match def.placeholder_type {
+                        PlaceholderType::Bool => "false".to_string(),
+                        _ => String::new(),
+                    })
+            } else {
+                resolve_placeholder(
+                    name,
+                    def,
+                    pattern.as_ref(),
+                    options,
+                    prefill,
+                    define,
+                    interactive && asked,
+                    preview,
+                )?
+            };
+            variables.insert(name.clone(), value);
         }
 
-        // A category-linked placeholder the user already chose in the picker is
-        // filled without prompting.
-        if let Some(prefill) = resolved_options.get(name).and_then(|r| r.prefill.as_ref()) {
-            variables.insert(name.clone(), prefill.clone());
-            continue;
+        if deferred.len() == before {
+            let names: Vec<&str> = deferred.iter().map(|(n, _, _)| n.as_str()).collect();
+            bail!(
+                "placeholders {} have `when` conditions that depend on each other",
+                names.join(", ")
+            );
         }
+        pending = deferred;
+    }
+    Ok(())
+}
 
-        // The concrete option list for a `select` placeholder.
-        let options = resolved_options
-            .get(name)
-            .map(|r| &r.options)
-            .unwrap_or(&no_options);
+/// Resolve one placeholder's value, from (in order) its define, the picker's
+/// pre-fill, a prompt, or its default. A `preview` skips the "no value"
+/// errors, returning the default (`false` for a bool) or `"<name>"` unchecked.
+// [impl format.templates.placeholder-validation]
+#[allow(clippy::too_many_arguments)]
+fn resolve_placeholder(
+    name: &str,
+    def: &PlaceholderDef,
+    pattern: Option<&regex::Regex>,
+    options: &[String],
+    prefill: Option<&String>,
+    define: Option<&String>,
+    interactive: bool,
+    preview: bool,
+) -> Result<String> {
+    // Check pre-set overrides first
+    if let Some(value) = define {
+        // [impl cli.new.define-validation]
+        check_value(def, pattern, options, value).map_err(|problem| {
+            anyhow::anyhow!("invalid value '{value}' for placeholder '{name}': {problem}")
+        })?;
+        return Ok(value.clone());
+    }
 
-        let value = match def.placeholder_type {
-            PlaceholderType::String => {
-                if interactive {
-                    let prompt = def.prompt.as_deref().unwrap_or(name);
-                    let mut builder = dialoguer::Input::<String>::new().with_prompt(prompt);
-                    if let Some(default) = &def.default {
-                        builder = builder.default(default.clone());
-                    }
-                    builder
-                        .interact_text()
-                        .with_context(|| format!("failed to read placeholder '{name}'"))?
-                } else {
-                    def.default.clone().ok_or_else(|| {
-                        anyhow::anyhow!("placeholder '{name}' has no default and no value provided")
-                    })?
+    // A category-linked placeholder the user already chose in the picker is
+    // filled without prompting.
+    if let Some(prefill) = prefill {
+        return Ok(prefill.clone());
+    }
+
+    if preview && !interactive {
+        return Ok(match (&def.default, &def.placeholder_type) {
+            (Some(default), _) => default.clone(),
+            (None, PlaceholderType::Bool) => "false".to_string(),
+            (None, _) => format!("<{name}>"),
+        });
+    }
+
+    let check_default = |value: String| -> Result<String> {
+        check_value(def, pattern, options, &value).map_err(|problem| {
+            anyhow::anyhow!("default '{value}' of placeholder '{name}' is invalid: {problem}")
+        })?;
+        Ok(value)
+    };
+
+    let value = match def.placeholder_type {
+        PlaceholderType::String | PlaceholderType::Int => {
+            if interactive {
+                let prompt = def.prompt.as_deref().unwrap_or(name);
+                let mut builder = dialoguer::Input::<String>::new()
+                    .with_prompt(prompt)
+                    .allow_empty(!def.required)
+                    .validate_with(|input: &String| check_value(def, pattern, options, input));
+                if let Some(default) = &def.default {
+                    builder = builder.default(default.clone());
                 }
+                builder
+                    .interact_text()
+                    .with_context(|| format!("failed to read placeholder '{name}'"))?
+            } else {
+                check_default(def.default.clone().ok_or_else(|| {
+                    anyhow::anyhow!("placeholder '{name}' has no default and no value provided")
+                })?)?
             }
-            PlaceholderType::Bool => {
-                if interactive {
-                    let prompt = def.prompt.as_deref().unwrap_or(name);
-                    let default_val = def
-                        .default
-                        .as_deref()
-                        .map(|d| d.eq_ignore_ascii_case("true"))
-                        .unwrap_or(false);
-                    let val = dialoguer::Confirm::new()
-                        .with_prompt(prompt)
-                        .default(default_val)
-                        .interact()
-                        .with_context(|| format!("failed to read placeholder '{name}'"))?;
-                    val.to_string()
-                } else {
-                    def.default.clone().unwrap_or_else(|| "false".to_string())
-                }
+        }
+        PlaceholderType::Bool => {
+            if interactive {
+                let prompt = def.prompt.as_deref().unwrap_or(name);
+                let default_val = def
+                    .default
+                    .as_deref()
+                    .map(|d| d.eq_ignore_ascii_case("true"))
+                    .unwrap_or(false);
+                let val = dialoguer::Confirm::new()
+                    .with_prompt(prompt)
+                    .default(default_val)
+                    .interact()
+                    .with_context(|| format!("failed to read placeholder '{name}'"))?;
+                val.to_string()
+            } else {
+                def.default.clone().unwrap_or_else(|| "false".to_string())
             }
-            PlaceholderType::Select => {
-                if options.is_empty() {
-                    bail!("select placeholder '{name}' has no options");
+        }
+        PlaceholderType::List => {
+            if interactive {
+                let prompt = def.prompt.as_deref().unwrap_or(name);
+                let mut builder = dialoguer::Input::<String>::new()
+                    .with_prompt(format!("{prompt} (comma-separated)"))
+                    .allow_empty(true)
+                    .validate_with(|input: &String| check_value(def, pattern, options, input));
+                if let Some(default) = &def.default {
+                    builder = builder.default(default.clone());
                 }
-                if interactive {
-                    let prompt = def.prompt.as_deref().unwrap_or(name);
-                    let default_idx = def
-                        .default
-                        .as_ref()
-                        .and_then(|d| options.iter().position(|o| o == d))
-                        .unwrap_or(0);
-                    let idx = dialoguer::Select::new()
+                let input = builder
+                    .interact_text()
+                    .with_context(|| format!("failed to read placeholder '{name}'"))?;
+                list_items(&input).join(",")
+            } else {
+                check_default(def.default.clone().unwrap_or_default())?
+            }
+        }
+        PlaceholderType::Multiselect => {
+            if options.is_empty() {
+                bail!("multiselect placeholder '{name}' has no options");
+            }
+            let default = def.default.clone().unwrap_or_default();
+            if interactive {
+                let prompt = def.prompt.as_deref().unwrap_or(name);
+                let chosen = list_items(&default);
+                let defaults: Vec<bool> = options
+                    .iter()
+                    .map(|o| chosen.contains(&o.as_str()))
+                    .collect();
+                loop {
+                    let picked = dialoguer::MultiSelect::new()
                         .with_prompt(prompt)
                         .items(options)
-                        .default(default_idx)
+                        .defaults(&defaults)
                         .interact()
                         .with_context(|| format!("failed to read placeholder '{name}'"))?;
-                    options[idx].clone()
-                } else {
-                    let val = def.default.clone().ok_or_else(|| {
-                        anyhow::anyhow!("placeholder '{name}' has no default and no value provided")
-                    })?;
-                    if !options.contains(&val) {
-                        bail!(
-                            "placeholder '{name}' default '{}' is not in options: {:?}",
-                            val,
-                            options
-                        );
+                    let value = picked
+                        .iter()
+                        .map(|&i| options[i].as_str())
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    match check_value(def, pattern, options, &value) {
+                        Ok(()) => break value,
+                        Err(problem) => eprintln!("{problem}"),
                     }
-                    val
                 }
+            } else {
+                check_default(default)?
             }
-        };
-        variables.insert(name.clone(), value);
+        }
+        PlaceholderType::Select => {
+            if options.is_empty() {
+                bail!("select placeholder '{name}' has no options");
+            }
+            if interactive {
+                let prompt = def.prompt.as_deref().unwrap_or(name);
+                let default_idx = def
+                    .default
+                    .as_ref()
+                    .and_then(|d| options.iter().position(|o| o == d))
+                    .unwrap_or(0);
+                let idx = dialoguer::Select::new()
+                    .with_prompt(prompt)
+                    .items(options)
+                    .default(default_idx)
+                    .interact()
+                    .with_context(|| format!("failed to read placeholder '{name}'"))?;
+                options[idx].clone()
+            } else {
+                let val = def.default.clone().ok_or_else(|| {
+                    anyhow::anyhow!("placeholder '{name}' has no default and no value provided")
+                })?;
+                if !options.contains(&val) {
+                    bail!(
+                        "placeholder '{name}' default '{}' is not in options: {:?}",
+                        val,
+                        options
+                    );
+                }
+                val
+            }
+        }
+    };
+    Ok(value)
+}
+
+/// Check a value against a placeholder's type, its options, and its
+/// `required`, length and `pattern` constraints, describing the first one it
+/// violates. List
+/// values are checked item by item, and their length counts items.
+fn check_value(
+    def: &PlaceholderDef,
+    pattern: Option<&regex::Regex>,
+    options: &[String],
+    value: &str,
+) -> std::result::Result<(), String> {
+    let (items, len, unit) = if def.placeholder_type.is_list() {
+        let items = list_items(value);
+        let len = items.len();
+        (items, len, "items")
+    } else {
+        (vec![value], value.chars().count(), "characters")
+    };
+    if def.required && items.iter().all(|item| item.trim().is_empty()) {
+        return Err("a value is required".to_string());
+    }
+    if def.placeholder_type == PlaceholderType::Int && value.trim().parse::<i64>().is_err() {
+        return Err("must be a whole number".to_string());
+    }
+    if def.placeholder_type == PlaceholderType::Bool && value != "true" && value != "false" {
+        return Err("must be `true` or `false`".to_string());
+    }
+    if def.placeholder_type == PlaceholderType::Select && !options.iter().any(|o| o == value) {
+        return Err(format!("must be one of the options: {options:?}"));
+    }
+    if def.placeholder_type == PlaceholderType::Multiselect
+        && let Some(unknown) = items
+            .iter()
+            .find(|item| !options.iter().any(|o| o == *item))
+    {
+        return Err(format!(
+            "'{unknown}' is not one of the options: {options:?}"
+        ));
+    }
+    if let Some(min) = def.min_length
+        && len < min
+    {
+        return Err(format!("must be at least {min} {unit}"));
+    }
+    if let Some(max) = def.max_length
+        && len > max
+    {
+        return Err(format!("must be at most {max} {unit}"));
+    }
+    if let (Some(re), Some(source)) = (pattern, &def.pattern)
+        && let Some(item) = items.iter().find(|item| !re.is_match(item))
+    {
+        return Err(if def.placeholder_type.is_list() {
+            format!("'{item}' must match the pattern `{source}`")
+        } else {
+            format!("must match the pattern `{source}`")
+        });
+    }
+    Ok(())
+}
+
+/// Check that a project name is a valid crate name, describing the problem
+/// if not.
+// [impl cli.new.project-name]
+pub(crate) fn check_crate_name(name: &str) -> std::result::Result<(), String> {
+    let Some(first) = name.chars().next() else {
+        return Err("the project name must not be empty".to_string());
+    };
+    if first.is_ascii_digit() {
+        return Err(format!(
+            "'{name}' is not a valid crate name: it starts with a digit"
+        ));
+    }
+    if let Some(bad) = name
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && *c != '-' && *c != '_')
+    {
+        return Err(format!(
+            "'{name}' is not a valid crate name: '{bad}' is not allowed (use letters, digits, '-' and '_')"
+        ));
     }
     Ok(())
 }
 
+/// A variable's value as MiniJinja sees it, typed by its placeholder
+/// definition: integers and lists become real MiniJinja values, and
+/// `"true"`/`"false"` become bools so `{% if flag %}` works.
+fn jinja_value(def: Option<&PlaceholderDef>, value: &str) -> minijinja::Value {
+    match def.map(|d| &d.placeholder_type) {
+        Some(PlaceholderType::Int) => value
+            .trim()
+            .parse::<i64>()
+            .map(minijinja::Value::from)
+            .unwrap_or_else(|_| minijinja::Value::from(value)),
+        Some(PlaceholderType::List | PlaceholderType::Multiselect) => {
+            minijinja::Value::from_iter(list_items(value).into_iter().map(str::to_string))
+        }
+        _ => match value {
+            "true" => minijinja::Value::from(true),
+            "false" => minijinja::Value::from(false),
+            _ => minijinja::Value::from(value),
+        },
+    }
+}
+
 fn build_jinja_env(
     crate_root: &Path,
     variables: &BTreeMap<String, String>,
+    placeholders: &BTreeMap<String, PlaceholderDef>,
 ) -> Result<minijinja::Environment<'static>> {
     let mut env = minijinja::Environment::new();
 
@@ -612,14 +1324,9 @@ fn build_jinja_env(
         }
     });
 
-    // Register all variables as globals.
-    // "true"/"false" strings are registered as actual bools so {% if flag %} works.
+    // Register all variables as globals, typed by their placeholder.
     for (key, value) in variables {
-        match value.as_str() {
-            "true" => env.add_global(key.clone(), true),
-            "false" => env.add_global(key.clone(), false),
-            _ => env.add_global(key.clone(), value.clone()),
-        }
+        env.add_global(key.clone(), jinja_value(placeholders.get(key), value));
     }
 
     // Register pin_github_action — reads from the global cache (populated by prefetch or on-demand).
@@ -831,6 +1538,33 @@ fn resolve_ref(owner_repo: &str, ref_name: &str) -> Result<(String, String)> {
     Ok((sha, ref_name.to_string()))
 }
 
+/// Evaluate a MiniJinja `condition` against the template's variables.
+/// `what` names the condition in errors.
+fn eval_condition(env: &minijinja::Environment<'_>, expr: &str, what: &str) -> Result<bool> {
+    let value = env
+        .compile_expression(expr)
+        .and_then(|compiled| compiled.eval(minijinja::context! {}))
+        .with_context(|| format!("failed to evaluate {what} '{expr}'"))?;
+    Ok(value.is_true())
+}
+
+/// Whether a file's content must be copied rather than rendered: it is not
+/// UTF-8, or it holds a NUL byte, which text never does.
+// [impl format.templates.binary]
+fn is_binary(content: &[u8]) -> bool {
+    content.contains(&0) || std::str::from_utf8(content).is_err()
+}
+
+/// Whether a template-relative path matches any of the glob patterns.
+fn matches_glob(rel_path: &Path, patterns: &[glob::Pattern]) -> bool {
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    let path = rel_path.to_string_lossy().replace('\\', "/");
+    patterns.iter().any(|p| p.matches_with(&path, options))
+}
+
 /// Check if a relative path should be ignored.
 fn should_ignore(rel_path: &Path, ignore_set: &[&str]) -> bool {
     // Check each component of the path against the ignore set
@@ -843,6 +1577,93 @@ fn should_ignore(rel_path: &Path, ignore_set: &[&str]) -> bool {
     false
 }
 
+/// Select the hooks whose `condition` holds and render their arguments.
+// [impl format.templates.hooks]
+fn render_hooks(
+    crate_root: &Path,
+    config: &BpTemplateConfig,
+    variables: &BTreeMap<String, String>,
+) -> Result<Vec<Hook>> {
+    if config.hooks.is_empty() {
+        return Ok(Vec::new());
+    }
+    let env = build_jinja_env(crate_root, variables, &config.placeholders)?;
+    let mut hooks = Vec::new();
+    for def in &config.hooks {
+        if let Some(condition) = &def.condition
+            && !eval_condition(&env, condition, "hook `condition`")?
+        {
+            continue;
+        }
+        let raw: Vec<&str> = match &def.run {
+            HookCommand::Line(line) => line.split_whitespace().collect(),
+            HookCommand::Args(args) => args.iter().map(String::as_str).collect(),
+        };
+        if raw.is_empty() {
+            bail!("hook has an empty `run` command");
+        }
+        let args = raw
+            .into_iter()
+            .map(|arg| {
+                env.render_str(arg, minijinja::context! {})
+                    .with_context(|| format!("failed to render hook argument '{arg}'"))
+            })
+            .collect::<Result<_>>()?;
+        hooks.push(Hook {
+            description: def.description.clone(),
+            args,
+        });
+    }
+    Ok(hooks)
+}
+
+/// Run post-generate hooks in `dir`, in order, stopping at the first failure.
+// [impl cli.new.hooks]
+pub(crate) fn run_hooks(hooks: &[Hook], dir: &Path, policy: HookPolicy) -> Result<()> {
+    if hooks.is_empty() {
+        return Ok(());
+    }
+    match policy {
+        HookPolicy::Skip => {
+            eprintln!("Skipped post-generate steps (pass --allow-hooks to run them):");
+            for hook in hooks {
+                eprintln!("  {hook}");
+            }
+            return Ok(());
+        }
+        HookPolicy::Prompt => {
+            eprintln!("The template has post-generate steps:");
+            for hook in hooks {
+                eprintln!("  {hook}");
+            }
+            let run = dialoguer::Confirm::new()
+                .with_prompt(format!("Run them in {}?", dir.display()))
+                .default(true)
+                .interact()
+                .context("prompt failed")?;
+            if !run {
+                eprintln!("Skipped post-generate steps.");
+                return Ok(());
+            }
+        }
+        HookPolicy::Allow => {}
+    }
+
+    for hook in hooks {
+        let command = hook.args.join(" ");
+        eprintln!("Running `{command}`");
+        let status = std::process::Command::new(&hook.args[0])
+            .args(&hook.args[1..])
+            .current_dir(dir)
+            .status()
+            .with_context(|| format!("failed to run `{command}`"))?;
+        if !status.success() {
+            bail!("post-generate step `{command}` failed ({status})");
+        }
+    }
+    Ok(())
+}
+
 fn git_init(project_dir: &Path) -> Result<()> {
     let output = std::process::Command::new("git")
         .args(["init"])
@@ -908,6 +1729,7 @@ pub(crate) fn preview_template(opts: &PreviewOpts<'_>) -> Result<(String, Vec<Re
         defines: opts.defines.clone(),
         active_features: std::collections::BTreeSet::new(),
         interactive_override: Some(false),
+        source: opts.source.clone(),
     };
     let files = preview(opts)?;
     Ok((crate_name, files))
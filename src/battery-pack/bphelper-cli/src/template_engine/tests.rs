@@ -25,7 +25,14 @@ fn test_resolve(
             _ => None,
         })
         .collect();
-    resolve_placeholders(defs, &resolved, defines, variables, interactive_override)
+    resolve_placeholders(
+        defs,
+        &resolved,
+        defines,
+        variables,
+        interactive_override,
+        false,
+    )
 }
 
 // -- Config parsing --
@@ -46,7 +53,13 @@ fn parse_config_full() {
             dest = "LICENSE"
         "#;
     let config: BpTemplateConfig = toml::from_str(toml).unwrap();
-    assert_eq!(config.ignore, vec!["hooks", ".git"]);
+    assert_eq!(
+        config.ignore,
+        vec![
+            IgnoreRule::Name("hooks".to_string()),
+            IgnoreRule::Name(".git".to_string())
+        ]
+    );
     assert_eq!(config.placeholders.len(), 1);
     let desc = &config.placeholders["description"];
     assert_eq!(desc.prompt.as_deref(), Some("Describe it"));
@@ -79,6 +92,11 @@ PlaceholderDef {
     default: None,
     placeholder_type: String,
     options: None,
+    pattern: None,
+    required: false,
+    min_length: None,
+    max_length: None,
+    when: None,
 }
 
 "#]]
@@ -130,6 +148,7 @@ fn resolve_uses_define_over_default() {
             default: Some("fallback".to_string()),
             placeholder_type: PlaceholderType::String,
             options: None,
+            ..Default::default()
         },
     );
     let mut defines = BTreeMap::new();
@@ -150,6 +169,7 @@ fn resolve_uses_default_non_interactive() {
             default: Some("fallback".to_string()),
             placeholder_type: PlaceholderType::String,
             options: None,
+            ..Default::default()
         },
     );
     let defines = BTreeMap::new();
@@ -170,6 +190,7 @@ fn resolve_no_default_non_interactive_errors() {
             default: None,
             placeholder_type: PlaceholderType::String,
             options: None,
+            ..Default::default()
         },
     );
     let defines = BTreeMap::new();
@@ -192,6 +213,7 @@ fn resolve_rejects_kebab_case_name() {
             default: Some("val".to_string()),
             placeholder_type: PlaceholderType::String,
             options: None,
+            ..Default::default()
         },
     );
     let err = test_resolve(&defs, &BTreeMap::new(), &mut BTreeMap::new(), Some(false)).unwrap_err();
@@ -201,6 +223,429 @@ fn resolve_rejects_kebab_case_name() {
     );
 }
 
+// -- placeholder validation --
+// [verify format.templates.placeholder-validation]
+// [verify cli.new.define-validation]
+
+fn string_placeholder(default: &str) -> PlaceholderDef {
+    PlaceholderDef {
+        default: Some(default.to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn resolve_define_must_match_pattern() {
+    let defs = BTreeMap::from([(
+        "repo_owner".to_string(),
+        PlaceholderDef {
+            pattern: Some("[A-Za-z0-9-]+".to_string()),
+            ..string_placeholder("OWNER")
+        },
+    )]);
+    let defines = BTreeMap::from([("repo_owner".to_string(), "my org".to_string())]);
+    let err = test_resolve(&defs, &defines, &mut BTreeMap::new(), Some(false)).unwrap_err();
+    assert_data_eq!(
+        err.to_string(),
+        str![
+            "invalid value 'my org' for placeholder 'repo_owner': must match the pattern `[A-Za-z0-9-]+`"
+        ]
+    );
+
+    // The pattern must match the whole value, not just a part of it.
+    let defines = BTreeMap::from([("repo_owner".to_string(), "my-org".to_string())]);
+    let mut vars = BTreeMap::new();
+    test_resolve(&defs, &defines, &mut vars, Some(false)).unwrap();
+    assert_eq!(vars["repo_owner"], "my-org");
+}
+
+#[test]
+fn resolve_enforces_required_and_length() {
+    let defs = BTreeMap::from([(
+        "description".to_string(),
+        PlaceholderDef {
+            required: true,
+            min_length: Some(3),
+            max_length: Some(5),
+            ..string_placeholder("hello")
+        },
+    )]);
+    let check = |value: &str| {
+        let defines = BTreeMap::from([("description".to_string(), value.to_string())]);
+        test_resolve(&defs, &defines, &mut BTreeMap::new(), Some(false)).map_err(|e| e.to_string())
+    };
+    assert_data_eq!(
+        check(" ").unwrap_err(),
+        str!["invalid value ' ' for placeholder 'description': a value is required"]
+    );
+    assert_data_eq!(
+        check("hi").unwrap_err(),
+        str!["invalid value 'hi' for placeholder 'description': must be at least 3 characters"]
+    );
+    assert_data_eq!(
+        check("héllo!").unwrap_err(),
+        str!["invalid value 'héllo!' for placeholder 'description': must be at most 5 characters"]
+    );
+    check("héllo").unwrap();
+}
+
+#[test]
+fn resolve_default_is_validated_too() {
+    let defs = BTreeMap::from([(
+        "repo_owner".to_string(),
+        PlaceholderDef {
+            pattern: Some("[a-z]+".to_string()),
+            ..string_placeholder("OWNER")
+        },
+    )]);
+    let err = test_resolve(&defs, &BTreeMap::new(), &mut BTreeMap::new(), Some(false)).unwrap_err();
+    assert_data_eq!(
+        err.to_string(),
+        str![
+            "default 'OWNER' of placeholder 'repo_owner' is invalid: must match the pattern `[a-z]+`"
+        ]
+    );
+}
+
+#[test]
+fn resolve_invalid_pattern_errors() {
+    let defs = BTreeMap::from([(
+        "name".to_string(),
+        PlaceholderDef {
+            pattern: Some("[".to_string()),
+            ..string_placeholder("x")
+        },
+    )]);
+    let err = test_resolve(&defs, &BTreeMap::new(), &mut BTreeMap::new(), Some(false)).unwrap_err();
+    assert_data_eq!(
+        err.to_string(),
+        str!["placeholder 'name' has an invalid pattern"]
+    );
+}
+
+// -- int, list and multiselect placeholders --
+// [verify format.templates.placeholder-types]
+
+#[test]
+fn parse_config_natural_defaults() {
+    let config: BpTemplateConfig = toml::from_str(indoc! {r#"
+        [placeholders.jobs]
+        type = "int"
+        default = 4
+
+        [placeholders.targets]
+        type = "multiselect"
+        options = ["linux", "macos", "windows"]
+        default = ["linux", "macos"]
+
+        [placeholders.flag]
+        type = "bool"
+        default = true
+    "#})
+    .unwrap();
+    assert_eq!(config.placeholders["jobs"].default.as_deref(), Some("4"));
+    assert_eq!(
+        config.placeholders["targets"].default.as_deref(),
+        Some("linux,macos")
+    );
+    assert_eq!(config.placeholders["flag"].default.as_deref(), Some("true"));
+}
+
+fn targets_placeholder() -> BTreeMap<String, PlaceholderDef> {
+    BTreeMap::from([(
+        "targets".to_string(),
+        PlaceholderDef {
+            placeholder_type: PlaceholderType::Multiselect,
+            options: Some(OptionsSource::Literal(vec![
+                "linux".to_string(),
+                "macos".to_string(),
+                "windows".to_string(),
+            ])),
+            default: Some("linux".to_string()),
+            ..Default::default()
+        },
+    )])
+}
+
+#[test]
+fn resolve_multiselect_define_must_use_options() {
+    let defs = targets_placeholder();
+    let defines = BTreeMap::from([("targets".to_string(), "linux,beos".to_string())]);
+    let err = test_resolve(&defs, &defines, &mut BTreeMap::new(), Some(false)).unwrap_err();
+    assert_data_eq!(
+        err.to_string(),
+        str![[
+            r#"invalid value 'linux,beos' for placeholder 'targets': 'beos' is not one of the options: ["linux", "macos", "windows"]"#
+        ]]
+    );
+
+    let defines = BTreeMap::from([("targets".to_string(), "linux, windows".to_string())]);
+    let mut vars = BTreeMap::new();
+    test_resolve(&defs, &defines, &mut vars, Some(false)).unwrap();
+    assert_eq!(vars["targets"], "linux, windows");
+}
+
+// [verify cli.new.define-validation]
+#[test]
+fn resolve_select_define_must_use_options() {
+    let defs = BTreeMap::from([(
+        "ci".to_string(),
+        PlaceholderDef {
+            placeholder_type: PlaceholderType::Select,
+            options: Some(OptionsSource::Literal(vec![
+                "github".to_string(),
+                "none".to_string(),
+            ])),
+            default: Some("github".to_string()),
+            ..Default::default()
+        },
+    )]);
+    let defines = BTreeMap::from([("ci".to_string(), "gitlab".to_string())]);
+    let err = test_resolve(&defs, &defines, &mut BTreeMap::new(), Some(false)).unwrap_err();
+    assert_data_eq!(
+        err.to_string(),
+        str![[
+            r#"invalid value 'gitlab' for placeholder 'ci': must be one of the options: ["github", "none"]"#
+        ]]
+    );
+
+    let defines = BTreeMap::from([("ci".to_string(), "none".to_string())]);
+    let mut vars = BTreeMap::new();
+    test_resolve(&defs, &defines, &mut vars, Some(false)).unwrap();
+    assert_eq!(vars["ci"], "none");
+}
+
+// [verify cli.new.define-validation]
+#[test]
+fn resolve_bool_define_must_be_true_or_false() {
+    let defs = BTreeMap::from([(
+        "fuzzing".to_string(),
+        PlaceholderDef {
+            placeholder_type: PlaceholderType::Bool,
+            ..string_placeholder("false")
+        },
+    )]);
+    let defines = BTreeMap::from([("fuzzing".to_string(), "yes".to_string())]);
+    let err = test_resolve(&defs, &defines, &mut BTreeMap::new(), Some(false)).unwrap_err();
+    assert_data_eq!(
+        err.to_string(),
+        str!["invalid value 'yes' for placeholder 'fuzzing': must be `true` or `false`"]
+    );
+
+    let defines = BTreeMap::from([("fuzzing".to_string(), "true".to_string())]);
+    let mut vars = BTreeMap::new();
+    test_resolve(&defs, &defines, &mut vars, Some(false)).unwrap();
+    assert_eq!(vars["fuzzing"], "true");
+}
+
+#[test]
+fn resolve_int_rejects_non_numbers() {
+    let defs = BTreeMap::from([(
+        "jobs".to_string(),
+        PlaceholderDef {
+            placeholder_type: PlaceholderType::Int,
+            ..string_placeholder("4")
+        },
+    )]);
+    let defines = BTreeMap::from([("jobs".to_string(), "four".to_string())]);
+    let err = test_resolve(&defs, &defines, &mut BTreeMap::new(), Some(false)).unwrap_err();
+    assert_data_eq!(
+        err.to_string(),
+        str!["invalid value 'four' for placeholder 'jobs': must be a whole number"]
+    );
+}
+
+#[test]
+fn resolve_list_checks_each_item_and_counts_items() {
+    let defs = BTreeMap::from([(
+        "crates".to_string(),
+        PlaceholderDef {
+            placeholder_type: PlaceholderType::List,
+            pattern: Some("[a-z-]+".to_string()),
+            max_length: Some(2),
+            ..Default::default()
+        },
+    )]);
+    let check = |value: &str| {
+        let defines = BTreeMap::from([("crates".to_string(), value.to_string())]);
+        test_resolve(&defs, &defines, &mut BTreeMap::new(), Some(false)).map_err(|e| e.to_string())
+    };
+    assert_data_eq!(
+        check("core,Bad").unwrap_err(),
+        str![
+            "invalid value 'core,Bad' for placeholder 'crates': 'Bad' must match the pattern `[a-z-]+`"
+        ]
+    );
+    assert_data_eq!(
+        check("a,b,c").unwrap_err(),
+        str!["invalid value 'a,b,c' for placeholder 'crates': must be at most 2 items"]
+    );
+
+    // Without a default, a list is empty rather than an error.
+    let mut vars = BTreeMap::new();
+    test_resolve(&defs, &BTreeMap::new(), &mut vars, Some(false)).unwrap();
+    assert_eq!(vars["crates"], "");
+}
+
+#[test]
+fn jinja_renders_lists_and_ints_as_real_values() {
+    let mut defs = targets_placeholder();
+    defs.insert(
+        "jobs".to_string(),
+        PlaceholderDef {
+            placeholder_type: PlaceholderType::Int,
+            ..Default::default()
+        },
+    );
+    let vars = BTreeMap::from([
+        ("targets".to_string(), "linux, macos".to_string()),
+        ("jobs".to_string(), "4".to_string()),
+    ]);
+    let env = build_jinja_env(Path::new("."), &vars, &defs).unwrap();
+    let result = env
+        .render_str(
+            "{% for t in targets %}{{ t }};{% endfor %} {{ targets | length }} {{ jobs * 2 }}",
+            minijinja::context! {},
+        )
+        .unwrap();
+    assert_eq!(result, "linux;macos; 2 8");
+
+    let vars = BTreeMap::from([("targets".to_string(), String::new())]);
+    let env = build_jinja_env(Path::new("."), &vars, &defs).unwrap();
+    let result = env
+        .render_str(
+            "{% if targets %}some{% else %}none{% endif %}",
+            minijinja::context! {},
+        )
+        .unwrap();
+    assert_eq!(result, "none");
+}
+
+// -- placeholder conditions --
+// [verify format.templates.placeholder-when]
+
+/// `ci_platform` select plus `audit_issue`, asked only on GitHub. The
+/// condition names a placeholder that sorts after it, so resolution order
+/// matters.
+fn audit_issue_defs(audit_default: Option<&str>) -> BTreeMap<String, PlaceholderDef> {
+    BTreeMap::from([
+        (
+            "audit_issue".to_string(),
+            PlaceholderDef {
+                default: audit_default.map(str::to_string),
+                placeholder_type: PlaceholderType::Bool,
+                when: Some(r#"ci_platform == "github""#.to_string()),
+                ..Default::default()
+            },
+        ),
+        (
+            "ci_platform".to_string(),
+            PlaceholderDef {
+                default: Some("github".to_string()),
+                placeholder_type: PlaceholderType::Select,
+                options: Some(OptionsSource::Literal(vec![
+                    "github".to_string(),
+                    "none".to_string(),
+                ])),
+                ..Default::default()
+            },
+        ),
+    ])
+}
+
+#[test]
+fn resolve_when_holds_uses_normal_resolution() {
+    let mut vars = BTreeMap::new();
+    test_resolve(
+        &audit_issue_defs(Some("true")),
+        &BTreeMap::new(),
+        &mut vars,
+        Some(false),
+    )
+    .unwrap();
+    assert_eq!(vars["audit_issue"], "true");
+}
+
+#[test]
+fn resolve_when_false_skips_to_default() {
+    let defines = BTreeMap::from([("ci_platform".to_string(), "none".to_string())]);
+
+    let mut vars = BTreeMap::new();
+    test_resolve(
+        &audit_issue_defs(Some("true")),
+        &defines,
+        &mut vars,
+        Some(true),
+    )
+    .unwrap();
+    assert_eq!(vars["audit_issue"], "true");
+
+    let mut vars = BTreeMap::new();
+    test_resolve(&audit_issue_defs(None), &defines, &mut vars, Some(true)).unwrap();
+    assert_eq!(vars["audit_issue"], "false");
+}
+
+#[test]
+fn resolve_when_unknown_placeholder_errors() {
+    let defs = BTreeMap::from([(
+        "audit_issue".to_string(),
+        PlaceholderDef {
+            when: Some(r#"platform == "github""#.to_string()),
+            ..string_placeholder("x")
+        },
+    )]);
+    let err = test_resolve(&defs, &BTreeMap::new(), &mut BTreeMap::new(), Some(false)).unwrap_err();
+    assert_data_eq!(
+        err.to_string(),
+        str!["placeholder 'audit_issue' `when` refers to unknown placeholder 'platform'"]
+    );
+}
+
+#[test]
+fn resolve_when_cycle_errors() {
+    let defs = BTreeMap::from([
+        (
+            "a".to_string(),
+            PlaceholderDef {
+                when: Some("b".to_string()),
+                ..string_placeholder("x")
+            },
+        ),
+        (
+            "b".to_string(),
+            PlaceholderDef {
+                when: Some("a".to_string()),
+                ..string_placeholder("x")
+            },
+        ),
+    ]);
+    let err = test_resolve(&defs, &BTreeMap::new(), &mut BTreeMap::new(), Some(false)).unwrap_err();
+    assert_data_eq!(
+        err.to_string(),
+        str!["placeholders a, b have `when` conditions that depend on each other"]
+    );
+}
+
+// [verify cli.new.project-name]
+#[test]
+fn check_crate_name_rejects_invalid_names() {
+    check_crate_name("my-project_2").unwrap();
+    assert_data_eq!(
+        check_crate_name("").unwrap_err(),
+        str!["the project name must not be empty"]
+    );
+    assert_data_eq!(
+        check_crate_name("2fast").unwrap_err(),
+        str!["'2fast' is not a valid crate name: it starts with a digit"]
+    );
+    assert_data_eq!(
+        check_crate_name("my project").unwrap_err(),
+        str![
+            "'my project' is not a valid crate name: ' ' is not allowed (use letters, digits, '-' and '_')"
+        ]
+    );
+}
+
 // -- build_jinja_env --
 
 #[test]
@@ -209,7 +654,7 @@ fn jinja_env_renders_variables() {
     vars.insert("project_name".to_string(), "my-app".to_string());
     vars.insert("crate_name".to_string(), "my_app".to_string());
 
-    let env = build_jinja_env(Path::new("."), &vars).unwrap();
+    let env = build_jinja_env(Path::new("."), &vars, &BTreeMap::new()).unwrap();
     let result = env
         .render_str(
             "name = {{ project_name }}, crate = {{ crate_name }}",
@@ -227,7 +672,7 @@ fn jinja_env_no_html_escaping() {
         "A <bold> & cool thing".to_string(),
     );
 
-    let env = build_jinja_env(Path::new("."), &vars).unwrap();
+    let env = build_jinja_env(Path::new("."), &vars, &BTreeMap::new()).unwrap();
     let result = env
         .render_str("{{ description }}", minijinja::context! {})
         .unwrap();
@@ -237,7 +682,7 @@ fn jinja_env_no_html_escaping() {
 #[test]
 fn jinja_env_raw_block_passthrough() {
     let vars = BTreeMap::new();
-    let env = build_jinja_env(Path::new("."), &vars).unwrap();
+    let env = build_jinja_env(Path::new("."), &vars, &BTreeMap::new()).unwrap();
     let result = env
         .render_str(
             "{% raw %}{{ not_a_variable }}{% endraw %}",
@@ -300,7 +745,7 @@ TOML parse error at line 3, column 20
   |
 3 |             type = "number"
   |                    ^^^^^^^^
-unknown variant `number`, expected one of `string`, `bool`, `select`
+unknown variant `number`, expected one of `string`, `bool`, `select`, `int`, `list`, `multiselect`
 
 "#]]
     );
@@ -318,6 +763,7 @@ fn resolve_bool_true_non_interactive() {
             default: Some("true".to_string()),
             placeholder_type: PlaceholderType::Bool,
             options: None,
+            ..Default::default()
         },
     );
     let mut vars = BTreeMap::new();
@@ -335,6 +781,7 @@ fn resolve_bool_false_non_interactive() {
             default: Some("false".to_string()),
             placeholder_type: PlaceholderType::Bool,
             options: None,
+            ..Default::default()
         },
     );
     let mut vars = BTreeMap::new();
@@ -352,6 +799,7 @@ fn resolve_bool_no_default_is_false() {
             default: None,
             placeholder_type: PlaceholderType::Bool,
             options: None,
+            ..Default::default()
         },
     );
     let mut vars = BTreeMap::new();
@@ -369,6 +817,7 @@ fn resolve_bool_define_override() {
             default: Some("false".to_string()),
             placeholder_type: PlaceholderType::Bool,
             options: None,
+            ..Default::default()
         },
     );
     let mut defines = BTreeMap::new();
@@ -393,6 +842,7 @@ fn resolve_select_non_interactive() {
                 "github".to_string(),
                 "gitlab".to_string(),
             ])),
+            ..Default::default()
         },
     );
     let mut vars = BTreeMap::new();
@@ -413,6 +863,7 @@ fn resolve_select_invalid_default_errors() {
                 "github".to_string(),
                 "gitlab".to_string(),
             ])),
+            ..Default::default()
         },
     );
     let err = test_resolve(&defs, &BTreeMap::new(), &mut BTreeMap::new(), Some(false)).unwrap_err();
@@ -430,6 +881,7 @@ fn resolve_select_empty_options_errors() {
             default: Some("github".to_string()),
             placeholder_type: PlaceholderType::Select,
             options: None,
+            ..Default::default()
         },
     );
     let err = test_resolve(&defs, &BTreeMap::new(), &mut BTreeMap::new(), Some(false)).unwrap_err();
@@ -449,6 +901,7 @@ fn resolve_select_define_override() {
                 "github".to_string(),
                 "gitlab".to_string(),
             ])),
+            ..Default::default()
         },
     );
     let mut defines = BTreeMap::new();
@@ -464,7 +917,7 @@ fn resolve_select_define_override() {
 fn jinja_bool_true_is_truthy() {
     let mut vars = BTreeMap::new();
     vars.insert("flag".to_string(), "true".to_string());
-    let env = build_jinja_env(Path::new("."), &vars).unwrap();
+    let env = build_jinja_env(Path::new("."), &vars, &BTreeMap::new()).unwrap();
     let result = env
         .render_str(
             "{% if flag %}yes{% else %}no{% endif %}",
@@ -478,7 +931,7 @@ fn jinja_bool_true_is_truthy() {
 fn jinja_bool_false_is_falsy() {
     let mut vars = BTreeMap::new();
     vars.insert("flag".to_string(), "false".to_string());
-    let env = build_jinja_env(Path::new("."), &vars).unwrap();
+    let env = build_jinja_env(Path::new("."), &vars, &BTreeMap::new()).unwrap();
     let result = env
         .render_str(
             "{% if flag %}yes{% else %}no{% endif %}",
@@ -508,7 +961,7 @@ fn rust_stable_version_returns_semver() {
 #[test]
 fn rust_stable_version_available_in_jinja() {
     let vars = BTreeMap::new();
-    let env = build_jinja_env(Path::new("."), &vars).unwrap();
+    let env = build_jinja_env(Path::new("."), &vars, &BTreeMap::new()).unwrap();
     let result = env
         .render_str("{{ rust_stable_version() }}", minijinja::context! {})
         .unwrap();
@@ -521,7 +974,7 @@ fn rust_stable_version_available_in_jinja() {
 #[test]
 fn pin_github_action_resolves_known_repo() {
     let vars = BTreeMap::new();
-    let env = build_jinja_env(Path::new("."), &vars).unwrap();
+    let env = build_jinja_env(Path::new("."), &vars, &BTreeMap::new()).unwrap();
     let result = env
         .render_str(
             r#"{{ pin_github_action("actions/checkout", "v4") }}"#,
@@ -548,7 +1001,7 @@ fn pin_github_action_resolves_known_repo() {
 #[test]
 fn pin_github_action_bad_repo_returns_error_marker() {
     let vars = BTreeMap::new();
-    let env = build_jinja_env(Path::new("."), &vars).unwrap();
+    let env = build_jinja_env(Path::new("."), &vars, &BTreeMap::new()).unwrap();
     let result = env
         .render_str(
             r#"{{ pin_github_action("nonexistent-owner-xyz/nonexistent-repo-xyz", "v999") }}"#,
@@ -564,7 +1017,7 @@ fn pin_github_action_bad_repo_returns_error_marker() {
 #[test]
 fn pin_github_action_subpath() {
     let vars = BTreeMap::new();
-    let env = build_jinja_env(Path::new("."), &vars).unwrap();
+    let env = build_jinja_env(Path::new("."), &vars, &BTreeMap::new()).unwrap();
     let result = env
         .render_str(
             r#"{{ pin_github_action("github/codeql-action", "v3", "upload-sarif") }}"#,
@@ -597,6 +1050,7 @@ fn preview_renders_template_in_memory() {
         defines: BTreeMap::new(),
         active_features: std::collections::BTreeSet::new(),
         interactive_override: None,
+        source: CrateSource::crates_io(),
     };
 
     let files = preview(opts).unwrap();
@@ -651,6 +1105,7 @@ fn preview_maps_underscore_cargo_toml_to_cargo_toml() {
         defines: BTreeMap::new(),
         active_features: std::collections::BTreeSet::new(),
         interactive_override: Some(false),
+        source: CrateSource::crates_io(),
     };
 
     let files = preview(opts).unwrap();
@@ -702,6 +1157,7 @@ fn preview_preserves_underscore_cargo_toml_under_templates_dir() {
         defines: BTreeMap::new(),
         active_features: std::collections::BTreeSet::new(),
         interactive_override: Some(false),
+        source: CrateSource::crates_io(),
     };
 
     let files = preview(opts).unwrap();
@@ -733,6 +1189,7 @@ fn preview_resolves_bp_managed_deps() {
         defines: BTreeMap::new(),
         active_features: std::collections::BTreeSet::new(),
         interactive_override: None,
+        source: CrateSource::crates_io(),
     };
 
     let files = preview(opts).unwrap();
@@ -799,6 +1256,7 @@ fn preview_warns_on_unresolvable_bp_managed_dep() {
         defines: BTreeMap::new(),
         active_features: std::collections::BTreeSet::new(),
         interactive_override: Some(false),
+        source: CrateSource::crates_io(),
     };
 
     // Should succeed (warn, not error) since the battery pack may not exist yet
@@ -885,6 +1343,7 @@ fn preview_normalizes_backslashes_and_applies_cargo_toml_rename_correctly() {
         defines: BTreeMap::new(),
         active_features: std::collections::BTreeSet::new(),
         interactive_override: Some(false),
+        source: CrateSource::crates_io(),
     };
 
     let files = preview(opts).unwrap();
@@ -978,6 +1437,7 @@ fn options_category_derives_members() {
         defines: BTreeMap::new(),
         active_features: BTreeSet::new(),
         interactive_override: Some(false),
+        source: CrateSource::crates_io(),
     };
     let resolved = resolve_option_sources(&opts, &config).unwrap();
     // `utils` contains the `logging` feature and the `heapless` dependency.
@@ -985,6 +1445,31 @@ fn options_category_derives_members() {
     assert!(resolved["util"].prefill.is_none());
 }
 
+// [verify format.templates.placeholder-types]
+#[test]
+fn options_category_multiselect_prefills_every_active_member() {
+    let mut config = category_placeholder_config("utils");
+    config
+        .placeholders
+        .get_mut("util")
+        .unwrap()
+        .placeholder_type = PlaceholderType::Multiselect;
+    let opts = RenderOpts {
+        crate_root: category_fixture(),
+        template_path: "templates/blinky".to_string(),
+        project_name: "p".to_string(),
+        defines: BTreeMap::new(),
+        active_features: BTreeSet::from(["logging".to_string(), "heapless".to_string()]),
+        interactive_override: Some(false),
+        source: CrateSource::crates_io(),
+    };
+    let resolved = resolve_option_sources(&opts, &config).unwrap();
+    assert_eq!(
+        resolved["util"].prefill.as_deref(),
+        Some("heapless,logging")
+    );
+}
+
 // [verify template.options-category-prefill-from-picker]
 #[test]
 fn options_category_prefills_from_active_features() {
@@ -996,12 +1481,13 @@ fn options_category_prefills_from_active_features() {
         defines: BTreeMap::new(),
         active_features: BTreeSet::from(["logging".to_string()]),
         interactive_override: Some(false),
+        source: CrateSource::crates_io(),
     };
     let resolved = resolve_option_sources(&opts, &config).unwrap();
     assert_eq!(resolved["util"].prefill.as_deref(), Some("logging"));
 
     // End to end: prepare_render should fill the variable without a default.
-    let vars = prepare_render(&opts, &config).unwrap();
+    let vars = prepare_render(&opts, &config, false).unwrap();
     assert_eq!(vars["util"], "logging");
 }
 
@@ -1018,6 +1504,7 @@ fn preview_applies_category_prefill_over_fallback() {
         defines: BTreeMap::new(),
         active_features: BTreeSet::from(["logging".to_string()]),
         interactive_override: Some(false),
+        source: CrateSource::crates_io(),
     };
     let files = preview(opts).unwrap();
     let main_rs = files
@@ -1047,6 +1534,7 @@ fn options_category_unknown_errors() {
         defines: BTreeMap::new(),
         active_features: BTreeSet::new(),
         interactive_override: Some(false),
+        source: CrateSource::crates_io(),
     };
     let err = resolve_option_sources(&opts, &config).unwrap_err();
     assert!(
@@ -1054,3 +1542,471 @@ fn options_category_unknown_errors() {
         "error should name the category: {err}"
     );
 }
+
+// -- Test matrix --
+
+/// Write a template dir whose `bp-template.toml` is `config`; returns the crate root.
+fn matrix_template(config: &str) -> tempfile::TempDir {
+    let tmp = tempfile::tempdir().unwrap();
+    let template = tmp.path().join("templates/default");
+    std::fs::create_dir_all(&template).unwrap();
+    std::fs::write(template.join("bp-template.toml"), config).unwrap();
+    tmp
+}
+
+// [verify format.templates.matrix]
+#[test]
+fn test_matrix_each_flips_bools_and_walks_select_options() {
+    let tmp = matrix_template(indoc! {r#"
+        [placeholders.ci_platform]
+        type = "select"
+        options = ["github", "gitlab", "none"]
+        default = "github"
+
+        [placeholders.fuzzing]
+        type = "bool"
+
+        [placeholders.audit]
+        type = "bool"
+        default = "true"
+
+        [placeholders.owner]
+        default = "OWNER"
+
+        [matrix]
+        each = true
+
+        [[matrix.include]]
+        ci_platform = "none"
+        fuzzing = "true"
+
+        [[matrix.include]]
+        fuzzing = "true"
+    "#});
+
+    let sets = test_matrix(tmp.path(), "templates/default").unwrap();
+    assert_data_eq!(
+        sets.to_debug(),
+        str![[r#"
+[
+    {},
+    {
+        "audit": "false",
+    },
+    {
+        "ci_platform": "gitlab",
+    },
+    {
+        "ci_platform": "none",
+    },
+    {
+        "fuzzing": "true",
+    },
+    {
+        "ci_platform": "none",
+        "fuzzing": "true",
+    },
+]
+
+"#]]
+    );
+}
+
+// [verify format.templates.matrix]
+#[test]
+fn test_matrix_each_toggles_multiselect_options() {
+    let tmp = matrix_template(indoc! {r#"
+        [placeholders.targets]
+        type = "multiselect"
+        options = ["linux", "macos", "windows"]
+        default = ["linux"]
+
+        [placeholders.jobs]
+        type = "int"
+        default = 2
+
+        [matrix]
+        each = true
+    "#});
+    let sets = test_matrix(tmp.path(), "templates/default").unwrap();
+    assert_data_eq!(
+        sets.to_debug(),
+        str![[r#"
+[
+    {},
+    {
+        "targets": "",
+    },
+    {
+        "targets": "linux,macos",
+    },
+    {
+        "targets": "linux,windows",
+    },
+]
+
+"#]]
+    );
+}
+
+// [verify format.templates.matrix]
+#[test]
+fn test_matrix_each_skips_sets_that_restate_the_defaults() {
+    let tmp = matrix_template(indoc! {r#"
+        [placeholders.ci_platform]
+        type = "select"
+        options = ["github", "gitlab"]
+
+        [placeholders.audit]
+        type = "bool"
+        default = "true"
+
+        [matrix]
+        each = true
+
+        [[matrix.include]]
+        audit = "true"
+    "#});
+    let sets = test_matrix(tmp.path(), "templates/default").unwrap();
+    assert_data_eq!(
+        sets.to_debug(),
+        str![[r#"
+[
+    {},
+    {
+        "audit": "false",
+    },
+    {
+        "ci_platform": "gitlab",
+    },
+]
+
+"#]]
+    );
+}
+
+// [verify format.templates.matrix]
+#[test]
+fn test_matrix_defaults_only_without_matrix_table() {
+    let tmp = matrix_template("[placeholders.fuzzing]\ntype = \"bool\"\n");
+    let sets = test_matrix(tmp.path(), "templates/default").unwrap();
+    assert_eq!(sets, vec![BTreeMap::new()]);
+}
+
+// [verify format.templates.matrix]
+#[test]
+fn test_matrix_include_unknown_placeholder_errors() {
+    let tmp = matrix_template("[[matrix.include]]\nfuzing = \"true\"\n");
+    let err = test_matrix(tmp.path(), "templates/default").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "[matrix] include references unknown placeholder 'fuzing'"
+    );
+}
+
+// -- Conditional files and directories --
+
+/// Render the `matrix_template` fixture with `defines`, returning the output paths.
+fn rendered_paths(tmp: &tempfile::TempDir, defines: &[(&str, &str)]) -> Vec<String> {
+    let opts = RenderOpts {
+        crate_root: tmp.path().to_path_buf(),
+        template_path: "templates/default".to_string(),
+        project_name: "p".to_string(),
+        defines: defines
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        active_features: BTreeSet::new(),
+        interactive_override: Some(false),
+        source: CrateSource::crates_io(),
+    };
+    preview(opts).unwrap().into_iter().map(|f| f.path).collect()
+}
+
+// [verify format.templates.conditional-ignore]
+#[test]
+fn conditional_ignore_drops_whole_directories() {
+    let tmp = matrix_template(indoc! {r#"
+        ignore = ["scratch", { paths = ["fuzz/**"], unless = "fuzzing" }]
+
+        [placeholders.fuzzing]
+        type = "bool"
+    "#});
+    let template = tmp.path().join("templates/default");
+    for path in [
+        "fuzz/Cargo.toml",
+        "fuzz/targets/a.rs",
+        "scratch/notes.txt",
+        "README.md",
+    ] {
+        let file = template.join(path);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(file, "x\n").unwrap();
+    }
+
+    assert_eq!(rendered_paths(&tmp, &[]), vec!["README.md"]);
+    assert_eq!(
+        rendered_paths(&tmp, &[("fuzzing", "true")]),
+        vec!["README.md", "fuzz/Cargo.toml", "fuzz/targets/a.rs"]
+    );
+}
+
+// [verify format.templates.conditional-ignore]
+#[test]
+fn conditional_ignore_tables_with_condition() {
+    let tmp = matrix_template(indoc! {r#"
+        [placeholders.ci_platform]
+        type = "select"
+        options = ["github", "none"]
+        default = "github"
+
+        [[ignore]]
+        paths = [".github"]
+        condition = 'ci_platform != "github"'
+    "#});
+    let workflow = tmp
+        .path()
+        .join("templates/default/.github/workflows/ci.yml");
+    std::fs::create_dir_all(workflow.parent().unwrap()).unwrap();
+    std::fs::write(&workflow, "on: push\n").unwrap();
+
+    assert_eq!(rendered_paths(&tmp, &[]), vec![".github/workflows/ci.yml"]);
+    assert!(rendered_paths(&tmp, &[("ci_platform", "none")]).is_empty());
+}
+
+// [verify format.templates.files-condition]
+// [verify format.templates.empty-files]
+#[test]
+fn files_include_condition_and_empty_files() {
+    let tmp = matrix_template(indoc! {r#"
+        [placeholders.license]
+        type = "bool"
+
+        [[files]]
+        src = "shared/LICENSE"
+        dest = "LICENSE"
+        condition = "license"
+    "#});
+    std::fs::create_dir_all(tmp.path().join("shared")).unwrap();
+    std::fs::write(tmp.path().join("shared/LICENSE"), "MIT\n").unwrap();
+    // An empty template file is kept; one that renders away is dropped.
+    let template = tmp.path().join("templates/default");
+    std::fs::write(template.join(".gitkeep"), "").unwrap();
+    std::fs::write(template.join("gone.txt"), "{% if license %}x{% endif %}").unwrap();
+
+    assert_eq!(rendered_paths(&tmp, &[]), vec![".gitkeep"]);
+    assert_eq!(
+        rendered_paths(&tmp, &[("license", "true")]),
+        vec![".gitkeep", "LICENSE", "gone.txt"]
+    );
+}
+
+// -- Binary and verbatim files --
+
+// [verify format.templates.binary]
+// [verify format.templates.copy]
+#[test]
+fn binary_and_copy_files_are_not_rendered() {
+    let tmp = matrix_template(indoc! {r#"
+        copy = ["fixtures/**"]
+    "#});
+    let template = tmp.path().join("templates/default");
+    let blob: &[u8] = b"\x7fELF\0\x01{{ not_a_placeholder }}\xff";
+    std::fs::write(template.join("bootloader.bin"), blob).unwrap();
+    std::fs::create_dir_all(template.join("fixtures")).unwrap();
+    std::fs::write(template.join("fixtures/raw.txt"), "{{ project_name }}\n").unwrap();
+    std::fs::write(template.join("name.txt"), "{{ project_name }}\n").unwrap();
+
+    let opts = RenderOpts {
+        crate_root: tmp.path().to_path_buf(),
+        template_path: "templates/default".to_string(),
+        project_name: "p".to_string(),
+        defines: BTreeMap::new(),
+        active_features: BTreeSet::new(),
+        interactive_override: Some(false),
+        source: CrateSource::crates_io(),
+    };
+    let files = preview(opts).unwrap();
+    let file = |path: &str| files.iter().find(|f| f.path == path).unwrap();
+
+    assert_eq!(file("bootloader.bin").data(), blob);
+    assert_eq!(
+        file("bootloader.bin").display_content(),
+        format!("<binary file, {} bytes>", blob.len())
+    );
+    assert_eq!(file("fixtures/raw.txt").data(), b"{{ project_name }}\n");
+    assert_eq!(file("name.txt").data(), b"p");
+}
+
+// -- Post-generate hooks --
+
+fn hook_opts(tmp: &tempfile::TempDir) -> RenderOpts {
+    RenderOpts {
+        crate_root: tmp.path().to_path_buf(),
+        template_path: "templates/default".to_string(),
+        project_name: "my-app".to_string(),
+        defines: BTreeMap::new(),
+        active_features: BTreeSet::new(),
+        interactive_override: Some(false),
+        source: CrateSource::crates_io(),
+    }
+}
+
+// [verify format.templates.hooks]
+#[test]
+fn hooks_render_arguments_and_respect_conditions() {
+    let tmp = matrix_template(indoc! {r#"
+        [placeholders.xtask]
+        type = "bool"
+
+        [[hooks]]
+        run = "cargo generate-lockfile"
+
+        [[hooks]]
+        run = ["git", "remote", "add", "origin", "https://example.com/{{ project_name }} repo"]
+        description = "Point at the remote"
+
+        [[hooks]]
+        run = "cargo xtask codegen"
+        condition = "xtask"
+    "#});
+    let hooks = template_hooks(&hook_opts(&tmp), &BTreeMap::new()).unwrap();
+    assert_eq!(
+        hooks,
+        vec![
+            Hook {
+                description: None,
+                args: vec!["cargo".into(), "generate-lockfile".into()],
+            },
+            Hook {
+                description: Some("Point at the remote".into()),
+                args: vec![
+                    "git".into(),
+                    "remote".into(),
+                    "add".into(),
+                    "origin".into(),
+                    "https://example.com/my-app repo".into(),
+                ],
+            },
+        ]
+    );
+    assert_eq!(
+        hooks[1].to_string(),
+        "git remote add origin https://example.com/my-app repo  # Point at the remote"
+    );
+
+    let placeholders = BTreeMap::from([("xtask".to_string(), "true".to_string())]);
+    let hooks = template_hooks(&hook_opts(&tmp), &placeholders).unwrap();
+    assert_eq!(hooks.len(), 3);
+    assert_eq!(hooks[2].args, ["cargo", "xtask", "codegen"]);
+}
+
+/// Generate the `matrix_template` fixture into `dest` with the given hook policy.
+fn generate_with_hooks(
+    tmp: &tempfile::TempDir,
+    dest: &Path,
+    hooks: Option<HookPolicy>,
+) -> Result<PathBuf> {
+    generate(GenerateOpts {
+        render: hook_opts(tmp),
+        destination: Some(dest.to_path_buf()),
+        git_init: false,
+        hooks,
+    })
+}
+
+// [verify cli.new.hooks]
+#[test]
+fn hooks_run_in_project_dir_only_when_allowed() {
+    let tmp = matrix_template(indoc! {r#"
+        [[hooks]]
+        run = "git init --quiet"
+    "#});
+    std::fs::write(tmp.path().join("templates/default/README.md"), "hi\n").unwrap();
+
+    for policy in [None, Some(HookPolicy::Skip)] {
+        let out = tempfile::tempdir().unwrap();
+        let project = generate_with_hooks(&tmp, out.path(), policy).unwrap();
+        assert!(project.join("README.md").exists());
+        assert!(!project.join(".git").exists(), "{policy:?} ran the hook");
+    }
+
+    let out = tempfile::tempdir().unwrap();
+    let project = generate_with_hooks(&tmp, out.path(), Some(HookPolicy::Allow)).unwrap();
+    assert!(project.join(".git").is_dir());
+}
+
+// [verify cli.new.hooks]
+#[test]
+fn failing_hook_fails_generation() {
+    let tmp = matrix_template(indoc! {r#"
+        [[hooks]]
+        run = "git not-a-git-command"
+    "#});
+    let out = tempfile::tempdir().unwrap();
+    let err = generate_with_hooks(&tmp, out.path(), Some(HookPolicy::Allow)).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("post-generate step `git not-a-git-command` failed"),
+        "{err}"
+    );
+}
+
+#[test]
+fn hook_policy_from_flags() {
+    assert_eq!(HookPolicy::from_flags(true, false), HookPolicy::Allow);
+    assert_eq!(HookPolicy::from_flags(true, true), HookPolicy::Allow);
+    assert_eq!(HookPolicy::from_flags(false, true), HookPolicy::Prompt);
+    assert_eq!(HookPolicy::from_flags(false, false), HookPolicy::Skip);
+}
+
+// -- Merge strategy overrides --
+
+// [verify format.templates.merge]
+#[test]
+fn merge_overrides_compile_globs() {
+    let tmp = matrix_template(indoc! {r#"
+        [merge]
+        ".github/CODEOWNERS" = "lines"
+        "docs/*.txt" = "markdown"
+    "#});
+    let overrides = merge_overrides(&hook_opts(&tmp)).unwrap();
+    let overrides: Vec<(&str, crate::merge::MergeStrategy)> = overrides
+        .iter()
+        .map(|(glob, strategy)| (glob.as_str(), *strategy))
+        .collect();
+    assert_eq!(
+        overrides,
+        [
+            (".github/CODEOWNERS", crate::merge::MergeStrategy::Lines),
+            ("docs/*.txt", crate::merge::MergeStrategy::Markdown),
+        ]
+    );
+
+    let tmp = matrix_template(indoc! {r#"
+        [merge]
+        "a.txt" = "xml"
+    "#});
+    let err = merge_overrides(&hook_opts(&tmp)).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("unknown variant `xml`"),
+        "{err:#}"
+    );
+}
+
+// [verify format.templates.merge]
+#[test]
+fn merge_overrides_keep_declaration_order() {
+    // Declared before the broader glob that sorts ahead of it, so it must win.
+    let tmp = matrix_template(indoc! {r#"
+        [merge]
+        "docs/notes.txt" = "lines"
+        "*/*.txt" = "plain"
+    "#});
+    let overrides = merge_overrides(&hook_opts(&tmp)).unwrap();
+    let first_match = overrides
+        .iter()
+        .find(|(glob, _)| glob.matches("docs/notes.txt"))
+        .map(|(_, strategy)| *strategy);
+    assert_eq!(first_match, Some(crate::merge::MergeStrategy::Lines));
+}
@@ -70,6 +70,271 @@ pub(crate) fn render_error(frame: &mut Frame, state: &ErrorScreen) {
     frame.render_widget(paragraph, center);
 }
 
+// [impl tui.main.sections]
+// [impl tui.main.no-project]
+pub(crate) fn render_menu(frame: &mut Frame, state: &MenuScreen) {
+    let area = frame.area();
+
+    let [header, main, footer] = Layout::vertical([
+        Constraint::Length(2),
+        Constraint::Fill(1),
+        Constraint::Length(1),
+    ])
+    .areas(area);
+
+    frame.render_widget(
+        Paragraph::new("Battery Packs")
+            .style(Style::default().bold())
+            .centered(),
+        header,
+    );
+
+    let items: Vec<ListItem> = MenuItem::ALL
+        .iter()
+        .map(|item| {
+            let (label, hint) = match item {
+                MenuItem::Installed if !state.in_project => {
+                    ("Installed battery packs", "no Rust project found")
+                }
+                MenuItem::Installed => (
+                    "Installed battery packs",
+                    "manage the crates and features in this project",
+                ),
+                MenuItem::Browse => ("Browse", "discover and add battery packs"),
+                MenuItem::NewProject => ("New project", "create a project from a template"),
+            };
+            let disabled = *item == MenuItem::Installed && !state.in_project;
+            let label_style = if disabled {
+                Style::default().fg(Color::DarkGray)
+            } else {
+                Style::default().fg(Color::Green).bold()
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{label:<26}"), label_style),
+                Span::styled(hint, Style::default().fg(Color::DarkGray)),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL))
+        .highlight_style(
+            Style::default()
+                .bg(Color::Blue)
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("> ");
+    let mut list_state = ListState::default();
+    list_state.select(Some(state.selected_index));
+    frame.render_stateful_widget(list, main, &mut list_state);
+
+    frame.render_widget(
+        Paragraph::new("↑↓/jk Navigate | Enter Select | q Quit")
+            .style(Style::default().white().on_dark_gray()),
+        footer,
+    );
+}
+
+// [impl tui.installed.list-packs]
+pub(crate) fn render_installed(frame: &mut Frame, state: &mut InstalledScreen) {
+    let area = frame.area();
+
+    let [header, main, footer] = Layout::vertical([
+        Constraint::Length(2),
+        Constraint::Fill(1),
+        Constraint::Length(1),
+    ])
+    .areas(area);
+
+    frame.render_widget(
+        Paragraph::new("Installed Battery Packs")
+            .style(Style::default().bold())
+            .centered(),
+        header,
+    );
+
+    if state.packs.is_empty() {
+        frame.render_widget(
+            Paragraph::new("No battery packs installed.")
+                .block(Block::default().borders(Borders::ALL))
+                .style(Style::default().fg(Color::DarkGray)),
+            main,
+        );
+    } else {
+        let items: Vec<ListItem> = state
+            .packs
+            .iter()
+            .map(|view| {
+                let status = &view.status;
+                let drift = match status.warnings.len() {
+                    0 => Span::styled("up to date", Style::default().fg(Color::DarkGray)),
+                    n => Span::styled(
+                        format!("⚠ {n} outdated"),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:<20}", status.short_name),
+                        Style::default().fg(Color::Green).bold(),
+                    ),
+                    Span::raw("  "),
+                    Span::styled(
+                        format!("{:<10}", status.version),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::raw("  "),
+                    drift,
+                ]))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL))
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Blue)
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(list, main, &mut state.list_state);
+    }
+
+    frame.render_widget(
+        Paragraph::new("↑↓/jk Navigate | Enter Manage | Esc Back | q Quit")
+            .style(Style::default().white().on_dark_gray()),
+        footer,
+    );
+}
+
+// [impl tui.installed.show-state]
+pub(crate) fn render_installed_pack(frame: &mut Frame, state: &InstalledPackScreen) {
+    let area = frame.area();
+    let view = &state.pack;
+    let spec = &view.pack.spec;
+
+    let [header, main, footer] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Fill(1),
+        Constraint::Length(1),
+    ])
+    .areas(area);
+
+    let mut header_spans = vec![
+        Span::styled(&spec.name, Style::default().fg(Color::Green).bold()),
+        Span::raw(" "),
+        Span::styled(&view.status.version, Style::default().fg(Color::DarkGray)),
+    ];
+    if state.is_modified() {
+        header_spans.push(Span::styled(
+            "  (modified)",
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    frame.render_widget(Paragraph::new(Line::from(header_spans)).centered(), header);
+
+    let mut lines: Vec<Line> = Vec::new();
+    let mut selected_line = None;
+    let mut row_index = 0;
+    let mut push_row = |lines: &mut Vec<Line<'_>>, spans: Vec<Span<'static>>| {
+        let selected = row_index == state.selected_index;
+        let mut line = Line::from(spans);
+        if selected {
+            selected_line = Some(lines.len());
+            line = line.style(Style::default().fg(Color::Black).bg(Color::Cyan));
+        }
+        lines.push(line);
+        row_index += 1;
+    };
+
+    for group in &state.groups {
+        match &group.feature {
+            Some(feature) => {
+                let mark = if state.features.contains(feature) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                push_row(
+                    &mut lines,
+                    vec![Span::styled(
+                        format!("{mark} ✦ {feature}"),
+                        Style::default().bold(),
+                    )],
+                );
+            }
+            None => lines.push(Line::styled("    Other crates", Style::default().bold())),
+        }
+        for crate_name in &group.crates {
+            let on = state.crates.contains(crate_name);
+            let mark = if on { "[x]" } else { "[ ]" };
+            let kind = state.kind_of(crate_name);
+            let kind_changed =
+                state.initial_dep_kinds.get(crate_name) != state.dep_kinds.get(crate_name);
+            let change = match (on, state.initial_crates.contains(crate_name)) {
+                (true, false) => "+",
+                (false, true) => "-",
+                (true, true) if kind_changed => "~",
+                _ => " ",
+            };
+            let crate_spec = &spec.crates[crate_name];
+            // [impl tui.installed.dep-kind]
+            let kind_style = if kind == crate_spec.dep_kind {
+                Style::default().fg(Color::DarkGray)
+            } else {
+                Style::default().fg(Color::Magenta)
+            };
+            let mut spans = vec![
+                Span::raw(format!("  {change} {mark} {crate_name:<20} ")),
+                Span::styled(format!("{kind:<6} "), kind_style),
+                Span::styled(
+                    format!("{:<8}", crate_spec.version),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ];
+            if let Some(version) = view.present.get(crate_name) {
+                spans.push(Span::raw(format!("  project: {version}")));
+            }
+            if let Some(warning) = view
+                .status
+                .warnings
+                .iter()
+                .find(|w| &w.crate_name == crate_name)
+            {
+                spans.push(Span::styled(
+                    format!("  ⚠ {} recommended", warning.recommended_version),
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+            push_row(&mut lines, spans);
+        }
+        lines.push(Line::from(""));
+    }
+
+    let visible_height = main.height.saturating_sub(2) as usize; // borders
+    let scroll_offset = selected_line
+        .map(|line| line.saturating_sub(visible_height.saturating_sub(1)))
+        .unwrap_or(0);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL))
+            .scroll((scroll_offset as u16, 0)),
+        main,
+    );
+
+    let footer_text = match &state.message {
+        Some(message) => message.clone(),
+        None => "↑↓/jk Navigate | Space Toggle | d Dep kind | Enter Apply | Esc Back | q Quit"
+            .to_string(),
+    };
+    frame.render_widget(
+        Paragraph::new(footer_text).style(Style::default().white().on_dark_gray()),
+        footer,
+    );
+}
+
 pub(crate) fn render_list(frame: &mut Frame, state: &mut ListScreen) {
     let area = frame.area();
 
@@ -451,7 +716,7 @@ pub(crate) fn highlight_preview(files: &[crate::template_engine::RenderedFile])
             .unwrap_or_else(|| ss.find_syntax_plain_text());
 
         let mut h = HighlightLines::new(syntax, theme);
-        for line in file.content.lines() {
+        for line in file.display_content().lines() {
             let spans: Vec<Span<'static>> = match h.highlight_line(line, &ss) {
                 Ok(ranges) => ranges
                     .into_iter()
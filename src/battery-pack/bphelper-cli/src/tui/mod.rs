@@ -7,15 +7,16 @@ pub(crate) use utils::*;
 #[cfg(test)]
 mod tests;
 
+use crate::commands::InstalledPackView;
 use crate::registry::{
     BatteryPackDetail, BatteryPackSummary, CrateSource, fetch_battery_pack_detail,
     fetch_battery_pack_list,
 };
 use anyhow::Result;
+use bphelper_manifest::DepKind;
 use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
 use ratatui::{Frame, text::Text, widgets::ListState};
-use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet};
 use std::rc::Rc;
 use std::time::Duration;
 
@@ -32,6 +33,12 @@ pub(crate) struct ShowOpts<'a> {
     pub defines: BTreeMap<String, String>,
 }
 
+/// Run the TUI starting from the main menu.
+pub(crate) fn run_menu(source: CrateSource) -> Result<()> {
+    let app = App::new_menu(source);
+    app.run()
+}
+
 /// Run the TUI starting from the list view
 pub(crate) fn run_list(source: CrateSource, filter: Option<String>) -> Result<()> {
     let app = App::new_list(source, filter);
@@ -80,6 +87,7 @@ fn run_preview(opts: ShowOpts<'_>) -> Result<()> {
         pending_action: None,
         in_project: false,
         installed_bp_names: Vec::new(),
+        has_menu: false,
     };
     app.run()
 }
@@ -97,6 +105,9 @@ struct App {
     pending_action: Option<PendingAction>,
     in_project: bool,
     installed_bp_names: Vec<String>,
+    /// Whether the TUI started at the main menu, so Esc on a top-level
+    /// screen returns there instead of quitting.
+    has_menu: bool,
 }
 
 enum Screen {
@@ -106,6 +117,9 @@ enum Screen {
     Loading(LoadingState),
     /// [impl tui.network.error]
     Error(ErrorScreen),
+    Menu(MenuScreen),
+    Installed(InstalledScreen),
+    InstalledPack(InstalledPackScreen),
     List(ListScreen),
     Detail(DetailScreen),
     NewProjectForm(FormScreen),
@@ -123,6 +137,7 @@ pub(crate) struct LoadingState {
 }
 
 pub(crate) enum LoadingTarget {
+    Installed,
     List {
         filter: Option<String>,
     },
@@ -133,6 +148,318 @@ pub(crate) enum LoadingTarget {
     },
 }
 
+/// An entry of the main menu.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum MenuItem {
+    Installed,
+    Browse,
+    NewProject,
+}
+
+impl MenuItem {
+    const ALL: [MenuItem; 3] = [MenuItem::Installed, MenuItem::Browse, MenuItem::NewProject];
+}
+
+pub(crate) struct MenuScreen {
+    /// Index into `MenuItem::ALL`
+    selected_index: usize,
+    in_project: bool,
+}
+
+impl MenuScreen {
+    fn selected_item(&self) -> MenuItem {
+        MenuItem::ALL[self.selected_index]
+    }
+}
+
+pub(crate) struct InstalledScreen {
+    packs: Vec<Rc<InstalledPackView>>,
+    list_state: ListState,
+}
+
+/// A selectable row of the installed-pack screen.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum PackRow {
+    /// A feature, toggled as a group.
+    Feature(String),
+    /// A curated crate.
+    Crate(String),
+}
+
+/// Crates shown under one heading: a feature's members, or (with no
+/// feature) the crates that belong to none.
+#[derive(Debug)]
+pub(crate) struct CrateGroup {
+    feature: Option<String>,
+    crates: Vec<String>,
+}
+
+/// Toggles the curated crates and features of one installed pack.
+///
+/// Crates are tracked by name, so a crate listed under several features is
+/// on or off everywhere at once. Nothing is written until the selection is
+/// applied.
+pub(crate) struct InstalledPackScreen {
+    pack: Rc<InstalledPackView>,
+    groups: Vec<CrateGroup>,
+    /// Index into rows()
+    selected_index: usize,
+    /// Features switched on.
+    features: BTreeSet<String>,
+    /// Crates switched on.
+    crates: BTreeSet<String>,
+    /// Crates in the project when the screen opened.
+    initial_crates: BTreeSet<String>,
+    initial_features: BTreeSet<String>,
+    /// Crates given a different dependency kind than the pack's.
+    dep_kinds: BTreeMap<String, DepKind>,
+    initial_dep_kinds: BTreeMap<String, DepKind>,
+    /// Why the last toggle was refused, shown until the next key.
+    message: Option<String>,
+    /// The installed list to return to, and the index to restore there.
+    packs: Vec<Rc<InstalledPackView>>,
+    list_index: usize,
+}
+
+impl InstalledPackScreen {
+    // [impl tui.installed.list-crates]
+    // [impl tui.installed.hidden]
+    fn new(packs: Vec<Rc<InstalledPackView>>, list_index: usize) -> Self {
+        let pack = Rc::clone(&packs[list_index]);
+        let spec = &pack.pack.spec;
+        let visible = |name: &str| spec.crates.contains_key(name) && !spec.is_hidden(name);
+
+        // `default` first, then the other features in name order.
+        let mut feature_names: Vec<&String> = spec.features.keys().collect();
+        feature_names.sort_by_key(|name| name.as_str() != "default");
+        let mut groups = Vec::new();
+        let mut grouped = BTreeSet::new();
+        for name in feature_names {
+            let mut crates: Vec<String> = Vec::new();
+            for fref in &spec.features[name] {
+                let dep = fref.dep_name();
+                if visible(dep) && !crates.iter().any(|c| c == dep) {
+                    crates.push(dep.to_string());
+                }
+            }
+            if crates.is_empty() {
+                continue;
+            }
+            grouped.extend(crates.iter().cloned());
+            groups.push(CrateGroup {
+                feature: Some(name.clone()),
+                crates,
+            });
+        }
+        let ungrouped: Vec<String> = spec
+            .crates
+            .keys()
+            .filter(|name| visible(name) && !grouped.contains(*name))
+            .cloned()
+            .collect();
+        if !ungrouped.is_empty() {
+            groups.push(CrateGroup {
+                feature: None,
+                crates: ungrouped,
+            });
+        }
+
+        let crates: BTreeSet<String> = pack
+            .present
+            .keys()
+            .filter(|name| visible(name))
+            .cloned()
+            .collect();
+        let mut screen = Self {
+            groups,
+            selected_index: 0,
+            features: BTreeSet::new(),
+            crates: crates.clone(),
+            initial_crates: crates,
+            initial_features: BTreeSet::new(),
+            dep_kinds: pack.dep_kinds.clone(),
+            initial_dep_kinds: pack.dep_kinds.clone(),
+            message: None,
+            packs,
+            list_index,
+            pack,
+        };
+        // A recorded feature counts as on only while all its crates are there.
+        let active: Vec<String> = screen
+            .groups
+            .iter()
+            .filter_map(|g| g.feature.clone())
+            .filter(|name| match &screen.pack.pack.active_features {
+                bphelper_manifest::ActiveFeatures::All => true,
+                bphelper_manifest::ActiveFeatures::Subset(set) => set.contains(name),
+            })
+            .filter(|name| {
+                screen
+                    .feature_members(name)
+                    .iter()
+                    .all(|c| screen.crates.contains(c))
+            })
+            .collect();
+        screen.features.extend(active);
+        screen.initial_features = screen.features.clone();
+        screen
+    }
+
+    /// All selectable rows, in display order.
+    fn rows(&self) -> impl Iterator<Item = PackRow> + '_ {
+        self.groups.iter().flat_map(|group| {
+            group
+                .feature
+                .iter()
+                .cloned()
+                .map(PackRow::Feature)
+                .chain(group.crates.iter().cloned().map(PackRow::Crate))
+        })
+    }
+
+    fn row_count(&self) -> usize {
+        self.groups
+            .iter()
+            .map(|g| g.crates.len() + usize::from(g.feature.is_some()))
+            .sum()
+    }
+
+    fn selected_row(&self) -> Option<PackRow> {
+        self.rows().nth(self.selected_index)
+    }
+
+    fn select_next(&mut self) {
+        let count = self.row_count();
+        wrapping_nav(&mut self.selected_index, count, true);
+    }
+
+    fn select_prev(&mut self) {
+        let count = self.row_count();
+        wrapping_nav(&mut self.selected_index, count, false);
+    }
+
+    /// The visible crates listed under `feature`.
+    fn feature_members(&self, feature: &str) -> &[String] {
+        self.groups
+            .iter()
+            .find(|g| g.feature.as_deref() == Some(feature))
+            .map(|g| g.crates.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// An enabled feature other than `except` that lists `crate_name`.
+    fn required_by(&self, crate_name: &str, except: Option<&str>) -> Option<&String> {
+        self.features.iter().find(|f| {
+            Some(f.as_str()) != except && self.feature_members(f).iter().any(|c| c == crate_name)
+        })
+    }
+
+    fn toggle_selected(&mut self) {
+        self.message = None;
+        match self.selected_row() {
+            Some(PackRow::Feature(name)) => self.toggle_feature(&name),
+            Some(PackRow::Crate(name)) => self.toggle_crate(&name),
+            None => {}
+        }
+    }
+
+    // [impl tui.installed.features]
+    fn toggle_feature(&mut self, name: &str) {
+        if self.features.contains(name) {
+            self.disable_feature(name);
+        } else {
+            self.deselect_siblings(name);
+            self.features.insert(name.to_string());
+            let members = self.feature_members(name).to_vec();
+            self.crates.extend(members);
+        }
+    }
+
+    fn disable_feature(&mut self, name: &str) {
+        self.features.remove(name);
+        for member in self.feature_members(name).to_vec() {
+            if self.required_by(&member, None).is_none() {
+                self.crates.remove(&member);
+            }
+        }
+    }
+
+    // [impl tui.installed.toggle-crate]
+    fn toggle_crate(&mut self, name: &str) {
+        if !self.crates.contains(name) {
+            self.deselect_siblings(name);
+            self.crates.insert(name.to_string());
+        } else if let Some(feature) = self.required_by(name, None) {
+            self.message = Some(format!(
+                "{name} is required by feature '{feature}'; turn the feature off first"
+            ));
+        } else {
+            self.crates.remove(name);
+        }
+    }
+
+    /// Switch off the features and crates that share an `at-most-one`
+    /// category with `item`.
+    fn deselect_siblings(&mut self, item: &str) {
+        let spec = &self.pack.pack.spec;
+        let features: Vec<String> = self
+            .features
+            .iter()
+            .filter(|f| crate::commands::exclusive_siblings(spec, item, f))
+            .cloned()
+            .collect();
+        let crates: Vec<String> = self
+            .crates
+            .iter()
+            .filter(|c| crate::commands::exclusive_siblings(spec, item, c))
+            .cloned()
+            .collect();
+        for feature in features {
+            self.disable_feature(&feature);
+        }
+        for crate_name in crates {
+            if self.required_by(&crate_name, None).is_none() {
+                self.crates.remove(&crate_name);
+            }
+        }
+    }
+
+    /// The dependency kind `crate_name` is (or will be) added as.
+    fn kind_of(&self, crate_name: &str) -> DepKind {
+        self.dep_kinds
+            .get(crate_name)
+            .or_else(|| Some(&self.pack.pack.spec.crates.get(crate_name)?.dep_kind))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Move the crate under the cursor to the next dependency kind.
+    // [impl tui.installed.dep-kind]
+    fn cycle_selected_kind(&mut self) {
+        self.message = None;
+        match self.selected_row() {
+            Some(PackRow::Crate(name)) => {
+                let kind = crate::manifest::next_dep_kind(self.kind_of(&name));
+                if self.pack.pack.spec.crates[&name].dep_kind == kind {
+                    self.dep_kinds.remove(&name);
+                } else {
+                    self.dep_kinds.insert(name, kind);
+                }
+            }
+            Some(PackRow::Feature(_)) => {
+                self.message = Some("Select a crate to change its dependency kind".to_string());
+            }
+            None => {}
+        }
+    }
+
+    fn is_modified(&self) -> bool {
+        self.crates != self.initial_crates
+            || self.features != self.initial_features
+            || self.dep_kinds != self.initial_dep_kinds
+    }
+}
+
 pub(crate) struct ListScreen {
     items: Vec<BatteryPackSummary>,
     list_state: ListState,
@@ -292,9 +619,14 @@ enum PendingAction {
     UseTemplate {
         battery_pack: String,
         template: String,
-        source: Option<PathBuf>,
         pack_path: Option<String>,
     },
+    ApplyInstalled {
+        pack: Rc<InstalledPackView>,
+        features: BTreeSet<String>,
+        crates: BTreeSet<String>,
+        dep_kinds: BTreeMap<String, DepKind>,
+    },
 }
 
 // ============================================================================
@@ -302,6 +634,24 @@ enum PendingAction {
 // ============================================================================
 
 impl App {
+    // [impl tui.main.context-detection]
+    fn new_menu(source: CrateSource) -> Self {
+        let (in_project, installed_bp_names) = detect_project_state();
+        Self {
+            source,
+            pack_path: None,
+            screen: Screen::Menu(MenuScreen {
+                selected_index: if in_project { 0 } else { 1 },
+                in_project,
+            }),
+            should_quit: false,
+            pending_action: None,
+            in_project,
+            installed_bp_names,
+            has_menu: true,
+        }
+    }
+
     fn new_list(source: CrateSource, filter: Option<String>) -> Self {
         let (in_project, installed_bp_names) = detect_project_state();
         Self {
@@ -315,6 +665,7 @@ impl App {
             pending_action: None,
             in_project,
             installed_bp_names,
+            has_menu: false,
         }
     }
 
@@ -335,6 +686,7 @@ impl App {
             pending_action: None,
             in_project,
             installed_bp_names,
+            has_menu: false,
         }
     }
 
@@ -407,6 +759,29 @@ impl App {
         };
 
         match state.target {
+            LoadingTarget::Installed => {
+                let result = std::env::current_dir()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|dir| crate::commands::load_installed_pack_views(&dir, &self.source));
+                match result {
+                    Ok(packs) => {
+                        let mut list_state = ListState::default();
+                        if !packs.is_empty() {
+                            list_state.select(Some(0));
+                        }
+                        self.screen = Screen::Installed(InstalledScreen {
+                            packs: packs.into_iter().map(Rc::new).collect(),
+                            list_state,
+                        });
+                    }
+                    Err(e) => {
+                        self.screen = Screen::Error(ErrorScreen {
+                            message: format!("{e:#}"),
+                            retry_target: LoadingTarget::Installed,
+                        });
+                    }
+                }
+            }
             LoadingTarget::List { filter } => {
                 match fetch_battery_pack_list(&self.source, filter.as_deref()) {
                     Ok(items) => {
@@ -482,7 +857,9 @@ impl App {
             }
             PendingAction::AddToProject { battery_pack } => {
                 let status = std::process::Command::new("cargo")
-                    .args(["bp", "add", battery_pack])
+                    .arg("bp")
+                    .args(self.source.cli_args())
+                    .args(["add", battery_pack])
                     .status()?;
 
                 if status.success() {
@@ -500,7 +877,8 @@ impl App {
                 name,
             } => {
                 let mut cmd = std::process::Command::new("cargo");
-                cmd.args(["bp", "new", battery_pack, "-n", name]);
+                cmd.arg("bp").args(self.source.cli_args());
+                cmd.args(["new", battery_pack, "-n", name]);
                 if let Some(tmpl) = template {
                     cmd.args(["-t", tmpl]);
                 }
@@ -517,14 +895,10 @@ impl App {
             PendingAction::UseTemplate {
                 battery_pack,
                 template,
-                source,
                 pack_path,
             } => {
                 let mut cmd = std::process::Command::new("cargo");
-                cmd.arg("bp");
-                if let Some(path) = source {
-                    cmd.args(["--crate-source", &path.to_string_lossy()]);
-                }
+                cmd.arg("bp").args(self.source.cli_args());
                 cmd.args(["add", battery_pack, "-t", template]);
                 if let Some(path) = pack_path {
                     cmd.args(["--path", path]);
@@ -539,6 +913,33 @@ impl App {
                     Ok(false)
                 }
             }
+            PendingAction::ApplyInstalled {
+                pack,
+                features,
+                crates,
+                dep_kinds,
+            } => {
+                let result = std::env::current_dir()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|dir| {
+                        crate::commands::apply_installed_selection(
+                            &dir,
+                            &self.source,
+                            &pack.pack.spec,
+                            features,
+                            crates,
+                            dep_kinds,
+                        )
+                    });
+                match result {
+                    Ok(()) => Ok(true),
+                    Err(e) => {
+                        println!("error: {e:#}");
+                        wait_for_enter();
+                        Ok(false)
+                    }
+                }
+            }
         }
     }
 
@@ -547,6 +948,19 @@ impl App {
         enum Action {
             None,
             Quit,
+            MenuNext,
+            MenuPrev,
+            MenuSelect(MenuItem),
+            BackToMenu(MenuItem),
+            InstalledUp,
+            InstalledDown,
+            InstalledOpen(usize),
+            PackNext,
+            PackPrev,
+            PackToggle,
+            PackCycleKind,
+            PackApply,
+            PackBack,
             ListSelect(usize),
             ListUp,
             ListDown,
@@ -591,6 +1005,38 @@ impl App {
                 self.handle_error_key(key);
                 return;
             }
+            Screen::Menu(state) => match key {
+                KeyCode::Tab | KeyCode::Down | KeyCode::Char('j') => Action::MenuNext,
+                KeyCode::BackTab | KeyCode::Up | KeyCode::Char('k') => Action::MenuPrev,
+                KeyCode::Enter => match state.selected_item() {
+                    // [impl tui.main.no-project]
+                    MenuItem::Installed if !state.in_project => Action::None,
+                    item => Action::MenuSelect(item),
+                },
+                KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+                _ => Action::None,
+            },
+            Screen::Installed(state) => match key {
+                KeyCode::Up | KeyCode::Char('k') => Action::InstalledUp,
+                KeyCode::Down | KeyCode::Char('j') => Action::InstalledDown,
+                KeyCode::Enter => match state.list_state.selected() {
+                    Some(selected) => Action::InstalledOpen(selected),
+                    None => Action::None,
+                },
+                KeyCode::Esc => Action::BackToMenu(MenuItem::Installed),
+                KeyCode::Char('q') => Action::Quit,
+                _ => Action::None,
+            },
+            Screen::InstalledPack(_) => match key {
+                KeyCode::Tab | KeyCode::Down | KeyCode::Char('j') => Action::PackNext,
+                KeyCode::BackTab | KeyCode::Up | KeyCode::Char('k') => Action::PackPrev,
+                KeyCode::Char(' ') => Action::PackToggle,
+                KeyCode::Char('d') => Action::PackCycleKind,
+                KeyCode::Enter => Action::PackApply,
+                KeyCode::Esc => Action::PackBack,
+                KeyCode::Char('q') => Action::Quit,
+                _ => Action::None,
+            },
             Screen::List(state) => match key {
                 KeyCode::Up | KeyCode::Char('k') => Action::ListUp,
                 KeyCode::Down | KeyCode::Char('j') => Action::ListDown,
@@ -601,6 +1047,7 @@ impl App {
                         Action::None
                     }
                 }
+                KeyCode::Esc if self.has_menu => Action::BackToMenu(MenuItem::Browse),
                 KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
                 _ => Action::None,
             },
@@ -769,6 +1216,100 @@ impl App {
         match action {
             Action::None => {}
             Action::Quit => self.should_quit = true,
+            Action::MenuNext => {
+                if let Screen::Menu(state) = &mut self.screen {
+                    wrapping_nav(&mut state.selected_index, MenuItem::ALL.len(), true);
+                }
+            }
+            Action::MenuPrev => {
+                if let Screen::Menu(state) = &mut self.screen {
+                    wrapping_nav(&mut state.selected_index, MenuItem::ALL.len(), false);
+                }
+            }
+            Action::MenuSelect(item) => {
+                self.screen = Screen::Loading(match item {
+                    MenuItem::Installed => LoadingState {
+                        message: "Loading installed battery packs...".to_string(),
+                        target: LoadingTarget::Installed,
+                    },
+                    // New projects start from a pack's templates, reached
+                    // through its detail screen.
+                    MenuItem::Browse | MenuItem::NewProject => LoadingState {
+                        message: "Loading battery packs...".to_string(),
+                        target: LoadingTarget::List { filter: None },
+                    },
+                });
+            }
+            Action::BackToMenu(item) => {
+                self.screen = Screen::Menu(MenuScreen {
+                    selected_index: MenuItem::ALL.iter().position(|i| *i == item).unwrap_or(0),
+                    in_project: self.in_project,
+                });
+            }
+            Action::InstalledUp => {
+                if let Screen::Installed(state) = &mut self.screen {
+                    list_nav(&mut state.list_state, state.packs.len(), false);
+                }
+            }
+            Action::InstalledDown => {
+                if let Screen::Installed(state) = &mut self.screen {
+                    list_nav(&mut state.list_state, state.packs.len(), true);
+                }
+            }
+            Action::InstalledOpen(selected) => {
+                if let Screen::Installed(state) = &self.screen
+                    && selected < state.packs.len()
+                {
+                    let packs = state.packs.clone();
+                    self.screen = Screen::InstalledPack(InstalledPackScreen::new(packs, selected));
+                }
+            }
+            Action::PackNext => {
+                if let Screen::InstalledPack(state) = &mut self.screen {
+                    state.message = None;
+                    state.select_next();
+                }
+            }
+            Action::PackPrev => {
+                if let Screen::InstalledPack(state) = &mut self.screen {
+                    state.message = None;
+                    state.select_prev();
+                }
+            }
+            Action::PackToggle => {
+                if let Screen::InstalledPack(state) = &mut self.screen {
+                    state.toggle_selected();
+                }
+            }
+            Action::PackCycleKind => {
+                if let Screen::InstalledPack(state) = &mut self.screen {
+                    state.cycle_selected_kind();
+                }
+            }
+            Action::PackApply => {
+                if let Screen::InstalledPack(state) = &mut self.screen {
+                    if state.is_modified() {
+                        self.pending_action = Some(PendingAction::ApplyInstalled {
+                            pack: Rc::clone(&state.pack),
+                            features: state.features.clone(),
+                            crates: state.crates.clone(),
+                            dep_kinds: state.dep_kinds.clone(),
+                        });
+                    } else {
+                        state.message = Some("Nothing to apply".to_string());
+                    }
+                }
+            }
+            Action::PackBack => {
+                if let Screen::InstalledPack(state) = &mut self.screen {
+                    let mut list_state = ListState::default();
+                    list_state.select(Some(state.list_index));
+                    self.screen = Screen::Installed(InstalledScreen {
+                        packs: std::mem::take(&mut state.packs),
+                        list_state,
+                    });
+                }
+            }
             Action::ListUp => {
                 if let Screen::List(state) = &mut self.screen {
                     list_nav(&mut state.list_state, state.items.len(), false);
@@ -841,14 +1382,9 @@ impl App {
                 });
             }
             Action::DetailUseTemplate(detail, template, selected_index, came_from_list) => {
-                let source_path = match &self.source {
-                    CrateSource::Local(p) => Some(p.clone()),
-                    CrateSource::Registry => None,
-                };
                 self.pending_action = Some(PendingAction::UseTemplate {
                     battery_pack: detail.short_name.clone(),
                     template,
-                    source: source_path,
                     pack_path: self.pack_path.clone(),
                 });
                 self.screen = Screen::Detail(DetailScreen {
@@ -979,6 +1515,7 @@ impl App {
                             defines: BTreeMap::new(),
                             active_features: std::collections::BTreeSet::new(),
                             interactive_override: None,
+                            source: self.source.clone(),
                         };
                         match crate::template_engine::preview(opts) {
                             Ok(files) => highlight_preview(&files),
@@ -1058,6 +1595,9 @@ impl App {
             Screen::Empty => {}
             Screen::Loading(state) => render_loading(frame, state),
             Screen::Error(state) => render_error(frame, state),
+            Screen::Menu(state) => render_menu(frame, state),
+            Screen::Installed(state) => render_installed(frame, state),
+            Screen::InstalledPack(state) => render_installed_pack(frame, state),
             Screen::List(state) => render_list(frame, state),
             Screen::Detail(state) => render_detail(frame, state),
             Screen::NewProjectForm(state) => render_form(frame, state),
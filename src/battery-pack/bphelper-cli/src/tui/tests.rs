@@ -104,13 +104,14 @@ fn detail_selected_item_returns_correct_item() {
 /// Helper: create an App with a given screen (bypasses loading).
 fn make_app(screen: Screen) -> App {
     App {
-        source: CrateSource::Registry,
+        source: CrateSource::crates_io(),
         pack_path: None,
         screen,
         should_quit: false,
         pending_action: None,
         in_project: true,
         installed_bp_names: Vec::new(),
+        has_menu: false,
     }
 }
 
@@ -590,3 +591,361 @@ fn preview_standalone_renders_header_and_content() {
         output
     );
 }
+
+// --- Main menu and installed packs ---
+
+fn fixture_spec(name: &str) -> bphelper_manifest::BatteryPackSpec {
+    let manifest = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../../tests/fixtures")
+        .join(name)
+        .join("Cargo.toml");
+    bphelper_manifest::parse_battery_pack_from_path(&manifest).unwrap()
+}
+
+/// An installed pack whose project has `present` (crate, version) deps and
+/// `features` recorded as active.
+fn make_installed(
+    fixture: &str,
+    present: &[(&str, &str)],
+    features: &[&str],
+) -> Rc<InstalledPackView> {
+    let spec = fixture_spec(fixture);
+    let status = cargo_bp_script::InstalledPackStatus::new(
+        crate::registry::short_name(&spec.name),
+        &spec.name,
+        &spec.version,
+    );
+    Rc::new(InstalledPackView {
+        pack: crate::registry::InstalledPack {
+            short_name: crate::registry::short_name(&spec.name).to_string(),
+            version: spec.version.clone(),
+            active_features: bphelper_manifest::ActiveFeatures::Subset(
+                features.iter().map(|f| f.to_string()).collect(),
+            ),
+            spec,
+        },
+        status,
+        present: present
+            .iter()
+            .map(|(name, version)| (name.to_string(), version.to_string()))
+            .collect(),
+        dep_kinds: BTreeMap::new(),
+    })
+}
+
+fn fancy_screen() -> InstalledPackScreen {
+    InstalledPackScreen::new(
+        vec![make_installed(
+            "fancy-battery-pack",
+            &[("clap", "4"), ("dialoguer", "0.11"), ("serde", "1")],
+            &["default"],
+        )],
+        0,
+    )
+}
+
+fn names(set: &BTreeSet<String>) -> Vec<&str> {
+    set.iter().map(String::as_str).collect()
+}
+
+fn menu_app(in_project: bool) -> App {
+    let mut app = make_app(Screen::Menu(MenuScreen {
+        selected_index: 0,
+        in_project,
+    }));
+    app.in_project = in_project;
+    app.has_menu = true;
+    app
+}
+
+/// [verify tui.main.sections]
+/// [verify tui.main.no-project]
+#[test]
+fn menu_lists_sections_and_flags_missing_project() {
+    let output = render_app_to_string(&mut menu_app(true), 80, 8);
+    for section in ["Installed battery packs", "Browse", "New project"] {
+        assert!(output.contains(section), "missing {section}:\n{output}");
+    }
+    assert!(!output.contains("no Rust project found"), "{output}");
+
+    let output = render_app_to_string(&mut menu_app(false), 80, 8);
+    assert!(output.contains("no Rust project found"), "{output}");
+}
+
+/// [verify tui.main.no-project]
+#[test]
+fn menu_installed_needs_a_project() {
+    let mut app = menu_app(false);
+    app.handle_key(KeyCode::Enter);
+    assert!(matches!(app.screen, Screen::Menu(_)));
+
+    // Browse and New project still work.
+    app.handle_key(KeyCode::Down);
+    app.handle_key(KeyCode::Enter);
+    assert!(matches!(
+        app.screen,
+        Screen::Loading(LoadingState {
+            target: LoadingTarget::List { .. },
+            ..
+        })
+    ));
+}
+
+/// [verify tui.nav.keyboard]
+#[test]
+fn menu_opens_installed_and_esc_returns() {
+    let mut app = menu_app(true);
+    app.handle_key(KeyCode::Enter);
+    assert!(matches!(
+        app.screen,
+        Screen::Loading(LoadingState {
+            target: LoadingTarget::Installed,
+            ..
+        })
+    ));
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(0));
+    app.screen = Screen::Installed(InstalledScreen {
+        packs: vec![make_installed("fancy-battery-pack", &[], &[])],
+        list_state,
+    });
+    app.handle_key(KeyCode::Enter);
+    assert!(matches!(app.screen, Screen::InstalledPack(_)));
+    app.handle_key(KeyCode::Esc);
+    assert!(matches!(app.screen, Screen::Installed(_)));
+    app.handle_key(KeyCode::Esc);
+    assert!(matches!(app.screen, Screen::Menu(_)));
+    assert!(!app.should_quit);
+}
+
+/// [verify tui.nav.keyboard]
+#[test]
+fn list_esc_returns_to_menu_when_started_there() {
+    let mut app = menu_app(true);
+    app.screen = Screen::List(ListScreen {
+        items: Vec::new(),
+        list_state: ListState::default(),
+        filter: None,
+    });
+    app.handle_key(KeyCode::Esc);
+    assert!(matches!(app.screen, Screen::Menu(_)));
+    assert!(!app.should_quit);
+}
+
+/// [verify tui.installed.list-packs]
+#[test]
+fn installed_list_shows_versions_and_drift() {
+    let mut outdated = make_installed("fancy-battery-pack", &[("clap", "3")], &["default"]);
+    Rc::get_mut(&mut outdated).unwrap().status = outdated
+        .status
+        .clone()
+        .with_warning(cargo_bp_script::DependencyWarning::new("clap", "3", "4"));
+    let mut list_state = ListState::default();
+    list_state.select(Some(0));
+    let mut app = make_app(Screen::Installed(InstalledScreen {
+        packs: vec![
+            outdated,
+            make_installed("category-battery-pack", &[], &["default"]),
+        ],
+        list_state,
+    }));
+
+    let output = render_app_to_string(&mut app, 60, 8);
+    assert_data_eq!(output, snapbox::file![_]);
+}
+
+/// [verify tui.installed.list-crates]
+/// [verify tui.installed.hidden]
+#[test]
+fn installed_pack_groups_visible_crates_by_feature() {
+    let screen = fancy_screen();
+    let groups: Vec<_> = screen
+        .groups
+        .iter()
+        .map(|g| (g.feature.as_deref(), g.crates.clone()))
+        .collect();
+    assert_eq!(
+        groups,
+        [
+            (Some("default"), vec!["clap".into(), "dialoguer".into()]),
+            (
+                Some("fancy"),
+                vec![
+                    "clap".into(),
+                    "console".into(),
+                    "dialoguer".into(),
+                    "indicatif".into()
+                ]
+            ),
+            (
+                Some("indicators"),
+                vec!["console".into(), "indicatif".into()]
+            ),
+            (None, vec!["assert_cmd".into(), "predicates".into()]),
+        ]
+    );
+    // Hidden `serde` is in the project but not shown or managed.
+    assert_eq!(names(&screen.crates), ["clap", "dialoguer"]);
+    assert_eq!(names(&screen.features), ["default"]);
+}
+
+/// [verify tui.installed.toggle-crate]
+#[test]
+fn installed_pack_toggles_crates() {
+    let mut screen = fancy_screen();
+
+    screen.toggle_crate("assert_cmd");
+    assert!(screen.crates.contains("assert_cmd"));
+    screen.toggle_crate("assert_cmd");
+    assert!(!screen.crates.contains("assert_cmd"));
+
+    // `clap` stays while the enabled `default` feature needs it.
+    screen.toggle_crate("clap");
+    assert!(screen.crates.contains("clap"));
+    assert_eq!(
+        screen.message.as_deref(),
+        Some("clap is required by feature 'default'; turn the feature off first")
+    );
+    assert!(!screen.is_modified());
+}
+
+/// [verify tui.installed.features]
+#[test]
+fn installed_pack_toggles_features_as_groups() {
+    let mut screen = fancy_screen();
+
+    screen.toggle_feature("indicators");
+    assert_eq!(
+        names(&screen.crates),
+        ["clap", "console", "dialoguer", "indicatif"]
+    );
+
+    // Crates still needed by `indicators` survive turning `default` off.
+    screen.toggle_feature("default");
+    assert_eq!(names(&screen.crates), ["console", "indicatif"]);
+    assert_eq!(names(&screen.features), ["indicators"]);
+    assert!(screen.is_modified());
+}
+
+/// [verify tui.installed.toggle-crate]
+/// [verify tui.picker.radio]
+#[test]
+fn installed_pack_radio_category_keeps_one_pick() {
+    let mut screen = InstalledPackScreen::new(
+        vec![make_installed(
+            "category-battery-pack",
+            &[("heapless", "0.8"), ("stm32f4xx-hal", "0.22")],
+            &["default", "stm32f4"],
+        )],
+        0,
+    );
+    assert_eq!(names(&screen.features), ["default", "stm32f4"]);
+
+    screen.toggle_feature("nrf52840");
+    assert_eq!(names(&screen.features), ["default", "nrf52840"]);
+    assert_eq!(names(&screen.crates), ["heapless", "nrf52840-hal"]);
+}
+
+/// [verify tui.installed.show-state]
+#[test]
+fn installed_pack_renders_state_of_each_crate() {
+    let mut screen = fancy_screen();
+    screen.toggle_crate("assert_cmd");
+    let mut app = make_app(Screen::InstalledPack(screen));
+
+    let output = render_app_to_string(&mut app, 80, 24);
+    assert_data_eq!(output, snapbox::file![_]);
+}
+
+/// [verify tui.nav.exit]
+#[test]
+fn installed_pack_enter_applies_selection() {
+    let mut app = make_app(Screen::InstalledPack(fancy_screen()));
+
+    app.handle_key(KeyCode::Enter);
+    assert!(app.pending_action.is_none(), "nothing changed yet");
+
+    // Rows: default, clap, dialoguer, fancy, ...
+    app.handle_key(KeyCode::Down);
+    app.handle_key(KeyCode::Down);
+    app.handle_key(KeyCode::Down);
+    app.handle_key(KeyCode::Char(' '));
+    app.handle_key(KeyCode::Enter);
+
+    let Some(PendingAction::ApplyInstalled {
+        features, crates, ..
+    }) = &app.pending_action
+    else {
+        panic!("expected an apply action");
+    };
+    assert_eq!(names(features), ["default", "fancy"]);
+    assert_eq!(names(crates), ["clap", "console", "dialoguer", "indicatif"]);
+}
+
+/// [verify tui.installed.dep-kind]
+#[test]
+fn installed_pack_d_cycles_dependency_kind() {
+    let mut app = make_app(Screen::InstalledPack(fancy_screen()));
+
+    // Rows: default, clap, ...
+    app.handle_key(KeyCode::Down);
+    app.handle_key(KeyCode::Char('d'));
+    let Screen::InstalledPack(state) = &app.screen else {
+        panic!("expected the installed pack screen");
+    };
+    assert_eq!(state.kind_of("clap"), DepKind::Dev);
+    assert!(state.is_modified());
+    let output = render_app_to_string(&mut app, 80, 8);
+    assert!(output.contains("~ [x] clap"), "{output}");
+    assert!(output.contains("dev-dependencies"), "{output}");
+
+    // Build, then back to the pack's own kind, which is no override at all.
+    app.handle_key(KeyCode::Char('d'));
+    app.handle_key(KeyCode::Char('d'));
+    let Screen::InstalledPack(state) = &app.screen else {
+        panic!("expected the installed pack screen");
+    };
+    assert_eq!(state.kind_of("clap"), DepKind::Normal);
+    assert!(state.dep_kinds.is_empty());
+    assert!(!state.is_modified());
+
+    app.handle_key(KeyCode::Char('d'));
+    app.handle_key(KeyCode::Enter);
+    let Some(PendingAction::ApplyInstalled { dep_kinds, .. }) = &app.pending_action else {
+        panic!("expected an apply action");
+    };
+    assert_eq!(
+        dep_kinds,
+        &BTreeMap::from([("clap".to_string(), DepKind::Dev)])
+    );
+}
+
+/// [verify tui.installed.dep-kind]
+#[test]
+fn installed_pack_d_on_a_feature_explains_itself() {
+    let mut app = make_app(Screen::InstalledPack(fancy_screen()));
+    app.handle_key(KeyCode::Char('d'));
+    let Screen::InstalledPack(state) = &app.screen else {
+        panic!("expected the installed pack screen");
+    };
+    assert_eq!(
+        state.message.as_deref(),
+        Some("Select a crate to change its dependency kind")
+    );
+    assert!(!state.is_modified());
+}
+
+/// [verify tui.nav.cancel]
+#[test]
+fn installed_pack_esc_discards_changes() {
+    let mut app = make_app(Screen::InstalledPack(fancy_screen()));
+    app.handle_key(KeyCode::Char(' '));
+    app.handle_key(KeyCode::Esc);
+
+    assert!(app.pending_action.is_none());
+    let Screen::Installed(state) = &app.screen else {
+        panic!("expected the installed list");
+    };
+    assert_eq!(state.list_state.selected(), Some(0));
+}
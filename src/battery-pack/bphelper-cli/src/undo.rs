@@ -0,0 +1,308 @@
+//! Snapshots for `cargo bp undo`.
+//!
+//! While `add`, `sync`, `rm`, `update`, `upgrade-template` or `tui` runs,
+//! every file it is about to change is first copied into
+//! `target/bp-undo/<timestamp>/` under the workspace root, and every file
+//! it is about to create is listed in that snapshot's `manifest.toml`.
+//! `cargo bp undo` puts the newest snapshot back: saved files are restored,
+//! created files are deleted, and the snapshot itself is removed, so running
+//! it again steps further back.
+//!
+//! Recording is scoped to the running command by [`begin`]; the write paths
+//! ([`Changeset::commit`](crate::changeset::Changeset::commit) and the
+//! template merge) call [`record`] before touching a file, which does
+//! nothing when no command is recording.
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where snapshots live, relative to the workspace root.
+pub(crate) const UNDO_DIR: &str = "target/bp-undo";
+
+const MANIFEST_FILE: &str = "manifest.toml";
+
+thread_local! {
+    static RECORDING: RefCell<Option<Snapshot>> = const { RefCell::new(None) };
+}
+
+/// Keeps a snapshot recording until dropped.
+#[must_use]
+pub(crate) struct Recording(());
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        RECORDING.take();
+    }
+}
+
+/// Start recording a snapshot of the files changed under `root` by
+/// `command` (shown by `cargo bp undo`). Nothing is written until the first
+/// file is recorded, so a command that changes nothing leaves no snapshot.
+// [impl cli.undo.snapshot]
+pub(crate) fn begin(root: &Path, command: &str) -> Recording {
+    RECORDING.set(Some(Snapshot {
+        root: root.to_path_buf(),
+        dir: None,
+        manifest: SnapshotManifest {
+            command: command.to_string(),
+            saved: Vec::new(),
+            created: Vec::new(),
+        },
+    }));
+    Recording(())
+}
+
+/// Save `path` as it is now, before it is written or deleted. A path that
+/// doesn't exist yet is recorded as created. Only the first call for a path
+/// counts, so the snapshot holds the content from before the command ran.
+pub(crate) fn record(path: &Path) -> Result<()> {
+    RECORDING.with_borrow_mut(|recording| match recording {
+        Some(snapshot) => snapshot.record(path),
+        None => Ok(()),
+    })
+}
+
+/// The `manifest.toml` of a snapshot.
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotManifest {
+    /// The command line that made the snapshot.
+    command: String,
+    /// Files that existed, with their saved copies.
+    #[serde(default)]
+    saved: Vec<SavedFile>,
+    /// Files that didn't exist.
+    #[serde(default)]
+    created: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedFile {
+    /// The file, relative to the workspace root (or absolute if outside it).
+    path: String,
+    /// The saved copy, relative to the snapshot directory.
+    backup: String,
+}
+
+struct Snapshot {
+    root: PathBuf,
+    /// The snapshot directory, once the first file has been recorded.
+    dir: Option<PathBuf>,
+    manifest: SnapshotManifest,
+}
+
+impl Snapshot {
+    fn record(&mut self, path: &Path) -> Result<()> {
+        let rel = relative_path(&self.root, path);
+        let manifest = &self.manifest;
+        if manifest.created.contains(&rel) || manifest.saved.iter().any(|f| f.path == rel) {
+            return Ok(());
+        }
+
+        let dir = match &self.dir {
+            Some(dir) => dir.clone(),
+            None => {
+                let dir = create_snapshot_dir(&self.root.join(UNDO_DIR))?;
+                self.dir.insert(dir).clone()
+            }
+        };
+        match std::fs::read(path) {
+            Ok(content) => {
+                let backup = format!("files/{}", self.manifest.saved.len());
+                let backup_path = dir.join(&backup);
+                std::fs::create_dir_all(dir.join("files"))
+                    .and_then(|()| std::fs::write(&backup_path, content))
+                    .with_context(|| format!("Failed to save {} for undo", path.display()))?;
+                self.manifest.saved.push(SavedFile { path: rel, backup });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.manifest.created.push(rel);
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        }
+
+        // Keep the manifest current, so a command that fails partway can
+        // still be undone.
+        let manifest_path = dir.join(MANIFEST_FILE);
+        std::fs::write(&manifest_path, toml::to_string(&self.manifest)?)
+            .with_context(|| format!("Failed to write {}", manifest_path.display()))
+    }
+}
+
+/// Create a new, empty snapshot directory under `base`, named after the
+/// current time.
+fn create_snapshot_dir(base: &Path) -> Result<PathBuf> {
+    std::fs::create_dir_all(base)
+        .with_context(|| format!("Failed to create {}", base.display()))?;
+    let stamp = timestamp(SystemTime::now());
+    for n in 0.. {
+        let name = match n {
+            0 => stamp.clone(),
+            n => format!("{stamp}.{n}"),
+        };
+        let dir = base.join(name);
+        match std::fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to create {}", dir.display()));
+            }
+        }
+    }
+    unreachable!()
+}
+
+/// `time` in UTC as `YYYYMMDD-HHMMSS-mmm`, which sorts chronologically.
+fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}-{:03}",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// `path` relative to `root` with `/` separators, or in full if it lies
+/// outside `root`. Symlinks in the directories of `path` that exist are
+/// resolved, so it matches however `root` was spelled.
+fn relative_path(root: &Path, path: &Path) -> String {
+    let canonical = |p: &Path| {
+        let p = if p.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            p
+        };
+        p.canonicalize().ok()
+    };
+    let root = canonical(root).unwrap_or_else(|| root.to_path_buf());
+    let mut dir = path;
+    let mut rest = Vec::new();
+    let path = loop {
+        let (Some(parent), Some(name)) = (dir.parent(), dir.file_name()) else {
+            break path.to_path_buf();
+        };
+        rest.push(name);
+        dir = parent;
+        if let Some(dir) = canonical(dir) {
+            break rest.iter().rev().fold(dir, |p, name| p.join(name));
+        }
+    };
+    path.strip_prefix(&root)
+        .unwrap_or(&path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// What [`undo_latest`] put back.
+#[derive(Debug)]
+pub(crate) struct Undone {
+    /// The command line that was undone.
+    pub command: String,
+    /// Files restored to their saved content.
+    pub restored: Vec<String>,
+    /// Created files that were deleted.
+    pub removed: Vec<String>,
+}
+
+/// Put back the newest snapshot under `root`, then delete it.
+///
+/// Created files are deleted along with any directories they leave empty;
+/// saved files are restored, recreating them if they were deleted.
+// [impl cli.undo.restore]
+// [impl cli.undo.empty]
+pub(crate) fn undo_latest(root: &Path) -> Result<Undone> {
+    let base = root.join(UNDO_DIR);
+    let Some(dir) = latest_snapshot(&base)? else {
+        bail!("Nothing to undo: no snapshots in {}", base.display());
+    };
+    let manifest_path = dir.join(MANIFEST_FILE);
+    let content = std::fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Failed to read {}", manifest_path.display()))?;
+    let manifest: SnapshotManifest = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", manifest_path.display()))?;
+
+    let mut removed = Vec::new();
+    for rel in manifest.created.iter().rev() {
+        let path = root.join(rel);
+        match std::fs::remove_file(&path) {
+            Ok(()) => removed.push(rel.clone()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to delete {}", path.display()));
+            }
+        }
+        remove_empty_parents(root, &path);
+    }
+
+    let mut restored = Vec::new();
+    for file in &manifest.saved {
+        let path = root.join(&file.path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::copy(dir.join(&file.backup), &path)
+            .with_context(|| format!("Failed to restore {}", path.display()))?;
+        restored.push(file.path.clone());
+    }
+
+    std::fs::remove_dir_all(&dir).with_context(|| format!("Failed to remove {}", dir.display()))?;
+    Ok(Undone {
+        command: manifest.command,
+        restored,
+        removed,
+    })
+}
+
+/// The newest snapshot directory in `base`, if any.
+fn latest_snapshot(base: &Path) -> Result<Option<PathBuf>> {
+    let entries = match std::fs::read_dir(base) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", base.display())),
+    };
+    let mut snapshots = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.join(MANIFEST_FILE).is_file() {
+            snapshots.push(path);
+        }
+    }
+    // Names are timestamps (with a `.<n>` suffix for snapshots made in the
+    // same millisecond), so the newest sorts last.
+    Ok(snapshots.into_iter().max())
+}
+
+/// Remove the directories between `path` and `root` that are now empty.
+fn remove_empty_parents(root: &Path, path: &Path) {
+    let mut dir = path.parent();
+    while let Some(d) = dir {
+        if d == root || !d.starts_with(root) || std::fs::remove_dir(d).is_err() {
+            break;
+        }
+        dir = d.parent();
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,107 @@
+use super::*;
+use crate::changeset::Changeset;
+
+// [verify cli.undo.snapshot]
+// [verify cli.undo.restore]
+#[test]
+fn undo_restores_saved_files_and_removes_created_ones() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    let manifest = root.join("Cargo.toml");
+    let created = root.join(".github/workflows/ci.yml");
+    std::fs::write(&manifest, "old\n").unwrap();
+
+    {
+        let _recording = begin(root, "cargo bp add ci -t full");
+        let mut changes = Changeset::new();
+        changes.write(&manifest, "new\n").unwrap();
+        changes.commit().unwrap();
+
+        record(&created).unwrap();
+        std::fs::create_dir_all(created.parent().unwrap()).unwrap();
+        std::fs::write(&created, "on: push\n").unwrap();
+
+        // A second write keeps the content from before the command.
+        let mut changes = Changeset::new();
+        changes.write(&manifest, "newer\n").unwrap();
+        changes.commit().unwrap();
+    }
+
+    let undone = undo_latest(root).unwrap();
+    assert_eq!(undone.command, "cargo bp add ci -t full");
+    assert_eq!(undone.restored, ["Cargo.toml"]);
+    assert_eq!(undone.removed, [".github/workflows/ci.yml"]);
+    assert_eq!(std::fs::read_to_string(&manifest).unwrap(), "old\n");
+    assert!(!root.join(".github").exists());
+    assert_eq!(std::fs::read_dir(root.join(UNDO_DIR)).unwrap().count(), 0);
+}
+
+#[test]
+fn undo_recreates_deleted_files() {
+    let tmp = tempfile::tempdir().unwrap();
+    let build_rs = tmp.path().join("build.rs");
+    std::fs::write(&build_rs, "fn main() {}\n").unwrap();
+
+    {
+        let _recording = begin(tmp.path(), "cargo bp rm cli");
+        let mut changes = Changeset::new();
+        changes.remove(&build_rs).unwrap();
+        changes.commit().unwrap();
+    }
+    assert!(!build_rs.exists());
+
+    undo_latest(tmp.path()).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&build_rs).unwrap(),
+        "fn main() {}\n"
+    );
+}
+
+// [verify cli.undo.snapshot]
+#[test]
+fn nothing_is_recorded_outside_a_command() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("Cargo.toml");
+
+    record(&path).unwrap();
+    {
+        // A command that writes nothing leaves no snapshot either.
+        let _recording = begin(tmp.path(), "cargo bp sync");
+    }
+    record(&path).unwrap();
+
+    assert!(!tmp.path().join("target").exists());
+}
+
+// [verify cli.undo.restore]
+// [verify cli.undo.empty]
+#[test]
+fn undo_steps_back_through_snapshots() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("Cargo.toml");
+    std::fs::write(&path, "1\n").unwrap();
+
+    for (command, content) in [("first", "2\n"), ("second", "3\n")] {
+        let _recording = begin(tmp.path(), command);
+        let mut changes = Changeset::new();
+        changes.write(&path, content).unwrap();
+        changes.commit().unwrap();
+    }
+
+    assert_eq!(undo_latest(tmp.path()).unwrap().command, "second");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "2\n");
+    assert_eq!(undo_latest(tmp.path()).unwrap().command, "first");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "1\n");
+
+    let err = undo_latest(tmp.path()).unwrap_err();
+    assert!(err.to_string().starts_with("Nothing to undo"), "{err}");
+}
+
+#[test]
+fn timestamps_sort_chronologically() {
+    let at = |millis: u64| timestamp(UNIX_EPOCH + std::time::Duration::from_millis(millis));
+    assert_eq!(at(0), "19700101-000000-000");
+    assert_eq!(at(1_792_234_567_891), "20261017-105607-891");
+    assert_eq!(at(951_782_400_000), "20000229-000000-000");
+    assert!(at(1_000) < at(1_001));
+}
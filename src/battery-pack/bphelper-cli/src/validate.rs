@@ -2,6 +2,7 @@
 
 use anyhow::{Context, Result, bail};
 use bphelper_manifest::parse_battery_pack_from_path;
+use std::collections::BTreeMap;
 use std::path::Path;
 
 /// Sentinel error: template validation was skipped (not a real failure).
@@ -193,11 +194,12 @@ pub fn validate(manifest_dir: &str) -> Result<()> {
         }
 
         println!("validating template '{name}'...");
-        build_and_test_template(
+        let sets = crate::template_engine::test_matrix(&packaged_dir, &template.path)?;
+        build_and_test_matrix(
             &packaged_dir,
             &template.path,
             name,
-            std::collections::BTreeMap::new(),
+            sets,
             shared_target_dir.as_std_path(),
             &metadata,
         )?;
@@ -216,6 +218,8 @@ pub fn validate(manifest_dir: &str) -> Result<()> {
 ///
 /// Generates from the source tree (not the packaged tarball) so battery pack authors can
 /// exercise feature/placeholder combinations beyond the template defaults in their own tests.
+/// If the template declares a `[matrix]`, every combination is built with `defines` layered
+/// on top.
 pub fn validate_template_with(
     manifest_dir: &str,
     template_name: &str,
@@ -240,45 +244,108 @@ pub fn validate_template_with(
         .context("failed to run cargo metadata")?;
     let shared_target_dir = metadata.target_directory.join("bp-validate");
 
-    let defines = defines
-        .iter()
-        .map(|(k, v)| (k.to_string(), v.to_string()))
-        .collect();
-    build_and_test_template(
+    let mut sets = Vec::new();
+    for mut set in crate::template_engine::test_matrix(manifest_dir, &template.path)? {
+        set.extend(defines.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+        if !sets.contains(&set) {
+            sets.push(set);
+        }
+    }
+    build_and_test_matrix(
         manifest_dir,
         &template.path,
         template_name,
-        defines,
+        sets,
         shared_target_dir.as_std_path(),
         &metadata,
     )
 }
 
+/// Build and test a template once per placeholder set, sharing one target dir.
+///
+/// Every combination is built even after a failure; the error lists each
+/// failing combination.
+// [impl cli.validate.templates.matrix]
+fn build_and_test_matrix(
+    crate_root: &Path,
+    template_path: &str,
+    name: &str,
+    sets: Vec<BTreeMap<String, String>>,
+    shared_target_dir: &Path,
+    metadata: &cargo_metadata::Metadata,
+) -> Result<()> {
+    let total = sets.len();
+    let mut failures = Vec::new();
+    for defines in sets {
+        if total > 1 {
+            println!("  {}", combination_label(name, &defines));
+        }
+        if let Err(e) = build_and_test_template(
+            crate_root,
+            template_path,
+            name,
+            defines,
+            shared_target_dir,
+            metadata,
+        ) {
+            failures.push(e);
+        }
+    }
+
+    if total == 1
+        && let Some(failure) = failures.pop()
+    {
+        return Err(failure);
+    }
+    if !failures.is_empty() {
+        let details: Vec<String> = failures.iter().map(|e| format!("{e:#}")).collect();
+        bail!(
+            "{} of {total} combination(s) of template '{name}' failed:\n\n{}",
+            failures.len(),
+            details.join("\n\n")
+        );
+    }
+    Ok(())
+}
+
+/// How a template combination is named in output: `'full'` for the defaults,
+/// `'full' (ci_platform=none, all=true)` otherwise.
+fn combination_label(name: &str, defines: &BTreeMap<String, String>) -> String {
+    if defines.is_empty() {
+        return format!("'{name}'");
+    }
+    let values: Vec<String> = defines.iter().map(|(k, v)| format!("{k}={v}")).collect();
+    format!("'{name}' ({})", values.join(", "))
+}
+
 /// Generate a template into a temp dir with the given placeholders, then run
 /// `cargo check` and `cargo test` against it.
 fn build_and_test_template(
     crate_root: &Path,
     template_path: &str,
-    label: &str,
-    defines: std::collections::BTreeMap<String, String>,
+    name: &str,
+    defines: BTreeMap<String, String>,
     shared_target_dir: &Path,
     metadata: &cargo_metadata::Metadata,
 ) -> Result<()> {
+    let label = combination_label(name, &defines);
     let tmp = tempfile::tempdir().context("failed to create temp directory")?;
     let opts = crate::template_engine::GenerateOpts {
         render: crate::template_engine::RenderOpts {
             crate_root: crate_root.to_path_buf(),
             template_path: template_path.to_string(),
-            project_name: format!("bp-validate-{label}"),
+            project_name: format!("bp-validate-{name}"),
             defines,
             active_features: std::collections::BTreeSet::new(),
             interactive_override: Some(false),
+            source: crate::registry::CrateSource::crates_io(),
         },
         destination: Some(tmp.path().to_path_buf()),
         git_init: false,
+        hooks: None,
     };
     let project_dir = crate::template_engine::generate(opts)
-        .with_context(|| format!("failed to generate template '{label}'"))?;
+        .with_context(|| format!("failed to generate template {label}"))?;
 
     write_crates_io_patches(&project_dir, metadata)?;
 
@@ -297,7 +364,7 @@ fn build_and_test_template(
             .with_context(|| format!("failed to run cargo {}", args.join(" ")))?;
         anyhow::ensure!(
             output.status.success(),
-            "cargo {} failed for template '{label}':\n{}\n{}",
+            "cargo {} failed for template {label}:\n{}\n{}",
             args.join(" "),
             String::from_utf8_lossy(&output.stdout),
             String::from_utf8_lossy(&output.stderr)
@@ -386,6 +453,7 @@ fn try_render(
         defines: std::collections::BTreeMap::new(),
         active_features: std::collections::BTreeSet::new(),
         interactive_override: Some(false),
+        source: crate::registry::CrateSource::crates_io(),
     };
     crate::template_engine::preview(opts)
 }
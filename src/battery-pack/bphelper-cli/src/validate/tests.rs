@@ -211,6 +211,78 @@ test result: FAILED. 0 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out;
     );
 }
 
+// [verify cli.validate.templates.matrix]
+#[test]
+fn validate_reports_each_failing_matrix_combination() {
+    let tmp = tempfile::tempdir().unwrap();
+    let bp = tmp.path().join("test-bp");
+
+    std::fs::create_dir_all(bp.join("src")).unwrap();
+    std::fs::create_dir_all(bp.join("templates/default/src")).unwrap();
+
+    std::fs::write(
+        bp.join("Cargo.toml"),
+        indoc! {r#"
+            [package]
+            name = "test-battery-pack"
+            version = "0.1.0"
+            edition = "2021"
+            description = "test"
+            keywords = ["battery-pack"]
+
+            [package.metadata.battery.templates]
+            default = { path = "templates/default", description = "test" }
+        "#},
+    )
+    .unwrap();
+    std::fs::write(bp.join("src/lib.rs"), "").unwrap();
+
+    std::fs::write(
+        bp.join("templates/default/bp-template.toml"),
+        indoc! {r#"
+            [placeholders.broken]
+            type = "bool"
+
+            [matrix]
+            each = true
+        "#},
+    )
+    .unwrap();
+    std::fs::write(
+        bp.join("templates/default/_Cargo.toml"),
+        indoc! {r#"
+            [package]
+            name = "{{ project_name }}"
+            version = "0.1.0"
+            edition = "2021"
+        "#},
+    )
+    .unwrap();
+    std::fs::write(
+        bp.join("templates/default/src/main.rs"),
+        indoc! {r#"
+            {% if broken %}compile_error!("broken combination");
+            {% endif %}fn main() {}
+        "#},
+    )
+    .unwrap();
+
+    let err = super::validate(bp.to_str().unwrap()).unwrap_err();
+    let msg = format!("{err:#}");
+
+    snapbox::assert_data_eq!(
+        msg,
+        snapbox::str![[r#"
+1 of 2 combination(s) of template 'default' failed:
+
+cargo check failed for template 'default' (broken=true):
+...
+error: broken combination
+...
+"#]]
+    );
+}
+
 #[test]
 fn validate_catches_excluded_template_file() {
     // A template file excluded from the tarball via `package.exclude` should
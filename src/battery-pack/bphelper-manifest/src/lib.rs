@@ -45,6 +45,9 @@ pub enum Error {
     #[error("cycle in local feature references: {path}")]
     FeatureCycle { path: String },
 
+    #[error("cycle in extended battery packs: {path}")]
+    ExtendsCycle { path: String },
+
     #[error("reading {path}: {source}")]
     Io {
         path: String,
@@ -133,9 +136,12 @@ impl ValidationReport {
 /// The dependency kind, determined by which section of the battery pack's
 /// Cargo.toml the crate appears in.
 // [impl format.deps.kind-mapping]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
 pub enum DepKind {
     /// `[dependencies]` — becomes a regular dependency for the user.
+    #[default]
     Normal,
     /// `[dev-dependencies]` — becomes a dev-dependency for the user.
     Dev,
@@ -163,9 +169,121 @@ pub struct CrateSpec {
     pub features: BTreeSet<String>,
     /// Which dependency section this crate comes from.
     pub dep_kind: DepKind,
+    /// Further sections the crate goes in besides `dep_kind`: set when the
+    /// packs it was merged from list it as both a dev- and a build-dependency.
+    // [impl manifest.merge.dep-kind]
+    #[serde(default)]
+    pub extra_dep_kinds: Vec<DepKind>,
     /// Whether this crate is marked `optional = true`.
     // [impl format.features.optional]
     pub optional: bool,
+    /// The crate's real name when the pack renames it (`package = "..."`).
+    /// The spec is keyed by the rename.
+    // [impl format.deps.rename]
+    #[serde(default)]
+    pub package: Option<String>,
+    /// Whether the crate's default features are enabled; `false` for
+    /// `default-features = false`.
+    // [impl format.deps.default-features]
+    #[serde(default = "default_true")]
+    pub default_features: bool,
+    /// The platform the crate is limited to, from a
+    /// `[target.'cfg(...)'.dependencies]` section.
+    // [impl format.deps.target]
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Where the crate is fetched from.
+    // [impl format.deps.source]
+    #[serde(default)]
+    pub source: DepSource,
+}
+
+impl Default for CrateSpec {
+    fn default() -> Self {
+        Self {
+            version: String::new(),
+            features: BTreeSet::new(),
+            dep_kind: DepKind::Normal,
+            extra_dep_kinds: Vec::new(),
+            optional: false,
+            package: None,
+            default_features: true,
+            target: None,
+            source: DepSource::CratesIo,
+        }
+    }
+}
+
+impl CrateSpec {
+    /// Every dependency kind the crate is added as: `dep_kind`, then
+    /// `extra_dep_kinds`.
+    pub fn dep_kinds(&self) -> impl Iterator<Item = DepKind> + '_ {
+        std::iter::once(self.dep_kind).chain(self.extra_dep_kinds.iter().copied())
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Where a curated crate comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DepSource {
+    /// crates.io.
+    #[default]
+    CratesIo,
+    /// An alternative registry, identified by its index URL.
+    Registry {
+        /// Index URL, as written to `registry-index`.
+        index: String,
+    },
+    /// A git repository, optionally at a branch, tag or revision.
+    Git {
+        /// Repository URL.
+        url: String,
+        /// `branch = "..."`.
+        branch: Option<String>,
+        /// `tag = "..."`.
+        tag: Option<String>,
+        /// `rev = "..."`.
+        rev: Option<String>,
+    },
+}
+
+impl DepSource {
+    /// Parse a `cargo metadata` source (`git+https://...?branch=main#sha`)
+    /// and registry index URL. Path dependencies and crates.io map to
+    /// [`DepSource::CratesIo`].
+    fn from_metadata(source: Option<&str>, registry: Option<&str>) -> Self {
+        if let Some(git) = source.and_then(|s| s.strip_prefix("git+")) {
+            let git = git.split_once('#').map_or(git, |(url, _)| url);
+            let (url, query) = git.split_once('?').unwrap_or((git, ""));
+            let mut branch = None;
+            let mut tag = None;
+            let mut rev = None;
+            for pair in query.split('&') {
+                match pair.split_once('=') {
+                    Some(("branch", v)) => branch = Some(v.to_string()),
+                    Some(("tag", v)) => tag = Some(v.to_string()),
+                    Some(("rev", v)) => rev = Some(v.to_string()),
+                    _ => {}
+                }
+            }
+            return DepSource::Git {
+                url: url.to_string(),
+                branch,
+                tag,
+                rev,
+            };
+        }
+        match registry {
+            Some(index) => DepSource::Registry {
+                index: index.to_string(),
+            },
+            None => DepSource::CratesIo,
+        }
+    }
 }
 
 /// How many items a user may pick from a category.
@@ -757,6 +875,19 @@ impl BatteryPackSpec {
         resolved
     }
 
+    /// Resolve crates like [`resolve_for_features`](Self::resolve_for_features),
+    /// then expand the battery packs this pack extends into their own crates.
+    ///
+    /// See [`expand_extended_packs`].
+    // [impl format.extends.resolution]
+    pub fn resolve_extended<E: From<Error>>(
+        &self,
+        active: &ActiveFeatures,
+        load: &mut impl FnMut(&str, &CrateSpec) -> Result<BatteryPackSpec, E>,
+    ) -> Result<BTreeMap<String, CrateSpec>, E> {
+        expand_extended_packs(&self.name, self.resolve_for_features(active), load)
+    }
+
     /// Check whether a crate name matches the hidden patterns.
     // [impl format.hidden.effect]
     pub fn is_hidden(&self, crate_name: &str) -> bool {
@@ -903,6 +1034,14 @@ pub struct MergedCrateSpec {
     pub dep_kinds: Vec<DepKind>,
     /// Whether this crate is optional.
     pub optional: bool,
+    /// The crate's real name when it is renamed (first pack wins).
+    pub package: Option<String>,
+    /// Whether default features are enabled (enabled if any pack enables them).
+    pub default_features: bool,
+    /// Platform gate; `None` (unconditional) unless every pack uses the same one.
+    pub target: Option<String>,
+    /// Where the crate comes from (first pack wins).
+    pub source: DepSource,
 }
 
 /// Merge crate specs from multiple battery packs.
@@ -913,9 +1052,13 @@ pub struct MergedCrateSpec {
 /// - Features: union all (`manifest.merge.features`)
 /// - Dep kind: Normal wins (widest scope); if dev vs build conflict,
 ///   adds to both sections (`manifest.merge.dep-kind`)
+/// - Default features and platform: the widest wins -- defaults on if any
+///   pack enables them, unconditional unless every pack uses the same
+///   `cfg` (`manifest.merge.target`)
 // [impl manifest.merge.version]
 // [impl manifest.merge.features]
 // [impl manifest.merge.dep-kind]
+// [impl manifest.merge.target]
 pub fn merge_crate_specs(
     specs: &[BTreeMap<String, CrateSpec>],
 ) -> BTreeMap<String, MergedCrateSpec> {
@@ -942,6 +1085,16 @@ pub fn merge_crate_specs(
                     if !spec.optional {
                         existing.optional = false;
                     }
+
+                    // Default features: if any pack wants them, keep them
+                    if spec.default_features {
+                        existing.default_features = true;
+                    }
+
+                    // Target: differing platform gates widen to unconditional
+                    if existing.target != spec.target {
+                        existing.target = None;
+                    }
                 }
                 None => {
                     merged.insert(
@@ -951,6 +1104,10 @@ pub fn merge_crate_specs(
                             features: spec.features.clone(),
                             dep_kinds: vec![spec.dep_kind],
                             optional: spec.optional,
+                            package: spec.package.clone(),
+                            default_features: spec.default_features,
+                            target: spec.target.clone(),
+                            source: spec.source.clone(),
                         },
                     );
                 }
@@ -1018,6 +1175,109 @@ fn merge_dep_kinds(existing: &[DepKind], incoming: DepKind) -> Vec<DepKind> {
     kinds
 }
 
+// ============================================================================
+// Pack composition
+// ============================================================================
+
+/// Return the battery pack name behind a resolved crate entry, if the crate
+/// is a battery pack this pack extends (a regular `*-battery-pack` dependency,
+/// possibly renamed).
+// [impl format.extends.dependency]
+pub fn extended_pack_name<'a>(key: &'a str, spec: &'a CrateSpec) -> Option<&'a str> {
+    let name = spec.package.as_deref().unwrap_or(key);
+    (spec.dep_kind == DepKind::Normal && name.ends_with("-battery-pack")).then_some(name)
+}
+
+/// Expand the battery packs in a resolved crate set into their own crates.
+///
+/// `root` is the name of the pack `crates` was resolved from. Each extended
+/// pack is fetched with `load` and resolved with the features its entry
+/// carries -- `cli/indicators` in the outer pack reaches `cli-battery-pack`
+/// as `indicators` -- plus `default` unless it is declared with
+/// `default-features = false`. Expansion is recursive, and the pack entries
+/// themselves are dropped: users get the curated crates, not the packs.
+///
+/// Crates reached through several packs are de-duplicated with
+/// [`merge_crate_specs`]. A crate merged into both `[dev-dependencies]` and
+/// `[build-dependencies]` keeps dev as its `dep_kind` and build in
+/// `extra_dep_kinds`.
+// [impl format.extends.features]
+// [impl format.extends.cycle]
+// [impl format.extends.merge]
+pub fn expand_extended_packs<E: From<Error>>(
+    root: &str,
+    crates: BTreeMap<String, CrateSpec>,
+    load: &mut impl FnMut(&str, &CrateSpec) -> Result<BatteryPackSpec, E>,
+) -> Result<BTreeMap<String, CrateSpec>, E> {
+    if !crates
+        .iter()
+        .any(|(key, spec)| extended_pack_name(key, spec).is_some())
+    {
+        return Ok(crates);
+    }
+
+    let mut layers = Vec::new();
+    let mut stack = vec![root.to_string()];
+    collect_extended_layers(crates, load, &mut stack, &mut layers)?;
+
+    Ok(merge_crate_specs(&layers)
+        .into_iter()
+        .map(|(name, merged)| {
+            let mut dep_kinds = merged.dep_kinds.into_iter();
+            let spec = CrateSpec {
+                version: merged.version,
+                features: merged.features,
+                dep_kind: dep_kinds.next().unwrap_or_default(),
+                extra_dep_kinds: dep_kinds.collect(),
+                optional: merged.optional,
+                package: merged.package,
+                default_features: merged.default_features,
+                target: merged.target,
+                source: merged.source,
+            };
+            (name, spec)
+        })
+        .collect())
+}
+
+/// Push `crates` (minus its pack entries) onto `layers`, then recurse into
+/// each extended pack. `stack` holds the packs being expanded, for cycles.
+fn collect_extended_layers<E: From<Error>>(
+    mut crates: BTreeMap<String, CrateSpec>,
+    load: &mut impl FnMut(&str, &CrateSpec) -> Result<BatteryPackSpec, E>,
+    stack: &mut Vec<String>,
+    layers: &mut Vec<BTreeMap<String, CrateSpec>>,
+) -> Result<(), E> {
+    let packs: Vec<(String, CrateSpec)> = crates
+        .extract_if(.., |key, spec| extended_pack_name(key, spec).is_some())
+        .collect();
+    layers.push(crates);
+
+    for (key, entry) in packs {
+        let name = extended_pack_name(&key, &entry).expect("extracted as a pack");
+        if stack.iter().any(|stacked| stacked == name) {
+            let mut cycle = stack.clone();
+            cycle.push(name.to_string());
+            return Err(Error::ExtendsCycle {
+                path: cycle.join("->"),
+            }
+            .into());
+        }
+
+        let nested = load(name, &entry)?;
+        let mut features = entry.features.clone();
+        if entry.default_features {
+            features.insert("default".to_string());
+        }
+        let resolved = nested.resolve_for_features(&ActiveFeatures::Subset(features));
+
+        stack.push(name.to_string());
+        collect_extended_layers(resolved, load, stack, layers)?;
+        stack.pop();
+    }
+    Ok(())
+}
+
 // ============================================================================
 // Raw deserialization types (internal)
 // ============================================================================
@@ -1093,13 +1353,28 @@ fn package_to_spec(pkg: &Package) -> Result<BatteryPackSpec, Error> {
             .map(str::to_owned)
             .unwrap_or(version);
 
+        // A renamed crate is keyed (and referenced by features) by its
+        // rename, with the real name kept in `package`.
+        let (key, package) = match &dep.rename {
+            Some(rename) => (rename.clone(), Some(dep.name.clone())),
+            None => (dep.name.clone(), None),
+        };
+
         crates.insert(
-            dep.name.clone(),
+            key,
             CrateSpec {
                 version,
                 features: dep.features.iter().cloned().collect(),
                 dep_kind: kind,
+                extra_dep_kinds: Vec::new(),
                 optional: dep.optional,
+                package,
+                default_features: dep.uses_default_features,
+                target: dep.target.as_ref().map(ToString::to_string),
+                source: DepSource::from_metadata(
+                    dep.source.as_ref().map(|s| s.repr.as_str()),
+                    dep.registry.as_deref(),
+                ),
             },
         );
     }
@@ -1109,7 +1384,7 @@ fn package_to_spec(pkg: &Package) -> Result<BatteryPackSpec, Error> {
         .dependencies
         .iter()
         .filter(|dep| dep.optional)
-        .map(|dep| dep.name.as_str())
+        .map(|dep| dep.rename.as_deref().unwrap_or(&dep.name))
         .collect::<BTreeSet<_>>();
 
     // Skip cargo's auto-generated `feat = ["dep:feat"]` entries that mirror an
@@ -1510,6 +1785,58 @@ mod tests {
         assert!(resolved.contains_key("serde"));
     }
 
+    #[test]
+    // [verify format.deps.rename]
+    // [verify format.deps.default-features]
+    // [verify format.deps.target]
+    // [verify format.deps.source]
+    fn dep_details_are_carried_into_spec() {
+        let manifest = indoc! {r#"
+            [package]
+            name = "test-battery-pack"
+            version = "0.1.0"
+            keywords = ["battery-pack"]
+
+            [dependencies]
+            tower-http = { version = "0.6", default-features = false, optional = true }
+            json = { package = "serde_json", version = "1", optional = true }
+            tracey = { git = "https://github.com/example/tracey", branch = "main" }
+
+            [target.'cfg(not(target_env = "msvc"))'.dependencies]
+            tikv-jemallocator = { version = "0.7", optional = true }
+
+            [features]
+            default = ["json"]
+            jemalloc = ["tikv-jemallocator"]
+        "#};
+
+        let spec = parse_test(manifest).unwrap();
+        spec.validate().unwrap();
+
+        let tower_http = &spec.crates["tower-http"];
+        assert!(!tower_http.default_features);
+        assert_eq!(tower_http.target, None);
+
+        let json = &spec.crates["json"];
+        assert_eq!(json.package.as_deref(), Some("serde_json"));
+        assert!(json.default_features);
+
+        assert_eq!(
+            spec.crates["tikv-jemallocator"].target.as_deref(),
+            Some(r#"cfg(not(target_env = "msvc"))"#)
+        );
+
+        assert_eq!(
+            spec.crates["tracey"].source,
+            DepSource::Git {
+                url: "https://github.com/example/tracey".to_string(),
+                branch: Some("main".to_string()),
+                tag: None,
+                rev: None,
+            }
+        );
+    }
+
     #[test]
     fn feature_with_weak_slash_feature_resolve() {
         let manifest = indoc! {r#"
@@ -1863,6 +2190,7 @@ mod tests {
                     features: BTreeSet::new(),
                     dep_kind: DepKind::Normal,
                     optional: true,
+                    ..Default::default()
                 },
             )]),
             features: BTreeMap::from([(
@@ -2826,6 +3154,7 @@ foo-battery-pack 0.1.0
                     features: BTreeSet::new(),
                     dep_kind: DepKind::Normal,
                     optional: true,
+                    ..Default::default()
                 },
             )]),
             features: BTreeMap::from([(
@@ -3227,6 +3556,7 @@ foo-battery-pack 0.1.0
                 .collect::<BTreeSet<_>>(),
             dep_kind,
             optional: false,
+            ..Default::default()
         }
     }
 
@@ -3439,6 +3769,185 @@ foo-battery-pack 0.1.0
         assert_eq!(merged["serde"].dep_kinds, vec![DepKind::Normal]);
     }
 
+    #[test]
+    // [verify manifest.merge.target]
+    fn merge_default_features_and_target_widen() {
+        let gated = |cfg: &str, default_features| CrateSpec {
+            target: Some(cfg.to_string()),
+            default_features,
+            ..crate_spec("0.7", &[], DepKind::Normal)
+        };
+
+        let merged = merge_crate_specs(&[
+            BTreeMap::from([("jemalloc".to_string(), gated("cfg(unix)", false))]),
+            BTreeMap::from([("jemalloc".to_string(), gated("cfg(unix)", true))]),
+        ]);
+        assert_eq!(merged["jemalloc"].target.as_deref(), Some("cfg(unix)"));
+        assert!(merged["jemalloc"].default_features);
+
+        let merged = merge_crate_specs(&[
+            BTreeMap::from([("jemalloc".to_string(), gated("cfg(unix)", false))]),
+            BTreeMap::from([("jemalloc".to_string(), gated("cfg(windows)", false))]),
+        ]);
+        assert_eq!(merged["jemalloc"].target, None);
+        assert!(!merged["jemalloc"].default_features);
+    }
+
+    // -- Pack composition tests --
+
+    /// Parse a pack whose `[dependencies]` and `[features]` are given.
+    fn pack(name: &str, deps: &str, features: &str) -> BatteryPackSpec {
+        parse_test(&format!(
+            "[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nkeywords = [\"battery-pack\"]\n\n\
+             [dependencies]\n{deps}\n[features]\n{features}\n"
+        ))
+        .unwrap()
+    }
+
+    fn loader(
+        packs: Vec<BatteryPackSpec>,
+    ) -> impl FnMut(&str, &CrateSpec) -> Result<BatteryPackSpec, Error> {
+        move |name, _entry| {
+            Ok(packs
+                .iter()
+                .find(|spec| spec.name == name)
+                .unwrap_or_else(|| panic!("unexpected pack {name}"))
+                .clone())
+        }
+    }
+
+    fn cli_pack() -> BatteryPackSpec {
+        pack(
+            "cli-battery-pack",
+            indoc! {r#"
+                clap = { version = "4", features = ["derive"], optional = true }
+                anyhow = { version = "1.0.80", optional = true }
+                indicatif = { version = "0.17", optional = true }
+            "#},
+            indoc! {r#"
+                default = ["clap", "anyhow"]
+                indicators = ["indicatif"]
+            "#},
+        )
+    }
+
+    #[test]
+    // [verify format.extends.dependency]
+    // [verify format.extends.resolution]
+    // [verify format.extends.features]
+    // [verify format.extends.merge]
+    fn extended_packs_expand_with_forwarded_features() {
+        let error_pack = pack("error-battery-pack", r#"anyhow = "1.0.90""#, "");
+        let house = pack(
+            "house-battery-pack",
+            indoc! {r#"
+                cli = { package = "cli-battery-pack", version = "0.6", optional = true }
+                error-battery-pack = "0.6"
+                tracing = { version = "0.1", optional = true }
+            "#},
+            indoc! {r#"
+                default = ["cli", "tracing"]
+                indicators = ["cli/indicators"]
+            "#},
+        );
+
+        let resolved = house
+            .resolve_extended(
+                &ActiveFeatures::Subset(BTreeSet::from([
+                    "default".to_string(),
+                    "indicators".to_string(),
+                ])),
+                &mut loader(vec![cli_pack(), error_pack]),
+            )
+            .unwrap();
+
+        // Packs are replaced by their crates; anyhow is merged to the newest version.
+        snapbox::assert_data_eq!(
+            render_resolved(&resolved),
+            snapbox::str![[r#"
+anyhow 1.0.90
+clap 4
+indicatif 0.17
+tracing 0.1
+"#]]
+        );
+    }
+
+    #[test]
+    // [verify format.extends.features]
+    fn extended_pack_without_default_features() {
+        let house = pack(
+            "house-battery-pack",
+            r#"cli-battery-pack = { version = "0.6", default-features = false, features = ["indicators"] }"#,
+            "",
+        );
+
+        let resolved = house
+            .resolve_extended(
+                &ActiveFeatures::Subset(BTreeSet::from(["default".to_string()])),
+                &mut loader(vec![cli_pack()]),
+            )
+            .unwrap();
+        assert_eq!(render_resolved(&resolved), "indicatif 0.17");
+    }
+
+    #[test]
+    // [verify manifest.merge.dep-kind]
+    fn extended_packs_keep_both_dev_and_build_kinds() {
+        let testing = parse_test(indoc! {r#"
+            [package]
+            name = "testing-battery-pack"
+            version = "0.1.0"
+            keywords = ["battery-pack"]
+
+            [dev-dependencies]
+            serde = "1"
+        "#})
+        .unwrap();
+        let house = parse_test(indoc! {r#"
+            [package]
+            name = "house-battery-pack"
+            version = "0.1.0"
+            keywords = ["battery-pack"]
+
+            [dependencies]
+            testing-battery-pack = "0.1"
+
+            [build-dependencies]
+            serde = "1"
+        "#})
+        .unwrap();
+
+        let resolved = house
+            .resolve_extended(
+                &ActiveFeatures::Subset(BTreeSet::from(["default".to_string()])),
+                &mut loader(vec![testing]),
+            )
+            .unwrap();
+        assert_eq!(
+            resolved["serde"].dep_kinds().collect::<Vec<_>>(),
+            vec![DepKind::Dev, DepKind::Build]
+        );
+    }
+
+    #[test]
+    // [verify format.extends.cycle]
+    fn extended_pack_cycle_is_an_error() {
+        let a = pack("a-battery-pack", r#"b-battery-pack = "0.1""#, "");
+        let b = pack("b-battery-pack", r#"a-battery-pack = "0.1""#, "");
+
+        let err = a
+            .resolve_extended(
+                &ActiveFeatures::Subset(BTreeSet::from(["default".to_string()])),
+                &mut loader(vec![a.clone(), b]),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cycle in extended battery packs: a-battery-pack->b-battery-pack->a-battery-pack"
+        );
+    }
+
     // -- Version comparison unit tests --
 
     #[test]
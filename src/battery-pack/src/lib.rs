@@ -58,7 +58,9 @@ pub mod build {
 /// ```
 #[cfg(feature = "cli")]
 pub mod testing {
-    pub use bphelper_cli::{PreviewBuilder, PreviewFile, validate, validate_template_with};
+    pub use bphelper_cli::{
+        PickerSnapshot, PreviewBuilder, PreviewFile, validate, validate_template_with,
+    };
 }
 
 #[cfg(test)]
@@ -0,0 +1,302 @@
+//! Schema for `cargo bp check --json` output.
+//!
+//! `cargo bp check` compares each installed battery pack against the
+//! project's dependencies and exits non-zero when they disagree. With
+//! `--json` it also prints a [`CheckReport`] describing every problem it
+//! found, so CI can both fail the build and annotate the offending lines.
+//!
+//! The types follow the same `new(required)` + chainable `with_*` pattern
+//! as the [status](crate::status) schema:
+//!
+//! ```
+//! use cargo_bp_script::{CheckIssue, CheckReport, PackCheck, ProjectInfo};
+//!
+//! let report = CheckReport::new(ProjectInfo::new("Cargo.toml")).with_pack(
+//!     PackCheck::new("cli", "cli-battery-pack")
+//!         .with_version("0.3.0")
+//!         .with_issue(CheckIssue::outdated_version("clap", "4.4.0", "4.5.0"))
+//!         .with_issue(CheckIssue::missing_dependency("dialoguer", "0.11")),
+//! );
+//! assert!(!report.is_ok());
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::status::{ProjectInfo, SCHEMA_VERSION};
+
+/// Top-level report emitted by `cargo bp check --json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CheckReport {
+    /// Schema version. Currently always `"1"`.
+    pub schema_version: String,
+
+    /// Information about the project that was checked.
+    pub project: ProjectInfo,
+
+    /// One entry per installed battery pack, in the order they appear in
+    /// `[build-dependencies]`.
+    pub packs: Vec<PackCheck>,
+}
+
+/// Top-level report emitted by `cargo bp check --json --workspace`
+/// (or with `-p <member>`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct WorkspaceCheckReport {
+    /// Schema version. Currently always `"1"`.
+    pub schema_version: String,
+
+    /// One report per selected workspace member, sorted by package name.
+    pub members: Vec<CheckReport>,
+}
+
+/// Check result for a single installed battery pack.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PackCheck {
+    /// Short name without the `-battery-pack` suffix, e.g. `"cli"`.
+    pub short_name: String,
+
+    /// Full crate name, e.g. `"cli-battery-pack"`.
+    pub name: String,
+
+    /// Version of the battery pack that was checked against. Absent when
+    /// the pack could not be loaded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+
+    /// Why the pack could not be loaded, if it couldn't. A pack that fails
+    /// to load fails the check.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// Every way the project disagrees with the pack. Empty when the
+    /// project is in sync.
+    pub issues: Vec<CheckIssue>,
+}
+
+/// One disagreement between a battery pack and the project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CheckIssue {
+    /// What kind of problem this is.
+    pub kind: CheckIssueKind,
+
+    /// The dependency the issue is about (e.g. `"clap"`).
+    pub crate_name: String,
+
+    /// Version currently in the project, when relevant.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_version: Option<String>,
+
+    /// Version recommended by the battery pack, when relevant.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recommended_version: Option<String>,
+
+    /// Features the battery pack enables that the project's entry lacks.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing_features: Vec<String>,
+
+    /// Human-readable description, suitable for a CI annotation.
+    pub message: String,
+}
+
+/// The kinds of problem `cargo bp check` reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum CheckIssueKind {
+    /// The project's version is older than the pack recommends.
+    OutdatedVersion,
+    /// A crate the pack provides for the active features is not a
+    /// dependency of the project.
+    MissingDependency,
+    /// The project's entry lacks Cargo features the pack enables.
+    MissingFeatures,
+    /// The entry and `[workspace.dependencies]` disagree: either a
+    /// `workspace = true` reference with no workspace entry, or a member
+    /// declaring its own version for a crate the workspace manages.
+    WorkspaceRefMismatch,
+}
+
+// ============================================================================
+// Builders
+// ============================================================================
+
+impl CheckReport {
+    /// Start building a report with the current [`SCHEMA_VERSION`] and
+    /// no packs.
+    pub fn new(project: ProjectInfo) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION.to_string(),
+            project,
+            packs: Vec::new(),
+        }
+    }
+
+    /// Append a single pack result.
+    pub fn with_pack(mut self, pack: PackCheck) -> Self {
+        self.packs.push(pack);
+        self
+    }
+
+    /// Extend the report with multiple pack results.
+    pub fn with_packs(mut self, packs: impl IntoIterator<Item = PackCheck>) -> Self {
+        self.packs.extend(packs);
+        self
+    }
+
+    /// Whether every pack loaded and none reported an issue.
+    pub fn is_ok(&self) -> bool {
+        self.packs.iter().all(PackCheck::is_ok)
+    }
+}
+
+impl WorkspaceCheckReport {
+    /// Start building a workspace report with the current
+    /// [`SCHEMA_VERSION`] and no members.
+    pub fn new() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION.to_string(),
+            members: Vec::new(),
+        }
+    }
+
+    /// Append a single member's report.
+    pub fn with_member(mut self, member: CheckReport) -> Self {
+        self.members.push(member);
+        self
+    }
+
+    /// Extend the report with multiple members' reports.
+    pub fn with_members(mut self, members: impl IntoIterator<Item = CheckReport>) -> Self {
+        self.members.extend(members);
+        self
+    }
+
+    /// Whether every member passed.
+    pub fn is_ok(&self) -> bool {
+        self.members.iter().all(CheckReport::is_ok)
+    }
+}
+
+impl Default for WorkspaceCheckReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PackCheck {
+    /// Start building a pack result with no version, error or issues.
+    pub fn new(short_name: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            short_name: short_name.into(),
+            name: name.into(),
+            version: None,
+            error: None,
+            issues: Vec::new(),
+        }
+    }
+
+    /// Set the version of the pack that was checked against.
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Record why the pack could not be loaded.
+    pub fn with_error(mut self, error: impl Into<String>) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+
+    /// Append a single issue.
+    pub fn with_issue(mut self, issue: CheckIssue) -> Self {
+        self.issues.push(issue);
+        self
+    }
+
+    /// Extend the issues list with multiple issues.
+    pub fn with_issues(mut self, issues: impl IntoIterator<Item = CheckIssue>) -> Self {
+        self.issues.extend(issues);
+        self
+    }
+
+    /// Whether the pack loaded and reported no issues.
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none() && self.issues.is_empty()
+    }
+}
+
+impl CheckIssue {
+    /// Build an issue from its kind, crate and message. Prefer the
+    /// per-kind constructors, which fill in a standard message.
+    pub fn new(
+        kind: CheckIssueKind,
+        crate_name: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind,
+            crate_name: crate_name.into(),
+            current_version: None,
+            recommended_version: None,
+            missing_features: Vec::new(),
+            message: message.into(),
+        }
+    }
+
+    /// The project has `current` but the pack recommends `recommended`.
+    pub fn outdated_version(
+        crate_name: impl Into<String>,
+        current: impl Into<String>,
+        recommended: impl Into<String>,
+    ) -> Self {
+        let crate_name = crate_name.into();
+        let (current, recommended) = (current.into(), recommended.into());
+        let message = format!("{crate_name}: {current} → {recommended}");
+        Self {
+            current_version: Some(current),
+            recommended_version: Some(recommended),
+            ..Self::new(CheckIssueKind::OutdatedVersion, crate_name, message)
+        }
+    }
+
+    /// The pack provides `crate_name` but the project doesn't depend on it.
+    pub fn missing_dependency(
+        crate_name: impl Into<String>,
+        recommended: impl Into<String>,
+    ) -> Self {
+        let crate_name = crate_name.into();
+        let recommended = recommended.into();
+        let message = format!("{crate_name}: missing from Cargo.toml");
+        Self {
+            recommended_version: (!recommended.is_empty()).then_some(recommended),
+            ..Self::new(CheckIssueKind::MissingDependency, crate_name, message)
+        }
+    }
+
+    /// The project's entry for `crate_name` lacks `features`.
+    pub fn missing_features<I, S>(crate_name: impl Into<String>, features: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let crate_name = crate_name.into();
+        let features: Vec<String> = features.into_iter().map(Into::into).collect();
+        let message = format!("{crate_name}: missing features {}", features.join(", "));
+        Self {
+            missing_features: features,
+            ..Self::new(CheckIssueKind::MissingFeatures, crate_name, message)
+        }
+    }
+
+    /// The entry for `crate_name` and `[workspace.dependencies]` disagree,
+    /// as described by `detail`.
+    pub fn workspace_ref_mismatch(crate_name: impl Into<String>, detail: &str) -> Self {
+        let crate_name = crate_name.into();
+        let message = format!("{crate_name}: {detail}");
+        Self::new(CheckIssueKind::WorkspaceRefMismatch, crate_name, message)
+    }
+}
@@ -38,22 +38,29 @@
 
 #![deny(missing_docs)]
 
+pub mod check;
 pub mod list;
+pub mod plan;
 pub mod runner;
 pub mod show;
 pub mod status;
 
 // Re-export the most commonly used items at the crate root for
 // ergonomic access. The full API stays addressable via the modules.
+pub use check::{CheckIssue, CheckIssueKind, CheckReport, PackCheck, WorkspaceCheckReport};
 pub use list::{ListReport, PackSummary};
+pub use plan::{FileAction, PlanReport, PlannedChange};
 pub use runner::{
-    Error, ListCommand, ShowCommand, StatusCommand, parse_list, parse_show, parse_status,
+    CheckCommand, Error, ListCommand, PlanCommand, ShowCommand, StatusCommand, parse_check,
+    parse_list, parse_plan, parse_show, parse_status, parse_workspace_check,
+    parse_workspace_status,
 };
 pub use show::{
     CategoryInfo, ExampleInfo, FeatureInfo, OwnerInfo, PickModeInfo, ShowReport, TemplateInfo,
 };
 pub use status::{
     DependencyWarning, InstalledPackStatus, ProjectInfo, SCHEMA_VERSION, StatusReport,
+    WorkspaceStatusReport,
 };
 
 #[cfg(test)]
@@ -178,6 +185,112 @@ mod tests {
         assert!(matches!(err, Error::Parse { .. }), "got {err:?}");
     }
 
+    /// Workspace reports round-trip through `parse_workspace_status`, and
+    /// each member keeps its package name.
+    #[test]
+    fn round_trip_workspace_status_report() {
+        let report = WorkspaceStatusReport::new()
+            .with_member(
+                StatusReport::new(ProjectInfo::new("/ws/api/Cargo.toml").with_package_name("api"))
+                    .with_pack(InstalledPackStatus::new("cli", "cli-battery-pack", "0.3.0")),
+            )
+            .with_members([StatusReport::new(
+                ProjectInfo::new("/ws/worker/Cargo.toml").with_package_name("worker"),
+            )]);
+
+        let bytes = serde_json::to_vec(&report).expect("serialize");
+        let parsed = parse_workspace_status(&bytes).expect("parse_workspace_status");
+        assert_eq!(parsed, report);
+        assert_eq!(
+            parsed.members[1].project.package_name.as_deref(),
+            Some("worker")
+        );
+    }
+
+    /// JSON serialization round-trips through `parse_check`, with issue
+    /// kinds in kebab-case.
+    #[test]
+    fn round_trip_check_report() {
+        let report = CheckReport::new(ProjectInfo::new("/tmp/proj/Cargo.toml"))
+            .with_pack(
+                PackCheck::new("cli", "cli-battery-pack")
+                    .with_version("0.3.0")
+                    .with_issue(CheckIssue::outdated_version("clap", "4.4.0", "4.5.0"))
+                    .with_issues([
+                        CheckIssue::missing_dependency("dialoguer", "0.11"),
+                        CheckIssue::missing_features("clap", ["derive", "env"]),
+                        CheckIssue::workspace_ref_mismatch(
+                            "anyhow",
+                            "not in [workspace.dependencies]",
+                        ),
+                    ]),
+            )
+            .with_pack(PackCheck::new("error", "error-battery-pack").with_error("not found"));
+
+        let json = serde_json::to_value(&report).expect("serialize");
+        assert_eq!(json["packs"][0]["issues"][2]["kind"], "missing-features");
+        assert_eq!(
+            json["packs"][0]["issues"][2]["message"],
+            "clap: missing features derive, env"
+        );
+
+        let bytes = serde_json::to_vec(&report).expect("serialize");
+        let parsed = parse_check(&bytes).expect("parse_check");
+        assert_eq!(parsed, report);
+    }
+
+    /// A check passes only when every pack loaded without issues.
+    #[test]
+    fn check_report_is_ok() {
+        let clean = PackCheck::new("cli", "cli-battery-pack").with_version("0.3.0");
+        let project = || ProjectInfo::new("Cargo.toml");
+
+        assert!(CheckReport::new(project()).is_ok());
+        assert!(CheckReport::new(project()).with_pack(clean.clone()).is_ok());
+        assert!(
+            !CheckReport::new(project())
+                .with_pack(
+                    clean
+                        .clone()
+                        .with_issue(CheckIssue::missing_dependency("clap", "4"))
+                )
+                .is_ok()
+        );
+        assert!(
+            !WorkspaceCheckReport::new()
+                .with_member(CheckReport::new(project()).with_pack(clean))
+                .with_member(
+                    CheckReport::new(project())
+                        .with_pack(PackCheck::new("error", "error-battery-pack").with_error("x")),
+                )
+                .is_ok()
+        );
+    }
+
+    /// JSON serialisation round-trips through `parse_plan`.
+    #[test]
+    fn round_trip_plan_report() {
+        let report = PlanReport::new("sync")
+            .with_change(
+                PlannedChange::new("Cargo.toml", FileAction::Modify)
+                    .with_diff("@@ -1 +1 @@\n-clap = \"4.4\"\n+clap = \"4.5\"\n"),
+            )
+            .with_change(PlannedChange::new("battery-pack.toml", FileAction::Create));
+
+        let json = serde_json::to_value(&report).expect("serialize");
+        assert_eq!(json["command"], "sync");
+        assert_eq!(json["changes"][1]["action"], "create");
+
+        let bytes = serde_json::to_vec(&report).expect("serialize");
+        let parsed = parse_plan(&bytes).expect("parse_plan");
+        assert_eq!(parsed, report);
+        assert_eq!(
+            parsed.change("battery-pack.toml").map(|c| c.action),
+            Some(FileAction::Create)
+        );
+        assert!(PlanReport::new("rm").is_empty());
+    }
+
     /// `ListReport::default()` creates an empty report.
     #[test]
     fn list_report_default() {
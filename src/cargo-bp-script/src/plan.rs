@@ -0,0 +1,122 @@
+//! Schema for `cargo bp add|sync|rm --dry-run --json` output.
+//!
+//! With `--dry-run`, the manifest-changing commands compute every edit to
+//! `Cargo.toml`, the workspace manifest and `battery-pack.toml` without
+//! writing anything. With `--json` as well, they print a [`PlanReport`]
+//! listing each file that would change and its diff.
+//!
+//! The types follow the same `new(required)` + chainable `with_*` pattern
+//! as the [status](crate::status) schema:
+//!
+//! ```
+//! use cargo_bp_script::{FileAction, PlanReport, PlannedChange};
+//!
+//! let report = PlanReport::new("sync").with_change(
+//!     PlannedChange::new("Cargo.toml", FileAction::Modify)
+//!         .with_diff("@@ -1 +1 @@\n-clap = \"4.4\"\n+clap = \"4.5\"\n"),
+//! );
+//! assert!(!report.is_empty());
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::status::SCHEMA_VERSION;
+
+/// Top-level report emitted by `cargo bp <command> --dry-run --json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PlanReport {
+    /// Schema version. Currently always `"1"`.
+    pub schema_version: String,
+
+    /// The command that was planned: `"add"`, `"sync"` or `"rm"`.
+    pub command: String,
+
+    /// One entry per file that would change, sorted by path. Empty when
+    /// the command would change nothing.
+    pub changes: Vec<PlannedChange>,
+}
+
+/// A single file the command would create, modify or delete.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PlannedChange {
+    /// Path of the file, relative to the workspace root (or to the crate,
+    /// outside a workspace), with `/` separators.
+    pub path: String,
+
+    /// What would happen to the file.
+    pub action: FileAction,
+
+    /// Unified diff hunks from the current to the planned content, without
+    /// the `---`/`+++` header lines.
+    pub diff: String,
+}
+
+/// What a planned change does to its file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum FileAction {
+    /// The file doesn't exist yet.
+    Create,
+    /// The file exists and its content would change.
+    Modify,
+    /// The file would be deleted.
+    Delete,
+}
+
+// ============================================================================
+// Builders
+// ============================================================================
+
+impl PlanReport {
+    /// Start building a report for `command` with the current
+    /// [`SCHEMA_VERSION`] and no changes.
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION.to_string(),
+            command: command.into(),
+            changes: Vec::new(),
+        }
+    }
+
+    /// Append a single change.
+    pub fn with_change(mut self, change: PlannedChange) -> Self {
+        self.changes.push(change);
+        self
+    }
+
+    /// Extend the report with multiple changes.
+    pub fn with_changes(mut self, changes: impl IntoIterator<Item = PlannedChange>) -> Self {
+        self.changes.extend(changes);
+        self
+    }
+
+    /// Whether the command would change nothing.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The change planned for `path`, if any.
+    pub fn change(&self, path: &str) -> Option<&PlannedChange> {
+        self.changes.iter().find(|c| c.path == path)
+    }
+}
+
+impl PlannedChange {
+    /// Start building a change with an empty diff.
+    pub fn new(path: impl Into<String>, action: FileAction) -> Self {
+        Self {
+            path: path.into(),
+            action,
+            diff: String::new(),
+        }
+    }
+
+    /// Set the diff hunks.
+    pub fn with_diff(mut self, diff: impl Into<String>) -> Self {
+        self.diff = diff.into();
+        self
+    }
+}
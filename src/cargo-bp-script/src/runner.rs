@@ -7,9 +7,11 @@ use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 use std::process::{Command, ExitStatus};
 
+use crate::check::{CheckReport, WorkspaceCheckReport};
 use crate::list::ListReport;
+use crate::plan::PlanReport;
 use crate::show::ShowReport;
-use crate::status::StatusReport;
+use crate::status::{StatusReport, WorkspaceStatusReport};
 
 /// Error returned by the runner.
 #[derive(Debug, thiserror::Error)]
@@ -67,6 +69,7 @@ pub struct StatusCommand {
     cwd: Option<PathBuf>,
     crate_source: Option<PathBuf>,
     path: Option<PathBuf>,
+    packages: Vec<String>,
 }
 
 impl Default for StatusCommand {
@@ -76,6 +79,7 @@ impl Default for StatusCommand {
             cwd: None,
             crate_source: None,
             path: None,
+            packages: Vec::new(),
         }
     }
 }
@@ -128,10 +132,39 @@ impl StatusCommand {
         self
     }
 
+    /// Forward `-p <member>` to `cargo bp status` when calling
+    /// [`run_workspace`](Self::run_workspace). May be called repeatedly;
+    /// with no packages, every workspace member with packs is reported.
+    pub fn package(mut self, name: impl Into<String>) -> Self {
+        self.packages.push(name.into());
+        self
+    }
+
     /// Spawn `cargo bp status --json`, capture stdout, and parse it
     /// into a [`StatusReport`].
     pub fn run(&self) -> Result<StatusReport, Error> {
-        // Layout: <program> bp [--crate-source <p>] status --json [--path <p>]
+        let output = spawn(&self.program, &mut self.command(&[]))?;
+        parse_status(&output)
+    }
+
+    /// Spawn `cargo bp status --json --workspace` (or `-p <member>` for
+    /// each [`package`](Self::package)), capture stdout, and parse it into
+    /// a [`WorkspaceStatusReport`].
+    pub fn run_workspace(&self) -> Result<WorkspaceStatusReport, Error> {
+        let mut selection = Vec::new();
+        if self.packages.is_empty() {
+            selection.push(OsString::from("--workspace"));
+        }
+        for package in &self.packages {
+            selection.push(OsString::from("-p"));
+            selection.push(OsString::from(package));
+        }
+        let output = spawn(&self.program, &mut self.command(&selection))?;
+        parse_workspace_status(&output)
+    }
+
+    fn command(&self, extra: &[OsString]) -> Command {
+        // Layout: <program> bp [--crate-source <p>] status --json [--path <p>] [extra...]
         let mut cmd = Command::new(&self.program);
         cmd.arg("bp");
         if let Some(cs) = &self.crate_source {
@@ -141,12 +174,250 @@ impl StatusCommand {
         if let Some(p) = &self.path {
             cmd.arg("--path").arg(p);
         }
+        cmd.args(extra);
+        if let Some(d) = &self.cwd {
+            cmd.current_dir(d);
+        }
+        cmd
+    }
+}
+
+// ============================================================================
+// CheckCommand
+// ============================================================================
+
+/// Builder for invoking `cargo bp check --json` and parsing its output.
+///
+/// `cargo bp check` exits non-zero when it finds problems, but the runner
+/// still returns the parsed report; use [`CheckReport::is_ok`] to decide
+/// whether to fail. An [`Error::ExitStatus`] means the check could not run
+/// at all.
+///
+/// # Example
+///
+/// ```no_run
+/// use cargo_bp_script::CheckCommand;
+///
+/// let report = CheckCommand::new().run()?;
+/// for pack in &report.packs {
+///     for issue in &pack.issues {
+///         println!("{}: {}", pack.short_name, issue.message);
+///     }
+/// }
+/// if !report.is_ok() {
+///     std::process::exit(1);
+/// }
+/// # Ok::<(), cargo_bp_script::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct CheckCommand {
+    program: OsString,
+    cwd: Option<PathBuf>,
+    crate_source: Option<PathBuf>,
+    path: Option<PathBuf>,
+    packages: Vec<String>,
+}
+
+impl Default for CheckCommand {
+    fn default() -> Self {
+        Self {
+            program: OsString::from("cargo"),
+            cwd: None,
+            crate_source: None,
+            path: None,
+            packages: Vec::new(),
+        }
+    }
+}
+
+impl CheckCommand {
+    /// Create a new builder with default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Override the program used to invoke `cargo bp`.
+    pub fn program(mut self, program: impl Into<OsString>) -> Self {
+        self.program = program.into();
+        self
+    }
+
+    /// Run the command in a different working directory.
+    pub fn cwd(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(dir.into());
+        self
+    }
+
+    /// Forward `--crate-source <path>` to `cargo bp`.
+    pub fn crate_source(mut self, path: impl Into<PathBuf>) -> Self {
+        self.crate_source = Some(path.into());
+        self
+    }
+
+    /// Forward `--path <path>` to `cargo bp check`.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Forward `-p <member>` to `cargo bp check` when calling
+    /// [`run_workspace`](Self::run_workspace). May be called repeatedly;
+    /// with no packages, every workspace member with packs is checked.
+    pub fn package(mut self, name: impl Into<String>) -> Self {
+        self.packages.push(name.into());
+        self
+    }
+
+    /// Spawn `cargo bp check --json`, capture stdout, and parse it into
+    /// a [`CheckReport`].
+    pub fn run(&self) -> Result<CheckReport, Error> {
+        run_report(&self.program, &mut self.command(&[]), parse_check)
+    }
+
+    /// Spawn `cargo bp check --json --workspace` (or `-p <member>` for
+    /// each [`package`](Self::package)), capture stdout, and parse it into
+    /// a [`WorkspaceCheckReport`].
+    pub fn run_workspace(&self) -> Result<WorkspaceCheckReport, Error> {
+        let mut selection = Vec::new();
+        if self.packages.is_empty() {
+            selection.push(OsString::from("--workspace"));
+        }
+        for package in &self.packages {
+            selection.push(OsString::from("-p"));
+            selection.push(OsString::from(package));
+        }
+        run_report(
+            &self.program,
+            &mut self.command(&selection),
+            parse_workspace_check,
+        )
+    }
+
+    fn command(&self, extra: &[OsString]) -> Command {
+        // Layout: <program> bp [--crate-source <p>] check --json [--path <p>] [extra...]
+        let mut cmd = Command::new(&self.program);
+        cmd.arg("bp");
+        if let Some(cs) = &self.crate_source {
+            cmd.arg("--crate-source").arg(cs);
+        }
+        cmd.arg("check").arg("--json");
+        if let Some(p) = &self.path {
+            cmd.arg("--path").arg(p);
+        }
+        cmd.args(extra);
+        if let Some(d) = &self.cwd {
+            cmd.current_dir(d);
+        }
+        cmd
+    }
+}
+
+// ============================================================================
+// PlanCommand
+// ============================================================================
+
+/// Builder for invoking `cargo bp add|sync|rm --dry-run --json` and parsing
+/// its output. Nothing is written to the project.
+///
+/// # Example
+///
+/// ```no_run
+/// use cargo_bp_script::PlanCommand;
+///
+/// let plan = PlanCommand::sync().arg("--workspace").run()?;
+/// for change in &plan.changes {
+///     println!("{}:\n{}", change.path, change.diff);
+/// }
+/// # Ok::<(), cargo_bp_script::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct PlanCommand {
+    program: OsString,
+    cwd: Option<PathBuf>,
+    crate_source: Option<PathBuf>,
+    path: Option<PathBuf>,
+    subcommand: &'static str,
+    args: Vec<OsString>,
+}
+
+impl PlanCommand {
+    fn new(subcommand: &'static str, args: Vec<OsString>) -> Self {
+        Self {
+            program: OsString::from("cargo"),
+            cwd: None,
+            crate_source: None,
+            path: None,
+            subcommand,
+            args,
+        }
+    }
+
+    /// Plan `cargo bp sync`.
+    pub fn sync() -> Self {
+        Self::new("sync", Vec::new())
+    }
+
+    /// Plan `cargo bp add <battery_pack>`.
+    pub fn add(battery_pack: impl Into<String>) -> Self {
+        Self::new("add", vec![OsString::from(battery_pack.into())])
+    }
+
+    /// Plan `cargo bp rm <battery_pack>`.
+    pub fn rm(battery_pack: impl Into<String>) -> Self {
+        Self::new("rm", vec![OsString::from(battery_pack.into())])
+    }
+
+    /// Override the program used to invoke `cargo bp`.
+    pub fn program(mut self, program: impl Into<OsString>) -> Self {
+        self.program = program.into();
+        self
+    }
+
+    /// Run the command in a different working directory.
+    pub fn cwd(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(dir.into());
+        self
+    }
+
+    /// Forward `--crate-source <path>` to `cargo bp`.
+    pub fn crate_source(mut self, path: impl Into<PathBuf>) -> Self {
+        self.crate_source = Some(path.into());
+        self
+    }
+
+    /// Forward `--path <path>` to `cargo bp add` or `cargo bp sync`.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Append an argument to the subcommand, e.g. `-F indicators` for
+    /// `add`, `--workspace` for `sync` or `--remove-deps` for `rm`.
+    pub fn arg(mut self, arg: impl Into<OsString>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Spawn `cargo bp <subcommand> --dry-run --json`, capture stdout, and
+    /// parse it into a [`PlanReport`].
+    pub fn run(&self) -> Result<PlanReport, Error> {
+        // Layout: <program> bp [--crate-source <p>] <sub> --dry-run --json [--path <p>] [args...]
+        let mut cmd = Command::new(&self.program);
+        cmd.arg("bp");
+        if let Some(cs) = &self.crate_source {
+            cmd.arg("--crate-source").arg(cs);
+        }
+        cmd.arg(self.subcommand).arg("--dry-run").arg("--json");
+        if let Some(p) = &self.path {
+            cmd.arg("--path").arg(p);
+        }
+        cmd.args(&self.args);
         if let Some(d) = &self.cwd {
             cmd.current_dir(d);
         }
 
         let output = spawn(&self.program, &mut cmd)?;
-        parse_status(&output)
+        parse_plan(&output)
     }
 }
 
@@ -339,6 +610,41 @@ pub fn parse_status(bytes: &[u8]) -> Result<StatusReport, Error> {
     })
 }
 
+/// Parse a `cargo bp status --json --workspace` payload into a
+/// [`WorkspaceStatusReport`].
+pub fn parse_workspace_status(bytes: &[u8]) -> Result<WorkspaceStatusReport, Error> {
+    serde_json::from_slice(bytes).map_err(|source| Error::Parse {
+        command: "cargo bp status --json --workspace".into(),
+        source,
+    })
+}
+
+/// Parse a `cargo bp check --json` payload into a [`CheckReport`].
+pub fn parse_check(bytes: &[u8]) -> Result<CheckReport, Error> {
+    serde_json::from_slice(bytes).map_err(|source| Error::Parse {
+        command: "cargo bp check --json".into(),
+        source,
+    })
+}
+
+/// Parse a `cargo bp check --json --workspace` payload into a
+/// [`WorkspaceCheckReport`].
+pub fn parse_workspace_check(bytes: &[u8]) -> Result<WorkspaceCheckReport, Error> {
+    serde_json::from_slice(bytes).map_err(|source| Error::Parse {
+        command: "cargo bp check --json --workspace".into(),
+        source,
+    })
+}
+
+/// Parse a `cargo bp add|sync|rm --dry-run --json` payload into a
+/// [`PlanReport`].
+pub fn parse_plan(bytes: &[u8]) -> Result<PlanReport, Error> {
+    serde_json::from_slice(bytes).map_err(|source| Error::Parse {
+        command: "cargo bp --dry-run --json".into(),
+        source,
+    })
+}
+
 /// Parse a `cargo bp list --json` payload into a [`ListReport`].
 pub fn parse_list(bytes: &[u8]) -> Result<ListReport, Error> {
     serde_json::from_slice(bytes).map_err(|source| Error::Parse {
@@ -375,6 +681,30 @@ fn spawn(program: &OsStr, cmd: &mut Command) -> Result<Vec<u8>, Error> {
     Ok(output.stdout)
 }
 
+/// Spawn a command whose non-zero exit still carries a report on stdout.
+///
+/// Output that parses is returned whatever the exit status; otherwise a
+/// failed run is reported as [`Error::ExitStatus`].
+fn run_report<T>(
+    program: &OsStr,
+    cmd: &mut Command,
+    parse: impl Fn(&[u8]) -> Result<T, Error>,
+) -> Result<T, Error> {
+    let output = cmd.output().map_err(|source| Error::Spawn {
+        program: program_display(program),
+        source,
+    })?;
+    match parse(&output.stdout) {
+        Ok(report) => Ok(report),
+        Err(_) if !output.status.success() => Err(Error::ExitStatus {
+            command: format_command(cmd),
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        }),
+        Err(err) => Err(err),
+    }
+}
+
 /// Best-effort display string for an `OsStr`, used only for error messages.
 fn program_display(program: &OsStr) -> String {
     program.to_string_lossy().into_owned()
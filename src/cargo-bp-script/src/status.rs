@@ -51,12 +51,29 @@ pub struct StatusReport {
     pub packs: Vec<InstalledPackStatus>,
 }
 
+/// Top-level report emitted by `cargo bp status --json --workspace`
+/// (or with `-p <member>`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct WorkspaceStatusReport {
+    /// Schema version. Currently always `"1"`.
+    pub schema_version: String,
+
+    /// One report per selected workspace member, sorted by package name.
+    pub members: Vec<StatusReport>,
+}
+
 /// Information about the project whose status was inspected.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[non_exhaustive]
 pub struct ProjectInfo {
     /// Path to the `Cargo.toml` that was inspected.
     pub manifest_path: PathBuf,
+
+    /// Package name of the inspected crate, when known. Always set for
+    /// the members of a [`WorkspaceStatusReport`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package_name: Option<String>,
 }
 
 /// Status of a single installed battery pack.
@@ -138,13 +155,49 @@ impl StatusReport {
     }
 }
 
+impl WorkspaceStatusReport {
+    /// Start building a workspace report with the current
+    /// [`SCHEMA_VERSION`] and no members.
+    pub fn new() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION.to_string(),
+            members: Vec::new(),
+        }
+    }
+
+    /// Append a single member's report.
+    pub fn with_member(mut self, member: StatusReport) -> Self {
+        self.members.push(member);
+        self
+    }
+
+    /// Extend the report with multiple members' reports.
+    pub fn with_members(mut self, members: impl IntoIterator<Item = StatusReport>) -> Self {
+        self.members.extend(members);
+        self
+    }
+}
+
+impl Default for WorkspaceStatusReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ProjectInfo {
     /// Build a [`ProjectInfo`] from the inspected manifest path.
     pub fn new(manifest_path: impl Into<PathBuf>) -> Self {
         Self {
             manifest_path: manifest_path.into(),
+            package_name: None,
         }
     }
+
+    /// Set the package name of the inspected crate.
+    pub fn with_package_name(mut self, name: impl Into<String>) -> Self {
+        self.package_name = Some(name.into());
+        self
+    }
 }
 
 impl InstalledPackStatus {
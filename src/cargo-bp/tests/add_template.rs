@@ -31,6 +31,27 @@ fn create_existing_project(dir: &Path) {
     std::fs::write(dir.join("src/main.rs"), "fn main() {}\n").unwrap();
 }
 
+/// Parse the `applied-templates` entries of the first pack in a state file
+/// into `(name, version)` pairs.
+fn applied_templates(state_content: &str) -> Vec<(String, Option<String>)> {
+    let state: toml::Value = toml::from_str(state_content).expect("valid state TOML");
+    state["battery-pack"][0]["applied-templates"]
+        .as_array()
+        .expect("applied-templates array")
+        .iter()
+        .map(|entry| match entry {
+            toml::Value::String(name) => (name.clone(), None),
+            other => (
+                other["name"].as_str().unwrap().to_string(),
+                other
+                    .get("version")
+                    .and_then(|v| v.as_str())
+                    .map(String::from),
+            ),
+        })
+        .collect()
+}
+
 #[test]
 fn add_template_merges_into_existing_project() {
     let tmp = tempfile::tempdir().unwrap();
@@ -427,8 +448,9 @@ fn add_template_records_in_state_and_shows_in_status() {
         state_content.contains("applied-templates"),
         "state file should contain applied-templates section:\n{state_content}"
     );
-    assert!(
-        state_content.contains(r#"applied-templates = ["default"]"#),
+    assert_eq!(
+        applied_templates(&state_content),
+        [("default".to_string(), Some("0.2.0".to_string()))],
         "state file should record the 'default' template:\n{state_content}"
     );
 
@@ -500,8 +522,9 @@ fn add_template_twice_does_not_duplicate_in_state() {
         count, 1,
         "applied-templates should appear once in state, found {count}:\n{state_content}"
     );
-    assert!(
-        state_content.contains(r#"applied-templates = ["default"]"#),
+    assert_eq!(
+        applied_templates(&state_content),
+        [("default".to_string(), Some("0.2.0".to_string()))],
         "should contain exactly one entry:\n{state_content}"
     );
 }
@@ -545,8 +568,9 @@ fn new_from_template_records_in_state() {
     );
 
     let state_content = std::fs::read_to_string(&state_path).unwrap();
-    assert!(
-        state_content.contains(r#"applied-templates = ["default"]"#),
+    assert_eq!(
+        applied_templates(&state_content),
+        [("default".to_string(), Some("0.2.0".to_string()))],
         "state file should record the template used:\n{state_content}"
     );
     assert!(
@@ -0,0 +1,151 @@
+//! Integration tests for `cargo bp check` as a CI gate.
+//!
+//! Exercises the exit status and `--json` output of the binary, and the
+//! `cargo-bp-script` runner that wraps it.
+
+use assert_cmd::Command;
+use cargo_bp_script::{CheckCommand, CheckIssueKind, parse_check};
+use std::path::{Path, PathBuf};
+
+fn cargo_bp() -> Command {
+    Command::new(assert_cmd::cargo::cargo_bin!("cargo-bp"))
+}
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("tests/fixtures")
+}
+
+fn fancy_fixture() -> PathBuf {
+    fixtures_dir().join("fancy-battery-pack")
+}
+
+/// Build a temp project using `fancy-battery-pack`, with `deps` appended
+/// after the `[build-dependencies]` table.
+fn make_project(deps: &str) -> tempfile::TempDir {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(
+        tmp.path().join("Cargo.toml"),
+        format!(
+            r#"[package]
+name = "test-consumer"
+version = "0.1.0"
+edition = "2021"
+
+[build-dependencies]
+fancy-battery-pack = "0.2.0"
+{deps}"#
+        ),
+    )
+    .unwrap();
+    std::fs::create_dir_all(tmp.path().join("src")).unwrap();
+    std::fs::write(tmp.path().join("src/lib.rs"), "").unwrap();
+    tmp
+}
+
+// [verify cli.check.exit-code]
+// [verify cli.check.json]
+// [verify cli.check.path]
+#[test]
+fn check_json_reports_drift_and_fails() {
+    let tmp = make_project("\n[dependencies]\nclap = \"3.0\"\n");
+    let fixture = fancy_fixture();
+
+    let output = cargo_bp()
+        .args([
+            "bp",
+            "check",
+            "--json",
+            "--path",
+            &fixture.to_string_lossy(),
+        ])
+        .current_dir(tmp.path())
+        .output()
+        .expect("failed to run cargo-bp");
+
+    assert!(!output.status.success(), "check should fail on drift");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("problem(s)"), "stderr: {stderr}");
+
+    let report = parse_check(&output.stdout).unwrap_or_else(|err| {
+        panic!(
+            "parse_check failed: {err}\nraw stdout: {}",
+            String::from_utf8_lossy(&output.stdout)
+        )
+    });
+    assert!(!report.is_ok());
+    assert_eq!(report.packs.len(), 1);
+    let pack = &report.packs[0];
+    assert_eq!(pack.name, "fancy-battery-pack");
+    assert_eq!(pack.version.as_deref(), Some("0.2.0"));
+
+    let kinds: Vec<_> = pack
+        .issues
+        .iter()
+        .map(|i| (i.crate_name.as_str(), i.kind))
+        .collect();
+    assert!(kinds.contains(&("clap", CheckIssueKind::OutdatedVersion)));
+    assert!(kinds.contains(&("clap", CheckIssueKind::MissingFeatures)));
+    assert!(kinds.contains(&("dialoguer", CheckIssueKind::MissingDependency)));
+}
+
+// [verify cli.check.exit-code]
+// [verify cli.check.output]
+#[test]
+fn check_in_sync_project_passes() {
+    let tmp = make_project(
+        r#"
+[dependencies]
+clap = { version = "4", features = ["derive"] }
+dialoguer = "0.11"
+
+[dev-dependencies]
+assert_cmd = "2.0"
+predicates = "3.0"
+"#,
+    );
+    let fixture = fancy_fixture();
+
+    let output = cargo_bp()
+        .args(["bp", "check", "--path", &fixture.to_string_lossy()])
+        .current_dir(tmp.path())
+        .output()
+        .expect("failed to run cargo-bp");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {stdout}\nstderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("All battery packs are up to date!"),
+        "{stdout}"
+    );
+}
+
+// [verify cli.check.json]
+#[test]
+fn check_command_runner_returns_report_on_failure() {
+    let tmp = make_project("");
+
+    let report = CheckCommand::new()
+        .program(assert_cmd::cargo::cargo_bin!("cargo-bp"))
+        .cwd(tmp.path())
+        .path(fancy_fixture())
+        .run()
+        .expect("CheckCommand::run should return the report despite the exit status");
+
+    assert!(!report.is_ok());
+    let missing: Vec<_> = report.packs[0]
+        .issues
+        .iter()
+        .filter(|i| i.kind == CheckIssueKind::MissingDependency)
+        .map(|i| i.crate_name.as_str())
+        .collect();
+    assert_eq!(missing, ["assert_cmd", "clap", "dialoguer", "predicates"]);
+}
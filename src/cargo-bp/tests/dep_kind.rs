@@ -0,0 +1,215 @@
+//! Integration tests for choosing a curated crate's dependency kind with
+//! `cargo bp add --as-dev/--as-build/--as-normal`, and `sync` keeping it.
+
+use assert_cmd::Command;
+use std::path::{Path, PathBuf};
+
+fn cargo_bp() -> Command {
+    Command::new(assert_cmd::cargo::cargo_bin!("cargo-bp"))
+}
+
+fn fancy_fixture() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("tests/fixtures/fancy-battery-pack")
+}
+
+fn make_project() -> tempfile::TempDir {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(
+        tmp.path().join("Cargo.toml"),
+        r#"[package]
+name = "test-consumer"
+version = "0.1.0"
+edition = "2021"
+
+[build-dependencies]
+fancy-battery-pack = "0.2.0"
+"#,
+    )
+    .unwrap();
+    tmp
+}
+
+fn run(tmp: &Path, args: &[&str]) -> String {
+    let fixture = fancy_fixture().to_string_lossy().into_owned();
+    let output = cargo_bp()
+        .arg("bp")
+        .args(args)
+        .args(["--path", &fixture])
+        .current_dir(tmp)
+        .output()
+        .expect("failed to run cargo-bp");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// The names in one dependency section of the project's Cargo.toml.
+fn section(tmp: &Path, name: &str) -> Vec<String> {
+    let content = std::fs::read_to_string(tmp.join("Cargo.toml")).unwrap();
+    let doc: toml::Value = toml::from_str(&content).unwrap();
+    doc.get(name)
+        .and_then(|t| t.as_table())
+        .map(|t| t.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn state(tmp: &Path) -> String {
+    std::fs::read_to_string(tmp.join("battery-pack.toml")).unwrap()
+}
+
+// [verify cli.add.dep-kind-override]
+// [verify manifest.state.dep-kinds]
+// [verify cli.sync.dep-kind]
+#[test]
+fn as_dev_is_recorded_and_kept_by_sync() {
+    let tmp = make_project();
+    run(tmp.path(), &["-N", "add", "fancy", "--as-dev", "clap"]);
+
+    assert_eq!(section(tmp.path(), "dependencies"), ["dialoguer"]);
+    assert_eq!(
+        section(tmp.path(), "dev-dependencies"),
+        ["assert_cmd", "clap", "predicates"]
+    );
+    assert!(
+        state(tmp.path()).contains("[battery-pack.dep-kinds]\nclap = \"dev\""),
+        "{}",
+        state(tmp.path())
+    );
+
+    let stdout = run(tmp.path(), &["sync"]);
+    assert!(
+        stdout.contains("All dependencies are up to date"),
+        "{stdout}"
+    );
+    assert_eq!(section(tmp.path(), "dependencies"), ["dialoguer"]);
+    assert_eq!(
+        section(tmp.path(), "dev-dependencies"),
+        ["assert_cmd", "clap", "predicates"]
+    );
+}
+
+// [verify cli.add.dep-kind-override]
+// [verify manifest.deps.move-kind]
+#[test]
+fn as_normal_moves_the_crate_back_and_drops_the_override() {
+    let tmp = make_project();
+    run(tmp.path(), &["-N", "add", "fancy", "--as-build", "clap"]);
+    assert_eq!(
+        section(tmp.path(), "build-dependencies"),
+        ["clap", "fancy-battery-pack"]
+    );
+
+    run(tmp.path(), &["-N", "add", "fancy", "--as-normal", "clap"]);
+    assert_eq!(section(tmp.path(), "dependencies"), ["clap", "dialoguer"]);
+    assert_eq!(
+        section(tmp.path(), "build-dependencies"),
+        ["fancy-battery-pack"]
+    );
+    assert!(
+        !state(tmp.path()).contains("dep-kinds"),
+        "{}",
+        state(tmp.path())
+    );
+}
+
+// [verify cli.sync.dep-kind]
+// [verify manifest.deps.move-kind]
+#[test]
+fn sync_moves_a_crate_to_the_kind_recorded_in_state() {
+    let tmp = make_project();
+    run(tmp.path(), &["-N", "add", "fancy"]);
+    let edited = format!(
+        "{}\n[battery-pack.dep-kinds]\nclap = \"dev\"\n",
+        state(tmp.path())
+    );
+    std::fs::write(tmp.path().join("battery-pack.toml"), edited).unwrap();
+
+    let stdout = run(tmp.path(), &["sync"]);
+    assert!(
+        stdout.contains("~ clap (moved to dev-dependencies)"),
+        "{stdout}"
+    );
+    assert_eq!(section(tmp.path(), "dependencies"), ["dialoguer"]);
+    assert_eq!(
+        section(tmp.path(), "dev-dependencies"),
+        ["assert_cmd", "clap", "predicates"]
+    );
+}
+
+// [verify cli.add.dep-kind-override]
+#[test]
+fn unknown_or_conflicting_kinds_are_rejected() {
+    let tmp = make_project();
+    let fixture = fancy_fixture().to_string_lossy().into_owned();
+    for (args, message) in [
+        (
+            vec!["--as-dev", "nonexistent"],
+            "'nonexistent' is not a crate in fancy-battery-pack",
+        ),
+        (
+            vec!["--as-dev", "clap", "--as-build", "clap"],
+            "'clap' can't be added as both dev-dependencies and build-dependencies",
+        ),
+    ] {
+        let output = cargo_bp()
+            .args(["bp", "-N", "add", "fancy", "--path", &fixture])
+            .args(&args)
+            .current_dir(tmp.path())
+            .output()
+            .expect("failed to run cargo-bp");
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains(message), "{stderr}");
+    }
+    assert!(!tmp.path().join("battery-pack.toml").exists());
+}
+
+// [verify cli.add.dep-kind-override]
+#[test]
+fn as_dev_for_an_optional_crate_needs_it_selected() {
+    let tmp = make_project();
+    let fixture = fancy_fixture().to_string_lossy().into_owned();
+    let output = cargo_bp()
+        .args(["bp", "-N", "add", "fancy", "--as-dev", "indicatif"])
+        .args(["--path", &fixture])
+        .current_dir(tmp.path())
+        .output()
+        .expect("failed to run cargo-bp");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("'indicatif' can't be added as dev-dependencies"),
+        "{stderr}"
+    );
+    assert!(!tmp.path().join("battery-pack.toml").exists());
+
+    run(
+        tmp.path(),
+        &[
+            "-N",
+            "add",
+            "fancy",
+            "-F",
+            "indicators",
+            "--as-dev",
+            "indicatif",
+        ],
+    );
+    assert_eq!(
+        section(tmp.path(), "dev-dependencies"),
+        ["assert_cmd", "indicatif", "predicates"]
+    );
+    assert!(
+        state(tmp.path()).contains("[battery-pack.dep-kinds]\nindicatif = \"dev\""),
+        "{}",
+        state(tmp.path())
+    );
+}
@@ -0,0 +1,241 @@
+//! Integration tests for `--dry-run` on `add`, `sync` and `rm`.
+//!
+//! Each test snapshots the project before running and checks that nothing
+//! on disk changed afterwards.
+
+use assert_cmd::Command;
+use cargo_bp_script::{FileAction, PlanCommand};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+fn cargo_bp() -> Command {
+    Command::new(assert_cmd::cargo::cargo_bin!("cargo-bp"))
+}
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("tests/fixtures")
+}
+
+fn fancy_fixture() -> PathBuf {
+    fixtures_dir().join("fancy-battery-pack")
+}
+
+/// A project using `fancy-battery-pack` with an outdated `clap`.
+fn make_project() -> tempfile::TempDir {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(
+        tmp.path().join("Cargo.toml"),
+        r#"[package]
+name = "test-consumer"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+clap = "3.0"
+
+[build-dependencies]
+fancy-battery-pack = "0.2.0"
+"#,
+    )
+    .unwrap();
+    std::fs::create_dir_all(tmp.path().join("src")).unwrap();
+    std::fs::write(tmp.path().join("src/lib.rs"), "").unwrap();
+    tmp
+}
+
+/// Every file under `root`, with its content.
+fn snapshot(root: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+    walkdir::WalkDir::new(root)
+        .into_iter()
+        .map(Result::unwrap)
+        .filter(|e| e.file_type().is_file())
+        .map(|e| (e.path().to_path_buf(), std::fs::read(e.path()).unwrap()))
+        .collect()
+}
+
+// [verify cli.dry-run.flag]
+// [verify cli.dry-run.diff]
+// [verify cli.dry-run.no-write]
+#[test]
+fn sync_dry_run_prints_diff_and_writes_nothing() {
+    let tmp = make_project();
+    let before = snapshot(tmp.path());
+    let fixture = fancy_fixture();
+
+    let output = cargo_bp()
+        .args(["bp", "sync", "--dry-run", "--path"])
+        .arg(&fixture)
+        .current_dir(tmp.path())
+        .output()
+        .expect("failed to run cargo-bp");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("--- a/Cargo.toml\n+++ b/Cargo.toml\n"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("-clap = \"3.0\""), "{stdout}");
+    assert!(stdout.contains("+++ b/battery-pack.toml"), "{stdout}");
+    assert!(stdout.contains("nothing was written"), "{stdout}");
+    assert_eq!(snapshot(tmp.path()), before);
+}
+
+// [verify cli.dry-run.json]
+// [verify cli.dry-run.no-write]
+#[test]
+fn sync_dry_run_json_reports_each_file() {
+    let tmp = make_project();
+    let before = snapshot(tmp.path());
+
+    let plan = PlanCommand::sync()
+        .program(assert_cmd::cargo::cargo_bin!("cargo-bp"))
+        .cwd(tmp.path())
+        .path(fancy_fixture())
+        .run()
+        .expect("PlanCommand::sync failed");
+
+    assert_eq!(plan.command, "sync");
+    let manifest = plan.change("Cargo.toml").expect("Cargo.toml planned");
+    assert_eq!(manifest.action, FileAction::Modify);
+    assert!(manifest.diff.contains("+dialoguer"), "{}", manifest.diff);
+    assert_eq!(
+        plan.change("battery-pack.toml").map(|c| c.action),
+        Some(FileAction::Create)
+    );
+    assert_eq!(snapshot(tmp.path()), before);
+}
+
+// [verify cli.dry-run.json]
+#[test]
+fn sync_dry_run_in_workspace_uses_root_relative_paths() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(
+        tmp.path().join("Cargo.toml"),
+        "[workspace]\nmembers = [\"api\"]\nresolver = \"2\"\n\n[workspace.dependencies]\nclap = \"3.0\"\n",
+    )
+    .unwrap();
+    let api = tmp.path().join("api");
+    std::fs::create_dir_all(api.join("src")).unwrap();
+    std::fs::write(api.join("src/lib.rs"), "").unwrap();
+    std::fs::write(
+        api.join("Cargo.toml"),
+        r#"[package]
+name = "api"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+clap.workspace = true
+
+[build-dependencies]
+fancy-battery-pack = "0.2.0"
+"#,
+    )
+    .unwrap();
+    let before = snapshot(tmp.path());
+
+    let plan = PlanCommand::sync()
+        .program(assert_cmd::cargo::cargo_bin!("cargo-bp"))
+        .cwd(&api)
+        .path(fancy_fixture())
+        .run()
+        .expect("PlanCommand::sync failed");
+
+    let paths: Vec<_> = plan.changes.iter().map(|c| c.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        ["Cargo.toml", "api/Cargo.toml", "api/battery-pack.toml"]
+    );
+    assert!(plan.changes[0].diff.contains("-clap = \"3.0\""));
+    assert_eq!(snapshot(tmp.path()), before);
+}
+
+// [verify cli.dry-run.flag]
+// [verify cli.dry-run.no-write]
+#[test]
+fn add_dry_run_json_plans_new_pack() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(
+        tmp.path().join("Cargo.toml"),
+        "[package]\nname = \"test-consumer\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    std::fs::create_dir_all(tmp.path().join("src")).unwrap();
+    std::fs::write(tmp.path().join("src/lib.rs"), "").unwrap();
+    let before = snapshot(tmp.path());
+
+    let plan = PlanCommand::add("fancy")
+        .program(assert_cmd::cargo::cargo_bin!("cargo-bp"))
+        .cwd(tmp.path())
+        .path(fancy_fixture())
+        .arg("-F")
+        .arg("indicators")
+        .run()
+        .expect("PlanCommand::add failed");
+
+    assert_eq!(plan.command, "add");
+    let diff = &plan.change("Cargo.toml").expect("Cargo.toml planned").diff;
+    for dep in ["clap", "dialoguer", "indicatif", "console"] {
+        assert!(diff.contains(&format!("+{dep}")), "missing {dep}:\n{diff}");
+    }
+    assert!(plan.change("battery-pack.toml").is_some());
+    assert_eq!(snapshot(tmp.path()), before);
+}
+
+// [verify cli.dry-run.json]
+// [verify cli.dry-run.no-write]
+#[test]
+fn rm_dry_run_json_plans_build_rs_deletion() {
+    let tmp = make_project();
+    std::fs::write(
+        tmp.path().join("build.rs"),
+        "fn main() {\n    fancy_battery_pack::validate();\n}\n",
+    )
+    .unwrap();
+    let before = snapshot(tmp.path());
+
+    let plan = PlanCommand::rm("fancy")
+        .program(assert_cmd::cargo::cargo_bin!("cargo-bp"))
+        .cwd(tmp.path())
+        .run()
+        .expect("PlanCommand::rm failed");
+
+    assert_eq!(plan.command, "rm");
+    assert_eq!(
+        plan.change("build.rs").map(|c| c.action),
+        Some(FileAction::Delete)
+    );
+    let manifest = plan.change("Cargo.toml").expect("Cargo.toml planned");
+    assert!(
+        manifest.diff.contains("-fancy-battery-pack"),
+        "{}",
+        manifest.diff
+    );
+    assert_eq!(snapshot(tmp.path()), before);
+}
+
+// [verify cli.dry-run.json]
+#[test]
+fn json_requires_dry_run() {
+    let tmp = make_project();
+
+    let output = cargo_bp()
+        .args(["bp", "sync", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .expect("failed to run cargo-bp");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--dry-run"), "stderr: {stderr}");
+}
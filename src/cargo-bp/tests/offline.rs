@@ -0,0 +1,135 @@
+//! Integration tests for `cargo bp --offline`.
+//!
+//! Each test points `CARGO_HOME` at a temp dir, seeding cargo's
+//! `registry/src` with the fancy fixture where a cached copy is needed.
+
+use assert_cmd::Command;
+use std::path::{Path, PathBuf};
+
+fn cargo_bp() -> Command {
+    Command::new(assert_cmd::cargo::cargo_bin!("cargo-bp"))
+}
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .join("battery-pack")
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("tests/fixtures")
+}
+
+/// Create a cargo home whose `registry/src` holds `fancy-battery-pack-0.2.0`,
+/// as if cargo had already downloaded it.
+fn cargo_home_with_fancy(root: &Path) -> PathBuf {
+    let home = root.join("cargo-home");
+    let dest = home.join("registry/src/index.crates.io-1949cf8c6b5b557f/fancy-battery-pack-0.2.0");
+    let fixture = fixtures_dir().join("fancy-battery-pack");
+    for entry in walkdir::WalkDir::new(&fixture) {
+        let entry = entry.unwrap();
+        let target = dest.join(entry.path().strip_prefix(&fixture).unwrap());
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&target).unwrap();
+        } else {
+            std::fs::copy(entry.path(), &target).unwrap();
+        }
+    }
+    home
+}
+
+// [verify cli.offline.flag]
+// [verify cli.offline.resolve]
+#[test]
+fn status_offline_resolves_from_cargo_registry_src() {
+    let tmp = tempfile::tempdir().unwrap();
+    let home = cargo_home_with_fancy(tmp.path());
+    let project = tmp.path().join("project");
+    std::fs::create_dir_all(project.join("src")).unwrap();
+    std::fs::write(
+        project.join("Cargo.toml"),
+        r#"[package]
+name = "test-consumer"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+clap = "3.0"
+
+[build-dependencies]
+fancy-battery-pack = "0.2.0"
+"#,
+    )
+    .unwrap();
+    std::fs::write(project.join("src/lib.rs"), "").unwrap();
+
+    let output = cargo_bp()
+        .args(["bp", "status", "--json"])
+        .env("CARGO_HOME", &home)
+        .env("CARGO_NET_OFFLINE", "true")
+        .current_dir(&project)
+        .output()
+        .expect("failed to run cargo-bp");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report = cargo_bp_script::parse_status(&output.stdout).unwrap();
+    assert_eq!(report.packs.len(), 1);
+    assert_eq!(report.packs[0].name, "fancy-battery-pack");
+    assert_eq!(report.packs[0].version, "0.2.0");
+}
+
+// [verify cli.offline.resolve]
+#[test]
+fn new_offline_renders_template_from_cache() {
+    let tmp = tempfile::tempdir().unwrap();
+    let home = cargo_home_with_fancy(tmp.path());
+
+    let output = cargo_bp()
+        .args([
+            "bp",
+            "--offline",
+            "new",
+            "fancy",
+            "--name",
+            "my-app",
+            "-t",
+            "default",
+        ])
+        .env("CARGO_HOME", &home)
+        .current_dir(tmp.path())
+        .output()
+        .expect("failed to run cargo-bp");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(tmp.path().join("my-app/Cargo.toml").exists());
+}
+
+// [verify cli.offline.no-network]
+#[test]
+fn show_offline_without_cache_fails() {
+    let tmp = tempfile::tempdir().unwrap();
+
+    let output = cargo_bp()
+        .args(["bp", "--offline", "show", "fancy", "--json"])
+        .env("CARGO_HOME", tmp.path())
+        .current_dir(tmp.path())
+        .output()
+        .expect("failed to run cargo-bp");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("'fancy-battery-pack' from crates.io is not available offline"),
+        "stderr: {stderr}"
+    );
+}
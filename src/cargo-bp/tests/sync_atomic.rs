@@ -0,0 +1,78 @@
+//! Integration tests for all-or-nothing manifest writes in `cargo bp sync`.
+
+use assert_cmd::Command;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+fn cargo_bp() -> Command {
+    Command::new(assert_cmd::cargo::cargo_bin!("cargo-bp"))
+}
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("tests/fixtures")
+}
+
+/// Every file under `root`, with its content.
+fn snapshot(root: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+    walkdir::WalkDir::new(root)
+        .into_iter()
+        .map(Result::unwrap)
+        .filter(|e| e.file_type().is_file())
+        .map(|e| (e.path().to_path_buf(), std::fs::read(e.path()).unwrap()))
+        .collect()
+}
+
+// [verify manifest.write.atomic]
+#[test]
+fn sync_writes_nothing_when_a_later_pack_fails_to_resolve() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(
+        tmp.path().join("Cargo.toml"),
+        "[workspace]\nmembers = [\"app\"]\nresolver = \"2\"\n\n[workspace.dependencies]\nclap = \"3.0\"\n",
+    )
+    .unwrap();
+    let app = tmp.path().join("app");
+    std::fs::create_dir_all(app.join("src")).unwrap();
+    std::fs::write(app.join("src/lib.rs"), "").unwrap();
+    // fancy resolves from the fixtures source and would bump clap in the
+    // workspace; missing-battery-pack doesn't exist there.
+    std::fs::write(
+        app.join("Cargo.toml"),
+        r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+clap.workspace = true
+
+[build-dependencies]
+fancy-battery-pack = "0.2.0"
+missing-battery-pack = "0.1.0"
+"#,
+    )
+    .unwrap();
+    let before = snapshot(tmp.path());
+
+    let output = cargo_bp()
+        .arg("bp")
+        .arg("--crate-source")
+        .arg(fixtures_dir())
+        .arg("sync")
+        .current_dir(&app)
+        .output()
+        .expect("failed to run cargo-bp");
+
+    assert!(!output.status.success(), "sync should fail");
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("missing-battery-pack"),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(snapshot(tmp.path()), before);
+}
@@ -0,0 +1,142 @@
+//! Integration tests for `cargo bp undo`.
+//!
+//! Each test snapshots the project, runs a file-changing command, undoes
+//! it, and checks that the project is back to how it started.
+
+use assert_cmd::Command;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+fn cargo_bp() -> Command {
+    Command::new(assert_cmd::cargo::cargo_bin!("cargo-bp"))
+}
+
+fn fancy_fixture() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("tests/fixtures/fancy-battery-pack")
+}
+
+/// A project using `fancy-battery-pack` with an outdated `clap`.
+fn make_project() -> tempfile::TempDir {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(
+        tmp.path().join("Cargo.toml"),
+        r#"[package]
+name = "test-consumer"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+clap = "3.0"
+
+[build-dependencies]
+fancy-battery-pack = "0.2.0"
+"#,
+    )
+    .unwrap();
+    tmp
+}
+
+/// Every file under `root` outside `target/`, with its content.
+fn snapshot(root: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+    walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.path() != root.join("target"))
+        .map(Result::unwrap)
+        .filter(|e| e.file_type().is_file())
+        .map(|e| (e.path().to_path_buf(), std::fs::read(e.path()).unwrap()))
+        .collect()
+}
+
+fn run(tmp: &Path, args: &[&str]) -> String {
+    let output = cargo_bp()
+        .args(args)
+        .current_dir(tmp)
+        .output()
+        .expect("failed to run cargo-bp");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+// [verify cli.undo.snapshot]
+// [verify cli.undo.restore]
+#[test]
+fn undo_reverts_sync() {
+    let tmp = make_project();
+    let before = snapshot(tmp.path());
+    let fixture = fancy_fixture().to_string_lossy().into_owned();
+
+    run(tmp.path(), &["bp", "sync", "--path", &fixture]);
+    assert_ne!(snapshot(tmp.path()), before);
+    assert!(tmp.path().join("target/bp-undo").is_dir());
+
+    let stdout = run(tmp.path(), &["bp", "undo"]);
+    assert!(stdout.contains("restored Cargo.toml"), "{stdout}");
+    assert!(stdout.contains("removed battery-pack.toml"), "{stdout}");
+    assert!(stdout.contains("Undid `cargo bp sync --path"), "{stdout}");
+    assert_eq!(snapshot(tmp.path()), before);
+}
+
+// [verify cli.undo.restore]
+#[test]
+fn undo_removes_files_created_by_a_template() {
+    let tmp = make_project();
+    let before = snapshot(tmp.path());
+    let fixture = fancy_fixture().to_string_lossy().into_owned();
+
+    run(
+        tmp.path(),
+        &[
+            "bp", "add", "fancy", "-t", "default", "--path", &fixture, "-N",
+        ],
+    );
+    assert!(tmp.path().join(".github/workflows/ci.yml").exists());
+    assert!(tmp.path().join("src/main.rs").exists());
+
+    run(tmp.path(), &["bp", "undo"]);
+    assert_eq!(snapshot(tmp.path()), before);
+    assert!(!tmp.path().join(".github").exists());
+    assert!(!tmp.path().join("src").exists());
+}
+
+// [verify cli.undo.restore]
+#[test]
+fn undo_steps_back_one_command_at_a_time() {
+    let tmp = make_project();
+    let fixture = fancy_fixture().to_string_lossy().into_owned();
+
+    run(tmp.path(), &["bp", "sync", "--path", &fixture]);
+    let after_sync = snapshot(tmp.path());
+    run(tmp.path(), &["bp", "rm", "fancy", "--keep-deps", "-N"]);
+    assert_ne!(snapshot(tmp.path()), after_sync);
+
+    run(tmp.path(), &["bp", "undo"]);
+    assert_eq!(snapshot(tmp.path()), after_sync);
+}
+
+// [verify cli.undo.snapshot]
+// [verify cli.undo.empty]
+#[test]
+fn dry_run_leaves_nothing_to_undo() {
+    let tmp = make_project();
+    let fixture = fancy_fixture().to_string_lossy().into_owned();
+
+    run(tmp.path(), &["bp", "sync", "--dry-run", "--path", &fixture]);
+
+    let output = cargo_bp()
+        .args(["bp", "undo"])
+        .current_dir(tmp.path())
+        .output()
+        .expect("failed to run cargo-bp");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Nothing to undo"), "{stderr}");
+}
@@ -0,0 +1,187 @@
+//! Integration tests for `cargo bp upgrade-template`.
+//!
+//! The originally applied version of the pack is seeded into a temp
+//! `CARGO_HOME`'s `registry/src`, so `--offline` can re-render it as the
+//! merge base; the new version is read with `--path`.
+
+use assert_cmd::Command;
+use std::path::{Path, PathBuf};
+
+fn cargo_bp() -> Command {
+    Command::new(assert_cmd::cargo::cargo_bin!("cargo-bp"))
+}
+
+/// Write `tpl-battery-pack` at `version` into `dir`, with a `base` template
+/// made of `files`.
+fn write_pack(dir: &Path, version: &str, files: &[(&str, &str)]) {
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join("src/lib.rs"), "").unwrap();
+    std::fs::write(
+        dir.join("Cargo.toml"),
+        format!(
+            r#"[package]
+name = "tpl-battery-pack"
+version = "{version}"
+edition = "2024"
+description = "A battery pack with one template"
+keywords = ["battery-pack"]
+
+[dependencies]
+anyhow = "1"
+
+[package.metadata.battery.templates]
+base = {{ path = "templates/base", description = "Base files" }}
+"#
+        ),
+    )
+    .unwrap();
+    let template = dir.join("templates/base");
+    std::fs::create_dir_all(&template).unwrap();
+    for (path, content) in files {
+        std::fs::write(template.join(path), content).unwrap();
+    }
+}
+
+/// The `tpl` pack as first applied: 0.1.0.
+const V1: &[(&str, &str)] = &[
+    ("notes.txt", "Project notes\n"),
+    ("clean.txt", "one\ntwo\n"),
+    ("deny.toml", "level = \"deny\"\n"),
+];
+
+/// The `tpl` pack as upgraded to: 0.2.0.
+const V2: &[(&str, &str)] = &[
+    ("notes.txt", "Project notes\n"),
+    ("clean.txt", "one\ntwo\nthree\n"),
+    ("deny.toml", "level = \"forbid\"\n"),
+];
+
+struct Setup {
+    _tmp: tempfile::TempDir,
+    home: PathBuf,
+    project: PathBuf,
+    v2: PathBuf,
+}
+
+/// A project with `tpl`'s `base` template applied at 0.1.0, which is also in
+/// the cargo home's registry cache, and 0.2.0 in a local directory.
+fn setup() -> Setup {
+    let tmp = tempfile::tempdir().unwrap();
+    let home = tmp.path().join("cargo-home");
+    let v1 = home.join("registry/src/index.crates.io-1949cf8c6b5b557f/tpl-battery-pack-0.1.0");
+    write_pack(&v1, "0.1.0", V1);
+    let v2 = tmp.path().join("tpl-0.2.0");
+    write_pack(&v2, "0.2.0", V2);
+
+    let project = tmp.path().join("project");
+    std::fs::create_dir_all(project.join("src")).unwrap();
+    std::fs::write(project.join("src/main.rs"), "fn main() {}\n").unwrap();
+    std::fs::write(
+        project.join("Cargo.toml"),
+        "[package]\nname = \"my-app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    run(
+        &project,
+        &home,
+        &["add", "tpl", "-t", "base", "--path", &v1.to_string_lossy()],
+    );
+    Setup {
+        _tmp: tmp,
+        home,
+        project,
+        v2,
+    }
+}
+
+fn run(project: &Path, home: &Path, args: &[&str]) -> String {
+    let output = cargo_bp()
+        .args(["bp", "--offline", "-N"])
+        .args(args)
+        .env("CARGO_HOME", home)
+        .current_dir(project)
+        .output()
+        .expect("failed to run cargo-bp");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+fn upgrade(setup: &Setup) -> String {
+    run(
+        &setup.project,
+        &setup.home,
+        &[
+            "upgrade-template",
+            "tpl",
+            "--path",
+            &setup.v2.to_string_lossy(),
+        ],
+    )
+}
+
+/// A project file's content, without its trailing newline (rendered
+/// template files don't keep it).
+fn read(setup: &Setup, path: &str) -> String {
+    let content = std::fs::read_to_string(setup.project.join(path)).unwrap();
+    content.trim_end_matches('\n').to_string()
+}
+
+/// The version recorded for the `base` template in `battery-pack.toml`.
+fn recorded_version(setup: &Setup) -> String {
+    let state: toml::Value = toml::from_str(&read(setup, "battery-pack.toml")).unwrap();
+    state["battery-pack"][0]["applied-templates"][0]["version"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+// [verify cli.upgrade-template.merge]
+// [verify cli.upgrade-template.record]
+#[test]
+fn upgrade_keeps_user_edits_and_applies_template_changes() {
+    let setup = setup();
+    assert_eq!(recorded_version(&setup), "0.1.0");
+    std::fs::write(setup.project.join("notes.txt"), "Our own notes\n").unwrap();
+
+    // Change deny.toml the way the new version does, so nothing conflicts.
+    // Files are written like rendered ones, without a trailing newline.
+    std::fs::write(setup.project.join("deny.toml"), "level = \"forbid\"").unwrap();
+    upgrade(&setup);
+
+    assert_eq!(read(&setup, "notes.txt"), "Our own notes");
+    assert_eq!(read(&setup, "clean.txt"), "one\ntwo\nthree");
+    assert_eq!(read(&setup, "deny.toml"), "level = \"forbid\"");
+    assert_eq!(recorded_version(&setup), "0.2.0");
+}
+
+// [verify cli.upgrade-template.conflict]
+// [verify cli.upgrade-template.record]
+#[test]
+fn upgrade_with_a_conflict_keeps_the_previous_record() {
+    let setup = setup();
+    std::fs::write(setup.project.join("deny.toml"), "level = \"warn\"").unwrap();
+
+    let stderr = upgrade(&setup);
+    let deny = read(&setup, "deny.toml");
+    assert!(deny.contains("<<<<<<< current"), "{deny}");
+    assert!(deny.contains(">>>>>>> tpl-battery-pack 0.2.0"), "{deny}");
+    // The clean file is upgraded anyway.
+    assert_eq!(read(&setup, "clean.txt"), "one\ntwo\nthree");
+    assert!(
+        stderr.contains("is still recorded at its previous version"),
+        "{stderr}"
+    );
+    assert_eq!(recorded_version(&setup), "0.1.0");
+
+    // Once the conflict is resolved, the next upgrade merges cleanly from
+    // 0.1.0 and records 0.2.0.
+    std::fs::write(setup.project.join("deny.toml"), "level = \"forbid\"").unwrap();
+    upgrade(&setup);
+    assert_eq!(read(&setup, "deny.toml"), "level = \"forbid\"");
+    assert_eq!(read(&setup, "clean.txt"), "one\ntwo\nthree");
+    assert_eq!(recorded_version(&setup), "0.2.0");
+}
@@ -0,0 +1,316 @@
+//! Integration tests for `--workspace` / `-p` on `sync`, `status` and `check`.
+
+use assert_cmd::Command;
+use cargo_bp_script::{StatusCommand, parse_workspace_status};
+use std::path::{Path, PathBuf};
+
+fn cargo_bp() -> Command {
+    Command::new(assert_cmd::cargo::cargo_bin!("cargo-bp"))
+}
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("tests/fixtures")
+}
+
+fn write_member(root: &Path, name: &str, extra: &str) {
+    let dir = root.join(name);
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join("src/lib.rs"), "").unwrap();
+    std::fs::write(
+        dir.join("Cargo.toml"),
+        format!(
+            r#"[package]
+name = "{name}"
+version = "0.1.0"
+edition = "2021"
+{extra}"#
+        ),
+    )
+    .unwrap();
+}
+
+/// A workspace with two members that use `fancy-battery-pack` (one with an
+/// outdated `clap`) and one member with no battery packs.
+fn make_workspace() -> tempfile::TempDir {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(
+        tmp.path().join("Cargo.toml"),
+        r#"[workspace]
+members = ["api", "worker", "plain"]
+resolver = "2"
+
+[workspace.dependencies]
+clap = "3.0"
+"#,
+    )
+    .unwrap();
+    let with_pack = r#"
+[dependencies]
+clap.workspace = true
+
+[build-dependencies]
+fancy-battery-pack = "0.2.0"
+"#;
+    write_member(tmp.path(), "api", with_pack);
+    write_member(tmp.path(), "worker", with_pack);
+    write_member(tmp.path(), "plain", "");
+    tmp
+}
+
+/// A workspace whose root is itself a package named `root`, with one member
+/// named `member`; both use `fancy-battery-pack`, and the shared `clap` is
+/// outdated.
+fn make_root_package_workspace(root: &str, member: &str) -> tempfile::TempDir {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(tmp.path().join("src")).unwrap();
+    std::fs::write(tmp.path().join("src/lib.rs"), "").unwrap();
+    std::fs::write(
+        tmp.path().join("Cargo.toml"),
+        format!(
+            r#"[package]
+name = "{root}"
+version = "0.1.0"
+edition = "2021"
+
+[build-dependencies]
+fancy-battery-pack = "0.2.0"
+
+[workspace]
+members = ["{member}"]
+resolver = "2"
+
+[workspace.dependencies]
+clap = "3.0"
+"#
+        ),
+    )
+    .unwrap();
+    write_member(
+        tmp.path(),
+        member,
+        r#"
+[dependencies]
+clap.workspace = true
+
+[build-dependencies]
+fancy-battery-pack = "0.2.0"
+"#,
+    );
+    tmp
+}
+
+// [verify cli.workspace.status]
+// [verify cli.workspace.flag]
+#[test]
+fn status_workspace_reports_each_member_with_packs() {
+    let tmp = make_workspace();
+    let fixture = fixtures_dir().join("fancy-battery-pack");
+
+    let report = StatusCommand::new()
+        .program(assert_cmd::cargo::cargo_bin!("cargo-bp"))
+        .cwd(tmp.path())
+        .path(&fixture)
+        .run_workspace()
+        .expect("StatusCommand::run_workspace failed");
+
+    let names: Vec<_> = report
+        .members
+        .iter()
+        .map(|m| m.project.package_name.as_deref().unwrap())
+        .collect();
+    assert_eq!(names, ["api", "worker"]);
+    for member in &report.members {
+        assert_eq!(member.packs.len(), 1);
+        assert!(
+            member.packs[0]
+                .warnings
+                .iter()
+                .any(|w| w.crate_name == "clap"),
+            "expected a clap warning for {:?}",
+            member.project.package_name,
+        );
+    }
+}
+
+// [verify cli.workspace.package]
+#[test]
+fn status_package_selects_named_members() {
+    let tmp = make_workspace();
+    let fixture = fixtures_dir().join("fancy-battery-pack");
+
+    let output = cargo_bp()
+        .args(["bp", "status", "--json", "-p", "worker", "-p", "plain"])
+        .args(["--path", &fixture.to_string_lossy()])
+        .current_dir(tmp.path())
+        .output()
+        .expect("failed to run cargo-bp");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let report = parse_workspace_status(&output.stdout).unwrap();
+    let names: Vec<_> = report
+        .members
+        .iter()
+        .map(|m| m.project.package_name.as_deref().unwrap())
+        .collect();
+    // Members named with -p are reported even without packs.
+    assert_eq!(names, ["plain", "worker"]);
+    assert!(report.members[0].packs.is_empty());
+}
+
+// [verify cli.workspace.package]
+#[test]
+fn status_package_rejects_unknown_member() {
+    let tmp = make_workspace();
+
+    let output = cargo_bp()
+        .args(["bp", "status", "-p", "nope"])
+        .current_dir(tmp.path())
+        .output()
+        .expect("failed to run cargo-bp");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("package 'nope' is not a member of the workspace"),
+        "stderr: {stderr}"
+    );
+}
+
+// [verify cli.workspace.sync]
+#[test]
+fn sync_workspace_updates_shared_deps_once_and_each_member() {
+    let tmp = make_workspace();
+    let fixture = fixtures_dir().join("fancy-battery-pack");
+
+    let output = cargo_bp()
+        .args(["bp", "sync", "--workspace"])
+        .args(["--path", &fixture.to_string_lossy()])
+        .current_dir(tmp.path())
+        .output()
+        .expect("failed to run cargo-bp");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("api:"), "stdout: {stdout}");
+    assert!(stdout.contains("worker:"), "stdout: {stdout}");
+    assert!(!stdout.contains("plain:"), "stdout: {stdout}");
+    assert_eq!(
+        stdout.matches("clap (updated in workspace)").count(),
+        1,
+        "shared deps should be updated once, stdout: {stdout}"
+    );
+
+    let ws: toml::Value =
+        toml::from_str(&std::fs::read_to_string(tmp.path().join("Cargo.toml")).unwrap()).unwrap();
+    let ws_deps = &ws["workspace"]["dependencies"];
+    assert!(ws_deps.get("dialoguer").is_some(), "{ws_deps:?}");
+
+    for member in ["api", "worker"] {
+        let manifest: toml::Value = toml::from_str(
+            &std::fs::read_to_string(tmp.path().join(member).join("Cargo.toml")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            manifest["dependencies"]["dialoguer"]["workspace"].as_bool(),
+            Some(true),
+            "{member} should reference dialoguer from the workspace"
+        );
+        assert!(tmp.path().join(member).join("battery-pack.toml").exists());
+    }
+    assert!(!tmp.path().join("plain/battery-pack.toml").exists());
+}
+
+// [verify cli.workspace.check]
+// [verify cli.check.workspace-refs]
+#[test]
+fn check_workspace_reports_each_member() {
+    let tmp = make_workspace();
+    let fixture = fixtures_dir().join("fancy-battery-pack");
+
+    let report = cargo_bp_script::CheckCommand::new()
+        .program(assert_cmd::cargo::cargo_bin!("cargo-bp"))
+        .cwd(tmp.path())
+        .path(&fixture)
+        .run_workspace()
+        .expect("CheckCommand::run_workspace failed");
+
+    assert!(!report.is_ok());
+    let names: Vec<_> = report
+        .members
+        .iter()
+        .map(|m| m.project.package_name.as_deref().unwrap())
+        .collect();
+    assert_eq!(names, ["api", "worker"]);
+    for member in &report.members {
+        let issues = &member.packs[0].issues;
+        assert!(
+            issues.iter().any(|i| i.crate_name == "clap"
+                && i.kind == cargo_bp_script::CheckIssueKind::OutdatedVersion),
+            "expected clap to be outdated for {:?}: {issues:?}",
+            member.project.package_name,
+        );
+    }
+}
+
+// [verify cli.workspace.sync]
+#[test]
+fn sync_workspace_with_a_root_package_keeps_every_edit() {
+    let fixture = fixtures_dir().join("fancy-battery-pack");
+    // The root package sorts both before and after the member.
+    for (root, member) in [("aaa", "zzz"), ("zzz", "aaa")] {
+        let tmp = make_root_package_workspace(root, member);
+
+        let output = cargo_bp()
+            .args(["bp", "sync", "--workspace"])
+            .args(["--path", &fixture.to_string_lossy()])
+            .current_dir(tmp.path())
+            .output()
+            .expect("failed to run cargo-bp");
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        let root_manifest: toml::Value =
+            toml::from_str(&std::fs::read_to_string(tmp.path().join("Cargo.toml")).unwrap())
+                .unwrap();
+        // The member's updates land in the shared [workspace.dependencies].
+        let ws_deps = &root_manifest["workspace"]["dependencies"];
+        assert_eq!(
+            ws_deps["clap"]["version"].as_str(),
+            Some("4"),
+            "{root}/{member}: {ws_deps:?}\n{stdout}"
+        );
+        assert!(ws_deps.get("dialoguer").is_some(), "{ws_deps:?}");
+        // The root package's own dependencies are synced directly, as
+        // `cargo bp sync` run in it does.
+        assert!(
+            root_manifest["dependencies"].get("dialoguer").is_some(),
+            "{root}/{member}: {root_manifest:?}\n{stdout}"
+        );
+
+        let member_manifest: toml::Value = toml::from_str(
+            &std::fs::read_to_string(tmp.path().join(member).join("Cargo.toml")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            member_manifest["dependencies"]["dialoguer"]["workspace"].as_bool(),
+            Some(true),
+            "{root}/{member}: {member_manifest:?}"
+        );
+    }
+}
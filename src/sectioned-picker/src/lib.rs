@@ -13,9 +13,15 @@
 //! - **Section toggle** — `a` checks/unchecks all items in a checkbox section
 //! - **Collapsing** — Left/Right arrows collapse/expand a section
 //! - **Item descriptions** — optional per-item explanatory text shown inline
+//! - **Linked items** — rows in different sections that share a key are one
+//!   selection; toggling one updates every copy
+//! - **Filtering** — `/` narrows the rows to items whose label or description
+//!   fuzzy-matches a typed query, keeping their section headers
 //! - **Smart scrolling** — keeps cursor visible; snaps to section header when near top
 //! - **Custom actions** — bind arbitrary keys to caller-defined handlers that can
-//!   take over the terminal (e.g., for previews)
+//!   take over the terminal (e.g., for previews) or relabel the current item
+//! - **Scripted runs** — [`run_picker_with`] drives the picker from recorded key
+//!   presses on any ratatui backend, for tests and snapshots
 //!
 //! # Example
 //!
@@ -38,8 +44,8 @@
 //!
 //! match run_picker("my-app v1.0", sections, Vec::new()).unwrap() {
 //!     PickerOutcome::Confirmed(results) => {
-//!         // results[0] = [true, false] — features section
-//!         // results[1] = [true]        — dependencies section
+//!         // results.sections[0] = [true, false] — features section
+//!         // results.sections[1] = [true]        — dependencies section
 //!     }
 //!     PickerOutcome::Cancelled => {}
 //! }
@@ -64,25 +70,48 @@
 //!
 //! Confirming is rejected when any radio section has more than one item
 //! checked; an inline error is shown and the picker stays open.
+//!
+//! # Filtering
+//!
+//! `/` starts typing a filter query; Backspace edits it, Up/Down move between
+//! the matching items, Enter stops typing, and Esc drops the filter. With the
+//! filter applied, the usual keys work on the matching rows (`a` toggles only
+//! the matches in a section) and Esc drops the filter before it cancels.
+//! Filtering only hides rows: checked state is kept for every item, and the
+//! results always cover all of them.
+//!
+//! # Linked items
+//!
+//! An item that belongs in several sections can be given the same key in
+//! each (see [`SectionItem::with_key`]). Its rows then act as one selection:
+//! a row starts checked when any copy is, toggling or relabeling one row
+//! updates the others, and checking a copy that sits in a radio section
+//! unchecks that section's other items — along with their own copies
+//! elsewhere. [`PickerResults::keys`] reports the state once per key.
 
 mod render;
+mod script;
 mod state;
 
 #[cfg(test)]
 mod tests;
 
-use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::event::{self, Event, KeyEventKind, KeyModifiers};
 use ratatui::{
-    TerminalOptions, Viewport,
+    Terminal, TerminalOptions, Viewport,
+    backend::Backend,
     crossterm::{
         ExecutableCommand,
         terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
     },
 };
+use std::collections::BTreeMap;
 use std::time::Duration;
 
+pub use crossterm::event::{KeyCode, KeyEvent};
 pub use render::render_picker;
-pub use state::PickerState;
+pub use script::parse_keys;
+pub use state::{PickerState, fuzzy_match};
 
 /// How many items a section allows to be selected at once.
 // [impl tui.picker.checkbox]
@@ -138,6 +167,8 @@ pub struct SectionItem {
     pub checked: bool,
     /// Optional explanatory text shown inline after the label.
     pub description: Option<String>,
+    /// Optional identity shared by rows that are the same logical item.
+    pub key: Option<String>,
 }
 
 impl SectionItem {
@@ -147,6 +178,7 @@ impl SectionItem {
             label: label.into(),
             checked,
             description: None,
+            key: None,
         }
     }
 
@@ -155,16 +187,24 @@ impl SectionItem {
         self.description = Some(description.into());
         self
     }
+
+    /// Link this item to every other item with the same key, in any
+    /// section, so that they are checked and unchecked together.
+    pub fn with_key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
 }
 
 /// Context passed to action handlers when a custom key is pressed.
 ///
-/// Provides access to the current cursor position and the terminal for
-/// full-screen takeover (e.g., rendering a preview).
+/// Provides access to the current cursor position, the item under it, and
+/// the terminal for full-screen takeover (e.g., rendering a preview).
 pub struct ActionContext<'a> {
     section_idx: usize,
     item_idx: usize,
-    terminal: &'a mut ratatui::DefaultTerminal,
+    state: &'a mut PickerState,
+    terminal: Option<&'a mut ratatui::DefaultTerminal>,
 }
 
 impl ActionContext<'_> {
@@ -178,9 +218,18 @@ impl ActionContext<'_> {
         self.item_idx
     }
 
+    /// Replace the label of the item under the cursor, e.g. to show a choice
+    /// the action just made. A no-op when the cursor is on a header.
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.state.set_current_label(label);
+    }
+
     /// Mutable access to the terminal for drawing custom screens.
-    pub fn terminal(&mut self) -> &mut ratatui::DefaultTerminal {
-        self.terminal
+    ///
+    /// `None` when the picker is driven by [`run_picker_with`]: there is no
+    /// real terminal to take over, so screen-taking actions should do nothing.
+    pub fn terminal(&mut self) -> Option<&mut ratatui::DefaultTerminal> {
+        self.terminal.as_deref_mut()
     }
 }
 
@@ -199,10 +248,20 @@ pub struct PickerAction<'a> {
     pub handler: ActionHandler<'a>,
 }
 
+/// The checked state of every item when the picker is confirmed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PickerResults {
+    /// Checked state per section and item, matching input order.
+    pub sections: Vec<Vec<bool>>,
+    /// Checked state per item key (see [`SectionItem::with_key`]). Rows
+    /// sharing a key always agree, so each key appears once.
+    pub keys: BTreeMap<String, bool>,
+}
+
 /// The outcome of a picker interaction.
 pub enum PickerOutcome {
-    /// User confirmed — returns checked state per section (matching input order).
-    Confirmed(Vec<Vec<bool>>),
+    /// User confirmed — returns the checked state of every item.
+    Confirmed(PickerResults),
     /// User cancelled (Esc).
     Cancelled,
 }
@@ -217,14 +276,16 @@ pub fn run_picker(
     sections: Vec<Section>,
     actions: Vec<PickerAction<'_>>,
 ) -> anyhow::Result<PickerOutcome> {
+    // Items and a header plus separator per section, the footer and border,
+    // and a line for the filter query.
     let height: u16 = sections
         .iter()
         .map(|sec| sec.items.len() as u16 + 2)
         .sum::<u16>()
-        + 2;
+        + 3;
     let mut state = PickerState::new(sections);
     if state.is_empty() {
-        return Ok(PickerOutcome::Confirmed(Vec::new()));
+        return Ok(PickerOutcome::Confirmed(PickerResults::default()));
     }
 
     let mut is_fullscreen = false;
@@ -256,65 +317,146 @@ fn run_picker_loop(
     state: &mut PickerState,
     mut actions: Vec<PickerAction<'_>>,
 ) -> anyhow::Result<PickerOutcome> {
-    let action_keys: Vec<char> = actions.iter().map(|a| a.key).collect();
-
     loop {
         terminal.draw(|frame| render_picker(frame, title, state, &actions))?;
 
         if event::poll(Duration::from_millis(100))?
             && let Event::Key(key) = event::read()?
+            && let Some(outcome) = handle_key(state, key, &mut actions, Some(&mut *terminal))
         {
-            if key.kind != KeyEventKind::Press {
-                continue;
-            }
+            return Ok(outcome);
+        }
+    }
+}
 
-            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
-                return Ok(PickerOutcome::Cancelled);
-            }
+/// Run the picker on any ratatui backend, reading keys from `events`
+/// instead of the terminal.
+///
+/// This drives the picker without a TTY — from a test with a
+/// [`TestBackend`](ratatui::backend::TestBackend), or to replay a recorded
+/// key sequence (see [`parse_keys`]). The picker is drawn before the first
+/// key and after each one. If `events` runs out before the picker is
+/// confirmed or cancelled, it is cancelled as if Esc were pressed, and
+/// `terminal` keeps the last frame — so a picker can be snapshotted part way
+/// through. Action handlers get no terminal (see [`ActionContext::terminal`]).
+///
+/// ```
+/// use ratatui::{Terminal, backend::TestBackend};
+/// use sectioned_picker::{PickerOutcome, Section, SectionItem, parse_keys, run_picker_with};
+///
+/// let sections = vec![Section::new(
+///     "Features:",
+///     vec![SectionItem::new("logging", false), SectionItem::new("metrics", false)],
+/// )];
+/// let mut terminal = Terminal::new(TestBackend::new(60, 8)).unwrap();
+/// let keys = parse_keys("j <enter>").unwrap();
+/// match run_picker_with("my-app", sections, Vec::new(), keys, &mut terminal).unwrap() {
+///     PickerOutcome::Confirmed(results) => assert_eq!(results.sections, [[false, true]]),
+///     PickerOutcome::Cancelled => unreachable!(),
+/// }
+/// ```
+// [impl tui.picker.scripted]
+pub fn run_picker_with<B: Backend>(
+    title: &str,
+    sections: Vec<Section>,
+    mut actions: Vec<PickerAction<'_>>,
+    events: impl IntoIterator<Item = KeyEvent>,
+    terminal: &mut Terminal<B>,
+) -> anyhow::Result<PickerOutcome> {
+    let mut state = PickerState::new(sections);
+    if state.is_empty() {
+        return Ok(PickerOutcome::Confirmed(PickerResults::default()));
+    }
+    let draw = |terminal: &mut Terminal<B>, state: &mut PickerState, actions: &[_]| {
+        terminal
+            .draw(|frame| render_picker(frame, title, state, actions))
+            .map(|_| ())
+            .map_err(|e| anyhow::anyhow!("failed to draw the picker: {e}"))
+    };
+
+    draw(terminal, &mut state, &actions)?;
+    for key in events {
+        if let Some(outcome) = handle_key(&mut state, key, &mut actions, None) {
+            return Ok(outcome);
+        }
+        draw(terminal, &mut state, &actions)?;
+    }
+    Ok(PickerOutcome::Cancelled)
+}
+
+/// Apply one key press to the picker. Returns the outcome once the picker
+/// is confirmed or cancelled.
+fn handle_key(
+    state: &mut PickerState,
+    key: KeyEvent,
+    actions: &mut [PickerAction<'_>],
+    terminal: Option<&mut ratatui::DefaultTerminal>,
+) -> Option<PickerOutcome> {
+    if key.kind != KeyEventKind::Press {
+        return None;
+    }
+
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        return Some(PickerOutcome::Cancelled);
+    }
+
+    // Any keypress clears a stale confirm error before it is handled.
+    state.clear_confirm_error();
 
-            // Any keypress clears a stale confirm error before it is handled.
-            state.clear_confirm_error();
-
-            match key.code {
-                KeyCode::Up | KeyCode::Char('k') => state.move_up(),
-                KeyCode::Down | KeyCode::Char('j') => state.move_down(),
-                KeyCode::Char(' ') => state.toggle(),
-                // [impl tui.picker.collapse]
-                KeyCode::Left => state.collapse_current(),
-                KeyCode::Right => state.expand_current(),
-                KeyCode::Backspace => state.backspace(),
-                // [impl tui.picker.confirm-validation]
-                KeyCode::Enter => {
-                    // A one-key selection convenience: if nothing is checked,
-                    // check the cursor item first. Skipped for radio sections,
-                    // where a deliberate empty selection is valid.
-                    if !state.has_any_checked()
-                        && state.current_section_mode() != SelectionMode::Radio
-                    {
-                        state.toggle();
-                    }
-                    match state.try_confirm() {
-                        Ok(results) => return Ok(PickerOutcome::Confirmed(results)),
-                        Err(msg) => state.set_confirm_error(msg),
-                    }
-                }
-                KeyCode::Esc | KeyCode::Char('q') => {
-                    return Ok(PickerOutcome::Cancelled);
-                }
-                KeyCode::Char('a') => state.toggle_current_section(),
-                KeyCode::Char(c) => {
-                    if let Some(idx) = action_keys.iter().position(|&k| k == c) {
-                        let (section_idx, item_idx) = state.current_coordinates();
-                        let mut ctx = ActionContext {
-                            section_idx,
-                            item_idx,
-                            terminal,
-                        };
-                        (actions[idx].handler)(&mut ctx);
-                    }
-                }
-                _ => {}
+    // [impl tui.picker.filter]
+    if state.is_editing_filter() {
+        match key.code {
+            KeyCode::Up => state.move_up(),
+            KeyCode::Down => state.move_down(),
+            KeyCode::Backspace => state.pop_filter_char(),
+            KeyCode::Enter => state.finish_filter(),
+            KeyCode::Esc => state.clear_filter(),
+            KeyCode::Char(c) => state.push_filter_char(c),
+            _ => {}
+        }
+        return None;
+    }
+
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => state.move_up(),
+        KeyCode::Down | KeyCode::Char('j') => state.move_down(),
+        KeyCode::Char(' ') => state.toggle(),
+        // [impl tui.picker.collapse]
+        KeyCode::Left => state.collapse_current(),
+        KeyCode::Right => state.expand_current(),
+        KeyCode::Backspace => state.backspace(),
+        // [impl tui.picker.confirm-validation]
+        KeyCode::Enter => {
+            // A one-key selection convenience: if nothing is checked,
+            // check the cursor item first. Skipped for radio sections,
+            // where a deliberate empty selection is valid.
+            if !state.has_any_checked() && state.current_section_mode() != SelectionMode::Radio {
+                state.toggle();
+            }
+            match state.try_confirm() {
+                Ok(results) => return Some(PickerOutcome::Confirmed(results)),
+                Err(msg) => state.set_confirm_error(msg),
+            }
+        }
+        KeyCode::Esc if state.filter().is_some() => state.clear_filter(),
+        KeyCode::Esc | KeyCode::Char('q') => return Some(PickerOutcome::Cancelled),
+        KeyCode::Char('a') => state.toggle_current_section(),
+        KeyCode::Char('/') => state.start_filter(),
+        KeyCode::Char(c) => {
+            if let Some(action) = actions.iter_mut().find(|a| a.key == c)
+                && !state.is_empty()
+            {
+                let (section_idx, item_idx) = state.current_coordinates();
+                let mut ctx = ActionContext {
+                    section_idx,
+                    item_idx,
+                    state,
+                    terminal,
+                };
+                (action.handler)(&mut ctx);
             }
         }
+        _ => {}
     }
+    None
 }
@@ -34,9 +34,16 @@ pub fn render_picker(
             .map(|title| format!("Multiple selections in \"{title}\" — pick one to resolve"))
     });
     let banner_height: u16 = if banner.is_some() { 1 } else { 0 };
+    // The filter line appears while a query is typed or applied.
+    let filter_height: u16 = if state.is_editing_filter() || state.filter().is_some() {
+        1
+    } else {
+        0
+    };
 
-    let [banner_area, main_area, footer_area] = Layout::vertical([
+    let [banner_area, filter_area, main_area, footer_area] = Layout::vertical([
         Constraint::Length(banner_height),
+        Constraint::Length(filter_height),
         Constraint::Fill(1),
         Constraint::Length(1),
     ])
@@ -51,6 +58,17 @@ pub fn render_picker(
         );
     }
 
+    // [impl tui.picker.filter]
+    if filter_height > 0 {
+        let query = state.filter().unwrap_or_default();
+        let cursor = if state.is_editing_filter() { "█" } else { "" };
+        let mut line = Line::from(vec![span!(" / ").bold(), span!("{query}{cursor}")]);
+        if state.is_empty() {
+            line.push_span(span!("  no matches").dim());
+        }
+        frame.render_widget(Paragraph::new(line), filter_area);
+    }
+
     // Update visible height from actual terminal geometry.
     state.visible_height = main_area.height.saturating_sub(2) as usize;
     state.ensure_cursor_visible();
@@ -62,6 +80,12 @@ pub fn render_picker(
     let mut section_mode = SelectionMode::Checkbox;
 
     for (i, entry) in state.entries.iter().enumerate() {
+        // Collapsed and filtered-out entries contribute no rendered line
+        // (mirrors entry_to_line).
+        if !state.is_shown(i) {
+            continue;
+        }
+        let is_cursor = Some(i) == current_entry_idx;
         match entry {
             Entry::Header {
                 title,
@@ -69,12 +93,11 @@ pub fn render_picker(
                 collapsed,
             } => {
                 section_mode = *mode;
-                if i > 0 {
+                if !lines.is_empty() {
                     lines.push(Line::from(""));
                 }
                 let chevron = if *collapsed { "▶ " } else { "▼ " };
                 let hint = state.section_hint(i);
-                let is_cursor = i == current_entry_idx;
                 let style = if is_cursor {
                     Style::default()
                         .fg(Color::Black)
@@ -89,12 +112,8 @@ pub fn render_picker(
                 label,
                 checked,
                 description,
+                ..
             } => {
-                // Collapsed items contribute no rendered line (mirrors entry_to_line).
-                if !state.visible[i] {
-                    continue;
-                }
-                let is_cursor = i == current_entry_idx;
                 let symbol = match section_mode {
                     SelectionMode::Checkbox if *checked => "[x]",
                     SelectionMode::Checkbox => "[ ]",
@@ -139,17 +158,33 @@ pub fn render_picker(
     .areas(footer_area);
     let left_footer = span!(" {title} ").on_green().black().bold();
     frame.render_widget(left_footer, footer_left);
-    let mut footer_parts = vec![
-        " ↑↓/jk Navigate".to_string(),
-        "Space Toggle".to_string(),
-        "←/→ Collapse/expand".to_string(),
-        "a Toggle section".to_string(),
-    ];
-    for action in actions {
-        footer_parts.push(format!("{} {}", action.key, action.label));
-    }
-    footer_parts.push("Enter Confirm".to_string());
-    footer_parts.push("Esc Cancel".to_string());
+    let footer_parts = if state.is_editing_filter() {
+        vec![
+            " Type to filter".to_string(),
+            "↑↓ Navigate".to_string(),
+            "Enter Done".to_string(),
+            "Esc Clear filter".to_string(),
+        ]
+    } else {
+        let mut parts = vec![
+            " ↑↓/jk Navigate".to_string(),
+            "Space Toggle".to_string(),
+            "←/→ Collapse/expand".to_string(),
+            "a Toggle section".to_string(),
+            "/ Filter".to_string(),
+        ];
+        for action in actions {
+            parts.push(format!("{} {}", action.key, action.label));
+        }
+        parts.push("Enter Confirm".to_string());
+        let esc = if state.filter().is_some() {
+            "Esc Clear filter"
+        } else {
+            "Esc Cancel"
+        };
+        parts.push(esc.to_string());
+        parts
+    };
 
     frame.render_widget(
         Paragraph::new(footer_parts.join(" | ")).style(Style::default().white().on_dark_gray()),
@@ -0,0 +1,65 @@
+//! Recorded key sequences for driving the picker without a terminal.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Parse a key sequence written as text into key presses, for
+/// [`run_picker_with`](crate::run_picker_with).
+///
+/// Every character is one key press — including spaces, which toggle the
+/// current item. Other keys are named in angle brackets: `<up>`, `<down>`,
+/// `<left>`, `<right>`, `<enter>`, `<esc>`, `<backspace>`, `<tab>`,
+/// `<space>`, and `<lt>` for a literal `<`. `<c-x>` presses `x` with Ctrl
+/// held. Names are case-insensitive.
+///
+/// ```
+/// let keys = sectioned_picker::parse_keys("jj <enter>").unwrap();
+/// assert_eq!(keys.len(), 4);
+/// ```
+// [impl tui.picker.scripted]
+pub fn parse_keys(script: &str) -> anyhow::Result<Vec<KeyEvent>> {
+    let mut keys = Vec::new();
+    let mut rest = script;
+    while let Some(c) = rest.chars().next() {
+        if c != '<' {
+            keys.push(KeyEvent::from(KeyCode::Char(c)));
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        let Some(end) = rest.find('>') else {
+            anyhow::bail!(
+                "unclosed `<` in key sequence {script:?} (write `<lt>` for a literal `<`)"
+            );
+        };
+        keys.push(named_key(&rest[1..end]).ok_or_else(|| {
+            anyhow::anyhow!("unknown key `{}` in key sequence {script:?}", &rest[..=end])
+        })?);
+        rest = &rest[end + 1..];
+    }
+    Ok(keys)
+}
+
+/// The key press for a name written between angle brackets.
+fn named_key(name: &str) -> Option<KeyEvent> {
+    let name = name.to_ascii_lowercase();
+    if let Some(c) = name.strip_prefix("c-") {
+        let mut chars = c.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return None;
+        };
+        return Some(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL));
+    }
+    let code = match name.as_str() {
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        "tab" => KeyCode::Tab,
+        "space" => KeyCode::Char(' '),
+        "lt" => KeyCode::Char('<'),
+        _ => return None,
+    };
+    Some(KeyEvent::from(code))
+}
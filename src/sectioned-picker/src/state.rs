@@ -2,7 +2,8 @@
 
 #[cfg(test)]
 use crate::SectionItem;
-use crate::{Section, SelectionMode};
+use crate::{PickerResults, Section, SelectionMode};
+use std::collections::BTreeMap;
 
 /// A single item in the picker — either a section header or a selectable entry.
 pub(crate) enum Entry {
@@ -15,6 +16,7 @@ pub(crate) enum Entry {
         label: String,
         checked: bool,
         description: Option<String>,
+        key: Option<String>,
     },
 }
 
@@ -24,7 +26,9 @@ pub(crate) enum Entry {
 /// Navigation moves the cursor between *nav stops* — visible items plus the
 /// headers of collapsed sections — so a collapsed section can still be focused
 /// and re-expanded. When nothing is collapsed, the nav stops are exactly the
-/// items, matching a plain multi-select list.
+/// items, matching a plain multi-select list. A filter narrows the nav stops
+/// to the matching items without touching their checked state. Items that
+/// share a key are kept checked or unchecked together.
 pub struct PickerState {
     pub(crate) entries: Vec<Entry>,
     /// (section_idx, item_ordinal) for each entry. Header entries carry their
@@ -44,6 +48,13 @@ pub struct PickerState {
     /// Transient inline error shown after a rejected confirm; cleared on the
     /// next keypress.
     pub(crate) confirm_error: Option<String>,
+    /// The fuzzy filter query; empty when no filter is applied.
+    filter: String,
+    /// True while keystrokes are being typed into the filter query.
+    editing_filter: bool,
+    /// Per-entry filter match. An item matches the query; a header matches
+    /// when any item in its section does. Only meaningful with a filter.
+    matches: Vec<bool>,
 }
 
 impl PickerState {
@@ -70,10 +81,37 @@ impl PickerState {
                     label: item.label,
                     checked: item.checked,
                     description: item.description,
+                    key: item.key,
                 });
             }
         }
 
+        // A linked item starts checked when any of its rows is. Conflicts this
+        // creates in radio sections are left for the user to resolve.
+        // [impl tui.picker.linked-items]
+        let checked_keys: Vec<String> = entries
+            .iter()
+            .filter_map(|entry| match entry {
+                Entry::Item {
+                    key: Some(key),
+                    checked: true,
+                    ..
+                } => Some(key.clone()),
+                _ => None,
+            })
+            .collect();
+        for entry in &mut entries {
+            if let Entry::Item {
+                key: Some(key),
+                checked,
+                ..
+            } = entry
+                && checked_keys.contains(key)
+            {
+                *checked = true;
+            }
+        }
+
         let mut state = Self {
             entries,
             coords,
@@ -83,6 +121,9 @@ impl PickerState {
             scroll: 0,
             visible_height: 0,
             confirm_error: None,
+            filter: String::new(),
+            editing_filter: false,
+            matches: Vec::new(),
         };
         state.rebuild_nav_stops();
         state
@@ -97,9 +138,25 @@ impl PickerState {
     }
 
     /// True if entry `i` is focusable — a visible item or a collapsed header.
+    /// While filtering, only matching items are focusable.
     fn is_nav_stop(&self, i: usize) -> bool {
         match &self.entries[i] {
+            Entry::Header { .. } if self.is_filtered() => false,
             Entry::Header { collapsed, .. } => *collapsed,
+            Entry::Item { .. } if self.is_filtered() => self.matches[i],
+            Entry::Item { .. } => self.visible[i],
+        }
+    }
+
+    /// True if entry `i` takes up a rendered line. While filtering, that's
+    /// the matching items and their section headers, whether collapsed or
+    /// not; otherwise every header and the items of expanded sections.
+    pub(crate) fn is_shown(&self, i: usize) -> bool {
+        if self.is_filtered() {
+            return self.matches[i];
+        }
+        match &self.entries[i] {
+            Entry::Header { .. } => true,
             Entry::Item { .. } => self.visible[i],
         }
     }
@@ -125,9 +182,10 @@ impl PickerState {
         }
     }
 
-    /// The entry index the cursor currently points at.
-    pub(crate) fn current_entry_idx(&self) -> usize {
-        self.nav_stops[self.cursor]
+    /// The entry index the cursor currently points at, or `None` when
+    /// nothing is focusable (a filter that matches no items).
+    pub(crate) fn current_entry_idx(&self) -> Option<usize> {
+        self.nav_stops.get(self.cursor).copied()
     }
 
     /// Find the `[header_idx, section_end)` entry range of the section that
@@ -146,13 +204,63 @@ impl PickerState {
 
     /// The selection mode of the section that contains the cursor.
     pub(crate) fn current_section_mode(&self) -> SelectionMode {
-        let (header_idx, _) = self.section_bounds(self.current_entry_idx());
+        match self.current_entry_idx() {
+            Some(idx) => self.section_mode(idx),
+            None => SelectionMode::Checkbox,
+        }
+    }
+
+    /// The selection mode of the section that contains entry `entry_idx`.
+    fn section_mode(&self, entry_idx: usize) -> SelectionMode {
+        let (header_idx, _) = self.section_bounds(entry_idx);
         match &self.entries[header_idx] {
             Entry::Header { mode, .. } => *mode,
             _ => SelectionMode::Checkbox,
         }
     }
 
+    /// Entry `idx` and every other item row sharing its key.
+    fn linked_rows(&self, idx: usize) -> Vec<usize> {
+        let Entry::Item { key: Some(key), .. } = &self.entries[idx] else {
+            return vec![idx];
+        };
+        (0..self.entries.len())
+            .filter(|&i| matches!(&self.entries[i], Entry::Item { key: Some(k), .. } if k == key))
+            .collect()
+    }
+
+    fn is_checked(&self, idx: usize) -> bool {
+        matches!(self.entries[idx], Entry::Item { checked: true, .. })
+    }
+
+    /// Set the checked state of item `idx` and every row linked to it.
+    ///
+    /// Checking a row that sits in a radio section — directly or through a
+    /// linked copy — first unchecks the other items there, and their own
+    /// linked rows, so every radio section keeps at most the new pick.
+    // [impl tui.picker.linked-items]
+    fn set_checked(&mut self, idx: usize, value: bool) {
+        let linked = self.linked_rows(idx);
+        if value {
+            for &row in &linked {
+                if self.section_mode(row) != SelectionMode::Radio {
+                    continue;
+                }
+                let (header_idx, section_end) = self.section_bounds(row);
+                for sibling in header_idx + 1..section_end {
+                    if !linked.contains(&sibling) && self.is_checked(sibling) {
+                        self.set_checked(sibling, false);
+                    }
+                }
+            }
+        }
+        for row in linked {
+            if let Entry::Item { checked, .. } = &mut self.entries[row] {
+                *checked = value;
+            }
+        }
+    }
+
     /// Toggle the checked state of the item under the cursor.
     ///
     /// In a radio section, checking an item first unchecks every other item in
@@ -160,62 +268,61 @@ impl PickerState {
     /// only selection (so empty is reachable), but *keeps* it checked when
     /// multiple items were selected (resolving a pre-existing conflict by
     /// confirming the toggled choice). A no-op when the cursor is on a header.
+    ///
+    /// Every row linked to the item follows it, and checking a copy in a
+    /// radio section clears that section's other items.
     pub fn toggle(&mut self) {
-        let idx = self.current_entry_idx();
+        let Some(idx) = self.current_entry_idx() else {
+            return;
+        };
         if !matches!(self.entries[idx], Entry::Item { .. }) {
             return;
         }
+        let was_checked = self.is_checked(idx);
 
         if self.current_section_mode() == SelectionMode::Radio {
-            let was_checked = matches!(self.entries[idx], Entry::Item { checked: true, .. });
             let (header_idx, section_end) = self.section_bounds(idx);
-
-            // Count how many siblings are currently checked (before clearing).
             let checked_count = (header_idx + 1..section_end)
-                .filter(|&i| matches!(self.entries[i], Entry::Item { checked: true, .. }))
+                .filter(|&i| self.is_checked(i))
                 .count();
 
-            // Clear all siblings.
-            for i in header_idx + 1..section_end {
-                if let Entry::Item { checked, .. } = &mut self.entries[i] {
-                    *checked = false;
-                }
-            }
-
-            // Re-check the toggled item unless it was the sole selection (allowing
+            // Checking clears the siblings. An already-checked item stays
+            // checked unless it was the sole selection (allowing
             // deselect-to-zero in the normal single-select case).
             let keep = !was_checked || checked_count > 1;
-            if keep && let Entry::Item { checked, .. } = &mut self.entries[idx] {
-                *checked = true;
-            }
+            self.set_checked(idx, keep);
             return;
         }
 
-        if let Entry::Item { checked, .. } = &mut self.entries[idx] {
-            *checked = !*checked;
-        }
+        self.set_checked(idx, !was_checked);
     }
 
     /// Toggle all items in the section that contains the cursor.
     ///
     /// In a checkbox section: if any item is unchecked, checks all; otherwise
     /// unchecks all. In a radio section this is a no-op — a radio section can
-    /// never have every item selected.
+    /// never have every item selected. While filtering, only the matching
+    /// items are considered and toggled.
     pub fn toggle_current_section(&mut self) {
         if self.current_section_mode() == SelectionMode::Radio {
             return;
         }
+        let Some(idx) = self.current_entry_idx() else {
+            return;
+        };
 
-        let (header_idx, section_end) = self.section_bounds(self.current_entry_idx());
+        let (header_idx, section_end) = self.section_bounds(idx);
+        let in_scope: Vec<usize> = (header_idx + 1..section_end)
+            .filter(|&i| !self.is_filtered() || self.matches[i])
+            .collect();
 
-        let all_checked = (header_idx + 1..section_end)
-            .all(|i| matches!(self.entries[i], Entry::Item { checked: true, .. }));
+        let all_checked = in_scope
+            .iter()
+            .all(|&i| matches!(self.entries[i], Entry::Item { checked: true, .. }));
         let target = !all_checked;
 
-        for i in header_idx + 1..section_end {
-            if let Entry::Item { checked, .. } = &mut self.entries[i] {
-                *checked = target;
-            }
+        for i in in_scope {
+            self.set_checked(i, target);
         }
     }
 
@@ -223,10 +330,13 @@ impl PickerState {
     ///
     /// Bound to Backspace in radio sections to clear the current pick.
     pub fn clear_current_section(&mut self) {
-        let (header_idx, section_end) = self.section_bounds(self.current_entry_idx());
+        let Some(idx) = self.current_entry_idx() else {
+            return;
+        };
+        let (header_idx, section_end) = self.section_bounds(idx);
         for i in header_idx + 1..section_end {
-            if let Entry::Item { checked, .. } = &mut self.entries[i] {
-                *checked = false;
+            if self.is_checked(i) {
+                self.set_checked(i, false);
             }
         }
     }
@@ -241,8 +351,29 @@ impl PickerState {
     /// The (section_idx, item_idx) for the current cursor position.
     ///
     /// When the cursor rests on a collapsed header, the item index is 0.
+    ///
+    /// # Panics
+    ///
+    /// When nothing is focusable (see [`is_empty`](Self::is_empty)).
     pub fn current_coordinates(&self) -> (usize, usize) {
-        self.coords[self.current_entry_idx()]
+        let idx = self
+            .current_entry_idx()
+            .expect("no focusable entry under the cursor");
+        self.coords[idx]
+    }
+
+    /// Replace the label of the item under the cursor, and of every row
+    /// linked to it. A no-op when the cursor is on a header.
+    pub fn set_current_label(&mut self, new_label: impl Into<String>) {
+        let Some(idx) = self.current_entry_idx() else {
+            return;
+        };
+        let new_label = new_label.into();
+        for row in self.linked_rows(idx) {
+            if let Entry::Item { label, .. } = &mut self.entries[row] {
+                label.clone_from(&new_label);
+            }
+        }
     }
 
     /// True if at least one item is checked across all sections.
@@ -253,9 +384,16 @@ impl PickerState {
     }
 
     /// Collapse the section that contains the cursor and focus its header, so
-    /// the section can be re-expanded from that position.
+    /// the section can be re-expanded from that position. A no-op while
+    /// filtering, which shows matches regardless of collapse.
     pub fn collapse_current(&mut self) {
-        let (header_idx, section_end) = self.section_bounds(self.current_entry_idx());
+        if self.is_filtered() {
+            return;
+        }
+        let Some(idx) = self.current_entry_idx() else {
+            return;
+        };
+        let (header_idx, section_end) = self.section_bounds(idx);
         if let Entry::Header { collapsed, .. } = &mut self.entries[header_idx] {
             *collapsed = true;
         }
@@ -268,9 +406,16 @@ impl PickerState {
     }
 
     /// Expand the section that contains the cursor and focus its first item (if
-    /// any), restoring normal traversal through the section.
+    /// any), restoring normal traversal through the section. A no-op while
+    /// filtering.
     pub fn expand_current(&mut self) {
-        let (header_idx, section_end) = self.section_bounds(self.current_entry_idx());
+        if self.is_filtered() {
+            return;
+        }
+        let Some(idx) = self.current_entry_idx() else {
+            return;
+        };
+        let (header_idx, section_end) = self.section_bounds(idx);
         if let Entry::Header { collapsed, .. } = &mut self.entries[header_idx] {
             *collapsed = false;
         }
@@ -299,6 +444,93 @@ impl PickerState {
         };
     }
 
+    /// True while a non-empty filter query narrows the rows.
+    pub(crate) fn is_filtered(&self) -> bool {
+        !self.filter.is_empty()
+    }
+
+    /// The applied filter query, if any.
+    pub fn filter(&self) -> Option<&str> {
+        Some(self.filter.as_str()).filter(|query| !query.is_empty())
+    }
+
+    /// True while keystrokes go to the filter query (after `/`).
+    pub fn is_editing_filter(&self) -> bool {
+        self.editing_filter
+    }
+
+    /// Start typing a filter query, keeping any query already applied.
+    // [impl tui.picker.filter]
+    pub fn start_filter(&mut self) {
+        self.editing_filter = true;
+    }
+
+    /// Stop typing the filter query. The filter stays applied, so the
+    /// matching rows can be navigated and toggled with the usual keys.
+    pub fn finish_filter(&mut self) {
+        self.editing_filter = false;
+    }
+
+    /// Drop the filter and show every row again. The cursor stays on the
+    /// item it was on, and checked state is untouched.
+    pub fn clear_filter(&mut self) {
+        self.editing_filter = false;
+        self.set_filter("");
+    }
+
+    /// Append a character to the filter query.
+    pub fn push_filter_char(&mut self, c: char) {
+        let mut query = std::mem::take(&mut self.filter);
+        query.push(c);
+        self.set_filter(query);
+    }
+
+    /// Remove the last character of the filter query.
+    pub fn pop_filter_char(&mut self) {
+        let mut query = std::mem::take(&mut self.filter);
+        query.pop();
+        self.set_filter(query);
+    }
+
+    /// Narrow the rows to items whose label or description fuzzy-matches
+    /// `query` (see [`fuzzy_match`]), keeping the headers of their sections.
+    /// An empty query shows every row. The cursor stays on its item when
+    /// that still matches, and otherwise moves to the first match.
+    // [impl tui.picker.filter]
+    pub fn set_filter(&mut self, query: impl Into<String>) {
+        let focused = self.current_entry_idx();
+        self.filter = query.into();
+        self.matches = self
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                Entry::Header { .. } => false,
+                Entry::Item {
+                    label, description, ..
+                } => {
+                    fuzzy_match(&self.filter, label)
+                        || description
+                            .as_deref()
+                            .is_some_and(|d| fuzzy_match(&self.filter, d))
+                }
+            })
+            .collect();
+        for i in 0..self.entries.len() {
+            if matches!(self.entries[i], Entry::Header { .. }) {
+                let (_, section_end) = self.section_bounds(i);
+                self.matches[i] = self.matches[i + 1..section_end].contains(&true);
+            }
+        }
+        self.rebuild_nav_stops();
+        match focused.and_then(|idx| self.nav_stops.iter().position(|&e| e == idx)) {
+            Some(pos) => self.cursor = pos,
+            None if self.is_filtered() => self.cursor = 0,
+            None => self.focus_entry(focused.unwrap_or(0)),
+        }
+        self.scroll = 0;
+        self.ensure_cursor_visible();
+    }
+
     /// Count checked items in the section whose header is at `header_idx`.
     fn section_checked_count(&self, header_idx: usize) -> usize {
         let (_, section_end) = self.section_bounds(header_idx);
@@ -358,7 +590,7 @@ impl PickerState {
     ///
     /// Returns `Err` naming the offending section when a radio section has more
     /// than one item checked.
-    pub fn try_confirm(&mut self) -> Result<Vec<Vec<bool>>, String> {
+    pub fn try_confirm(&mut self) -> Result<PickerResults, String> {
         if let Some(title) = self.radio_conflict_title() {
             return Err(format!("category '{title}' allows at most one selection"));
         }
@@ -375,13 +607,16 @@ impl PickerState {
         self.confirm_error = None;
     }
 
-    /// Extract checked state grouped by section (matching original input order).
+    /// Extract checked state grouped by section (matching original input order),
+    /// and once per item key.
     ///
     /// Collapsed sections are included — collapse only hides items from the UI,
     /// never from the results.
-    pub fn into_results(&mut self) -> Vec<Vec<bool>> {
+    // [impl tui.picker.linked-items]
+    pub fn into_results(&mut self) -> PickerResults {
         let entries = std::mem::take(&mut self.entries);
         let mut results: Vec<Vec<bool>> = Vec::new();
+        let mut keys: BTreeMap<String, bool> = BTreeMap::new();
         let mut current_section: Vec<bool> = Vec::new();
         let mut seen_header = false;
 
@@ -393,55 +628,56 @@ impl PickerState {
                     }
                     seen_header = true;
                 }
-                Entry::Item { checked, .. } => {
+                Entry::Item { checked, key, .. } => {
                     current_section.push(checked);
+                    if let Some(key) = key {
+                        keys.insert(key, checked);
+                    }
                 }
             }
         }
         if seen_header {
             results.push(current_section);
         }
-        results
+        PickerResults {
+            sections: results,
+            keys,
+        }
     }
 
     /// Compute which rendered line the cursor currently occupies.
     ///
     /// The rendered layout inserts a blank line before each section header
     /// (except the first), so the line count is not simply the entry index.
-    pub(crate) fn cursor_line(&self) -> usize {
-        self.entry_to_line(self.current_entry_idx())
+    pub(crate) fn cursor_line(&self) -> Option<usize> {
+        Some(self.entry_to_line(self.current_entry_idx()?))
     }
 
     /// Compute the rendered line of the section header for the current cursor.
-    fn section_header_line(&self) -> usize {
-        let (header_entry_idx, _) = self.section_bounds(self.current_entry_idx());
-        self.entry_to_line(header_entry_idx)
+    fn section_header_line(&self) -> Option<usize> {
+        let (header_entry_idx, _) = self.section_bounds(self.current_entry_idx()?);
+        Some(self.entry_to_line(header_entry_idx))
     }
 
     /// Map an entry index to its rendered line number.
     ///
-    /// Each header and each visible item occupies one line; a blank separator
-    /// precedes every non-first header. Items hidden by a collapsed section
-    /// contribute no lines, mirroring the render loop.
+    /// Each shown header and item occupies one line; a blank separator
+    /// precedes every shown header but the first. Entries hidden by a
+    /// collapsed section or the filter contribute no lines, mirroring the
+    /// render loop.
     fn entry_to_line(&self, target: usize) -> usize {
         let mut line = 0;
         for (i, entry) in self.entries.iter().enumerate() {
             if i == target {
                 return line;
             }
-            match entry {
-                Entry::Header { .. } => {
-                    if i > 0 {
-                        line += 1; // blank separator before non-first headers
-                    }
-                    line += 1; // the header itself
-                }
-                Entry::Item { .. } => {
-                    if self.visible[i] {
-                        line += 1;
-                    }
-                }
+            if !self.is_shown(i) {
+                continue;
             }
+            if matches!(entry, Entry::Header { .. }) && line > 0 {
+                line += 1; // blank separator before non-first headers
+            }
+            line += 1;
         }
         line
     }
@@ -455,11 +691,15 @@ impl PickerState {
         if self.visible_height == 0 {
             return;
         }
-        let cursor_line = self.cursor_line();
+        let (Some(cursor_line), Some(header_line)) =
+            (self.cursor_line(), self.section_header_line())
+        else {
+            self.scroll = 0;
+            return;
+        };
 
         // Scroll up if cursor is above viewport.
         if cursor_line < self.scroll {
-            let header_line = self.section_header_line();
             // Show the header too, but only if cursor-to-header distance fits.
             if cursor_line - header_line < self.visible_height {
                 self.scroll = header_line;
@@ -490,6 +730,19 @@ impl PickerState {
     }
 }
 
+/// True if every whitespace-separated term of `query` appears in `text` as
+/// a subsequence, ignoring case — so `"ss13"` matches `"ssd1306"`. An empty
+/// query matches everything.
+pub fn fuzzy_match(query: &str, text: &str) -> bool {
+    let text = text.to_lowercase();
+    query.split_whitespace().all(|term| {
+        let mut haystack = text.chars();
+        term.to_lowercase()
+            .chars()
+            .all(|c| haystack.any(|t| t == c))
+    })
+}
+
 /// Helper to construct a checkbox section concisely (used in tests).
 #[cfg(test)]
 pub fn section(title: impl Into<String>, items: &[(&str, bool)]) -> Section {
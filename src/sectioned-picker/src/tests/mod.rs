@@ -1,2 +1,3 @@
 mod render;
+mod script;
 mod state;
@@ -485,3 +485,48 @@ fn collapsed_header_is_highlighted_when_focused() {
         "collapsed header should be highlighted with Cyan background when focused"
     );
 }
+
+// ============================================================================
+// Filtering
+// ============================================================================
+
+// [verify tui.picker.filter]
+#[test]
+fn snapshot_filtered_picker() {
+    let mut state = PickerState::new(vec![
+        section("HAL:", &[("stm32f4xx-hal", true), ("nrf52840-hal", false)]),
+        section("Display:", &[("ssd1306", false), ("st7789", false)]),
+        section("Sensors:", &[("bme280", false)]),
+    ]);
+    state.start_filter();
+    state.set_filter("s7");
+    let output = render_to_string(60, 10, "embedded", &mut state);
+    assert_data_eq!(
+        output,
+        str![[r#"
+" / s7█                                                      "
+"────────────────────────────────────────────────────────────"
+" ▼ Display: (pick any number)                               "
+" > [ ] st7789                                               "
+"                                                            "
+"                                                            "
+"                                                            "
+"                                                            "
+"                                                            "
+" embedded  Type to filter | ↑↓ Navigate | Enter Done | Esc C"
+
+"#]]
+    );
+}
+
+// [verify tui.picker.filter]
+#[test]
+fn render_filter_without_matches() {
+    let mut state = PickerState::new(vec![section("S:", &[("alpha", false)])]);
+    state.set_filter("zz");
+    let output = render_to_string(200, 8, "test", &mut state);
+
+    assert!(output.contains(" / zz  no matches"), "{output}");
+    assert!(!output.contains("alpha"), "{output}");
+    assert!(output.contains("Esc Clear filter"), "{output}");
+}
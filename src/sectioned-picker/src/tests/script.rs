@@ -0,0 +1,122 @@
+//! Tests for scripted runs: key-sequence parsing and `run_picker_with`.
+
+use crate::state::{radio_section, section};
+use crate::{KeyCode, KeyEvent, PickerAction, PickerOutcome, parse_keys, run_picker_with};
+use crossterm::event::KeyModifiers;
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use snapbox::{assert_data_eq, str};
+
+fn terminal(width: u16, height: u16) -> Terminal<TestBackend> {
+    Terminal::new(TestBackend::new(width, height)).unwrap()
+}
+
+fn confirmed(outcome: PickerOutcome) -> Vec<Vec<bool>> {
+    match outcome {
+        PickerOutcome::Confirmed(results) => results.sections,
+        PickerOutcome::Cancelled => panic!("picker was cancelled"),
+    }
+}
+
+// ============================================================================
+// parse_keys
+// ============================================================================
+
+// [verify tui.picker.scripted]
+#[test]
+fn parse_keys_reads_characters_and_named_keys() {
+    let keys = parse_keys("j <Down><enter><lt><c-c>").unwrap();
+    assert_eq!(
+        keys,
+        vec![
+            KeyEvent::from(KeyCode::Char('j')),
+            KeyEvent::from(KeyCode::Char(' ')),
+            KeyEvent::from(KeyCode::Down),
+            KeyEvent::from(KeyCode::Enter),
+            KeyEvent::from(KeyCode::Char('<')),
+            KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+        ]
+    );
+    assert!(parse_keys("").unwrap().is_empty());
+}
+
+#[test]
+fn parse_keys_rejects_unknown_and_unclosed_names() {
+    assert_data_eq!(
+        parse_keys("j<pgdn>").unwrap_err().to_string(),
+        str![[r#"unknown key `<pgdn>` in key sequence "j<pgdn>""#]]
+    );
+    assert_data_eq!(
+        parse_keys("a<b").unwrap_err().to_string(),
+        str![[r#"unclosed `<` in key sequence "a<b" (write `<lt>` for a literal `<`)"#]]
+    );
+}
+
+// ============================================================================
+// run_picker_with
+// ============================================================================
+
+// [verify tui.picker.scripted]
+#[test]
+fn scripted_keys_toggle_and_confirm() {
+    let sections = vec![
+        section("Features:", &[("logging", false), ("metrics", false)]),
+        radio_section("Output:", &[("human", true), ("json", false)]),
+    ];
+    let keys = parse_keys("j jj <enter>").unwrap();
+    let outcome = run_picker_with("app", sections, Vec::new(), keys, &mut terminal(60, 12));
+    assert_eq!(
+        confirmed(outcome.unwrap()),
+        vec![vec![false, true], vec![false, true]]
+    );
+}
+
+// [verify tui.picker.scripted]
+#[test]
+fn scripted_run_without_confirm_is_cancelled_and_keeps_the_last_frame() {
+    let sections = vec![section("S:", &[("alpha", false), ("beta", false)])];
+    let mut terminal = terminal(40, 6);
+    let keys = parse_keys("j ").unwrap();
+    let outcome = run_picker_with("test", sections, Vec::new(), keys, &mut terminal).unwrap();
+    assert!(matches!(outcome, PickerOutcome::Cancelled));
+    assert_data_eq!(
+        terminal.backend().to_string(),
+        str![[r#"
+"────────────────────────────────────────"
+" ▼ S: (beta selected)                   "
+"   [ ] alpha                            "
+" > [x] beta                             "
+"                                        "
+" test  ↑↓/jk Navigate | Space Toggle | ←"
+
+"#]]
+    );
+}
+
+// [verify tui.picker.scripted]
+#[test]
+fn scripted_actions_run_without_a_terminal() {
+    let sections = vec![section("S:", &[("clap", false)])];
+    let mut had_terminal = None;
+    let actions = vec![PickerAction {
+        key: 'd',
+        label: "Dep kind",
+        handler: Box::new(|ctx| {
+            had_terminal = Some(ctx.terminal().is_some());
+            ctx.set_label("clap [dev]");
+        }),
+    }];
+    let mut terminal = terminal(40, 6);
+    let keys = parse_keys("d").unwrap();
+    run_picker_with("test", sections, actions, keys, &mut terminal).unwrap();
+    assert_eq!(had_terminal, Some(false));
+    assert!(terminal.backend().to_string().contains("> [ ] clap [dev]"));
+}
+
+#[test]
+fn scripted_ctrl_c_cancels() {
+    let sections = vec![section("S:", &[("alpha", true)])];
+    let keys = parse_keys("<c-c><enter>").unwrap();
+    let outcome = run_picker_with("test", sections, Vec::new(), keys, &mut terminal(40, 6));
+    assert!(matches!(outcome.unwrap(), PickerOutcome::Cancelled));
+}
@@ -77,7 +77,7 @@ fn navigation_skips_headers_across_sections() {
     );
     state.move_down();
     assert_eq!(state.cursor(), 1);
-    let entry_idx = state.current_entry_idx();
+    let entry_idx = state.current_entry_idx().unwrap();
     assert!(matches!(&state.entries[entry_idx], Entry::Item { label, .. } if label == "b"));
 }
 
@@ -89,7 +89,7 @@ fn navigation_skips_headers_across_sections() {
 fn toggle_flips_checked_state() {
     let mut state = make_state(vec![section("S:", &[("a", false), ("b", true)])], 10);
     state.toggle();
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![true, true]]);
 }
 
@@ -100,7 +100,7 @@ fn toggle_section_checks_all_when_some_unchecked() {
         10,
     );
     state.toggle_current_section();
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![true, true, true]]);
 }
 
@@ -108,7 +108,7 @@ fn toggle_section_checks_all_when_some_unchecked() {
 fn toggle_section_unchecks_all_when_all_checked() {
     let mut state = make_state(vec![section("S:", &[("a", true), ("b", true)])], 10);
     state.toggle_current_section();
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![false, false]]);
 }
 
@@ -122,7 +122,7 @@ fn toggle_section_only_affects_current_section() {
         20,
     );
     state.toggle_current_section();
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![true, true], vec![true]]);
 }
 
@@ -139,7 +139,7 @@ fn enter_with_nothing_checked_checks_cursor_item() {
     state.move_down();
     assert!(!state.has_any_checked());
     state.toggle();
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![false, true, false]]);
 }
 
@@ -151,7 +151,7 @@ fn enter_with_something_checked_does_not_toggle() {
     );
     state.move_down();
     assert!(state.has_any_checked());
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![true, false, false]]);
 }
 
@@ -239,6 +239,26 @@ fn action_handler_receives_correct_coordinates() {
     assert_eq!(item_idx, 1);
 }
 
+/// An action can relabel the item under the cursor (e.g. to show a choice it
+/// cycled); the checked state is untouched.
+#[test]
+fn set_current_label_relabels_only_the_cursor_item() {
+    let mut state = make_state(vec![section("Deps:", &[("a", true), ("b", false)])], 20);
+    state.move_down();
+    state.set_current_label("b [dev]");
+
+    let labels: Vec<_> = state
+        .entries
+        .iter()
+        .filter_map(|e| match e {
+            Entry::Item { label, .. } => Some(label.as_str()),
+            Entry::Header { .. } => None,
+        })
+        .collect();
+    assert_eq!(labels, ["a", "b [dev]"]);
+    assert_eq!(state.into_results().sections, vec![vec![true, false]]);
+}
+
 // ============================================================================
 // Results — checked state grouped by section
 // ============================================================================
@@ -253,7 +273,7 @@ fn into_results_groups_by_section() {
         ],
         20,
     );
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(
         results,
         vec![vec![true, false], vec![true], vec![false, false]]
@@ -269,7 +289,7 @@ fn empty_sections_produce_empty_vecs() {
         ],
         10,
     );
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![], vec![true]]);
 }
 
@@ -437,11 +457,11 @@ fn cursor_line_matches_expected_positions() {
         20,
     );
 
-    assert_eq!(state.cursor_line(), 1);
+    assert_eq!(state.cursor_line(), Some(1));
     state.move_down();
-    assert_eq!(state.cursor_line(), 2);
+    assert_eq!(state.cursor_line(), Some(2));
     state.move_down();
-    assert_eq!(state.cursor_line(), 5);
+    assert_eq!(state.cursor_line(), Some(5));
 }
 
 // ============================================================================
@@ -460,7 +480,7 @@ fn radio_mode_toggle_deselects_others() {
     );
     state.move_down(); // cursor on B
     state.toggle();
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![false, true, false]]);
 }
 
@@ -469,7 +489,7 @@ fn radio_mode_allows_deselect_all() {
     // Toggling the only checked item unchecks it — zero selections is allowed.
     let mut state = make_state(vec![radio_section("HAL:", &[("a", true)])], 10);
     state.toggle();
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![false]]);
 }
 
@@ -481,7 +501,7 @@ fn radio_mode_backspace_clears() {
         10,
     );
     state.backspace();
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![false, false]]);
 }
 
@@ -491,7 +511,7 @@ fn checkbox_mode_toggle_is_independent() {
     let mut state = make_state(vec![section("Utils:", &[("a", true), ("b", false)])], 10);
     state.move_down(); // cursor on B
     state.toggle();
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![true, true]]);
 }
 
@@ -503,7 +523,7 @@ fn radio_mode_section_toggle_is_noop() {
         10,
     );
     state.toggle_current_section();
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![true, false]]);
 }
 
@@ -518,7 +538,7 @@ fn checkbox_mode_section_toggle_selects_all() {
         10,
     );
     state.toggle_current_section();
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![true, true, true]]);
 }
 
@@ -526,7 +546,7 @@ fn checkbox_mode_section_toggle_selects_all() {
 fn radio_pre_selected_multiple_renders_honestly() {
     // A radio section constructed with two items checked keeps both on load.
     let mut state = make_state(vec![radio_section("HAL:", &[("a", true), ("b", true)])], 10);
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![true, true]]);
 }
 
@@ -543,7 +563,7 @@ fn radio_pre_selected_multiple_toggle_clears_others() {
     state.move_down(); // B
     state.move_down(); // C
     state.toggle();
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![false, false, true]]);
 }
 
@@ -554,7 +574,7 @@ fn radio_pre_selected_multiple_toggle_already_checked_keeps_it() {
     let mut state = make_state(vec![radio_section("HAL:", &[("a", true), ("b", true)])], 10);
     // Cursor starts on A (first item).
     state.toggle();
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![true, false]]);
 }
 
@@ -567,7 +587,7 @@ fn radio_single_selected_toggle_deselects_to_zero() {
     );
     // Cursor starts on A (first item).
     state.toggle();
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![false, false]]);
 }
 
@@ -595,7 +615,7 @@ fn radio_confirm_succeeds_valid_state() {
     );
     let ok = one.try_confirm();
     assert!(ok.is_ok());
-    assert_eq!(ok.unwrap(), vec![vec![true, false]]);
+    assert_eq!(ok.unwrap().sections, vec![vec![true, false]]);
 }
 
 // ============================================================================
@@ -642,6 +662,278 @@ fn into_results_includes_collapsed_sections() {
     // Checking an item then collapsing its section preserves the checked state.
     let mut state = make_state(vec![section("A:", &[("a1", true), ("a2", false)])], 20);
     state.collapse_current();
-    let results = state.into_results();
+    let results = state.into_results().sections;
     assert_eq!(results, vec![vec![true, false]]);
 }
+
+// ============================================================================
+// Filtering — fuzzy match, navigation, and results
+// ============================================================================
+
+#[test]
+fn fuzzy_match_finds_terms_as_subsequences() {
+    use crate::fuzzy_match;
+
+    assert!(fuzzy_match("ssd", "ssd1306 (0.8)"));
+    assert!(fuzzy_match("SS13", "ssd1306"));
+    assert!(fuzzy_match("oled ssd", "ssd1306    I2C OLED driver"));
+    assert!(fuzzy_match("", "anything"));
+    assert!(!fuzzy_match("sd31", "ssd1306"));
+    assert!(!fuzzy_match("ssd lcd", "ssd1306    I2C OLED driver"));
+}
+
+// [verify tui.picker.filter]
+#[test]
+fn filter_narrows_navigation_to_matching_items() {
+    let mut state = make_state(
+        vec![
+            section("HAL:", &[("stm32f4xx-hal", false), ("nrf52840-hal", false)]),
+            section("Display:", &[("ssd1306", false), ("st7789", false)]),
+        ],
+        20,
+    );
+    state.start_filter();
+    for c in "st".chars() {
+        state.push_filter_char(c);
+    }
+    assert_eq!(state.filter(), Some("st"));
+    assert_eq!(state.current_coordinates(), (0, 0)); // stm32f4xx-hal
+    state.move_down();
+    assert_eq!(state.current_coordinates(), (1, 1)); // st7789, skipping the rest
+    state.move_down();
+    assert_eq!(state.current_coordinates(), (1, 1));
+
+    // Headers are kept for sections with a match, and lines close up.
+    assert!(state.is_shown(0));
+    assert!(!state.is_shown(2)); // nrf52840-hal
+    assert_eq!(state.cursor_line(), Some(4));
+}
+
+// [verify tui.picker.filter]
+#[test]
+fn filter_matches_descriptions() {
+    let mut state = make_state(
+        vec![crate::Section::new(
+            "Display:",
+            vec![
+                crate::SectionItem::new("ssd1306", false).with_description("I2C OLED driver"),
+                crate::SectionItem::new("st7789", false).with_description("SPI LCD driver"),
+            ],
+        )],
+        20,
+    );
+    state.set_filter("lcd");
+    assert_eq!(state.current_coordinates(), (0, 1));
+    assert!(!state.is_shown(1));
+}
+
+// [verify tui.picker.filter]
+#[test]
+fn clearing_the_filter_keeps_checked_state_and_cursor_item() {
+    let mut state = make_state(
+        vec![
+            section("A:", &[("alpha", true), ("beta", false)]),
+            section("B:", &[("gamma", false), ("delta", true)]),
+        ],
+        20,
+    );
+    state.set_filter("ta");
+    assert_eq!(state.current_coordinates(), (0, 1)); // beta
+    state.toggle();
+    state.move_down();
+    assert_eq!(state.current_coordinates(), (1, 1)); // delta
+    state.toggle();
+
+    state.clear_filter();
+    assert_eq!(state.filter(), None);
+    assert_eq!(state.current_coordinates(), (1, 1));
+    state.move_up();
+    assert_eq!(state.current_coordinates(), (1, 0)); // gamma is back
+    assert_eq!(
+        state.into_results().sections,
+        vec![vec![true, true], vec![false, false]]
+    );
+}
+
+// [verify tui.picker.filter]
+#[test]
+fn filter_with_no_matches_leaves_nothing_focusable() {
+    let mut state = make_state(vec![section("A:", &[("alpha", true)])], 20);
+    state.set_filter("zzz");
+    assert!(state.is_empty());
+    // Keys that act on the cursor are harmless with nothing under it.
+    state.toggle();
+    state.toggle_current_section();
+    state.move_down();
+    state.collapse_current();
+    state.set_current_label("x");
+    assert_eq!(state.cursor_line(), None);
+
+    state.pop_filter_char();
+    state.set_filter("");
+    assert_eq!(state.current_coordinates(), (0, 0));
+    assert_eq!(state.into_results().sections, vec![vec![true]]);
+}
+
+// [verify tui.picker.filter]
+#[test]
+fn filter_shows_matches_in_collapsed_sections() {
+    let mut state = make_state(
+        vec![
+            section("A:", &[("a1", false)]),
+            section("B:", &[("b1", false), ("b2", false)]).collapsed(),
+        ],
+        20,
+    );
+    state.set_filter("b2");
+    assert_eq!(state.current_coordinates(), (1, 1));
+    // Collapse is left alone while filtering.
+    state.collapse_current();
+    assert_eq!(state.current_coordinates(), (1, 1));
+
+    // Clearing returns to the collapsed section's header.
+    state.clear_filter();
+    assert_eq!(state.current_coordinates(), (1, 0));
+    assert!(!state.is_shown(4));
+}
+
+// [verify tui.picker.filter]
+#[test]
+fn toggle_section_while_filtering_only_touches_matches() {
+    let mut state = make_state(
+        vec![section(
+            "Drivers:",
+            &[("ssd1306", false), ("st7789", false), ("sh1106", false)],
+        )],
+        20,
+    );
+    state.set_filter("s1");
+    state.toggle_current_section();
+    state.clear_filter();
+    assert_eq!(state.into_results().sections, vec![vec![true, false, true]]);
+}
+
+// ============================================================================
+// Linked items — rows sharing a key across sections
+// ============================================================================
+
+/// A section whose items are `(label, checked)`, each keyed by its label.
+fn keyed_section(title: &str, items: &[(&str, bool)]) -> crate::Section {
+    crate::Section::new(
+        title,
+        items
+            .iter()
+            .map(|(label, checked)| crate::SectionItem::new(*label, *checked).with_key(*label))
+            .collect(),
+    )
+}
+
+// [verify tui.picker.linked-items]
+#[test]
+fn toggling_a_linked_row_updates_every_copy() {
+    let mut state = make_state(
+        vec![
+            keyed_section("Quality:", &[("lint", false), ("spellcheck", false)]),
+            keyed_section("CI:", &[("lint", false), ("release", false)]),
+        ],
+        20,
+    );
+    state.toggle(); // lint, in Quality
+    let results = state.try_confirm().unwrap();
+    assert_eq!(results.sections, vec![vec![true, false], vec![true, false]]);
+    assert_eq!(
+        results.keys,
+        std::collections::BTreeMap::from([
+            ("lint".to_string(), true),
+            ("release".to_string(), false),
+            ("spellcheck".to_string(), false),
+        ])
+    );
+}
+
+// [verify tui.picker.linked-items]
+#[test]
+fn a_linked_item_starts_checked_when_any_copy_is() {
+    // Edit mode pre-checks one copy; the other copy must agree.
+    let mut state = make_state(
+        vec![
+            keyed_section("Quality:", &[("lint", false)]),
+            keyed_section("CI:", &[("lint", true)]),
+        ],
+        20,
+    );
+    assert_eq!(state.into_results().sections, vec![vec![true], vec![true]]);
+}
+
+// [verify tui.picker.linked-items]
+#[test]
+fn checking_a_copy_clears_its_radio_siblings_and_their_copies() {
+    let mut state = make_state(
+        vec![
+            keyed_section("Extras:", &[("mimalloc", false), ("jemalloc", true)]),
+            keyed_section("Allocator:", &[("mimalloc", false), ("jemalloc", true)]).radio(),
+        ],
+        20,
+    );
+    // Check mimalloc in the checkbox section: its radio copy takes the pick,
+    // so jemalloc is unchecked there and, through its link, in Extras too.
+    state.toggle();
+    assert_eq!(
+        state.into_results().sections,
+        vec![vec![true, false], vec![true, false]]
+    );
+}
+
+// [verify tui.picker.linked-items]
+#[test]
+fn clearing_a_radio_section_unchecks_linked_copies() {
+    let mut state = make_state(
+        vec![
+            keyed_section("HAL:", &[("stm32", true), ("nrf", false)]).radio(),
+            keyed_section("Chips:", &[("stm32", true)]),
+        ],
+        20,
+    );
+    state.backspace();
+    assert_eq!(
+        state.into_results().sections,
+        vec![vec![false, false], vec![false]]
+    );
+}
+
+#[test]
+fn unkeyed_rows_with_the_same_label_stay_independent() {
+    let mut state = make_state(
+        vec![
+            section("A:", &[("x", false)]),
+            section("B:", &[("x", false)]),
+        ],
+        20,
+    );
+    state.toggle();
+    let results = state.into_results();
+    assert_eq!(results.sections, vec![vec![true], vec![false]]);
+    assert!(results.keys.is_empty());
+}
+
+// [verify tui.picker.linked-items]
+#[test]
+fn relabeling_a_linked_row_relabels_every_copy() {
+    let mut state = make_state(
+        vec![
+            keyed_section("A:", &[("clap", false)]),
+            keyed_section("B:", &[("clap", false)]),
+        ],
+        20,
+    );
+    state.set_current_label("clap [dev]");
+    let labels: Vec<&str> = state
+        .entries
+        .iter()
+        .filter_map(|e| match e {
+            Entry::Item { label, .. } => Some(label.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(labels, ["clap [dev]", "clap [dev]"]);
+}